@@ -0,0 +1,86 @@
+/// Define a newtype message that carries a [`VBox`](crate::VBox) or an
+/// [`Envelope`](crate::Envelope), and implement the message traits of an actor
+/// framework for it.
+///
+/// The orphan rule forbids implementing a framework trait such as
+/// `actix::Message` for `VBox` outside both crates, and `vbox` does not depend
+/// on any actor framework. This macro writes the newtype and the boilerplate
+/// around it in one place: `From` the inner type, `Deref` and `DerefMut` to
+/// it, `into_inner()`, `Debug`, and the given trait impls, e.g.,
+/// `impl actix::Message { type Result = (); }` for actix, or
+/// `impl xtra::Message { type Result = (); }` for xtra before 0.6.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{actor_message, from_vbox, into_vbox, VBox};
+/// // A trait in the style of `actix::Message`.
+/// pub trait Message {
+///     type Result;
+/// }
+///
+/// actor_message! {
+///     /// A type-erased command to an actor.
+///     pub struct Command(VBox);
+///
+///     impl Message {
+///         type Result = u64;
+///     }
+/// }
+///
+/// let cmd = Command::from(into_vbox!(dyn Debug, 1u64));
+/// assert!(cmd.is::<u64>());
+///
+/// let d = from_vbox!(dyn Debug, cmd.into_inner());
+/// assert_eq!("1", format!("{:?}", d));
+/// ```
+#[macro_export]
+macro_rules! actor_message {
+    (
+        $(#[$m: meta])*
+        $vis: vis struct $name: ident($inner: ty);
+
+        $(
+            impl $tr: path { $($body: tt)* }
+        )*
+    ) => {
+        $(#[$m])*
+        $vis struct $name(pub $inner);
+
+        impl $name {
+            /// Returns the carried value.
+            pub fn into_inner(self) -> $inner {
+                self.0
+            }
+        }
+
+        impl ::std::convert::From<$inner> for $name {
+            fn from(v: $inner) -> Self {
+                $name(v)
+            }
+        }
+
+        impl ::std::ops::Deref for $name {
+            type Target = $inner;
+
+            fn deref(&self) -> &$inner {
+                &self.0
+            }
+        }
+
+        impl ::std::ops::DerefMut for $name {
+            fn deref_mut(&mut self) -> &mut $inner {
+                &mut self.0
+            }
+        }
+
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_tuple(::std::stringify!($name)).field(&self.0).finish()
+            }
+        }
+
+        $(
+            impl $tr for $name { $($body)* }
+        )*
+    };
+}
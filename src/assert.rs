@@ -0,0 +1,31 @@
+//! Assertions used by the macros, to produce compile errors that name the
+//! actual problem, instead of a coercion failure deep inside the expansion.
+//!
+//! Each function returns the value it is given, so that the error points to the
+//! argument passed to the macro.
+
+/// A payload packed into a `VBox` is sent to another thread.
+///
+/// ```compile_fail
+/// # use std::fmt::Debug;
+/// # use std::rc::Rc;
+/// # use vbox::into_vbox;
+/// // error: required by a bound in `payload_must_be_send`
+/// let _ = into_vbox!(dyn Debug, Rc::new(1u64));
+/// ```
+pub fn payload_must_be_send<T: Send>(value: T) -> T {
+    value
+}
+
+/// A payload packed into a `VBox` must not borrow anything, e.g., a closure
+/// must capture by `move`.
+///
+/// ```compile_fail
+/// # use vbox::into_vbox;
+/// let s = String::from("foo");
+/// // error: closure may outlive the current function, but it borrows `s`
+/// let _ = into_vbox!(dyn Fn() -> usize, || s.len());
+/// ```
+pub fn payload_must_be_static<T: 'static>(value: T) -> T {
+    value
+}
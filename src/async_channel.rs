@@ -0,0 +1,224 @@
+//! Typed endpoints over an [`async-channel`](https://docs.rs/async-channel)
+//! channel of [`VBox`]es.
+//!
+//! It is enabled by the `async-channel` feature.
+//!
+//! The channel carries erased `VBox`es, so that the channel type does not
+//! depend on `dyn Trait`. The endpoints pack and unpack `Box<dyn Trait>` for
+//! the user, and can be converted to and from the erased endpoints with
+//! `into_erased()` and `from_erased()`, e.g., to store them in a registry.
+//!
+//! ```
+//! # use std::fmt::Debug;
+//! # use vbox::async_channel;
+//! let (tx, rx) = async_channel::bounded::<dyn Debug + Send>(4);
+//!
+//! futures::executor::block_on(async move {
+//!     tx.send(Box::new(3u64)).await.unwrap();
+//!     let got = rx.recv().await.unwrap();
+//!     assert_eq!("3", format!("{:?}", got));
+//! });
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+pub use ::async_channel::RecvError;
+pub use ::async_channel::SendError;
+pub use ::async_channel::TryRecvError;
+pub use ::async_channel::TrySendError;
+
+use crate::VBox;
+
+/// The sending end of a channel of `Box<U>`, where `U` is `dyn Trait`.
+pub struct Sender<U>
+where U: ?Sized + 'static
+{
+    inner: ::async_channel::Sender<VBox>,
+    _p: PhantomData<fn(Box<U>)>,
+}
+
+/// The receiving end of a channel of `Box<U>`, where `U` is `dyn Trait`.
+pub struct Receiver<U>
+where U: ?Sized + 'static
+{
+    inner: ::async_channel::Receiver<VBox>,
+    _p: PhantomData<fn() -> Box<U>>,
+}
+
+/// Create a bounded channel of `Box<U>`.
+///
+/// It panics if `cap` is zero.
+pub fn bounded<U>(cap: usize) -> (Sender<U>, Receiver<U>)
+where U: ?Sized + Send + 'static {
+    let (tx, rx) = ::async_channel::bounded(cap);
+    (Sender::from_erased(tx), Receiver::from_erased(rx))
+}
+
+/// Create an unbounded channel of `Box<U>`.
+pub fn unbounded<U>() -> (Sender<U>, Receiver<U>)
+where U: ?Sized + Send + 'static {
+    let (tx, rx) = ::async_channel::unbounded();
+    (Sender::from_erased(tx), Receiver::from_erased(rx))
+}
+
+impl<U> Sender<U>
+where U: ?Sized + Send + 'static
+{
+    /// Wrap an erased sender. The receiving end must unpack the messages as
+    /// `U`.
+    pub fn from_erased(inner: ::async_channel::Sender<VBox>) -> Self {
+        Sender {
+            inner,
+            _p: PhantomData,
+        }
+    }
+
+    /// Returns the erased sender.
+    pub fn into_erased(self) -> ::async_channel::Sender<VBox> {
+        self.inner
+    }
+
+    /// Send a message, waiting for a free slot if the channel is full.
+    ///
+    /// If the channel is closed, the message is returned in the error.
+    pub async fn send(&self, msg: Box<U>) -> Result<(), SendError<Box<U>>> {
+        let res = self.inner.send(VBox::from_box(msg)).await;
+        res.map_err(|e| SendError(e.0.unpack::<U>()))
+    }
+
+    /// Send a message, blocking the current thread if the channel is full.
+    pub fn send_blocking(&self, msg: Box<U>) -> Result<(), SendError<Box<U>>> {
+        let res = self.inner.send_blocking(VBox::from_box(msg));
+        res.map_err(|e| SendError(e.0.unpack::<U>()))
+    }
+
+    /// Send a message if the channel is not full nor closed.
+    pub fn try_send(&self, msg: Box<U>) -> Result<(), TrySendError<Box<U>>> {
+        let res = self.inner.try_send(VBox::from_box(msg));
+        res.map_err(|e| match e {
+            TrySendError::Full(v) => TrySendError::Full(v.unpack::<U>()),
+            TrySendError::Closed(v) => TrySendError::Closed(v.unpack::<U>()),
+        })
+    }
+
+    /// Close the channel, and return `true` if it is closed by this call.
+    pub fn close(&self) -> bool {
+        self.inner.close()
+    }
+
+    /// Returns `true` if the channel is closed.
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    /// Returns the number of messages in the channel.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if the channel is empty.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl<U> Receiver<U>
+where U: ?Sized + Send + 'static
+{
+    /// Wrap an erased receiver. The sending end must pack the messages as
+    /// `U`.
+    pub fn from_erased(inner: ::async_channel::Receiver<VBox>) -> Self {
+        Receiver {
+            inner,
+            _p: PhantomData,
+        }
+    }
+
+    /// Returns the erased receiver.
+    pub fn into_erased(self) -> ::async_channel::Receiver<VBox> {
+        self.inner
+    }
+
+    /// Receive a message, waiting for one if the channel is empty.
+    ///
+    /// It panics if the message is not packed as `U`.
+    pub async fn recv(&self) -> Result<Box<U>, RecvError> {
+        self.inner.recv().await.map(VBox::unpack::<U>)
+    }
+
+    /// Receive a message, blocking the current thread if the channel is
+    /// empty.
+    pub fn recv_blocking(&self) -> Result<Box<U>, RecvError> {
+        self.inner.recv_blocking().map(VBox::unpack::<U>)
+    }
+
+    /// Receive a message if the channel is not empty.
+    pub fn try_recv(&self) -> Result<Box<U>, TryRecvError> {
+        self.inner.try_recv().map(VBox::unpack::<U>)
+    }
+
+    /// Close the channel, and return `true` if it is closed by this call.
+    pub fn close(&self) -> bool {
+        self.inner.close()
+    }
+
+    /// Returns `true` if the channel is closed.
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    /// Returns the number of messages in the channel.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if the channel is empty.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl<U> Clone for Sender<U>
+where U: ?Sized + 'static
+{
+    fn clone(&self) -> Self {
+        Sender {
+            inner: self.inner.clone(),
+            _p: PhantomData,
+        }
+    }
+}
+
+impl<U> Clone for Receiver<U>
+where U: ?Sized + 'static
+{
+    fn clone(&self) -> Self {
+        Receiver {
+            inner: self.inner.clone(),
+            _p: PhantomData,
+        }
+    }
+}
+
+impl<U> fmt::Debug for Sender<U>
+where U: ?Sized + 'static
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("type", &std::any::type_name::<U>())
+            .field("len", &self.inner.len())
+            .finish()
+    }
+}
+
+impl<U> fmt::Debug for Receiver<U>
+where U: ?Sized + 'static
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("type", &std::any::type_name::<U>())
+            .field("len", &self.inner.len())
+            .finish()
+    }
+}
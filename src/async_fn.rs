@@ -0,0 +1,60 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::VBox;
+
+type BoxFuture<R> = Pin<Box<dyn Future<Output = R> + Send>>;
+
+/// A type erased async closure that is called once, e.g., a handler built with
+/// `async move |x| { ... }`, or with `move |x| async move { ... }`.
+///
+/// The consumer calls it with the argument type `A` and awaits the output type
+/// `R`, without a `BoxFuture` in the signature of the producer. `A` and `R` are
+/// erased, and they must be the same as when it is created.
+///
+/// ```
+/// # use vbox::VAsyncFnOnce;
+/// let prefix = String::from("hello ");
+/// let f = VAsyncFnOnce::new(move |name: String| async move { prefix + name.as_str() });
+///
+/// let got: String = futures::executor::block_on(f.call(String::from("world")));
+/// assert_eq!("hello world", got);
+/// ```
+pub struct VAsyncFnOnce {
+    /// Packed as `dyn FnOnce(A) -> BoxFuture<R> + Send`.
+    inner: VBox,
+}
+
+impl VAsyncFnOnce {
+    /// Erase an async closure.
+    pub fn new<A, R, F, Fut>(f: F) -> Self
+    where
+        A: 'static,
+        R: 'static,
+        F: FnOnce(A) -> Fut + Send + 'static,
+        Fut: Future<Output = R> + Send + 'static,
+    {
+        let boxed = move |a: A| -> BoxFuture<R> { Box::pin(f(a)) };
+        let inner =
+            crate::into_vbox!(dyn FnOnce(A) -> BoxFuture<R> + Send, boxed);
+        VAsyncFnOnce { inner }
+    }
+
+    /// Call the async closure with the argument and return the future of its
+    /// output.
+    pub fn call<A, R>(self, a: A) -> BoxFuture<R>
+    where
+        A: 'static,
+        R: 'static,
+    {
+        let f = self.inner.unpack::<dyn FnOnce(A) -> BoxFuture<R> + Send>();
+        f(a)
+    }
+}
+
+impl fmt::Debug for VAsyncFnOnce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VAsyncFnOnce").field(&self.inner).finish()
+    }
+}
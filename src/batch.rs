@@ -0,0 +1,540 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::future;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::task::Poll;
+use std::task::Waker;
+use std::thread;
+use std::thread::JoinHandle;
+
+use crate::queue::register_waker;
+use crate::ImplKey;
+use crate::TypeKey;
+use crate::VBox;
+
+/// Groups [`VBox`]es by implementation, i.e., by `dyn Trait` and the concrete
+/// type, and feeds each group to its handler in a tight loop.
+///
+/// Dispatching interleaved message types jumps between the code of different
+/// implementations for each message. A `Batcher` buffers the messages with
+/// [`Batcher::push()`], and [`Batcher::flush()`] handles all the messages of
+/// one concrete type before moving to the next, so that the same code path
+/// stays hot. Messages of the same concrete type are handled in the order they
+/// are pushed.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use std::sync::{Arc, Mutex};
+/// # use vbox::{into_vbox, Batcher};
+/// let seen = Arc::new(Mutex::new(Vec::new()));
+///
+/// let mut batcher = Batcher::new();
+/// let s = seen.clone();
+/// batcher.on::<dyn Debug + Send>(move |d| s.lock().unwrap().push(format!("{:?}", d)));
+///
+/// batcher.push(into_vbox!(dyn Debug + Send, 1u64));
+/// batcher.push(into_vbox!(dyn Debug + Send, "a"));
+/// batcher.push(into_vbox!(dyn Debug + Send, 2u64));
+/// batcher.flush();
+///
+/// assert_eq!(vec!["1", "2", r#""a""#], *seen.lock().unwrap());
+/// ```
+#[derive(Default)]
+pub struct Batcher {
+    /// Handlers by the type id of `dyn Trait`, packed as `dyn FnMut(VBox) +
+    /// Send`.
+    handlers: HashMap<TypeId, VBox>,
+
+    /// Handler for messages packed as a trait without a handler.
+    unhandled: Option<VBox>,
+
+    /// Buffered messages, in the order the buckets are first seen.
+    buckets: Vec<(ImplKey, Vec<VBox>)>,
+}
+
+impl Batcher {
+    /// Create a batcher without any handler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the handler for messages packed as `U`, i.e., `dyn Trait`,
+    /// replacing the existing one.
+    pub fn on<U>(&mut self, mut f: impl FnMut(Box<U>) + Send + 'static)
+    where U: ?Sized + 'static {
+        let handler = move |vbox: VBox| f(vbox.unpack::<U>());
+        self.handlers.insert(
+            TypeId::of::<U>(),
+            crate::into_vbox!(dyn FnMut(VBox) + Send, handler),
+        );
+    }
+
+    /// Register the handler for messages packed as a trait without a handler.
+    ///
+    /// Without it, such messages are dropped.
+    pub fn on_unhandled(&mut self, f: impl FnMut(VBox) + Send + 'static) {
+        self.unhandled = Some(crate::into_vbox!(dyn FnMut(VBox) + Send, f));
+    }
+
+    /// Buffer a message until the next [`Batcher::flush()`].
+    pub fn push(&mut self, vbox: VBox) {
+        let key = vbox.impl_key();
+
+        match self.buckets.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, bucket)) => bucket.push(vbox),
+            None => self.buckets.push((key, vec![vbox])),
+        }
+    }
+
+    /// Returns the number of buffered messages.
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(|(_, b)| b.len()).sum()
+    }
+
+    /// Returns `true` if no message is buffered.
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// Handle all the buffered messages, one bucket at a time, and return the
+    /// number of messages handled, including the unhandled ones.
+    pub fn flush(&mut self) -> usize {
+        let mut n = 0;
+
+        for ((type_id, _), bucket) in std::mem::take(&mut self.buckets) {
+            n += bucket.len();
+
+            let handler = match self.handlers.get_mut(&type_id) {
+                Some(h) => h,
+                None => match &mut self.unhandled {
+                    Some(h) => h,
+                    None => continue,
+                },
+            };
+
+            let f = handler.as_dyn_mut::<dyn FnMut(VBox) + Send>();
+            for vbox in bucket {
+                f(vbox);
+            }
+        }
+
+        n
+    }
+}
+
+impl fmt::Debug for Batcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Batcher")
+            .field("handlers", &self.handlers.len())
+            .field("buffered", &self.len())
+            .finish()
+    }
+}
+
+/// How [`BatchPool::shutdown_with()`] stops the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownMode {
+    /// Handle all submitted messages, then wait for the workers to exit. The
+    /// same as [`BatchPool::shutdown()`].
+    FinishQueued,
+
+    /// Let the workers finish the batches they have taken, and wait for them
+    /// to exit. The messages still in the queue are returned.
+    FinishInFlight,
+
+    /// Return the messages still in the queue right away, without waiting for
+    /// the workers. The batches they have taken are still handled, in the
+    /// background.
+    Abort,
+}
+
+/// The queues of a [`BatchPool`]: one shared by all workers, and one for the
+/// messages pinned to each worker.
+struct Queues {
+    shared: VecDeque<VBox>,
+    pinned: Vec<VecDeque<VBox>>,
+
+    /// No message is accepted after it is set.
+    closed: bool,
+
+    /// Set when the workers stop taking queued messages.
+    stopping: bool,
+
+    /// The number of workers that have not exited.
+    alive: usize,
+
+    /// Whether each worker has not exited.
+    running: Vec<bool>,
+
+    /// Messages left behind by workers that panicked, see
+    /// [`BatchPool::take_orphaned()`].
+    orphaned: Vec<VBox>,
+}
+
+impl Queues {
+    fn len(&self) -> usize {
+        self.shared.len() + self.pinned.iter().map(|q| q.len()).sum::<usize>()
+    }
+}
+
+/// The state shared by a [`BatchPool`] and its workers.
+struct State {
+    queues: Mutex<Queues>,
+
+    /// Notified when a message is queued, or the pool is stopping.
+    cond: Condvar,
+
+    /// The number of messages submitted and not yet handled or returned.
+    pending: AtomicUsize,
+
+    /// Tasks waiting for `pending` to become 0.
+    drain_wakers: Mutex<Vec<Waker>>,
+}
+
+impl State {
+    fn done(&self, n: usize) {
+        if n > 0 && self.pending.fetch_sub(n, Ordering::AcqRel) == n {
+            self.drain_wakers.lock().unwrap().drain(..).for_each(Waker::wake);
+        }
+    }
+}
+
+/// Counts a worker as exited when it returns or panics, and releases the
+/// messages a panicking worker leaves behind, so that they are not pending
+/// forever.
+struct Alive {
+    state: Arc<State>,
+    index: usize,
+
+    /// The number of messages taken into the batcher and not yet handled.
+    in_flight: usize,
+}
+
+impl Drop for Alive {
+    fn drop(&mut self) {
+        let mut queues =
+            self.state.queues.lock().unwrap_or_else(|e| e.into_inner());
+        queues.alive -= 1;
+        queues.running[self.index] = false;
+
+        // No other worker handles the messages pinned to this one, or any
+        // message if this is the last one.
+        let mut orphaned =
+            queues.pinned[self.index].drain(..).collect::<Vec<_>>();
+        if queues.alive == 0 {
+            orphaned.extend(queues.shared.drain(..));
+        }
+        let n = orphaned.len();
+        queues.orphaned.extend(orphaned);
+        drop(queues);
+
+        // The batch being handled is lost with the panic.
+        self.state.done(self.in_flight + n);
+    }
+}
+
+/// A pool of worker threads, each of which drains up to `max_batch` messages
+/// at a time into its own [`Batcher`] and flushes it.
+///
+/// Messages are load balanced across the workers, except those pinned to a
+/// worker with [`BatchPool::pin_dyn()`] or [`BatchPool::pin_type()`], which are
+/// always handled by that worker, e.g., so that all storage commands run on
+/// one thread that owns non-`Sync` state. A worker handles its pinned messages
+/// before the shared ones.
+///
+/// It stops with [`BatchPool::shutdown()`], which handles all submitted
+/// messages first, or with [`BatchPool::shutdown_with()`] in another
+/// [`ShutdownMode`]. [`BatchPool::drain()`] waits until every submitted message
+/// is handled without stopping the pool.
+///
+/// If a handler panics, its worker exits, and the rest of the batch it is
+/// handling is dropped. The messages pinned to it, and the shared ones if no
+/// worker is left, are kept for [`BatchPool::take_orphaned()`].
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use std::sync::Arc;
+/// # use std::sync::atomic::{AtomicU64, Ordering};
+/// # use vbox::{into_vbox, BatchPool, Batcher};
+/// let total = Arc::new(AtomicU64::new(0));
+///
+/// let t = total.clone();
+/// let pool = BatchPool::new(2, 16, move || {
+///     let t = t.clone();
+///     let mut b = Batcher::new();
+///     b.on::<dyn Debug + Send>(move |_| {
+///         t.fetch_add(1, Ordering::Relaxed);
+///     });
+///     b
+/// });
+///
+/// for i in 0..10u64 {
+///     pool.submit(into_vbox!(dyn Debug + Send, i)).unwrap();
+/// }
+/// futures::executor::block_on(pool.drain());
+/// assert_eq!(10, total.load(Ordering::Relaxed));
+///
+/// pool.shutdown();
+/// ```
+pub struct BatchPool {
+    state: Arc<State>,
+    workers: Vec<JoinHandle<()>>,
+
+    /// Workers by the type id of `dyn Trait` the messages are packed as.
+    pinned_dyn: HashMap<TypeId, usize>,
+
+    /// Workers by the concrete type of the messages.
+    pinned_type: HashMap<TypeKey, usize>,
+}
+
+impl BatchPool {
+    /// Start `workers` threads. Each of them builds its handlers with
+    /// `make_batcher`.
+    ///
+    /// It panics if `workers` or `max_batch` is zero.
+    pub fn new<F>(workers: usize, max_batch: usize, make_batcher: F) -> Self
+    where F: Fn() -> Batcher + Send + Sync + 'static {
+        assert!(workers > 0, "workers must be positive");
+        assert!(max_batch > 0, "max_batch must be positive");
+
+        let state = Arc::new(State {
+            queues: Mutex::new(Queues {
+                shared: VecDeque::new(),
+                pinned: (0..workers).map(|_| VecDeque::new()).collect(),
+                closed: false,
+                stopping: false,
+                alive: workers,
+                running: vec![true; workers],
+                orphaned: Vec::new(),
+            }),
+            cond: Condvar::new(),
+            pending: AtomicUsize::new(0),
+            drain_wakers: Mutex::new(Vec::new()),
+        });
+        let make_batcher = Arc::new(make_batcher);
+
+        let workers = (0..workers)
+            .map(|index| {
+                let state = state.clone();
+                let make_batcher = make_batcher.clone();
+                thread::spawn(move || {
+                    let mut alive = Alive {
+                        state: state.clone(),
+                        index,
+                        in_flight: 0,
+                    };
+                    let mut batcher = make_batcher();
+                    while Self::fill(&state, index, &mut batcher, max_batch) {
+                        let n = batcher.len();
+                        alive.in_flight = n;
+                        batcher.flush();
+                        alive.in_flight = 0;
+                        state.done(n);
+                    }
+                })
+            })
+            .collect();
+
+        BatchPool {
+            state,
+            workers,
+            pinned_dyn: HashMap::new(),
+            pinned_type: HashMap::new(),
+        }
+    }
+
+    /// Wait for messages and take up to `max_batch` of them into the batcher,
+    /// the ones pinned to the worker first. It returns `false` if the pool is
+    /// stopping, or it is shut down and there is no message left for the
+    /// worker.
+    fn fill(
+        state: &State,
+        index: usize,
+        batcher: &mut Batcher,
+        max_batch: usize,
+    ) -> bool {
+        let mut queues = state.queues.lock().unwrap();
+
+        loop {
+            if queues.stopping {
+                return false;
+            }
+
+            let q = &mut *queues;
+            for queue in [&mut q.pinned[index], &mut q.shared] {
+                while batcher.len() < max_batch {
+                    let Some(vbox) = queue.pop_front() else {
+                        break;
+                    };
+                    batcher.push(vbox);
+                }
+            }
+
+            if !batcher.is_empty() {
+                return true;
+            }
+            if queues.closed {
+                return false;
+            }
+            queues = state.cond.wait(queues).unwrap();
+        }
+    }
+
+    /// Pin the messages packed as `U`, i.e., `dyn Trait`, to the worker at
+    /// `worker`, starting from 0.
+    ///
+    /// It panics if there is no such worker.
+    pub fn pin_dyn<U>(&mut self, worker: usize)
+    where U: ?Sized + 'static {
+        assert!(worker < self.workers.len(), "no such worker: {}", worker);
+        self.pinned_dyn.insert(TypeId::of::<U>(), worker);
+    }
+
+    /// Pin the messages of the concrete type `T` to the worker at `worker`,
+    /// starting from 0. It takes precedence over [`BatchPool::pin_dyn()`].
+    ///
+    /// It panics if there is no such worker.
+    pub fn pin_type<T: 'static>(&mut self, worker: usize) {
+        assert!(worker < self.workers.len(), "no such worker: {}", worker);
+        self.pinned_type.insert(TypeKey::of::<T>(), worker);
+    }
+
+    /// Returns the worker `vbox` is pinned to, if any.
+    pub fn pinned_worker(&self, vbox: &VBox) -> Option<usize> {
+        if let Some(w) = self.pinned_type.get(&TypeKey::of_vbox(vbox)) {
+            return Some(*w);
+        }
+        self.pinned_dyn.get(&vbox.type_id).copied()
+    }
+
+    /// Submit a message to the pool.
+    ///
+    /// If all the workers are gone, e.g., they panicked, or the worker the
+    /// message is pinned to is, the message is returned in `Err`.
+    pub fn submit(&self, vbox: VBox) -> Result<(), VBox> {
+        let pinned = self.pinned_worker(&vbox);
+
+        let mut queues = self.state.queues.lock().unwrap();
+
+        let alive = match pinned {
+            Some(w) => queues.running[w],
+            None => queues.alive > 0,
+        };
+        if !alive {
+            return Err(vbox);
+        }
+
+        match pinned {
+            Some(w) => queues.pinned[w].push_back(vbox),
+            None => queues.shared.push_back(vbox),
+        }
+        self.state.pending.fetch_add(1, Ordering::AcqRel);
+        drop(queues);
+
+        // A pinned message must wake its worker, which may not be the one
+        // `notify_one()` picks.
+        self.state.cond.notify_all();
+        Ok(())
+    }
+
+    /// Returns the number of messages submitted and not yet handled.
+    pub fn pending(&self) -> usize {
+        self.state.pending.load(Ordering::Acquire)
+    }
+
+    /// Wait until every message submitted so far is handled, or is lost or
+    /// orphaned by a worker that panicked.
+    ///
+    /// It does not stop the pool: messages submitted meanwhile are waited for
+    /// too.
+    pub async fn drain(&self) {
+        future::poll_fn(|cx| {
+            let mut wakers = self.state.drain_wakers.lock().unwrap();
+            if self.state.pending.load(Ordering::Acquire) == 0 {
+                return Poll::Ready(());
+            }
+            register_waker(&mut wakers, cx.waker());
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Take the messages left behind by the workers that panicked: the ones
+    /// pinned to such a worker, and the queued ones if no worker is left.
+    ///
+    /// They are not counted as pending any more.
+    pub fn take_orphaned(&self) -> Vec<VBox> {
+        std::mem::take(&mut self.state.queues.lock().unwrap().orphaned)
+    }
+
+    /// Stop accepting messages, wait for the workers to handle the submitted
+    /// ones and exit.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    /// Stop accepting messages, and stop the workers in `mode`.
+    ///
+    /// It returns the messages that are submitted but not taken by any
+    /// worker, which is always empty with [`ShutdownMode::FinishQueued`].
+    pub fn shutdown_with(mut self, mode: ShutdownMode) -> Vec<VBox> {
+        if mode == ShutdownMode::FinishQueued {
+            self.stop();
+            return Vec::new();
+        }
+
+        let mut queues = self.state.queues.lock().unwrap();
+        queues.closed = true;
+        queues.stopping = true;
+
+        let q = &mut *queues;
+        let mut queued = q.shared.drain(..).collect::<Vec<_>>();
+        for p in q.pinned.iter_mut() {
+            queued.extend(p.drain(..));
+        }
+        drop(queues);
+
+        self.state.cond.notify_all();
+        self.state.done(queued.len());
+
+        let workers = self.workers.drain(..).collect::<Vec<_>>();
+        if mode == ShutdownMode::FinishInFlight {
+            for w in workers {
+                let _ = w.join();
+            }
+        }
+        queued
+    }
+
+    fn stop(&mut self) {
+        self.state.queues.lock().unwrap().closed = true;
+        self.state.cond.notify_all();
+
+        for w in self.workers.drain(..) {
+            let _ = w.join();
+        }
+    }
+}
+
+impl Drop for BatchPool {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl fmt::Debug for BatchPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let queued = self.state.queues.lock().unwrap().len();
+        f.debug_struct("BatchPool")
+            .field("workers", &self.workers.len())
+            .field("queued", &queued)
+            .field("pending", &self.pending())
+            .finish()
+    }
+}
@@ -0,0 +1,168 @@
+//! Pair a producer and a consumer with a brand, so that a [`VBox`] from one
+//! pair can not be unpacked by the consumer of another, checked at compile
+//! time.
+//!
+//! [`pair()`] creates a [`Producer`] and a [`Consumer`] of `dyn Trait` that
+//! share a unique brand lifetime `'b`, in the style of `GhostCell`. A
+//! [`Branded<'b>`] built by the producer can only be unpacked by the consumer
+//! with the same brand, and no type check is needed when unpacking.
+//!
+//! ```
+//! # use std::fmt::Debug;
+//! # use vbox::brand;
+//! brand::pair::<dyn Debug + Send, _>(|tx, rx| {
+//!     let msg = tx.pack(Box::new(1u64));
+//!     assert_eq!("1", format!("{:?}", rx.unpack(msg)));
+//! });
+//! ```
+//!
+//! A message of another pair is rejected by the compiler:
+//!
+//! ```compile_fail
+//! # use std::fmt::Debug;
+//! # use vbox::brand;
+//! brand::pair::<dyn Debug + Send, _>(|tx1, _rx1| {
+//!     brand::pair::<dyn Debug + Send, _>(|_tx2, rx2| {
+//!         let msg = tx1.pack(Box::new(1u64));
+//!         // error: borrowed data escapes outside of closure
+//!         rx2.unpack(msg);
+//!     });
+//! });
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::VBox;
+
+/// An invariant lifetime, which can be neither shortened nor extended.
+type Brand<'b> = PhantomData<fn(&'b ()) -> &'b ()>;
+
+/// Call `f` with a producer and a consumer of `U`, i.e., `dyn Trait`, that
+/// share a brand no other pair has.
+pub fn pair<U, R>(
+    f: impl for<'b> FnOnce(Producer<'b, U>, Consumer<'b, U>) -> R,
+) -> R
+where U: ?Sized + 'static {
+    f(
+        Producer {
+            _brand: PhantomData,
+            _u: PhantomData,
+        },
+        Consumer {
+            _brand: PhantomData,
+            _u: PhantomData,
+        },
+    )
+}
+
+/// Packs messages as `U`, i.e., `dyn Trait`, with the brand `'b`.
+pub struct Producer<'b, U: ?Sized + 'static> {
+    _brand: Brand<'b>,
+    _u: PhantomData<fn() -> Box<U>>,
+}
+
+/// Unpacks messages packed by the [`Producer`] with the brand `'b`.
+pub struct Consumer<'b, U: ?Sized + 'static> {
+    _brand: Brand<'b>,
+    _u: PhantomData<fn() -> Box<U>>,
+}
+
+/// A [`VBox`] packed by the [`Producer`] with the brand `'b`.
+pub struct Branded<'b> {
+    vbox: VBox,
+    _brand: Brand<'b>,
+}
+
+impl<'b, U: ?Sized + 'static> Producer<'b, U> {
+    /// Pack a `Box<dyn Trait>` with the brand.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    pub fn pack(&self, boxed: Box<U>) -> Branded<'b>
+    where U: Send {
+        Branded {
+            vbox: VBox::from_box(boxed),
+            _brand: PhantomData,
+        }
+    }
+
+    /// Brand an existing `VBox`, e.g., built with [`into_vbox!`].
+    ///
+    /// If it is not packed as `U`, it is returned intact in `Err`.
+    ///
+    /// [`into_vbox!`]: crate::into_vbox
+    pub fn brand(&self, vbox: VBox) -> Result<Branded<'b>, VBox> {
+        if !vbox.is_dyn::<U>() {
+            return Err(vbox);
+        }
+        Ok(Branded {
+            vbox,
+            _brand: PhantomData,
+        })
+    }
+}
+
+impl<'b, U: ?Sized + 'static> Consumer<'b, U> {
+    /// Unpack a message packed by the producer of the same brand.
+    #[cfg_attr(any(feature = "log", feature = "timeline"), track_caller)]
+    pub fn unpack(&self, msg: Branded<'b>) -> Box<U> {
+        // The brand proves it is packed as `U`.
+        unsafe { msg.vbox.unpack_unchecked::<U>() }
+    }
+
+    /// Borrow the payload of a message as `&dyn Trait`.
+    pub fn as_dyn<'a>(&self, msg: &'a Branded<'b>) -> &'a U {
+        unsafe { msg.vbox.as_dyn_unchecked::<U>() }
+    }
+}
+
+impl<'b> Branded<'b> {
+    /// Returns the `VBox` without the brand.
+    pub fn into_vbox(self) -> VBox {
+        self.vbox
+    }
+}
+
+impl<'b, U: ?Sized + 'static> Clone for Producer<'b, U> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'b, U: ?Sized + 'static> Copy for Producer<'b, U> {}
+
+impl<'b, U: ?Sized + 'static> Clone for Consumer<'b, U> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'b, U: ?Sized + 'static> Copy for Consumer<'b, U> {}
+
+impl<'b, U: ?Sized + 'static> fmt::Debug for Producer<'b, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Producer")
+            .field("type_name", &std::any::type_name::<U>())
+            .finish()
+    }
+}
+
+impl<'b, U: ?Sized + 'static> fmt::Debug for Consumer<'b, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Consumer")
+            .field("type_name", &std::any::type_name::<U>())
+            .finish()
+    }
+}
+
+impl<'b> fmt::Debug for Branded<'b> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Branded").field(&self.vbox).finish()
+    }
+}
@@ -0,0 +1,216 @@
+use std::any::Any;
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::probe::CloneCap;
+use crate::probe::DebugCap;
+use crate::probe::DisplayCap;
+use crate::probe::DisposeCap;
+use crate::probe::FieldsCap;
+use crate::probe::No;
+use crate::probe::Yes;
+use crate::AsyncDispose;
+use crate::AutoHooks;
+use crate::MismatchPolicy;
+use crate::RecordFields;
+use crate::TypeCheck;
+use crate::VBox;
+
+/// Builds a [`VBox`] with the optional capabilities and metadata chosen one by
+/// one, instead of a macro for each combination of them.
+///
+/// It is created with [`VBox::builder()`], which packs the value as `dyn Any +
+/// Send`, or with [`vbox_builder!`](crate::vbox_builder), which packs it as the
+/// given `dyn Trait`.
+///
+/// The chosen capabilities are part of the type of the builder, so that
+/// [`VBoxBuilder::build()`] stores them in a static table as the macros do.
+///
+/// ```
+/// # use vbox::{from_vbox, vbox_builder};
+/// trait Command {
+///     fn run(&self) -> u64;
+/// }
+///
+/// #[derive(Debug, Clone)]
+/// struct Ping(u64);
+///
+/// impl Command for Ping {
+///     fn run(&self) -> u64 {
+///         self.0
+///     }
+/// }
+///
+/// let vbox = vbox_builder!(dyn Command, Ping(3))
+///     .with_debug()
+///     .with_clone()
+///     .tag(7)
+///     .build();
+///
+/// assert_eq!(7, vbox.tag());
+/// assert_eq!(Some("Ping(3)".to_string()), vbox.debug_string());
+///
+/// let copy = vbox.try_clone().unwrap();
+/// assert_eq!(7, copy.tag());
+/// assert_eq!(3, from_vbox!(dyn Command, copy).run());
+/// # let _ = from_vbox!(dyn Command, vbox);
+/// ```
+#[must_use = "call build() to create the VBox"]
+pub struct VBoxBuilder<
+    T,
+    U: ?Sized = dyn Any + Send,
+    D = No,
+    C = No,
+    P = No,
+    X = No,
+    F = No,
+> {
+    value: T,
+    coerce: fn(Box<T>) -> Box<U>,
+    type_check: Option<TypeCheck>,
+    mismatch_policy: Option<MismatchPolicy>,
+    tag: u32,
+    _caps: PhantomData<(D, C, P, X, F)>,
+}
+
+impl<T> VBoxBuilder<T>
+where T: Send + 'static
+{
+    pub(crate) fn new(value: T) -> Self {
+        VBoxBuilder {
+            value,
+            coerce: |b| b,
+            type_check: None,
+            mismatch_policy: None,
+            tag: 0,
+            _caps: PhantomData,
+        }
+    }
+}
+
+impl<T, U, D, C, P, X, F> VBoxBuilder<T, U, D, C, P, X, F>
+where
+    T: Send + 'static,
+    U: ?Sized + 'static,
+{
+    /// Pack the value as `V`, i.e., `dyn Trait`, instead. Do not use it
+    /// directly. Use [`vbox_builder!`](crate::vbox_builder) instead.
+    ///
+    /// # Safety
+    ///
+    /// `coerce` must be an unsizing coercion from `Box<T>` to `Box<dyn
+    /// Trait>`, i.e., it must return the very same box it is given.
+    #[doc(hidden)]
+    pub unsafe fn erase<V>(
+        self,
+        coerce: fn(Box<T>) -> Box<V>,
+    ) -> VBoxBuilder<T, V, D, C, P, X, F>
+    where
+        V: ?Sized + 'static,
+    {
+        VBoxBuilder {
+            value: self.value,
+            coerce,
+            type_check: self.type_check,
+            mismatch_policy: self.mismatch_policy,
+            tag: self.tag,
+            _caps: PhantomData,
+        }
+    }
+
+    /// Capture the `Debug` implementation, for [`VBox::debug_string()`].
+    pub fn with_debug(self) -> VBoxBuilder<T, U, Yes<T>, C, P, X, F>
+    where T: fmt::Debug {
+        self.caps()
+    }
+
+    /// Capture the `Clone` implementation, for [`VBox::try_clone()`].
+    pub fn with_clone(self) -> VBoxBuilder<T, U, D, Yes<T>, P, X, F>
+    where T: Clone {
+        self.caps()
+    }
+
+    /// Capture the `Display` implementation, for [`VBox::display_string()`].
+    pub fn with_display(self) -> VBoxBuilder<T, U, D, C, Yes<T>, X, F>
+    where T: fmt::Display {
+        self.caps()
+    }
+
+    /// Capture the [`AsyncDispose`] implementation, for [`VBox::dispose()`].
+    pub fn with_dispose(self) -> VBoxBuilder<T, U, D, C, P, Yes<T>, F>
+    where T: AsyncDispose {
+        self.caps()
+    }
+
+    /// Capture the [`RecordFields`] implementation, for [`VBox::fields()`].
+    pub fn with_fields(self) -> VBoxBuilder<T, U, D, C, P, X, Yes<T>>
+    where T: RecordFields {
+        self.caps()
+    }
+
+    /// Set the tag of the `VBox`, see [`VBox::tag()`].
+    pub fn tag(mut self, tag: u32) -> Self {
+        self.tag = tag;
+        self
+    }
+
+    /// Set how the `VBox` checks the type to unpack, see
+    /// [`VBox::set_type_check()`].
+    ///
+    /// # Safety
+    ///
+    /// The same as [`VBox::set_type_check()`].
+    pub unsafe fn type_check(mut self, type_check: TypeCheck) -> Self {
+        self.type_check = Some(type_check);
+        self
+    }
+
+    /// Set what the `VBox` does on a type mismatch, see
+    /// [`VBox::set_mismatch_policy()`].
+    pub fn mismatch_policy(mut self, policy: MismatchPolicy) -> Self {
+        self.mismatch_policy = Some(policy);
+        self
+    }
+
+    fn caps<D2, C2, P2, X2, F2>(self) -> VBoxBuilder<T, U, D2, C2, P2, X2, F2> {
+        VBoxBuilder {
+            value: self.value,
+            coerce: self.coerce,
+            type_check: self.type_check,
+            mismatch_policy: self.mismatch_policy,
+            tag: self.tag,
+            _caps: PhantomData,
+        }
+    }
+}
+
+impl<T, U, D, C, P, X, F> VBoxBuilder<T, U, D, C, P, X, F>
+where
+    T: Send + 'static,
+    U: ?Sized + 'static,
+    D: DebugCap,
+    C: CloneCap,
+    P: DisplayCap,
+    X: DisposeCap,
+    F: FieldsCap,
+{
+    /// Create the `VBox`.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    pub fn build(self) -> VBox {
+        // Safe: `coerce` is `|b| b` or is given to `erase()`, whose caller
+        // guarantees it is an unsizing coercion.
+        let mut vbox = unsafe { VBox::new(self.value, self.coerce) };
+        vbox.hooks = AutoHooks::<D, C, P, X, F>::HOOKS;
+        vbox.type_check = self.type_check;
+        vbox.mismatch_policy = self.mismatch_policy;
+        vbox.tag = self.tag;
+        vbox
+    }
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use crate::VBox;
+
+/// A token to signal cancellation, to which erased cleanup callbacks can be
+/// registered.
+///
+/// It is cheap to clone, all clones share the same state. It is `Send + Sync +
+/// 'static`, thus it can be packed into a message, so that a downstream
+/// handler can react to the cancellation without knowing what to clean up.
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use std::sync::atomic::{AtomicBool, Ordering};
+/// # use vbox::CancelToken;
+/// let token = CancelToken::new();
+/// let closed = Arc::new(AtomicBool::new(false));
+///
+/// let c = closed.clone();
+/// token.on_cancel(move || c.store(true, Ordering::Relaxed));
+///
+/// token.clone().cancel();
+/// assert!(token.is_canceled());
+/// assert!(closed.load(Ordering::Relaxed));
+/// ```
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    canceled: bool,
+
+    /// Callbacks packed as `dyn FnOnce() + Send`.
+    callbacks: Vec<VBox>,
+}
+
+impl CancelToken {
+    /// Create a token that is not canceled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if [`CancelToken::cancel()`] is called on this token or
+    /// any of its clones.
+    pub fn is_canceled(&self) -> bool {
+        self.inner.lock().unwrap().canceled
+    }
+
+    /// Cancel the token and run the registered callbacks in the order they are
+    /// registered.
+    ///
+    /// Calling it more than once has no effect.
+    pub fn cancel(&self) {
+        let callbacks = {
+            let mut inner = self.inner.lock().unwrap();
+            if inner.canceled {
+                return;
+            }
+            inner.canceled = true;
+            mem::take(&mut inner.callbacks)
+        };
+
+        // Run callbacks without holding the lock, a callback may access this
+        // token.
+        for cb in callbacks {
+            let f = cb.unpack::<dyn FnOnce() + Send>();
+            f();
+        }
+    }
+
+    /// Register a callback to run when the token is canceled.
+    ///
+    /// If the token is already canceled, the callback runs at once.
+    pub fn on_cancel(&self, f: impl FnOnce() + Send + 'static) {
+        self.add(crate::into_vbox!(dyn FnOnce() + Send, f));
+    }
+
+    /// Register an erased callback to run when the token is canceled.
+    ///
+    /// The `callback` must be packed as `dyn FnOnce() + Send`, otherwise it is
+    /// returned in `Err` right away, rather than failing at cancel time. If the
+    /// token is already canceled, the callback runs at once.
+    pub fn register(&self, callback: VBox) -> Result<(), VBox> {
+        if !callback.is_dyn::<dyn FnOnce() + Send>() {
+            return Err(callback);
+        }
+        self.add(callback);
+        Ok(())
+    }
+
+    /// Add a callback packed as `dyn FnOnce() + Send`, or run it if the token
+    /// is already canceled.
+    fn add(&self, callback: VBox) {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            if !inner.canceled {
+                inner.callbacks.push(callback);
+                return;
+            }
+        }
+
+        let f = callback.unpack::<dyn FnOnce() + Send>();
+        f();
+    }
+}
+
+impl fmt::Debug for CancelToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.lock().unwrap();
+        f.debug_struct("CancelToken")
+            .field("canceled", &inner.canceled)
+            .field("callbacks", &inner.callbacks.len())
+            .finish()
+    }
+}
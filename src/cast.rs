@@ -0,0 +1,142 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::fmt;
+use std::ptr::NonNull;
+use std::sync::OnceLock;
+use std::sync::RwLock;
+
+use crate::drop_in_place_raw_parts;
+use crate::from_raw_parts;
+use crate::split_raw_parts;
+use crate::VBox;
+
+/// A registry of casts between trait objects, a `QueryInterface` for
+/// [`VBox`].
+///
+/// A cast from a concrete type `T` to `dyn Trait` is registered with
+/// [`register_cast!`](crate::register_cast). Then a `VBox` of a `T`, packed as
+/// any trait object, can be queried whether it supports `dyn Trait`, and be
+/// cast to it.
+///
+/// A `VBox` built with [`into_vbox_dyn!`](crate::into_vbox_dyn) can not be
+/// cast, since the concrete type is unknown.
+///
+/// ```
+/// # use std::fmt::{Debug, Display};
+/// # use vbox::{from_vbox, into_vbox, register_cast, CastRegistry, VBox};
+/// let registry = CastRegistry::new();
+/// register_cast!(registry, u64 => dyn Display);
+///
+/// let vbox: VBox = into_vbox!(dyn Debug, 10u64);
+/// assert!(registry.supports::<dyn Display>(&vbox));
+///
+/// let vbox = registry.cast::<dyn Display>(vbox).unwrap();
+/// assert_eq!("10", from_vbox!(dyn Display, vbox).to_string());
+/// ```
+#[derive(Default)]
+pub struct CastRegistry {
+    /// The vtable of `dyn Trait` for a concrete type, keyed by the type id of
+    /// the concrete type and the type id of `dyn Trait`.
+    vtables: RwLock<HashMap<(TypeId, TypeId), usize>>,
+}
+
+impl CastRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide registry.
+    pub fn global() -> &'static CastRegistry {
+        static GLOBAL: OnceLock<CastRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(CastRegistry::new)
+    }
+
+    /// Register a cast from the concrete type `T` to `dyn Trait`. Do not use it
+    /// directly. Use [`register_cast!`](crate::register_cast) instead.
+    ///
+    /// # Safety
+    ///
+    /// `coerce` must be an unsizing coercion from `*mut T` to `*mut dyn
+    /// Trait`, i.e., it must return the very same pointer it is given.
+    pub unsafe fn register<T, U>(&self, coerce: fn(*mut T) -> *mut U)
+    where
+        T: 'static,
+        U: ?Sized + 'static,
+    {
+        // Only the vtable is used, the data pointer is never dereferenced.
+        let fat_ptr = coerce(NonNull::<T>::dangling().as_ptr());
+        let (_data, vtable) = split_raw_parts(fat_ptr);
+
+        let key = (TypeId::of::<T>(), TypeId::of::<U>());
+        self.vtables.write().unwrap().insert(key, vtable);
+    }
+
+    /// Returns `true` if the payload of `vbox` can be cast to `dyn Trait`.
+    pub fn supports<U>(&self, vbox: &VBox) -> bool
+    where U: ?Sized + 'static {
+        self.vtable_for::<U>(vbox).is_some()
+    }
+
+    /// Borrow the payload of `vbox` as `&dyn Trait`, if the cast is registered.
+    pub fn cast_ref<'a, U>(&self, vbox: &'a VBox) -> Option<&'a U>
+    where U: ?Sized + 'static {
+        let vtable = self.vtable_for::<U>(vbox)?;
+        Some(unsafe { &*from_raw_parts::<U>(vbox.data, vtable) })
+    }
+
+    /// Borrow the payload of `vbox` as `&mut dyn Trait`, if the cast is
+    /// registered.
+    pub fn cast_mut<'a, U>(&self, vbox: &'a mut VBox) -> Option<&'a mut U>
+    where U: ?Sized + 'static {
+        let vtable = self.vtable_for::<U>(vbox)?;
+        Some(unsafe { &mut *from_raw_parts::<U>(vbox.data, vtable) })
+    }
+
+    /// Re-pack the payload of `vbox` as `dyn Trait`, without moving it.
+    ///
+    /// If the cast is not registered, the `VBox` is returned intact in `Err`.
+    pub fn cast<U>(&self, mut vbox: VBox) -> Result<VBox, VBox>
+    where U: ?Sized + 'static {
+        let Some(vtable) = self.vtable_for::<U>(&vbox) else {
+            return Err(vbox);
+        };
+
+        vbox.vtable = vtable;
+        vbox.type_id = TypeId::of::<U>();
+        vbox.type_name = std::any::type_name::<U>;
+        vbox.drop_fn = drop_in_place_raw_parts::<U>;
+        Ok(vbox)
+    }
+
+    fn vtable_for<U>(&self, vbox: &VBox) -> Option<usize>
+    where U: ?Sized + 'static {
+        let key = (vbox.concrete_type_id?, TypeId::of::<U>());
+        self.vtables.read().unwrap().get(&key).copied()
+    }
+}
+
+impl fmt::Debug for CastRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let casts = self.vtables.read().unwrap().len();
+        f.debug_struct("CastRegistry").field("casts", &casts).finish()
+    }
+}
+
+/// Register a cast from a concrete type `T` to `dyn Trait` in a
+/// [`CastRegistry`].
+///
+/// ```
+/// # use std::fmt::Display;
+/// # use vbox::{register_cast, CastRegistry};
+/// register_cast!(CastRegistry::global(), u64 => dyn Display);
+/// ```
+#[macro_export]
+macro_rules! register_cast {
+    ($registry: expr, $t: ty => $u: ty) => {{
+        let registry: &$crate::CastRegistry = &$registry;
+        unsafe {
+            registry.register::<$t, $u>(|p| -> *mut $u { p });
+        }
+    }};
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+use std::panic::Location;
+use std::sync::Arc;
+use std::sync::OnceLock;
+
+use crate::MismatchPolicy;
+use crate::TypeCheck;
+use crate::TypeMismatch;
+
+/// Called with the mismatch before a type check panics.
+type MismatchFn = dyn Fn(&TypeMismatch) + Send + Sync;
+
+static CONFIG: OnceLock<VBoxConfig> = OnceLock::new();
+
+/// The process wide configuration of this crate, set once at startup with
+/// [`VBoxConfig::install()`], so that a large application configures the
+/// behavior in one place instead of calling the setters of every module.
+///
+/// An option of a module that is not enabled by its feature is ignored, e.g.,
+/// [`VBoxConfig::metrics()`] without the `stats` feature.
+///
+/// ```
+/// # use vbox::{TypeCheck, VBoxConfig};
+/// // Safety: `TypeCheck::TypeId` is the default and trusts no type name.
+/// unsafe { VBoxConfig::new().type_check(TypeCheck::TypeId) }
+///     .on_mismatch(|m| eprintln!("VBox type mismatch: {}", m))
+///     .metrics(false)
+///     .capture_location(false)
+///     .install()
+///     .unwrap();
+///
+/// assert!(!VBoxConfig::current().unwrap().is_metrics());
+/// ```
+#[derive(Clone)]
+pub struct VBoxConfig {
+    type_check: Option<TypeCheck>,
+    mismatch_policy: Option<MismatchPolicy>,
+    on_mismatch: Option<Arc<MismatchFn>>,
+    metrics: bool,
+    capture_location: bool,
+    recycle_capacity: Option<usize>,
+    timeline_capacity: Option<usize>,
+}
+
+impl Default for VBoxConfig {
+    fn default() -> Self {
+        VBoxConfig {
+            type_check: None,
+            mismatch_policy: None,
+            on_mismatch: None,
+            metrics: true,
+            capture_location: true,
+            recycle_capacity: None,
+            timeline_capacity: None,
+        }
+    }
+}
+
+impl VBoxConfig {
+    /// Create a configuration with the defaults, which is the behavior
+    /// without any configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How to check the type to unpack, for every `VBox` that does not set its
+    /// own, see [`TypeCheck::set_global()`]. If it is not set, the global mode
+    /// is left as is.
+    ///
+    /// # Safety
+    ///
+    /// The same as [`TypeCheck::set_global()`].
+    pub unsafe fn type_check(mut self, type_check: TypeCheck) -> Self {
+        self.type_check = Some(type_check);
+        self
+    }
+
+    /// What to do on a type mismatch, for every `VBox` that does not set its
+    /// own, see [`MismatchPolicy::set_global()`]. If it is not set, the
+    /// global policy is left as is.
+    pub fn mismatch_policy(mut self, policy: MismatchPolicy) -> Self {
+        self.mismatch_policy = Some(policy);
+        self
+    }
+
+    /// Call `f` when a type check fails, e.g., in [`from_vbox!`], before it
+    /// panics or aborts, e.g., to report to the monitoring.
+    ///
+    /// The panic-free forms such as [`VBox::try_unpack()`] do not call it.
+    ///
+    /// [`from_vbox!`]: crate::from_vbox
+    /// [`VBox::try_unpack()`]: crate::VBox::try_unpack
+    pub fn on_mismatch(
+        mut self,
+        f: impl Fn(&TypeMismatch) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_mismatch = Some(Arc::new(f));
+        self
+    }
+
+    /// Whether to update the counters of the `stats` module. It is on by
+    /// default.
+    pub fn metrics(mut self, on: bool) -> Self {
+        self.metrics = on;
+        self
+    }
+
+    /// Whether the events of the `log` feature include the call site. It is
+    /// on by default.
+    pub fn capture_location(mut self, on: bool) -> Self {
+        self.capture_location = on;
+        self
+    }
+
+    /// The number of allocations the `recycle` module keeps per layout on each
+    /// thread.
+    pub fn recycle_capacity(mut self, capacity: usize) -> Self {
+        self.recycle_capacity = Some(capacity);
+        self
+    }
+
+    /// The number of events the `timeline` module keeps.
+    pub fn timeline_capacity(mut self, capacity: usize) -> Self {
+        self.timeline_capacity = Some(capacity);
+        self
+    }
+
+    /// Install the configuration for the process.
+    ///
+    /// It can be installed only once. If one is already installed, this one
+    /// is returned in `Err`.
+    pub fn install(self) -> Result<(), VBoxConfig> {
+        CONFIG.set(self)?;
+        let config = CONFIG.get().unwrap();
+
+        if let Some(type_check) = config.type_check {
+            // Safety: it is the caller of `type_check()` who takes the
+            // responsibility.
+            unsafe { TypeCheck::set_global(type_check) };
+        }
+
+        if let Some(policy) = config.mismatch_policy {
+            MismatchPolicy::set_global(policy);
+        }
+
+        #[cfg(feature = "recycle")]
+        if let Some(capacity) = config.recycle_capacity {
+            crate::recycle::set_capacity(capacity);
+        }
+
+        #[cfg(feature = "timeline")]
+        if let Some(capacity) = config.timeline_capacity {
+            crate::timeline::set_capacity(capacity);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the installed configuration, if any.
+    pub fn current() -> Option<&'static VBoxConfig> {
+        CONFIG.get()
+    }
+
+    /// Returns whether the counters of the `stats` module are updated.
+    pub fn is_metrics(&self) -> bool {
+        self.metrics
+    }
+
+    /// Returns whether the events of the `log` feature include the call site.
+    pub fn is_capture_location(&self) -> bool {
+        self.capture_location
+    }
+}
+
+impl fmt::Debug for VBoxConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VBoxConfig")
+            .field("type_check", &self.type_check)
+            .field("mismatch_policy", &self.mismatch_policy)
+            .field("on_mismatch", &self.on_mismatch.is_some())
+            .field("metrics", &self.metrics)
+            .field("capture_location", &self.capture_location)
+            .field("recycle_capacity", &self.recycle_capacity)
+            .field("timeline_capacity", &self.timeline_capacity)
+            .finish()
+    }
+}
+
+/// Report a failed type check to the installed mismatch handler, if any.
+pub(crate) fn mismatch(m: &TypeMismatch) {
+    if let Some(f) = CONFIG.get().and_then(|c| c.on_mismatch.as_ref()) {
+        f(m);
+    }
+}
+
+/// Returns `true` unless metrics are turned off.
+#[cfg_attr(not(feature = "stats"), allow(dead_code))]
+pub(crate) fn metrics() -> bool {
+    match CONFIG.get() {
+        Some(c) => c.metrics,
+        None => true,
+    }
+}
+
+/// The call site in a log event: ` at <location>`, or nothing if the capture
+/// is turned off.
+#[cfg_attr(not(feature = "log"), allow(dead_code))]
+pub(crate) struct Site(pub(crate) &'static Location<'static>);
+
+impl fmt::Display for Site {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let capture = match CONFIG.get() {
+            Some(c) => c.capture_location,
+            None => true,
+        };
+
+        if capture {
+            write!(f, " at {}", self.0)?;
+        }
+        Ok(())
+    }
+}
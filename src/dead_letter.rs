@@ -0,0 +1,251 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::Dump;
+use crate::DumpEntry;
+use crate::VBox;
+use crate::VError;
+
+/// Why a message became a [`DeadLetter`].
+#[derive(Debug)]
+pub enum DeadLetterReason {
+    /// No handler is registered for the message.
+    NoHandler,
+
+    /// The message is not packed as the expected trait object.
+    TypeMismatch {
+        expected: &'static str,
+        actual: &'static str,
+    },
+
+    /// A handler or a validation stage rejected the message.
+    Rejected(VError),
+}
+
+impl fmt::Display for DeadLetterReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeadLetterReason::NoHandler => write!(f, "no handler"),
+            DeadLetterReason::TypeMismatch { expected, actual } => {
+                write!(f, "packed as: {}, expected: {}", actual, expected)
+            }
+            DeadLetterReason::Rejected(e) => write!(f, "rejected: {}", e),
+        }
+    }
+}
+
+/// A message that could not be delivered, kept intact with why and when.
+#[derive(Debug)]
+pub struct DeadLetter {
+    /// The sequence number in the queue, starting from 0, counting evicted
+    /// ones.
+    pub seq: u64,
+
+    /// When it is pushed into the queue.
+    pub at: SystemTime,
+
+    /// Why it could not be delivered.
+    pub reason: DeadLetterReason,
+
+    /// The undelivered message.
+    pub vbox: VBox,
+}
+
+impl DeadLetter {
+    /// Returns the summary of the message produced by the inspector
+    /// registered for its concrete type, see [`crate::inspector`].
+    pub fn summary(&self) -> Option<String> {
+        self.vbox.summary()
+    }
+}
+
+/// Formats as `#<seq> <type>: <reason>`, followed by the summary of the
+/// message in parentheses if an inspector of it is registered.
+impl fmt::Display for DeadLetter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let type_name = match self.vbox.concrete_type_name {
+            Some(name) => name(),
+            None => (self.vbox.type_name)(),
+        };
+        write!(f, "#{} {}: {}", self.seq, type_name, self.reason)?;
+        if let Some(summary) = self.summary() {
+            write!(f, " ({})", summary)?;
+        }
+        Ok(())
+    }
+}
+
+struct Inner {
+    letters: VecDeque<DeadLetter>,
+    pushed: u64,
+    evicted: u64,
+}
+
+/// A bounded queue of the messages a dispatch failed on, e.g., without a
+/// handler, of a wrong trait, or rejected by validation, kept for inspection
+/// instead of being dropped or panicking.
+///
+/// When it is full, the oldest letter is evicted to make room for a new one.
+/// It can be shared between threads, e.g., in an `Arc`.
+///
+/// ```
+/// # use std::fmt::{Debug, Display};
+/// # use vbox::{into_vbox, DeadLetterQueue, TypeDispatcher};
+/// let dlq = DeadLetterQueue::new(16);
+///
+/// let mut d = TypeDispatcher::new();
+/// d.on(|_: Box<u64>| {});
+///
+/// if let Err(vbox) = d.dispatch(into_vbox!(dyn Debug, "x")) {
+///     dlq.no_handler(vbox);
+/// }
+///
+/// let vbox = into_vbox!(dyn Debug, 1u64);
+/// if !vbox.is_dyn::<dyn Display>() {
+///     dlq.type_mismatch::<dyn Display>(vbox);
+/// }
+///
+/// let reasons = dlq.inspect(|l| l.reason.to_string());
+/// assert_eq!("no handler", reasons[0]);
+/// assert!(reasons[1].starts_with("packed as: dyn core::fmt::Debug"));
+/// ```
+pub struct DeadLetterQueue {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+impl DeadLetterQueue {
+    /// Create a queue that keeps at most `capacity` letters.
+    ///
+    /// It panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be at least 1");
+        DeadLetterQueue {
+            capacity,
+            inner: Mutex::new(Inner {
+                letters: VecDeque::with_capacity(capacity),
+                pushed: 0,
+                evicted: 0,
+            }),
+        }
+    }
+
+    /// Push an undelivered message, and return the evicted oldest letter if
+    /// the queue is full.
+    pub fn push(
+        &self,
+        vbox: VBox,
+        reason: DeadLetterReason,
+    ) -> Option<DeadLetter> {
+        let mut inner = self.inner.lock().unwrap();
+
+        let letter = DeadLetter {
+            seq: inner.pushed,
+            at: SystemTime::now(),
+            reason,
+            vbox,
+        };
+        inner.pushed += 1;
+
+        let evicted = if inner.letters.len() >= self.capacity {
+            inner.evicted += 1;
+            inner.letters.pop_front()
+        } else {
+            None
+        };
+
+        inner.letters.push_back(letter);
+        evicted
+    }
+
+    /// Push a message that no handler is registered for.
+    pub fn no_handler(&self, vbox: VBox) -> Option<DeadLetter> {
+        self.push(vbox, DeadLetterReason::NoHandler)
+    }
+
+    /// Push a message that is expected to be packed as `U`, i.e., `dyn
+    /// Trait`, but is not.
+    pub fn type_mismatch<U>(&self, vbox: VBox) -> Option<DeadLetter>
+    where U: ?Sized + 'static {
+        let reason = DeadLetterReason::TypeMismatch {
+            expected: std::any::type_name::<U>(),
+            actual: (vbox.type_name)(),
+        };
+        self.push(vbox, reason)
+    }
+
+    /// Push a message rejected with `err`.
+    pub fn rejected(&self, vbox: VBox, err: VError) -> Option<DeadLetter> {
+        self.push(vbox, DeadLetterReason::Rejected(err))
+    }
+
+    /// Remove and return the oldest letter.
+    pub fn pop(&self) -> Option<DeadLetter> {
+        self.inner.lock().unwrap().letters.pop_front()
+    }
+
+    /// Remove and return all letters, oldest first, e.g., to replay them.
+    pub fn drain(&self) -> Vec<DeadLetter> {
+        self.inner.lock().unwrap().letters.drain(..).collect()
+    }
+
+    /// Call `f` with every letter, oldest first, without removing them, and
+    /// return the results.
+    pub fn inspect<R>(&self, f: impl FnMut(&DeadLetter) -> R) -> Vec<R> {
+        self.inner.lock().unwrap().letters.iter().map(f).collect()
+    }
+
+    /// Returns a snapshot of the letters, oldest first, tagged with why they
+    /// could not be delivered.
+    pub fn dump(&self) -> Dump {
+        let inner = self.inner.lock().unwrap();
+        let entries = inner
+            .letters
+            .iter()
+            .map(|l| {
+                let age = l.at.elapsed().ok();
+                DumpEntry::new(&l.vbox, Some(l.reason.to_string()), age)
+            })
+            .collect();
+        Dump::new("DeadLetterQueue", entries)
+    }
+
+    /// Returns the number of letters in the queue.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().letters.len()
+    }
+
+    /// Returns `true` if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum number of letters kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of letters ever pushed.
+    pub fn pushed(&self) -> u64 {
+        self.inner.lock().unwrap().pushed
+    }
+
+    /// Returns the number of letters evicted because the queue is full.
+    pub fn evicted(&self) -> u64 {
+        self.inner.lock().unwrap().evicted
+    }
+}
+
+impl fmt::Debug for DeadLetterQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.lock().unwrap();
+        f.debug_struct("DeadLetterQueue")
+            .field("capacity", &self.capacity)
+            .field("len", &inner.letters.len())
+            .field("pushed", &inner.pushed)
+            .field("evicted", &inner.evicted)
+            .finish()
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::VBox;
+
+/// The error returned by an RPC handler.
+pub type HandlerError = Box<dyn Error + Send + Sync>;
+
+/// Routes calls to handlers registered under string names, taking and
+/// returning erased values.
+///
+/// A handler is a `Fn(VBox) -> Result<VBox, HandlerError>`. The caller and the
+/// handler agree on what the arguments and the result are, the dispatcher does
+/// not need to know.
+///
+/// ```
+/// # use std::any::Any;
+/// # use vbox::{into_vbox, Dispatcher};
+/// let mut dispatcher = Dispatcher::new();
+/// dispatcher.register("add", |args| {
+///     let (a, b) = args.into_inner::<(u64, u64)>().map_err(|_| "bad args")?;
+///     Ok(into_vbox!(dyn Any + Send, a + b))
+/// });
+///
+/// let res = dispatcher.dispatch("add", into_vbox!(dyn Any + Send, (1u64, 2u64)));
+/// assert_eq!(3u64, res.unwrap().into_inner::<u64>().unwrap());
+/// ```
+#[derive(Default)]
+pub struct Dispatcher {
+    /// Handlers packed as `dyn Fn(VBox) -> Result<VBox, HandlerError> + Send`.
+    handlers: BTreeMap<String, VBox>,
+}
+
+/// The error returned by [`Dispatcher::dispatch()`].
+pub enum DispatchError {
+    /// No handler is registered under the name. The arguments are returned.
+    NotFound { name: String, args: VBox },
+
+    /// The handler returned an error.
+    Handler(HandlerError),
+}
+
+impl fmt::Debug for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DispatchError::NotFound { name, .. } => {
+                f.debug_struct("NotFound").field("name", name).finish()
+            }
+            DispatchError::Handler(e) => {
+                f.debug_tuple("Handler").field(e).finish()
+            }
+        }
+    }
+}
+
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DispatchError::NotFound { name, .. } => {
+                write!(f, "no handler registered under: {}", name)
+            }
+            DispatchError::Handler(e) => write!(f, "handler error: {}", e),
+        }
+    }
+}
+
+impl Error for DispatchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DispatchError::NotFound { .. } => None,
+            DispatchError::Handler(e) => Some(e.as_ref()),
+        }
+    }
+}
+
+impl Dispatcher {
+    /// Create a dispatcher without any handler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler under `name`, replacing the existing one.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        f: impl Fn(VBox) -> Result<VBox, HandlerError> + Send + 'static,
+    ) {
+        let f = crate::into_vbox!(
+            dyn Fn(VBox) -> Result<VBox, HandlerError> + Send,
+            f
+        );
+        self.handlers.insert(name.into(), f);
+    }
+
+    /// Register an erased handler under `name`, replacing the existing one.
+    ///
+    /// The `handler` must be packed as `dyn Fn(VBox) -> Result<VBox,
+    /// HandlerError> + Send`, otherwise it is returned in `Err` right away,
+    /// rather than failing at dispatch time.
+    pub fn register_vbox(
+        &mut self,
+        name: impl Into<String>,
+        handler: VBox,
+    ) -> Result<(), VBox> {
+        if !handler
+            .is_dyn::<dyn Fn(VBox) -> Result<VBox, HandlerError> + Send>()
+        {
+            return Err(handler);
+        }
+        self.handlers.insert(name.into(), handler);
+        Ok(())
+    }
+
+    /// Remove the handler registered under `name`, and return it.
+    pub fn unregister(&mut self, name: &str) -> Option<VBox> {
+        self.handlers.remove(name)
+    }
+
+    /// Returns `true` if a handler is registered under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.handlers.contains_key(name)
+    }
+
+    /// Returns the names of the registered handlers, in sorted order.
+    pub fn names(&self) -> Vec<&str> {
+        self.handlers.keys().map(|s| s.as_str()).collect()
+    }
+
+    /// Call the handler registered under `name` with `args`, and return its
+    /// result.
+    // The error carries the unhandled `args` back, which is as large as a
+    // `VBox`.
+    #[allow(clippy::result_large_err)]
+    pub fn dispatch(
+        &self,
+        name: &str,
+        args: VBox,
+    ) -> Result<VBox, DispatchError> {
+        let Some(handler) = self.handlers.get(name) else {
+            return Err(DispatchError::NotFound {
+                name: name.to_string(),
+                args,
+            });
+        };
+
+        let f = handler
+            .as_dyn::<dyn Fn(VBox) -> Result<VBox, HandlerError> + Send>();
+        f(args).map_err(DispatchError::Handler)
+    }
+}
+
+impl fmt::Debug for Dispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dispatcher").field("names", &self.names()).finish()
+    }
+}
@@ -0,0 +1,32 @@
+use crate::BoxFuture;
+
+/// A payload that needs async cleanup, e.g., a connection that has to send a
+/// goodbye or flush a buffer before it is closed.
+///
+/// A `VBox` built with [`into_vbox_disposable!`](crate::into_vbox_disposable)
+/// captures this implementation at pack time, and [`VBox::dispose()`] runs it
+/// without knowing the concrete type. If the `VBox` is dropped instead, the
+/// payload is dropped synchronously as usual, so `Drop` should still release
+/// what it can.
+///
+/// [`VBox::dispose()`]: crate::VBox::dispose
+pub trait AsyncDispose: Send + 'static {
+    /// Consume the value and clean it up.
+    fn dispose(self) -> BoxFuture<'static, ()>;
+}
+
+/// Disposes the payload at the data pointer.
+pub(crate) type DisposeFn = unsafe fn(*mut ()) -> BoxFuture<'static, ()>;
+
+/// Move the payload out of `data` and return the future that disposes it.
+///
+/// # Safety
+///
+/// `data` must point to a valid `T`, which must not be used or dropped
+/// afterwards.
+pub(crate) unsafe fn dispose_raw<T: AsyncDispose>(
+    data: *mut (),
+) -> BoxFuture<'static, ()> {
+    let value = std::ptr::read(data as *mut T);
+    value.dispose()
+}
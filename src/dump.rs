@@ -0,0 +1,116 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::VBox;
+
+/// A snapshot of the messages pending in a queue or a mailbox of this crate,
+/// for an admin endpoint or a panic hook, e.g., [`VQueue::dump()`].
+///
+/// It displays as a header followed by one line per message, in delivery
+/// order:
+///
+/// ```text
+/// VQueue: 2 pending, 24 bytes
+///   #0 u64 as dyn core::fmt::Debug + core::marker::Send, 8 bytes, age 1.2ms
+///   #1 &str as dyn core::fmt::Debug + core::marker::Send, 16 bytes, age 803µs
+/// ```
+///
+/// [`VQueue::dump()`]: crate::VQueue::dump
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dump {
+    /// The name of the queue type, e.g., `VQueue`.
+    pub name: &'static str,
+
+    /// The pending messages, in delivery order.
+    pub entries: Vec<DumpEntry>,
+}
+
+/// A pending message in a [`Dump`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpEntry {
+    /// The name of the concrete type, or of the trait object if the concrete
+    /// type is unknown.
+    pub type_name: &'static str,
+
+    /// The name of the trait object the message is packed as.
+    pub packed_as: &'static str,
+
+    /// Where the message is queued, e.g., the priority or the shard, if the
+    /// queue has such a notion.
+    pub tag: Option<String>,
+
+    /// How long the message has been queued, if the queue records it.
+    pub age: Option<Duration>,
+
+    /// The size of the payload, see [`VBox::size_of_payload()`].
+    pub size: usize,
+
+    /// The summary of the payload by a registered inspector, see
+    /// [`crate::inspector`].
+    pub summary: Option<String>,
+}
+
+impl Dump {
+    pub(crate) fn new(name: &'static str, entries: Vec<DumpEntry>) -> Self {
+        Dump { name, entries }
+    }
+
+    /// Returns the total size of the payloads.
+    pub fn bytes(&self) -> usize {
+        self.entries.iter().map(|e| e.size).sum()
+    }
+}
+
+impl DumpEntry {
+    pub(crate) fn new(
+        vbox: &VBox,
+        tag: Option<String>,
+        age: Option<Duration>,
+    ) -> Self {
+        let packed_as = (vbox.type_name)();
+        DumpEntry {
+            type_name: vbox.concrete_type_name.map_or(packed_as, |f| f()),
+            packed_as,
+            tag,
+            age,
+            size: vbox.size_of_payload(),
+            summary: vbox.summary(),
+        }
+    }
+}
+
+impl fmt::Display for Dump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} pending, {} bytes",
+            self.name,
+            self.entries.len(),
+            self.bytes()
+        )?;
+        for (i, e) in self.entries.iter().enumerate() {
+            write!(f, "\n  #{} {}", i, e)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DumpEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.type_name)?;
+        if self.type_name != self.packed_as {
+            write!(f, " as {}", self.packed_as)?;
+        }
+        if let Some(tag) = &self.tag {
+            write!(f, " [{}]", tag)?;
+        }
+        write!(f, ", {} bytes", self.size)?;
+        if let Some(age) = self.age {
+            write!(f, ", age {:?}", age)?;
+        }
+        if let Some(summary) = &self.summary {
+            write!(f, " ({})", summary)?;
+        }
+        Ok(())
+    }
+}
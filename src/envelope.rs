@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use crate::VBox;
+use crate::VOnce;
+use crate::VOnceReceiver;
+
+/// A message carrying a [`VBox`] body, with a correlation id and an optional
+/// channel to send back the response.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{from_vbox, into_vbox, Envelope, VBox};
+/// let (req, rx) = Envelope::request(into_vbox!(dyn Debug, "ping"));
+///
+/// // The responder
+/// let body = from_vbox!(dyn Debug, req.body);
+/// req.reply_to.unwrap().send(into_vbox!(dyn Debug, format!("{:?}", body))).unwrap();
+///
+/// let resp = rx.recv().unwrap();
+/// assert_eq!(r#""\"ping\"""#, format!("{:?}", from_vbox!(dyn Debug, resp)));
+/// ```
+#[derive(Debug)]
+pub struct Envelope {
+    /// The correlation id, unique in this process.
+    pub id: u64,
+
+    /// Where to send the response, if a response is expected.
+    pub reply_to: Option<VOnce>,
+
+    /// The message body.
+    pub body: VBox,
+
+    /// Application defined metadata, such as the sender or a trace id.
+    pub meta: BTreeMap<String, String>,
+
+    /// The position in a stream of envelopes, assigned by a
+    /// [`Sequencer`](crate::Sequencer), for a
+    /// [`ReorderBuffer`](crate::ReorderBuffer) to restore the order on the
+    /// receiving side.
+    pub seq: Option<u64>,
+}
+
+impl Envelope {
+    /// Create an envelope that does not expect a response.
+    pub fn new(body: VBox) -> Self {
+        Envelope {
+            id: next_id(),
+            reply_to: None,
+            body,
+            meta: BTreeMap::new(),
+            seq: None,
+        }
+    }
+
+    /// Create an envelope that expects a response, and the receiver to wait
+    /// for the response.
+    pub fn request(body: VBox) -> (Self, VOnceReceiver) {
+        let (tx, rx) = VOnce::channel();
+
+        let mut envelope = Self::new(body);
+        envelope.reply_to = Some(tx);
+        (envelope, rx)
+    }
+
+    /// Add a metadata entry.
+    pub fn with_meta(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.meta.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set the sequence number, see [`Sequencer`](crate::Sequencer).
+    pub fn with_seq(mut self, seq: u64) -> Self {
+        self.seq = Some(seq);
+        self
+    }
+
+    /// Returns `true` if a response is expected and not yet sent.
+    pub fn expects_reply(&self) -> bool {
+        self.reply_to.is_some()
+    }
+
+    /// Send a response to the requester.
+    ///
+    /// It returns the response in `Err` if no response is expected, a response
+    /// is already sent, or the requester is gone.
+    pub fn reply(&mut self, response: VBox) -> Result<(), VBox> {
+        match self.reply_to.take() {
+            Some(tx) => tx.send(response),
+            None => Err(response),
+        }
+    }
+}
+
+fn next_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
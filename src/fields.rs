@@ -0,0 +1,116 @@
+use std::fmt;
+
+use crate::VBox;
+
+/// A payload that exposes structured fields for logging, e.g., the id and the
+/// kind of a request, without exposing its concrete type.
+///
+/// A `VBox` built with [`into_vbox_fields!`](crate::into_vbox_fields) captures
+/// this implementation at pack time, and [`VBox::fields()`] records the fields
+/// from inside the erased value. A `tracing` event records them with
+/// `%vbox.fields()`, or with `vbox.fields().as_tracing_value()` with the
+/// `tracing` feature. With the `valuable` feature, [`Fields`] implements
+/// `valuable::Valuable` as a map from the field names to the `Debug` output of
+/// the values.
+pub trait RecordFields: Send + 'static {
+    /// Call `record` with the name and the value of every field.
+    fn record_fields(&self, record: &mut dyn FnMut(&str, &dyn fmt::Debug));
+}
+
+/// Records the fields of the payload at the data pointer.
+pub(crate) type FieldsFn =
+    unsafe fn(*const (), &mut dyn FnMut(&str, &dyn fmt::Debug));
+
+/// Record the fields of the payload at `data`.
+///
+/// # Safety
+///
+/// `data` must point to a valid `T`.
+pub(crate) unsafe fn record_fields_raw<T: RecordFields>(
+    data: *const (),
+    record: &mut dyn FnMut(&str, &dyn fmt::Debug),
+) {
+    (*(data as *const T)).record_fields(record)
+}
+
+/// The fields of a [`VBox`] payload, returned by [`VBox::fields()`].
+///
+/// It displays as `name=value` pairs separated by a space, e.g., `id=3
+/// kind="get"`, which is suitable for a `tracing` field with the `%` sigil or
+/// a log line. It displays as nothing if no field is captured.
+///
+/// `Debug` formats it as a map.
+pub struct Fields<'a> {
+    vbox: &'a VBox,
+}
+
+impl<'a> Fields<'a> {
+    pub(crate) fn new(vbox: &'a VBox) -> Self {
+        Self { vbox }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Fields<'_> {
+    /// Return a value to record the fields as a single `tracing` field, e.g.,
+    /// `tracing::info!(fields = vbox.fields().as_tracing_value())`.
+    ///
+    /// `tracing::Value` is sealed and can not be implemented outside of
+    /// `tracing`, thus the fields are recorded with their `Display` form, i.e.,
+    /// `id=3 kind="get"`.
+    pub fn as_tracing_value(&self) -> tracing::field::DisplayValue<&Self> {
+        tracing::field::display(self)
+    }
+}
+
+#[cfg(feature = "valuable")]
+impl valuable::Valuable for Fields<'_> {
+    fn as_value(&self) -> valuable::Value<'_> {
+        valuable::Value::Mappable(self)
+    }
+
+    fn visit(&self, visit: &mut dyn valuable::Visit) {
+        self.vbox.record_fields(|name, value| {
+            let value = format!("{:?}", value);
+            visit.visit_entry(
+                valuable::Value::String(name),
+                valuable::Value::String(&value),
+            );
+        });
+    }
+}
+
+#[cfg(feature = "valuable")]
+impl valuable::Mappable for Fields<'_> {
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let mut n = 0;
+        self.vbox.record_fields(|_, _| n += 1);
+        (n, Some(n))
+    }
+}
+
+impl fmt::Display for Fields<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut res = Ok(());
+        let mut first = true;
+        self.vbox.record_fields(|name, value| {
+            if res.is_err() {
+                return;
+            }
+            let sep = if first { "" } else { " " };
+            first = false;
+            res = write!(f, "{}{}={:?}", sep, name, value);
+        });
+        res
+    }
+}
+
+impl fmt::Debug for Fields<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut m = f.debug_map();
+        self.vbox.record_fields(|name, value| {
+            m.entry(&name, value);
+        });
+        m.finish()
+    }
+}
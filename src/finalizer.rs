@@ -0,0 +1,147 @@
+use std::any::Any;
+use std::fmt;
+use std::mem;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::sync::PoisonError;
+use std::thread;
+
+use crate::VBox;
+
+/// A registry of erased teardown closures, executed in reverse order of
+/// registration on shutdown.
+///
+/// It can be used as a scoped registry, in which case the closures are run
+/// when it is dropped, or as a process-wide registry via
+/// [`Finalizers::global()`].
+///
+/// ```
+/// # use std::sync::{Arc, Mutex};
+/// # use vbox::Finalizers;
+/// let log = Arc::new(Mutex::new(Vec::new()));
+///
+/// let finalizers = Finalizers::new();
+/// let l = log.clone();
+/// finalizers.register("db", move || l.lock().unwrap().push("db"));
+/// let l = log.clone();
+/// finalizers.register("server", move || l.lock().unwrap().push("server"));
+///
+/// finalizers.shutdown();
+/// assert_eq!(vec!["server", "db"], *log.lock().unwrap());
+/// ```
+#[derive(Default)]
+pub struct Finalizers {
+    /// Name and closure packed as `dyn FnOnce() + Send`.
+    entries: Mutex<Vec<(String, VBox)>>,
+}
+
+impl Finalizers {
+    /// Create an empty scoped registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide registry.
+    ///
+    /// It is never dropped, [`Finalizers::shutdown()`] has to be called
+    /// explicitly, e.g., at the end of `main()`.
+    pub fn global() -> &'static Finalizers {
+        static GLOBAL: OnceLock<Finalizers> = OnceLock::new();
+        GLOBAL.get_or_init(Finalizers::new)
+    }
+
+    /// Register a teardown closure with a name for diagnosis.
+    pub fn register(
+        &self,
+        name: impl Into<String>,
+        f: impl FnOnce() + Send + 'static,
+    ) {
+        let f = crate::into_vbox!(dyn FnOnce() + Send, f);
+        self.entries.lock().unwrap().push((name.into(), f));
+    }
+
+    /// Register an erased teardown closure packed as `dyn FnOnce() + Send`.
+    ///
+    /// If it is not packed as `dyn FnOnce() + Send`, it is returned in `Err`
+    /// right away, rather than failing at shutdown.
+    pub fn register_vbox(
+        &self,
+        name: impl Into<String>,
+        f: VBox,
+    ) -> Result<(), VBox> {
+        if !f.is_dyn::<dyn FnOnce() + Send>() {
+            return Err(f);
+        }
+        self.entries.lock().unwrap().push((name.into(), f));
+        Ok(())
+    }
+
+    /// Returns the names of the registered closures, in registration order.
+    pub fn names(&self) -> Vec<String> {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Run all registered closures in reverse order of registration.
+    ///
+    /// Closures registered during shutdown are run too. If a closure panics,
+    /// the rest are still run, and then the first panic is resumed.
+    pub fn shutdown(&self) {
+        if let Some(e) = self.run_all() {
+            panic::resume_unwind(e);
+        }
+    }
+
+    /// Run all registered closures, and return the first panic.
+    fn run_all(&self) -> Option<Box<dyn Any + Send>> {
+        let mut first_panic = None;
+
+        loop {
+            let entries = mem::take(
+                &mut *self
+                    .entries
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner),
+            );
+            if entries.is_empty() {
+                break;
+            }
+
+            for (_name, f) in entries.into_iter().rev() {
+                let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                    f.unpack::<dyn FnOnce() + Send>()()
+                }));
+                if let Err(e) = res {
+                    first_panic.get_or_insert(e);
+                }
+            }
+        }
+
+        first_panic
+    }
+}
+
+impl Drop for Finalizers {
+    fn drop(&mut self) {
+        let Some(e) = self.run_all() else {
+            return;
+        };
+
+        if thread::panicking() {
+            // A second panic while unwinding aborts the process.
+            #[cfg(feature = "log")]
+            log::error!("a finalizer panicked while unwinding: {:?}", e);
+            drop(e);
+        } else {
+            panic::resume_unwind(e);
+        }
+    }
+}
+
+impl fmt::Debug for Finalizers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Finalizers").field("names", &self.names()).finish()
+    }
+}
@@ -0,0 +1,90 @@
+use std::fmt;
+use std::ops::Deref;
+use std::ops::DerefMut;
+
+use crate::VBox;
+
+/// A borrow of the payload of a [`VBox`] as `dyn Trait`, which derefs to
+/// `&mut dyn Trait`.
+///
+/// It is built with [`guard_vbox!`](crate::guard_vbox), which checks that the
+/// `VBox` is packed as `dyn Trait`. The payload is never moved out of the
+/// `VBox`, thus the `VBox` is intact when the guard is dropped, including when
+/// the handler returns early or panics. There is no "unpacked it, forgot to
+/// re-pack it" path.
+///
+/// ```
+/// # use vbox::{from_vbox, guard_vbox, into_vbox, VBox};
+/// let mut vbox: VBox = into_vbox!(dyn Iterator<Item = u64>, 1..4u64);
+///
+/// {
+///     let mut it = guard_vbox!(dyn Iterator<Item = u64>, &mut vbox);
+///     assert_eq!(Some(1), it.next());
+/// }
+///
+/// let rest: Vec<_> = from_vbox!(dyn Iterator<Item = u64>, vbox).collect();
+/// assert_eq!(vec![2, 3], rest);
+/// ```
+pub struct VBoxGuard<'a, U>
+where U: ?Sized + 'static
+{
+    payload: &'a mut U,
+}
+
+impl<'a, U> VBoxGuard<'a, U>
+where U: ?Sized + 'static
+{
+    /// Borrow the payload of `vbox` as `U`, i.e., `dyn Trait`. Use
+    /// [`guard_vbox!`](crate::guard_vbox) for a better readability.
+    ///
+    /// It panics if the `VBox` is not packed as `U`.
+    pub fn new(vbox: &'a mut VBox) -> Self {
+        VBoxGuard {
+            payload: vbox.as_dyn_mut::<U>(),
+        }
+    }
+}
+
+impl<'a, U> Deref for VBoxGuard<'a, U>
+where U: ?Sized + 'static
+{
+    type Target = U;
+
+    fn deref(&self) -> &Self::Target {
+        self.payload
+    }
+}
+
+impl<'a, U> DerefMut for VBoxGuard<'a, U>
+where U: ?Sized + 'static
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.payload
+    }
+}
+
+impl<'a, U> fmt::Debug for VBoxGuard<'a, U>
+where U: ?Sized + 'static
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VBoxGuard")
+            .field("type", &std::any::type_name::<U>())
+            .finish()
+    }
+}
+
+/// Borrow the payload of a [`VBox`] as `dyn Trait` with a [`VBoxGuard`],
+/// which derefs to `&mut dyn Trait`.
+///
+/// It panics if the `VBox` is not packed as `dyn Trait`.
+#[macro_export]
+macro_rules! guard_vbox {
+    (for<$($lt: lifetime),+> dyn $tr: path, $v: expr) => {
+        $crate::guard_vbox!(dyn for<$($lt),+> $tr, $v)
+    };
+
+    ($t: ty, $v: expr) => {{
+        let vbox: &mut $crate::VBox = $v;
+        $crate::VBoxGuard::<$t>::new(vbox)
+    }};
+}
@@ -0,0 +1,82 @@
+//! A global registry of inspectors: per concrete type closures producing a
+//! short summary of a payload for diagnostics.
+//!
+//! An inspector is registered once per type, e.g., at startup, and is then
+//! consulted by [`VBox::summary()`], by the `Debug` output of a `VBox` and by
+//! the `Display` output of a [`DeadLetter`], without changing the sites
+//! packing the payloads.
+//!
+//! ```
+//! # use std::fmt::Display;
+//! # use vbox::{inspector, into_vbox};
+//! struct Order {
+//!     id: u64,
+//!     lines: Vec<u64>,
+//! }
+//!
+//! # impl Display for Order {
+//! #     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+//! #         write!(f, "order")
+//! #     }
+//! # }
+//! inspector::register(|o: &Order| format!("order {} x{}", o.id, o.lines.len()));
+//!
+//! let vbox = into_vbox!(dyn Display, Order { id: 7, lines: vec![1, 2] });
+//! assert_eq!(Some("order 7 x2".to_string()), vbox.summary());
+//! ```
+//!
+//! [`VBox::summary()`]: crate::VBox::summary
+//! [`DeadLetter`]: crate::DeadLetter
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::sync::RwLock;
+
+/// Summarizes the payload at the data pointer.
+type InspectFn = dyn Fn(*const ()) -> String + Send + Sync;
+
+fn inspectors() -> &'static RwLock<HashMap<TypeId, Arc<InspectFn>>> {
+    static INSPECTORS: OnceLock<RwLock<HashMap<TypeId, Arc<InspectFn>>>> =
+        OnceLock::new();
+    INSPECTORS.get_or_init(Default::default)
+}
+
+/// Register the inspector of the concrete type `T`, and return `true` if it
+/// replaces a previously registered one.
+pub fn register<T, F>(f: F) -> bool
+where
+    T: 'static,
+    F: Fn(&T) -> String + Send + Sync + 'static,
+{
+    let inspect = move |data: *const ()| f(unsafe { &*(data as *const T) });
+    let mut inspectors = inspectors().write().unwrap();
+    inspectors.insert(TypeId::of::<T>(), Arc::new(inspect)).is_some()
+}
+
+/// Remove the inspector of `T`, and return `true` if there was one.
+pub fn unregister<T: 'static>() -> bool {
+    let mut inspectors = inspectors().write().unwrap();
+    inspectors.remove(&TypeId::of::<T>()).is_some()
+}
+
+/// Returns `true` if an inspector of `T` is registered.
+pub fn is_registered<T: 'static>() -> bool {
+    inspectors().read().unwrap().contains_key(&TypeId::of::<T>())
+}
+
+/// Summarize the payload at `data` of the concrete type `type_id`, if an
+/// inspector of it is registered.
+///
+/// # Safety
+///
+/// `data` must point to a valid value of the type of `type_id`.
+pub(crate) unsafe fn summarize(
+    type_id: TypeId,
+    data: *const (),
+) -> Option<String> {
+    // Released before calling the inspector, which may inspect another VBox.
+    let inspect = inspectors().read().unwrap().get(&type_id).cloned()?;
+    Some(inspect(data))
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+use std::sync::RwLock;
+
+use crate::ImplKey;
+use crate::VBox;
+
+/// A small index of a pair of a concrete type and a `dyn Trait`, assigned by a
+/// [`VTableInterner`].
+///
+/// It is a `u16`, and can be used in message headers, routing tables and
+/// statistics instead of a vtable pointer, e.g., as the tag of a compact wire
+/// format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ImplIndex(u16);
+
+impl ImplIndex {
+    /// Build an index from the `u16` returned by [`ImplIndex::as_u16()`].
+    pub fn from_u16(v: u16) -> Self {
+        ImplIndex(v)
+    }
+
+    /// Returns the index as a `u16`.
+    pub fn as_u16(self) -> u16 {
+        self.0
+    }
+}
+
+impl fmt::Display for ImplIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+struct Names {
+    type_name: Option<fn() -> &'static str>,
+    trait_name: fn() -> &'static str,
+}
+
+#[derive(Default)]
+struct Inner {
+    by_key: HashMap<ImplKey, u16>,
+
+    /// Names of the interned pairs, by index.
+    names: Vec<Names>,
+}
+
+/// Assigns a small [`ImplIndex`] to each pair of a concrete type and a `dyn
+/// Trait` it sees at runtime, in the order they are seen.
+///
+/// A pair is identified the same way as [`VBox::same_impl()`]: by the
+/// concrete type if it is known, otherwise by the vtable pointer. An index is
+/// stable for the lifetime of the interner. At most `u16::MAX + 1` pairs can
+/// be interned.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{into_vbox, VTableInterner};
+/// let interner = VTableInterner::new();
+///
+/// let a = interner.intern(&into_vbox!(dyn Debug, 1u64));
+/// let b = interner.intern(&into_vbox!(dyn Debug, 2u64));
+/// let c = interner.intern(&into_vbox!(dyn Debug, "3"));
+///
+/// assert_eq!(a, b);
+/// assert_ne!(a, c);
+/// assert_eq!(Some("u64"), interner.type_name(a));
+/// ```
+#[derive(Default)]
+pub struct VTableInterner {
+    inner: RwLock<Inner>,
+}
+
+impl VTableInterner {
+    /// Create an empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide interner.
+    pub fn global() -> &'static VTableInterner {
+        static GLOBAL: OnceLock<VTableInterner> = OnceLock::new();
+        GLOBAL.get_or_init(VTableInterner::new)
+    }
+
+    /// Returns the index of the pair of `vbox`, and assign one if it is not
+    /// seen before.
+    ///
+    /// # Panics
+    ///
+    /// It panics if more than `u16::MAX + 1` pairs are interned.
+    pub fn intern(&self, vbox: &VBox) -> ImplIndex {
+        if let Some(index) = self.index_of(vbox) {
+            return index;
+        }
+
+        let mut inner = self.inner.write().unwrap();
+        let key = vbox.impl_key();
+
+        // Interned by another thread between the locks.
+        if let Some(index) = inner.by_key.get(&key) {
+            return ImplIndex(*index);
+        }
+
+        let index =
+            u16::try_from(inner.names.len()).expect("VTableInterner is full");
+        inner.by_key.insert(key, index);
+        inner.names.push(Names {
+            type_name: vbox.concrete_type_name,
+            trait_name: vbox.type_name,
+        });
+        ImplIndex(index)
+    }
+
+    /// Returns the index of the pair of `vbox`, if it is interned.
+    pub fn index_of(&self, vbox: &VBox) -> Option<ImplIndex> {
+        let inner = self.inner.read().unwrap();
+        inner.by_key.get(&vbox.impl_key()).map(|i| ImplIndex(*i))
+    }
+
+    /// Returns `true` if `vbox` is of the pair at `index`.
+    pub fn matches(&self, index: ImplIndex, vbox: &VBox) -> bool {
+        self.index_of(vbox) == Some(index)
+    }
+
+    /// Returns the name of the concrete type of the pair at `index`, if the
+    /// index is assigned and the concrete type is known.
+    pub fn type_name(&self, index: ImplIndex) -> Option<&'static str> {
+        let inner = self.inner.read().unwrap();
+        let names = inner.names.get(index.0 as usize)?;
+        names.type_name.map(|f| f())
+    }
+
+    /// Returns the name of `dyn Trait` of the pair at `index`, if the index is
+    /// assigned.
+    pub fn trait_name(&self, index: ImplIndex) -> Option<&'static str> {
+        let inner = self.inner.read().unwrap();
+        let names = inner.names.get(index.0 as usize)?;
+        Some((names.trait_name)())
+    }
+
+    /// Returns the number of interned pairs.
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().names.len()
+    }
+
+    /// Returns `true` if no pair is interned.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Debug for VTableInterner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VTableInterner").field("len", &self.len()).finish()
+    }
+}
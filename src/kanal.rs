@@ -0,0 +1,245 @@
+//! Typed endpoints over a [`kanal`](https://docs.rs/kanal) channel of
+//! [`VBox`]es, for both the sync and the async flavors.
+//!
+//! It is enabled by the `kanal` feature.
+//!
+//! Just like the `async_channel` module, the channel carries erased `VBox`es,
+//! and the endpoints pack and unpack `Box<dyn Trait>` for the user. Packing
+//! does not move the payload: the allocation of the `Box` is handed over to the
+//! receiver as is.
+//!
+//! ```
+//! # use std::fmt::Debug;
+//! # use vbox::kanal;
+//! let (tx, rx) = kanal::bounded::<dyn Debug + Send>(4);
+//!
+//! tx.send(Box::new(3u64)).unwrap();
+//! assert_eq!("3", format!("{:?}", rx.recv().unwrap()));
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+pub use ::kanal::ReceiveError;
+pub use ::kanal::SendError;
+
+use crate::VBox;
+
+macro_rules! endpoint {
+    ($(#[$m: meta])* $name: ident, $inner: ident) => {
+        $(#[$m])*
+        pub struct $name<U>
+        where U: ?Sized + 'static
+        {
+            inner: ::kanal::$inner<VBox>,
+            _p: PhantomData<fn(Box<U>) -> Box<U>>,
+        }
+
+        impl<U> $name<U>
+        where U: ?Sized + Send + 'static
+        {
+            /// Wrap an erased endpoint. The other end must pack the messages as
+            /// `U`.
+            pub fn from_erased(inner: ::kanal::$inner<VBox>) -> Self {
+                $name {
+                    inner,
+                    _p: PhantomData,
+                }
+            }
+
+            /// Returns the erased endpoint.
+            pub fn into_erased(self) -> ::kanal::$inner<VBox> {
+                self.inner
+            }
+
+            /// Close the channel, and return `true` if it is closed by this
+            /// call.
+            pub fn close(&self) -> bool {
+                self.inner.close()
+            }
+
+            /// Returns `true` if the channel is closed.
+            pub fn is_closed(&self) -> bool {
+                self.inner.is_closed()
+            }
+
+            /// Returns the number of messages in the channel.
+            pub fn len(&self) -> usize {
+                self.inner.len()
+            }
+
+            /// Returns `true` if the channel is empty.
+            pub fn is_empty(&self) -> bool {
+                self.inner.is_empty()
+            }
+        }
+
+        impl<U> Clone for $name<U>
+        where U: ?Sized + 'static
+        {
+            fn clone(&self) -> Self {
+                $name {
+                    inner: self.inner.clone(),
+                    _p: PhantomData,
+                }
+            }
+        }
+
+        impl<U> fmt::Debug for $name<U>
+        where U: ?Sized + 'static
+        {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($name))
+                    .field("type", &std::any::type_name::<U>())
+                    .field("len", &self.inner.len())
+                    .finish()
+            }
+        }
+    };
+}
+
+endpoint!(
+    /// The sending end of a sync channel of `Box<U>`, where `U` is `dyn
+    /// Trait`.
+    Sender,
+    Sender
+);
+
+endpoint!(
+    /// The receiving end of a sync channel of `Box<U>`, where `U` is `dyn
+    /// Trait`.
+    Receiver,
+    Receiver
+);
+
+endpoint!(
+    /// The sending end of an async channel of `Box<U>`, where `U` is `dyn
+    /// Trait`.
+    AsyncSender,
+    AsyncSender
+);
+
+endpoint!(
+    /// The receiving end of an async channel of `Box<U>`, where `U` is `dyn
+    /// Trait`.
+    AsyncReceiver,
+    AsyncReceiver
+);
+
+/// Create a bounded sync channel of `Box<U>`.
+pub fn bounded<U>(cap: usize) -> (Sender<U>, Receiver<U>)
+where U: ?Sized + Send + 'static {
+    let (tx, rx) = ::kanal::bounded(cap);
+    (Sender::from_erased(tx), Receiver::from_erased(rx))
+}
+
+/// Create an unbounded sync channel of `Box<U>`.
+pub fn unbounded<U>() -> (Sender<U>, Receiver<U>)
+where U: ?Sized + Send + 'static {
+    let (tx, rx) = ::kanal::unbounded();
+    (Sender::from_erased(tx), Receiver::from_erased(rx))
+}
+
+/// Create a bounded async channel of `Box<U>`.
+pub fn bounded_async<U>(cap: usize) -> (AsyncSender<U>, AsyncReceiver<U>)
+where U: ?Sized + Send + 'static {
+    let (tx, rx) = ::kanal::bounded_async(cap);
+    (AsyncSender::from_erased(tx), AsyncReceiver::from_erased(rx))
+}
+
+/// Create an unbounded async channel of `Box<U>`.
+pub fn unbounded_async<U>() -> (AsyncSender<U>, AsyncReceiver<U>)
+where U: ?Sized + Send + 'static {
+    let (tx, rx) = ::kanal::unbounded_async();
+    (AsyncSender::from_erased(tx), AsyncReceiver::from_erased(rx))
+}
+
+impl<U> Sender<U>
+where U: ?Sized + Send + 'static
+{
+    /// Send a message, blocking the current thread if the channel is full.
+    ///
+    /// As with `kanal`, the message is dropped if the channel is closed.
+    pub fn send(&self, msg: Box<U>) -> Result<(), SendError> {
+        self.inner.send(VBox::from_box(msg))
+    }
+
+    /// Send a message if there is room, without waiting.
+    ///
+    /// The message is taken out of `msg` only if it is sent, i.e., `Ok(true)`
+    /// is returned. Otherwise it stays in `msg`, without being re-allocated.
+    pub fn try_send_option(
+        &self,
+        msg: &mut Option<Box<U>>,
+    ) -> Result<bool, SendError> {
+        let Some(boxed) = msg.take() else {
+            panic!("send data option is None");
+        };
+
+        let mut vbox = Some(VBox::from_box(boxed));
+        let res = self.inner.try_send_option(&mut vbox);
+
+        *msg = vbox.map(VBox::unpack::<U>);
+        res
+    }
+
+    /// Convert to the async flavor of the same channel.
+    pub fn to_async(self) -> AsyncSender<U> {
+        AsyncSender::from_erased(self.inner.to_async())
+    }
+}
+
+impl<U> Receiver<U>
+where U: ?Sized + Send + 'static
+{
+    /// Receive a message, blocking the current thread if the channel is
+    /// empty.
+    ///
+    /// It panics if the message is not packed as `U`.
+    pub fn recv(&self) -> Result<Box<U>, ReceiveError> {
+        self.inner.recv().map(VBox::unpack::<U>)
+    }
+
+    /// Receive a message if there is one, without waiting.
+    pub fn try_recv(&self) -> Result<Option<Box<U>>, ReceiveError> {
+        let got = self.inner.try_recv()?;
+        Ok(got.map(VBox::unpack::<U>))
+    }
+
+    /// Convert to the async flavor of the same channel.
+    pub fn to_async(self) -> AsyncReceiver<U> {
+        AsyncReceiver::from_erased(self.inner.to_async())
+    }
+}
+
+impl<U> AsyncSender<U>
+where U: ?Sized + Send + 'static
+{
+    /// Send a message, waiting for room if the channel is full.
+    ///
+    /// As with `kanal`, the message is dropped if the channel is closed.
+    pub async fn send(&self, msg: Box<U>) -> Result<(), SendError> {
+        self.inner.send(VBox::from_box(msg)).await
+    }
+
+    /// Convert to the sync flavor of the same channel.
+    pub fn to_sync(self) -> Sender<U> {
+        Sender::from_erased(self.inner.to_sync())
+    }
+}
+
+impl<U> AsyncReceiver<U>
+where U: ?Sized + Send + 'static
+{
+    /// Receive a message, waiting for one if the channel is empty.
+    ///
+    /// It panics if the message is not packed as `U`.
+    pub async fn recv(&self) -> Result<Box<U>, ReceiveError> {
+        self.inner.recv().await.map(VBox::unpack::<U>)
+    }
+
+    /// Convert to the sync flavor of the same channel.
+    pub fn to_sync(self) -> Receiver<U> {
+        Receiver::from_erased(self.inner.to_sync())
+    }
+}
@@ -0,0 +1,133 @@
+use std::any::TypeId;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+use crate::VBox;
+
+/// The identity of the concrete type of a payload, to key a map by.
+///
+/// A `VBox` built with [`into_vbox_dyn!`](crate::into_vbox_dyn) does not know
+/// its concrete type, and is identified by its vtable pointer instead, which
+/// never equals a known concrete type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TypeKey(Result<TypeId, usize>);
+
+impl TypeKey {
+    /// The key of a payload of type `T`.
+    pub fn of<T: 'static>() -> Self {
+        TypeKey(Ok(TypeId::of::<T>()))
+    }
+
+    /// The key of the payload of `vbox`.
+    pub fn of_vbox(vbox: &VBox) -> Self {
+        match vbox.concrete_type_id {
+            Some(type_id) => TypeKey(Ok(type_id)),
+            None => TypeKey(Err(vbox.vtable)),
+        }
+    }
+
+    /// Returns the type id of the concrete type, if it is known.
+    pub fn type_id(&self) -> Option<TypeId> {
+        self.0.ok()
+    }
+}
+
+/// A [`VBox`] that is hashed, compared and ordered by the concrete type of its
+/// payload, i.e., by its [`TypeKey`], so that it can be a key of a `HashMap` or
+/// a `BTreeMap`, e.g., one handler slot per message type.
+///
+/// A map keyed by `KeyByType` can be looked up with a [`TypeKey`].
+///
+/// With the `debug-unconsumed` feature, clippy reports `mutable_key_type` for
+/// such a map, because of the interior mutable tracker in `VBox`. It is a false
+/// positive: only the key is hashed and compared.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use std::fmt::Debug;
+/// # use vbox::{into_vbox, KeyByType, TypeKey};
+/// let mut latest: HashMap<KeyByType, u64> = HashMap::new();
+///
+/// latest.insert(KeyByType::new(into_vbox!(dyn Debug, 1u64)), 1);
+/// latest.insert(KeyByType::new(into_vbox!(dyn Debug, "a")), 2);
+/// latest.insert(KeyByType::new(into_vbox!(dyn Debug, 3u64)), 3);
+///
+/// assert_eq!(2, latest.len());
+/// assert_eq!(Some(&3), latest.get(&TypeKey::of::<u64>()));
+/// ```
+pub struct KeyByType {
+    key: TypeKey,
+    vbox: VBox,
+}
+
+impl KeyByType {
+    /// Wrap a `VBox` to be keyed by the concrete type of its payload.
+    pub fn new(vbox: VBox) -> Self {
+        KeyByType {
+            key: TypeKey::of_vbox(&vbox),
+            vbox,
+        }
+    }
+
+    /// Returns the key.
+    pub fn key(&self) -> TypeKey {
+        self.key
+    }
+
+    /// Borrow the wrapped `VBox`.
+    pub fn as_vbox(&self) -> &VBox {
+        &self.vbox
+    }
+
+    /// Returns the wrapped `VBox`.
+    pub fn into_inner(self) -> VBox {
+        self.vbox
+    }
+}
+
+impl From<VBox> for KeyByType {
+    fn from(vbox: VBox) -> Self {
+        Self::new(vbox)
+    }
+}
+
+impl Borrow<TypeKey> for KeyByType {
+    fn borrow(&self) -> &TypeKey {
+        &self.key
+    }
+}
+
+impl PartialEq for KeyByType {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for KeyByType {}
+
+impl Hash for KeyByType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.hash(state)
+    }
+}
+
+impl PartialOrd for KeyByType {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for KeyByType {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+impl fmt::Debug for KeyByType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("KeyByType").field(&self.vbox).finish()
+    }
+}
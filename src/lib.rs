@@ -5,10 +5,9 @@
 //! Only the creator and the consumer needs to agree on the type
 //! parameters.
 //!
-//! Internally, it stores the trait object's data pointer in a `Box<dyn Any +
-//! Send>`, so that the `Drop::drop()` will be called when the wrapper is
-//! dropped. And it stores the vtable pointer in another `usize` to make sure it
-//! is `Send`.
+//! Internally, it stores the trait object's data pointer and vtable pointer
+//! separately, along with a function to rebuild and drop the `Box<dyn Trait>`,
+//! so that the `Drop::drop()` will be called when the wrapper is dropped.
 //!
 //! # Example
 //! ```
@@ -25,78 +24,2348 @@
 //!
 //! assert_eq!("10", format!("{:?}", unpacked));
 //! ```
+//!
+//! # Non-`Send` payload
+//!
+//! A `VBox` requires the payload to be `Send`. For a single threaded
+//! environment, e.g., erasing `JsValue` wrappers on `wasm32`, use
+//! [`LocalVBox`] with [`into_local_vbox!`] and [`from_local_vbox!`].
+//!
+//! # Check policies
+//!
+//! A `VBox` always checks `dyn Trait` when unpacking. Use [`PolicyVBox`] to
+//! choose another [`policy`] for a boundary, e.g., to skip the check on an
+//! audited hot path.
+//!
+//! # Panic-free API
+//!
+//! Every operation that panics on a type mismatch has a counterpart that
+//! returns `Result` instead, for services where a panicking worker thread is
+//! unacceptable:
+//!
+//! - [`from_vbox!`] and [`VBox::unpack()`]: [`try_from_vbox!`] and
+//!   [`VBox::try_unpack()`], returning the `VBox` intact in the error.
+//! - [`VBox::as_dyn()`] and [`VBox::as_dyn_mut()`], used by [`with_vbox!`]:
+//!   [`VBox::try_as_dyn()`] and [`VBox::try_as_dyn_mut()`].
+//! - [`from_vbox_vec!`] and [`VBox::unpack_vec()`]: [`VBox::try_unpack_vec()`].
+//! - [`PackedVBox::as_dyn()`], [`PackedVBox::as_dyn_mut()`] and
+//!   [`PackedVBox::unpack()`]: [`PackedVBox::try_as_dyn()`],
+//!   [`PackedVBox::try_as_dyn_mut()`] and [`PackedVBox::try_unpack()`].
+//! - Downcasting with [`VBox::into_inner()`] and registry lookups with
+//!   [`registry::get()`] return `Result` already.
+//!
+//! To enforce it crate-wide, disallow the panicking forms in `clippy.toml`,
+//! e.g., `disallowed-methods = ["vbox::VBox::unpack", "vbox::VBox::as_dyn",
+//! "vbox::VBox::as_dyn_mut", "vbox::VBox::unpack_vec"]`, which covers the
+//! macros expanding to them.
+//!
+//! Where the panicking forms are used across an FFI boundary, which must not
+//! be unwound across, set [`MismatchPolicy::Abort`] to abort the process with
+//! a diagnostic instead.
+//!
+//! # Async traits
+//!
+//! A trait object generated by `#[async_trait]`, whose methods return a
+//! [`BoxFuture`], is packed as usual, e.g., as `dyn Store + Send + Sync`. On
+//! the other side, call and await its methods with [`VAsync`] or
+//! [`with_vbox_async!`].
+//!
+//! # Feature flags
+//!
+//! - `derive`: provide `#[derive(IntoVBox)]`, which generates an
+//!   `into_vbox_<trait>()` method for each trait listed in
+//!   `#[vbox(traits(...))]`, and `#[erasable]` for a trait, which generates an
+//!   extension trait with `value.erase()`.
+//! - `log`: emit debug level [`log`](https://docs.rs/log) events with the type
+//!   names and the call sites when a `VBox` is packed, unpacked or dropped. It
+//!   compiles to nothing when disabled.
+//! - `debug-unconsumed`: report a `VBox` that is dropped without being
+//!   unpacked, with its type name and creation site. See the `unconsumed`
+//!   module.
+//! - `test-util`: provide the `test_util` module with canned traits and
+//!   fixtures to test code that sends `VBox`es around.
+//! - `async-channel`: provide typed endpoints over `async-channel` channels of
+//!   `VBox`, see the `async_channel` module.
+//! - `kanal`: provide typed endpoints over `kanal` channels of `VBox`, see the
+//!   `kanal` module.
+//! - `pack-hook`: call a process wide hook on every pack, to enforce policies
+//!   such as a size limit. See the `pack_hook` module.
+//! - `recycle`: keep the freed payload allocations in a per-thread cache keyed
+//!   by layout, and reuse them in `into_vbox!`. See the `recycle` module.
+//! - `script`: provide a bridge where Rust closures are registered by name and
+//!   called from an embedded scripting engine with `VBox`-packed arguments and
+//!   results. See the `script` module.
+//! - `rhai`: enable `script` and register the functions of a `ScriptBridge` in
+//!   a [`rhai`](https://docs.rs/rhai) engine with `script::register_rhai()`.
+//! - `mlua`: enable `script` and register the functions of a `ScriptBridge` as
+//!   global functions of an [`mlua`](https://docs.rs/mlua) Lua state with
+//!   `script::register_lua()`.
+//! - `stats`: count the values packed, unpacked and live, and the bytes they
+//!   hold, per `dyn Trait` and per concrete type. See the `stats` module.
+//! - `stream`: adapt a `Stream` of `VBox`, such as a channel receiver, into a
+//!   stream of `Box<dyn Trait>`. See the `stream` module.
+//! - `timeline`: record every pack and unpack with the type names, the tag and
+//!   the call site into a bounded buffer, to diagnose ordering bugs. See the
+//!   `timeline` module.
+//! - `tracing`: provide `Fields::as_tracing_value()` to record the fields of a
+//!   payload as a [`tracing`](https://docs.rs/tracing) field.
+//! - `valuable`: implement `valuable::Valuable` for `Fields`, the fields of a
+//!   payload, see [`VBox::fields()`].
+
+mod actor;
+#[doc(hidden)] pub mod assert;
+#[cfg(feature = "async-channel")] pub mod async_channel;
+mod async_fn;
+mod batch;
+pub mod brand;
+mod builder;
+mod cancel;
+mod cast;
+mod config;
+mod dead_letter;
+mod dispatcher;
+mod dispose;
+mod dump;
+mod envelope;
+mod fields;
+mod finalizer;
+mod guard;
+pub mod inspector;
+mod interner;
+#[cfg(feature = "kanal")] pub mod kanal;
+mod key_by_type;
+mod local;
+mod lru_cache;
+mod map_ext;
+mod mismatch;
+mod mismatch_policy;
+#[cfg(feature = "pack-hook")] pub mod pack_hook;
+mod packed;
+mod pipeline;
+pub mod policy;
+mod priority;
+#[doc(hidden)] pub mod probe;
+mod queue;
+#[cfg(feature = "recycle")] pub mod recycle;
+pub mod registry;
+mod remote;
+mod reorder;
+mod retry;
+mod router;
+mod scope;
+#[cfg(feature = "script")] pub mod script;
+mod sharded;
+pub mod shm;
+mod slot;
+mod state_machine;
+#[cfg(feature = "stats")] pub mod stats;
+#[cfg(feature = "stream")] pub mod stream;
+#[cfg(any(feature = "pack-hook", feature = "timeline"))] mod tag;
+#[cfg(feature = "test-util")] pub mod test_util;
+#[cfg(feature = "timeline")] pub mod timeline;
+mod ttl_cache;
+mod type_check;
+mod type_dispatcher;
+#[cfg(feature = "debug-unconsumed")] pub mod unconsumed;
+#[cfg(not(feature = "debug-unconsumed"))] mod unconsumed;
+mod vasync;
+mod vcall;
+mod verror;
+mod vjoin;
+mod vonce;
+mod vpanic;
+mod vresult;
+mod vselect;
+mod vslab;
+mod vstatic;
+mod vtable;
+mod vvec;
+
+use std::alloc::Layout;
+use std::any::Any;
+use std::any::TypeId;
+use std::fmt;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::mem;
+use std::mem::ManuallyDrop;
+use std::ptr;
+
+pub use async_fn::VAsyncFnOnce;
+pub use batch::BatchPool;
+pub use batch::Batcher;
+pub use batch::ShutdownMode;
+pub use builder::VBoxBuilder;
+pub use cancel::CancelToken;
+pub use cast::CastRegistry;
+pub use config::VBoxConfig;
+pub use dead_letter::DeadLetter;
+pub use dead_letter::DeadLetterQueue;
+pub use dead_letter::DeadLetterReason;
+pub use dispatcher::DispatchError;
+pub use dispatcher::Dispatcher;
+pub use dispatcher::HandlerError;
+pub use dispose::AsyncDispose;
+use dispose::DisposeFn;
+pub use dump::Dump;
+pub use dump::DumpEntry;
+pub use envelope::Envelope;
+pub use fields::Fields;
+use fields::FieldsFn;
+pub use fields::RecordFields;
+pub use finalizer::Finalizers;
+pub use guard::VBoxGuard;
+pub use interner::ImplIndex;
+pub use interner::VTableInterner;
+pub use key_by_type::KeyByType;
+pub use key_by_type::TypeKey;
+pub use local::LocalVBox;
+pub use lru_cache::LruCache;
+pub use lru_cache::LruCapacity;
+pub use map_ext::VBoxMapExt;
+pub use mismatch::TypeMismatch;
+pub use mismatch::UnpackError;
+pub use mismatch_policy::MismatchPolicy;
+pub use packed::PackedVBox;
+pub use pipeline::Pipeline;
+pub use pipeline::PipelineError;
+pub use policy::PolicyVBox;
+pub use priority::PriorityMailbox;
+use probe::CloneCap;
+use probe::CloneFn;
+use probe::DebugCap;
+use probe::DisplayCap;
+use probe::DisposeCap;
+use probe::FieldsCap;
+use probe::No;
+pub use queue::Backpressure;
+pub use queue::DepthUnit;
+pub use queue::TryRecvError;
+pub use queue::TrySendError;
+pub use queue::VQueue;
+pub use queue::Watermarks;
+pub use remote::run_remote;
+pub use remote::run_remote_on;
+pub use reorder::ReorderBuffer;
+pub use reorder::Sequencer;
+pub use retry::Retry;
+pub use router::Fanout;
+pub use router::RouteFilter;
+pub use router::Router;
+pub use router::SubscriptionId;
+pub use router::TaggedVBox;
+pub use scope::async_scope;
+pub use scope::scope;
+pub use scope::AsyncScope;
+pub use scope::Scope;
+pub use sharded::ShardedQueue;
+pub use slot::Loan;
+pub use slot::LoanError;
+pub use slot::Slot;
+pub use state_machine::Next;
+pub use state_machine::StateMachine;
+pub use ttl_cache::TtlCache;
+pub use type_check::TypeCheck;
+pub use type_dispatcher::TypeDispatcher;
+pub use vasync::BoxFuture;
+pub use vasync::VAsync;
+#[cfg(feature = "derive")] pub use vbox_derive::erasable;
+#[cfg(feature = "derive")] pub use vbox_derive::IntoVBox;
+pub use vcall::VCall;
+pub use vcall::VCallBuilder;
+pub use verror::VError;
+pub use vjoin::join_vbox;
+pub use vjoin::JoinAll;
+pub use vonce::Canceled;
+pub use vonce::VOnce;
+pub use vonce::VOnceReceiver;
+pub use vpanic::VPanic;
+pub use vresult::VResult;
+pub use vselect::select_either;
+pub use vselect::select_vbox;
+pub use vselect::SelectEither;
+pub use vselect::SelectVBox;
+pub use vselect::VEither;
+pub use vselect::VFuture;
+pub use vslab::SlabError;
+pub use vslab::SlabKey;
+pub use vslab::VSlab;
+pub use vstatic::ErasedStaticRef;
+pub use vstatic::VStatic;
+pub use vtable::VTable;
+pub use vvec::VVec;
+
+/// A type erased Box of trait object that stores the vtable pointer.
+///
+/// This is just like a `Box<dyn Trait>` but erases type `Trait` so that the
+/// channel for sending it does not need to have `Trait` as one of its type
+/// parameters. Only the sending end and the receiving end need to agree on the
+/// type parameters.
+///
+/// Internally, it stores the trait object's data pointer and the vtable pointer
+/// separately. The layout of the payload and a `drop_fn` that drops the payload
+/// as `dyn Trait` are stored too, so that the `Drop::drop()` of the payload
+/// will be called and the memory will be released when the wrapper is dropped.
+///
+/// # Memory layout
+///
+/// The control data, i.e., the pointers, the type ids, the layout and the
+/// `drop_fn`, is stored inline in the `VBox` itself, e.g., in the slot of a
+/// channel buffer. The payload is stored in exactly one heap allocation, the
+/// same one a `Box<T>` would make, and a zero-sized payload is not allocated at
+/// all. Thus draining a queue of `VBox`es touches the same memory as draining a
+/// queue of `Box<dyn Trait>`es.
+///
+/// To keep the control data in the same allocation as the payload instead,
+/// pack it as a [`PackedVBox`] with [`into_packed_vbox!`].
+///
+/// # Lifetimes
+///
+/// The payload must be `'static`: `dyn Trait` is identified by its `TypeId`,
+/// which exists only for `'static` types. An explicit `dyn Trait + 'static` is
+/// accepted by the macros and is the same type as `dyn Trait`, thus either of
+/// them can be used to unpack. A closure may capture `&'static` data, but not a
+/// borrow of a local; use [`scope()`] to run closures that borrow locals.
+pub struct VBox {
+    /// The data pointer.
+    ///
+    /// It is owned by `VBox`. The payload is dropped by `drop_fn` and the
+    /// memory is released with `layout`.
+    data: *mut (),
+
+    /// The vtable pointer.
+    ///
+    /// A `usize` is as wide as a pointer on all targets, including `wasm32`.
+    vtable: usize,
+
+    /// Type id of `dyn Trait`, including the auto traits, e.g., `dyn Handler +
+    /// Send` and `dyn Handler + Send + Sync` have different type ids.
+    type_id: TypeId,
+
+    /// Name of `dyn Trait`, to report a mismatch when unpacking.
+    ///
+    /// It is a function pointer, which is half the size of a `&str`.
+    type_name: fn() -> &'static str,
+
+    /// Type id of the concrete type `T`, if it is known when packing.
+    ///
+    /// It is `None` if the `VBox` is built from an existing `Box<dyn Trait>`.
+    concrete_type_id: Option<TypeId>,
+
+    /// Name of the concrete type `T`, if it is known when packing, for
+    /// [`TypeCheck::TypeName`].
+    concrete_type_name: Option<fn() -> &'static str>,
+
+    /// Layout of the payload.
+    layout: Layout,
+
+    /// Rebuild the `*mut dyn Trait` from `data` and `vtable` and drop the
+    /// payload in place, without releasing the memory.
+    drop_fn: unsafe fn(*mut (), usize),
+
+    /// The optional functions of the concrete type captured when packing,
+    /// e.g., by [`into_vbox!`], [`into_vbox_debug!`],
+    /// [`into_vbox_disposable!`] or [`into_vbox_fields!`].
+    ///
+    /// They are kept in a static table, so that a `VBox` does not grow with
+    /// each of them.
+    hooks: Option<&'static Hooks>,
+
+    /// How to check the type to unpack, or `None` to use
+    /// [`TypeCheck::global()`].
+    type_check: Option<TypeCheck>,
+
+    /// What to do on a type mismatch, or `None` to use
+    /// [`MismatchPolicy::global()`].
+    mismatch_policy: Option<MismatchPolicy>,
+
+    /// A user defined tag, e.g., the kind of the message, or 0 if not set.
+    ///
+    /// A `u32` fits in the padding after `type_check` and `mismatch_policy`,
+    /// so that it does not grow the `VBox`.
+    tag: u32,
+
+    /// Detects dropping without unpacking, if `debug-unconsumed` is enabled.
+    tracker: unconsumed::Tracker,
+}
+
+/// See [`VBox::impl_key()`].
+pub(crate) type ImplKey = (TypeId, Result<TypeId, usize>);
+
+/// Formats the payload at the data pointer with `Debug`.
+type DebugFn = unsafe fn(*const (), &mut fmt::Formatter<'_>) -> fmt::Result;
+
+/// The optional functions of a concrete type, see [`VBox::hooks`].
+#[derive(Debug)]
+struct Hooks {
+    debug_fn: Option<DebugFn>,
+    clone_fn: Option<CloneFn>,
+    display_fn: Option<DebugFn>,
+    dispose_fn: Option<DisposeFn>,
+    fields_fn: Option<FieldsFn>,
+}
+
+/// Provides the static [`Hooks`] of `T`.
+struct HooksOf<T>(PhantomData<T>);
+
+impl<T: fmt::Debug> HooksOf<T> {
+    const DEBUG: &'static Hooks = &Hooks {
+        debug_fn: Some(debug_raw::<T>),
+        clone_fn: None,
+        display_fn: None,
+        dispose_fn: None,
+        fields_fn: None,
+    };
+}
+
+impl<T: AsyncDispose> HooksOf<T> {
+    const DISPOSE: &'static Hooks = &Hooks {
+        debug_fn: None,
+        clone_fn: None,
+        display_fn: None,
+        dispose_fn: Some(dispose::dispose_raw::<T>),
+        fields_fn: None,
+    };
+}
+
+impl<T: RecordFields> HooksOf<T> {
+    const FIELDS: &'static Hooks = &Hooks {
+        debug_fn: None,
+        clone_fn: None,
+        display_fn: None,
+        dispose_fn: None,
+        fields_fn: Some(fields::record_fields_raw::<T>),
+    };
+}
+
+/// The [`Hooks`] of the capabilities detected by [`into_vbox!`], or chosen
+/// with a [`VBoxBuilder`], or `None` if there is none.
+struct AutoHooks<D, C, P, X = No, F = No>(PhantomData<(D, C, P, X, F)>);
+
+impl<D, C, P, X, F> AutoHooks<D, C, P, X, F>
+where
+    D: DebugCap,
+    C: CloneCap,
+    P: DisplayCap,
+    X: DisposeCap,
+    F: FieldsCap,
+{
+    const ALL: &'static Hooks = &Hooks {
+        debug_fn: D::DEBUG_FN,
+        clone_fn: C::CLONE_FN,
+        display_fn: P::DISPLAY_FN,
+        dispose_fn: X::DISPOSE_FN,
+        fields_fn: F::FIELDS_FN,
+    };
+
+    const HOOKS: Option<&'static Hooks> = if D::DEBUG_FN.is_some()
+        || C::CLONE_FN.is_some()
+        || P::DISPLAY_FN.is_some()
+        || X::DISPOSE_FN.is_some()
+        || F::FIELDS_FN.is_some()
+    {
+        Some(Self::ALL)
+    } else {
+        None
+    };
+}
+
+/// Checks at compile time that `S` is zero-sized, for [`VBox::signal()`].
+struct ZeroSized<S>(PhantomData<S>);
+
+impl<S> ZeroSized<S> {
+    const ASSERT: () =
+        assert!(mem::size_of::<S>() == 0, "signal must be zero-sized");
+}
+
+/// A `VBox` can only be built from a `Send` payload.
+unsafe impl Send for VBox {}
+
+impl VBox {
+    /// Create a new VBox. Do not use it directly. Use [`into_vbox!`] instead.
+    ///
+    /// # Safety
+    ///
+    /// `coerce` must be an unsizing coercion from `Box<T>` to `Box<dyn
+    /// Trait>`, i.e., it must return the very same box it is given.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    pub unsafe fn new<T, U>(value: T, coerce: fn(Box<T>) -> Box<U>) -> Self
+    where
+        T: Send + 'static,
+        U: ?Sized + 'static,
+    {
+        #[cfg(feature = "stats")]
+        stats::name_type::<T>();
+
+        let concrete_type_id = Some(TypeId::of::<T>());
+        Self::from_box_unchecked(
+            coerce(new_box(value)),
+            concrete_type_id,
+            Some(std::any::type_name::<T>),
+        )
+    }
+
+    /// Create a new VBox and capture the implementations of the payload
+    /// detected by [`into_vbox!`]. Do not use it directly. Use [`into_vbox!`]
+    /// instead.
+    ///
+    /// # Safety
+    ///
+    /// `coerce` must be an unsizing coercion from `Box<T>` to `Box<dyn
+    /// Trait>`, i.e., it must return the very same box it is given.
+    #[doc(hidden)]
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    pub unsafe fn new_auto<T, U, D, C, P>(
+        value: T,
+        coerce: fn(Box<T>) -> Box<U>,
+        _caps: (D, C, P),
+    ) -> Self
+    where
+        T: Send + 'static,
+        U: ?Sized + 'static,
+        D: DebugCap,
+        C: CloneCap,
+        P: DisplayCap,
+    {
+        let mut vbox = Self::new(value, coerce);
+        vbox.hooks = AutoHooks::<D, C, P>::HOOKS;
+        vbox
+    }
+
+    /// Create a new VBox and capture the `Debug` implementation of the
+    /// payload. Do not use it directly. Use [`into_vbox_debug!`] instead.
+    ///
+    /// # Safety
+    ///
+    /// `coerce` must be an unsizing coercion from `Box<T>` to `Box<dyn
+    /// Trait>`, i.e., it must return the very same box it is given.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    pub unsafe fn new_debug<T, U>(
+        value: T,
+        coerce: fn(Box<T>) -> Box<U>,
+    ) -> Self
+    where
+        T: fmt::Debug + Send + 'static,
+        U: ?Sized + 'static,
+    {
+        let mut vbox = Self::new(value, coerce);
+        vbox.hooks = Some(HooksOf::<T>::DEBUG);
+        vbox
+    }
+
+    /// Create a new VBox and capture the [`AsyncDispose`] implementation of
+    /// the payload. Do not use it directly. Use [`into_vbox_disposable!`]
+    /// instead.
+    ///
+    /// # Safety
+    ///
+    /// `coerce` must be an unsizing coercion from `Box<T>` to `Box<dyn
+    /// Trait>`, i.e., it must return the very same box it is given.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    pub unsafe fn new_disposable<T, U>(
+        value: T,
+        coerce: fn(Box<T>) -> Box<U>,
+    ) -> Self
+    where
+        T: AsyncDispose,
+        U: ?Sized + 'static,
+    {
+        let mut vbox = Self::new(value, coerce);
+        vbox.hooks = Some(HooksOf::<T>::DISPOSE);
+        vbox
+    }
+
+    /// Create a new VBox and capture the [`RecordFields`] implementation of
+    /// the payload. Do not use it directly. Use [`into_vbox_fields!`] instead.
+    ///
+    /// # Safety
+    ///
+    /// `coerce` must be an unsizing coercion from `Box<T>` to `Box<dyn
+    /// Trait>`, i.e., it must return the very same box it is given.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    pub unsafe fn new_fields<T, U>(
+        value: T,
+        coerce: fn(Box<T>) -> Box<U>,
+    ) -> Self
+    where
+        T: RecordFields,
+        U: ?Sized + 'static,
+    {
+        let mut vbox = Self::new(value, coerce);
+        vbox.hooks = Some(HooksOf::<T>::FIELDS);
+        vbox
+    }
+
+    /// Start building a `VBox` of `value`, packed as `dyn Any + Send`, with
+    /// the optional capabilities and metadata chosen one by one. Use
+    /// [`vbox_builder!`] to pack it as another `dyn Trait`.
+    ///
+    /// ```
+    /// # use vbox::VBox;
+    /// let vbox = VBox::builder(3u64).with_display().tag(1).build();
+    ///
+    /// assert_eq!(Some("3".to_string()), vbox.display_string());
+    /// assert_eq!(None, vbox.debug_string());
+    /// assert_eq!(1, vbox.tag());
+    /// assert_eq!(3, vbox.into_inner::<u64>().unwrap());
+    /// ```
+    pub fn builder<T>(value: T) -> VBoxBuilder<T>
+    where T: Send + 'static {
+        VBoxBuilder::new(value)
+    }
+
+    /// Replace the payload with a new value. Do not use it directly. Use
+    /// [`replace_vbox!`] instead.
+    ///
+    /// If the new value has the same layout as the old payload, the old payload
+    /// is dropped and the new value is written into the same allocation.
+    /// Otherwise a new allocation is made.
+    ///
+    /// If dropping the old payload panics, the panic is propagated, the new
+    /// value is dropped, and the `VBox` is left with a `()` packed as `dyn Any
+    /// + Send`.
+    ///
+    /// # Safety
+    ///
+    /// `coerce` must be an unsizing coercion from `Box<T>` to `Box<dyn
+    /// Trait>`, i.e., it must return the very same box it is given.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    pub unsafe fn replace<T, U>(
+        &mut self,
+        value: T,
+        coerce: fn(Box<T>) -> Box<U>,
+    ) where
+        T: Send + 'static,
+        U: ?Sized + 'static,
+    {
+        self.tracker.consume();
+        let type_check = self.type_check;
+        let mismatch_policy = self.mismatch_policy;
+        let tag = self.tag;
+
+        if self.layout != Layout::new::<T>() || self.layout.size() == 0 {
+            *self = Self::new(value, coerce);
+            self.type_check = type_check;
+            self.mismatch_policy = mismatch_policy;
+            self.tag = tag;
+            return;
+        }
+
+        #[cfg(feature = "stats")]
+        stats::name_type::<T>();
+
+        let concrete_type_id = Some(TypeId::of::<T>());
+        let concrete_type_name = Some(std::any::type_name::<T> as fn() -> _);
+
+        // The pack hook may reject it before anything is changed.
+        Self::record_pack::<U>(
+            self.layout,
+            concrete_type_id,
+            concrete_type_name,
+        );
+
+        #[cfg(feature = "stats")]
+        stats::dropped(
+            self.type_id,
+            self.type_name,
+            self.concrete_type_id,
+            self.layout.size(),
+        );
+
+        // Until the new value is written, `self` refers to a dropped payload.
+        // If the drop panics, it is emptied instead of being dropped again.
+        let guard = EmptyOnUnwind(self);
+        (guard.0.drop_fn)(guard.0.data, guard.0.vtable);
+        mem::forget(guard);
+
+        let data = self.data as *mut T;
+        ptr::write(data, value);
+
+        let (data, vtable) = into_raw_parts(coerce(Box::from_raw(data)));
+
+        self.data = data;
+        self.vtable = vtable;
+        self.type_id = TypeId::of::<U>();
+        self.type_name = std::any::type_name::<U>;
+        self.concrete_type_id = concrete_type_id;
+        self.concrete_type_name = concrete_type_name;
+        self.drop_fn = drop_in_place_raw_parts::<U>;
+        self.hooks = None;
+        self.tracker = unconsumed::Tracker::new();
+    }
+
+    /// Pack `value` as the same `dyn Trait` as this `VBox`, reusing its
+    /// vtable, if the payload of this `VBox` is a `T`. Otherwise `value` is
+    /// returned in `Err`.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    pub(crate) fn pack_like<T>(&self, value: T) -> Result<VBox, T>
+    where T: Send + 'static {
+        if !self.is::<T>() {
+            return Err(value);
+        }
+
+        #[cfg(feature = "log")]
+        log::debug!(
+            "VBox pack: {}{}",
+            (self.type_name)(),
+            config::Site(std::panic::Location::caller())
+        );
+
+        #[cfg(feature = "pack-hook")]
+        pack_hook::check(
+            (self.type_name)(),
+            self.layout.size(),
+            self.layout.align(),
+        );
+
+        #[cfg(feature = "stats")]
+        stats::packed(
+            self.type_id,
+            self.type_name,
+            self.concrete_type_id,
+            self.layout.size(),
+        );
+
+        #[cfg(feature = "timeline")]
+        timeline::record(
+            timeline::Kind::Pack,
+            self.concrete_type_name.map(|f| f()),
+            (self.type_name)(),
+        );
+
+        Ok(VBox {
+            data: Box::into_raw(new_box(value)) as *mut (),
+            vtable: self.vtable,
+            type_id: self.type_id,
+            type_name: self.type_name,
+            concrete_type_id: self.concrete_type_id,
+            concrete_type_name: self.concrete_type_name,
+            layout: self.layout,
+            drop_fn: self.drop_fn,
+            hooks: self.hooks,
+            type_check: self.type_check,
+            mismatch_policy: self.mismatch_policy,
+            tag: 0,
+            tracker: unconsumed::Tracker::new(),
+        })
+    }
+
+    /// Create a `VBox` of `()`, packed as `dyn Any + Send`.
+    ///
+    /// It is a cheap contentless message, no allocation is made.
+    pub fn unit() -> Self {
+        Self::signal(())
+    }
+
+    /// Create a `VBox` of a zero-sized control message, e.g., `struct
+    /// Shutdown;`, packed as `dyn Any + Send`.
+    ///
+    /// No allocation is made. Use [`VBox::is_signal()`] to test for it.
+    ///
+    /// ```
+    /// # use vbox::VBox;
+    /// struct Shutdown;
+    ///
+    /// let vbox = VBox::signal(Shutdown);
+    /// assert!(vbox.is_signal::<Shutdown>());
+    /// ```
+    ///
+    /// A signal that is not zero-sized is a compile error:
+    ///
+    /// ```compile_fail
+    /// # use vbox::VBox;
+    /// let vbox = VBox::signal(1u64);
+    /// ```
+    pub fn signal<S: Send + 'static>(s: S) -> Self {
+        // Referring to the const evaluates it for this `S`.
+        #[allow(clippy::let_unit_value)]
+        let () = ZeroSized::<S>::ASSERT;
+
+        unsafe { Self::new(s, |b| -> Box<dyn Any + Send> { b }) }
+    }
+
+    /// Returns `true` if it is built with [`VBox::unit()`].
+    pub fn is_unit(&self) -> bool {
+        self.is_signal::<()>()
+    }
+
+    /// Returns `true` if it is a control message of type `S` built with
+    /// [`VBox::signal()`].
+    pub fn is_signal<S: 'static>(&self) -> bool {
+        self.is::<S>() && self.type_id == TypeId::of::<dyn Any + Send>()
+    }
+
+    /// Create a new VBox from an existing `Box<dyn Trait>`. Use
+    /// [`into_vbox_dyn!`] for a better readability.
+    ///
+    /// Since the concrete type is unknown, the vtable pointer is extracted from
+    /// the existing fat pointer. The trait object must be `Send`, e.g.,
+    /// `Box<dyn Trait + Send>`.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    pub fn from_box<U>(boxed: Box<U>) -> Self
+    where U: ?Sized + Send + 'static {
+        unsafe { Self::from_box_unchecked(boxed, None, None) }
+    }
+
+    /// Create a new VBox from a `Box<dyn Any + Send>`, packed as `dyn Any +
+    /// Send`.
+    ///
+    /// Unlike [`VBox::from_box()`], the concrete type is recovered from `Any`,
+    /// so that [`VBox::into_inner()`] works.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    pub fn from_any(boxed: Box<dyn Any + Send>) -> Self {
+        let concrete_type_id = Some(Any::type_id(&*boxed));
+        unsafe { Self::from_box_unchecked(boxed, concrete_type_id, None) }
+    }
+
+    /// Create a new VBox from a `Box<dyn Any + Send + Sync>`, e.g., an entry
+    /// of `http::Extensions`, packed as `dyn Any + Send + Sync`.
+    ///
+    /// The concrete type is recovered from `Any`, so that
+    /// [`VBox::into_inner()`] works.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    pub fn from_any_sync(boxed: Box<dyn Any + Send + Sync>) -> Self {
+        let concrete_type_id = Some(Any::type_id(&*boxed));
+        unsafe { Self::from_box_unchecked(boxed, concrete_type_id, None) }
+    }
+
+    /// Convert to `Box<dyn Any + Send>`.
+    ///
+    /// It succeeds if the `VBox` is packed as `dyn Any + Send` or `dyn Any +
+    /// Send + Sync`. Otherwise the `VBox` is returned intact in `Err`.
+    pub fn into_any(self) -> Result<Box<dyn Any + Send>, Self> {
+        if self.is_dyn::<dyn Any + Send + Sync>() {
+            return Ok(self.unpack::<dyn Any + Send + Sync>());
+        }
+        if self.is_dyn::<dyn Any + Send>() {
+            return Ok(self.unpack::<dyn Any + Send>());
+        }
+        Err(self)
+    }
+
+    /// Convert to `Box<dyn Any + Send + Sync>`.
+    ///
+    /// It succeeds only if the `VBox` is packed as `dyn Any + Send + Sync`,
+    /// e.g., with `into_vbox!(dyn Any + Send + Sync, v)` for a `Sync` payload.
+    /// Otherwise the `VBox` is returned intact in `Err`.
+    ///
+    /// ```
+    /// # use std::any::Any;
+    /// # use vbox::{into_vbox, VBox};
+    /// let vbox: VBox = into_vbox!(dyn Any + Send + Sync, 3u64);
+    /// let any = vbox.into_any_sync().unwrap();
+    /// assert_eq!(Some(&3u64), any.downcast_ref::<u64>());
+    ///
+    /// let vbox = VBox::from_any_sync(any);
+    /// assert_eq!(3u64, vbox.into_inner::<u64>().unwrap());
+    /// ```
+    pub fn into_any_sync(self) -> Result<Box<dyn Any + Send + Sync>, Self> {
+        if self.is_dyn::<dyn Any + Send + Sync>() {
+            return Ok(self.unpack::<dyn Any + Send + Sync>());
+        }
+        Err(self)
+    }
+
+    /// Create a new VBox from a data pointer and a vtable pointer of `dyn
+    /// Trait`, e.g., computed by an FFI producer or a code generator.
+    ///
+    /// `concrete_type_id` is the type id of the concrete type of the payload,
+    /// if it is known, see [`VBox::is()`].
+    ///
+    /// ```
+    /// # use std::any::TypeId;
+    /// # use std::fmt::Debug;
+    /// # use vbox::{from_vbox, VBox};
+    /// let fat: *mut dyn Debug = Box::into_raw(Box::new(10u64));
+    /// let (data, vtable): (*mut (), *const ()) = unsafe { std::mem::transmute(fat) };
+    ///
+    /// let vbox = unsafe {
+    ///     VBox::new_unchecked::<dyn Debug>(data, vtable, Some(TypeId::of::<u64>()))
+    /// };
+    /// assert!(vbox.is::<u64>());
+    /// assert_eq!("10", format!("{:?}", from_vbox!(dyn Debug, vbox)));
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// - `data` and `vtable` must be the parts of a valid `*mut dyn Trait`,
+    ///   where `dyn Trait` is `U`.
+    /// - `data` must be allocated by the global allocator with the layout of
+    ///   the payload, as `Box` does. The `VBox` takes the ownership of it.
+    /// - The payload must be `Send`.
+    /// - `concrete_type_id` must be `None` or the type id of the payload.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    pub unsafe fn new_unchecked<U>(
+        data: *mut (),
+        vtable: *const (),
+        concrete_type_id: Option<TypeId>,
+    ) -> Self
+    where
+        U: ?Sized + 'static,
+    {
+        let fat_ptr = from_raw_parts::<U>(data, vtable as usize);
+        Self::from_box_unchecked(Box::from_raw(fat_ptr), concrete_type_id, None)
+    }
+
+    /// `concrete_type_name` is the name of the concrete type, if it is known.
+    ///
+    /// # Safety
+    ///
+    /// The payload in the `boxed` must be `Send`.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    unsafe fn from_box_unchecked<U>(
+        boxed: Box<U>,
+        concrete_type_id: Option<TypeId>,
+        concrete_type_name: Option<fn() -> &'static str>,
+    ) -> Self
+    where
+        U: ?Sized + 'static,
+    {
+        let layout = Layout::for_value(&*boxed);
+        Self::record_pack::<U>(layout, concrete_type_id, concrete_type_name);
+
+        let (data, vtable) = into_raw_parts(boxed);
+
+        VBox {
+            data,
+            vtable,
+            type_id: TypeId::of::<U>(),
+            type_name: std::any::type_name::<U>,
+            concrete_type_id,
+            concrete_type_name,
+            layout,
+            drop_fn: drop_in_place_raw_parts::<U>,
+            hooks: None,
+            type_check: None,
+            mismatch_policy: None,
+            tag: 0,
+            tracker: unconsumed::Tracker::new(),
+        }
+    }
+
+    /// Report packing a payload of `layout` as `U` to the enabled features,
+    /// e.g., the pack hook, which may reject it by panicking.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    #[allow(unused_variables)]
+    fn record_pack<U>(
+        layout: Layout,
+        concrete_type_id: Option<TypeId>,
+        concrete_type_name: Option<fn() -> &'static str>,
+    ) where
+        U: ?Sized + 'static,
+    {
+        #[cfg(feature = "log")]
+        log::debug!(
+            "VBox pack: {}{}",
+            std::any::type_name::<U>(),
+            config::Site(std::panic::Location::caller())
+        );
+
+        #[cfg(feature = "pack-hook")]
+        pack_hook::check(
+            std::any::type_name::<U>(),
+            layout.size(),
+            layout.align(),
+        );
+
+        #[cfg(feature = "stats")]
+        stats::packed(
+            TypeId::of::<U>(),
+            std::any::type_name::<U>,
+            concrete_type_id,
+            layout.size(),
+        );
+
+        #[cfg(feature = "timeline")]
+        timeline::record(
+            timeline::Kind::Pack,
+            concrete_type_name.map(|f| f()),
+            std::any::type_name::<U>(),
+        );
+    }
+
+    /// Unpack the `VBox` and rebuild the original trait object. Do not use it
+    /// directly. Use [`from_vbox!`] instead.
+    #[cfg_attr(any(feature = "log", feature = "timeline"), track_caller)]
+    pub fn unpack<U>(self) -> Box<U>
+    where U: ?Sized + 'static {
+        self.check_type::<U>();
+        unsafe { self.unpack_unchecked::<U>() }
+    }
+
+    /// Unpack the `VBox` and rebuild the original trait object, or return the
+    /// `VBox` intact in `Err` if it is not packed as `U`. It never panics. Use
+    /// [`try_from_vbox!`] instead.
+    // The error carries the `VBox`, which is as large as a `VBox`.
+    #[allow(clippy::result_large_err)]
+    #[cfg_attr(any(feature = "log", feature = "timeline"), track_caller)]
+    pub fn try_unpack<U>(self) -> Result<Box<U>, UnpackError>
+    where U: ?Sized + 'static {
+        if !self.is_dyn::<U>() {
+            return Err(UnpackError::new(TypeMismatch::new::<U>(&self), self));
+        }
+        Ok(unsafe { self.unpack_unchecked::<U>() })
+    }
+
+    /// Unpack a batch of `VBox`es packed as the same `dyn Trait`. Do not use
+    /// it directly. Use [`from_vbox_vec!`] instead.
+    ///
+    /// The type is checked once per group of `VBox`es sharing a vtable, rather
+    /// than once per `VBox`. All of them are checked before any is unpacked.
+    #[cfg_attr(any(feature = "log", feature = "timeline"), track_caller)]
+    pub fn unpack_vec<U>(vboxes: Vec<VBox>) -> Vec<Box<U>>
+    where U: ?Sized + 'static {
+        match Self::try_unpack_vec::<U>(vboxes) {
+            Ok(unpacked) => unpacked,
+            Err(vboxes) => {
+                let bad = vboxes.iter().find(|v| !v.is_dyn::<U>()).unwrap();
+                bad.check_type::<U>();
+                unreachable!("the type check above must fail")
+            }
+        }
+    }
+
+    /// Unpack a batch of `VBox`es packed as the same `dyn Trait`, or return
+    /// all of them intact in `Err` if any is not packed as `U`.
+    ///
+    /// The type is checked once per group of `VBox`es sharing a vtable, rather
+    /// than once per `VBox`.
+    ///
+    /// ```
+    /// # use std::fmt::{Debug, Display};
+    /// # use vbox::{into_vbox, VBox};
+    /// let vboxes = vec![into_vbox!(dyn Display, 1u64), into_vbox!(dyn Debug, 2u64)];
+    ///
+    /// let Err(vboxes) = VBox::try_unpack_vec::<dyn Display>(vboxes) else {
+    ///     panic!("expect Err");
+    /// };
+    /// assert_eq!(2, vboxes.len());
+    /// ```
+    #[cfg_attr(any(feature = "log", feature = "timeline"), track_caller)]
+    pub fn try_unpack_vec<U>(
+        vboxes: Vec<VBox>,
+    ) -> Result<Vec<Box<U>>, Vec<VBox>>
+    where U: ?Sized + 'static {
+        // A batch usually has only a few implementations.
+        let mut checked = Vec::<(usize, TypeId, TypeCheck)>::new();
+
+        for v in vboxes.iter() {
+            let key = (v.vtable, v.type_id, v.type_check());
+            if checked.contains(&key) {
+                continue;
+            }
+            if !v.is_dyn::<U>() {
+                return Err(vboxes);
+            }
+            checked.push(key);
+        }
+
+        let unpacked = vboxes
+            .into_iter()
+            .map(|v| unsafe { v.unpack_unchecked::<U>() })
+            .collect();
+        Ok(unpacked)
+    }
+
+    /// Unpack without checking the type.
+    ///
+    /// # Safety
+    ///
+    /// The `VBox` must be packed as `U`.
+    #[cfg_attr(any(feature = "log", feature = "timeline"), track_caller)]
+    pub(crate) unsafe fn unpack_unchecked<U>(self) -> Box<U>
+    where U: ?Sized + 'static {
+        #[cfg(feature = "log")]
+        log::debug!(
+            "VBox unpack: {}{}",
+            (self.type_name)(),
+            config::Site(std::panic::Location::caller())
+        );
+
+        #[cfg(feature = "stats")]
+        stats::unpacked(
+            self.type_id,
+            self.type_name,
+            self.concrete_type_id,
+            self.layout.size(),
+        );
+
+        #[cfg(feature = "timeline")]
+        timeline::record(timeline::Kind::Unpack, None, (self.type_name)());
+
+        let this = ManuallyDrop::new(self);
+        Box::from_raw(from_raw_parts::<U>(this.data, this.vtable))
+    }
+
+    /// Consume the `VBox` and return its raw parts, without dropping the
+    /// payload or releasing the memory.
+    ///
+    /// The caller is responsible for the payload, e.g., an intrusive scheduler
+    /// or an FFI callback that rebuilds the `VBox` later with
+    /// [`VBox::from_raw()`], so that the payload is dropped.
+    ///
+    /// ```
+    /// # use std::fmt::Debug;
+    /// # use vbox::{from_vbox, into_vbox, VBox};
+    /// let vbox: VBox = into_vbox!(dyn Debug, 10u64);
+    ///
+    /// let raw = vbox.into_raw();
+    /// let vbox = unsafe { VBox::from_raw(raw) };
+    ///
+    /// assert_eq!("10", format!("{:?}", from_vbox!(dyn Debug, vbox)));
+    /// ```
+    pub fn into_raw(self) -> RawVBox {
+        let this = ManuallyDrop::new(self);
+        RawVBox {
+            data: this.data,
+            vtable: this.vtable,
+            type_id: this.type_id,
+            type_name: this.type_name,
+            concrete_type_id: this.concrete_type_id,
+            concrete_type_name: this.concrete_type_name,
+            layout: this.layout,
+            drop_fn: this.drop_fn,
+            hooks: this.hooks,
+            type_check: this.type_check,
+            mismatch_policy: this.mismatch_policy,
+            tag: this.tag,
+        }
+    }
+
+    /// Rebuild a `VBox` from the raw parts returned by [`VBox::into_raw()`].
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be returned by [`VBox::into_raw()`], and a `RawVBox` must be
+    /// rebuilt at most once, since the `VBox` owns the payload.
+    #[cfg_attr(feature = "debug-unconsumed", track_caller)]
+    pub unsafe fn from_raw(raw: RawVBox) -> Self {
+        VBox {
+            data: raw.data,
+            vtable: raw.vtable,
+            type_id: raw.type_id,
+            type_name: raw.type_name,
+            concrete_type_id: raw.concrete_type_id,
+            concrete_type_name: raw.concrete_type_name,
+            layout: raw.layout,
+            drop_fn: raw.drop_fn,
+            hooks: raw.hooks,
+            type_check: raw.type_check,
+            mismatch_policy: raw.mismatch_policy,
+            tag: raw.tag,
+            tracker: unconsumed::Tracker::new(),
+        }
+    }
+
+    /// Consume the `VBox` and leak the payload as a `&'static mut dyn Trait`.
+    /// Do not use it directly. Use [`leak_vbox!`] instead.
+    ///
+    /// The payload will never be dropped.
+    pub fn leak<U>(self) -> &'static mut U
+    where U: ?Sized + 'static {
+        Box::leak(self.unpack::<U>())
+    }
+
+    /// Drop the `VBox` and its payload on purpose.
+    ///
+    /// It is the same as `drop()`, except that it is not reported as a lost
+    /// message if the `debug-unconsumed` feature is enabled.
+    pub fn discard(self) {
+        self.tracker.consume();
+    }
+
+    /// Dispose the payload asynchronously, with the [`AsyncDispose`]
+    /// implementation captured by [`into_vbox_disposable!`].
+    ///
+    /// If no implementation is captured, the payload is dropped right away,
+    /// and the returned future is ready. It is not reported as a lost message
+    /// if the `debug-unconsumed` feature is enabled.
+    ///
+    /// ```
+    /// # use std::fmt::Debug;
+    /// # use vbox::{into_vbox_disposable, AsyncDispose, BoxFuture};
+    /// #[derive(Debug)]
+    /// struct Conn(u64);
+    ///
+    /// impl AsyncDispose for Conn {
+    ///     fn dispose(self) -> BoxFuture<'static, ()> {
+    ///         Box::pin(async move { println!("bye {}", self.0) })
+    ///     }
+    /// }
+    ///
+    /// let vbox = into_vbox_disposable!(dyn Debug + Send, Conn(1));
+    /// assert!(vbox.is_disposable());
+    /// futures::executor::block_on(vbox.dispose());
+    /// ```
+    #[cfg_attr(feature = "timeline", track_caller)]
+    pub fn dispose(self) -> BoxFuture<'static, ()> {
+        let Some(dispose_fn) = self.hooks.and_then(|h| h.dispose_fn) else {
+            self.discard();
+            return Box::pin(async {});
+        };
+
+        #[cfg(feature = "log")]
+        log::debug!("VBox dispose: {}", (self.type_name)());
+
+        #[cfg(feature = "stats")]
+        stats::dropped(
+            self.type_id,
+            self.type_name,
+            self.concrete_type_id,
+            self.layout.size(),
+        );
+
+        #[cfg(feature = "timeline")]
+        timeline::record(timeline::Kind::Unpack, None, (self.type_name)());
+
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            let fu = dispose_fn(this.data);
+            free_payload(this.data, this.layout);
+            fu
+        }
+    }
+
+    /// Returns `true` if it is built with [`into_vbox_disposable!`], i.e.,
+    /// [`VBox::dispose()`] runs an async cleanup.
+    pub fn is_disposable(&self) -> bool {
+        self.hooks.is_some_and(|h| h.dispose_fn.is_some())
+    }
+
+    /// Returns the summary of the payload produced by the inspector registered
+    /// for its concrete type with [`inspector::register()`].
+    ///
+    /// It returns `None` if no inspector is registered, or the concrete type
+    /// is unknown, e.g., the `VBox` is built from a `Box<dyn Trait>`.
+    pub fn summary(&self) -> Option<String> {
+        let type_id = self.concrete_type_id?;
+        unsafe { inspector::summarize(type_id, self.data) }
+    }
+
+    /// Returns the structured fields of the payload, recorded with the
+    /// [`RecordFields`] implementation captured by [`into_vbox_fields!`].
+    ///
+    /// It is meant for logging a message without unpacking it, e.g., with
+    /// `tracing::debug!(fields = %vbox.fields(), "received")`.
+    ///
+    /// ```
+    /// # use std::fmt::Debug;
+    /// # use vbox::{into_vbox_fields, RecordFields};
+    /// #[derive(Debug)]
+    /// struct Get {
+    ///     id: u64,
+    ///     key: String,
+    /// }
+    ///
+    /// impl RecordFields for Get {
+    ///     fn record_fields(&self, record: &mut dyn FnMut(&str, &dyn Debug)) {
+    ///         record("id", &self.id);
+    ///         record("key", &self.key);
+    ///     }
+    /// }
+    ///
+    /// let vbox = into_vbox_fields!(dyn Debug + Send, Get { id: 3, key: "a".to_string() });
+    /// assert_eq!(r#"id=3 key="a""#, vbox.fields().to_string());
+    /// ```
+    pub fn fields(&self) -> Fields<'_> {
+        Fields::new(self)
+    }
+
+    /// Call `record` with the name and the value of every field of the
+    /// payload, if it is built with [`into_vbox_fields!`].
+    ///
+    /// It returns `false` if no [`RecordFields`] implementation is captured.
+    pub fn record_fields(
+        &self,
+        mut record: impl FnMut(&str, &dyn fmt::Debug),
+    ) -> bool {
+        let Some(fields_fn) = self.hooks.and_then(|h| h.fields_fn) else {
+            return false;
+        };
+        unsafe { fields_fn(self.data, &mut record) };
+        true
+    }
+
+    /// Returns `true` if it is built with [`into_vbox_fields!`].
+    pub fn has_fields(&self) -> bool {
+        self.hooks.is_some_and(|h| h.fields_fn.is_some())
+    }
+
+    /// Borrow the payload as `&dyn Trait`. Do not use it directly. Use
+    /// [`with_vbox!`] instead.
+    pub fn as_dyn<U>(&self) -> &U
+    where U: ?Sized + 'static {
+        self.check_type::<U>();
+        unsafe { self.as_dyn_unchecked::<U>() }
+    }
+
+    /// Borrow the payload as `&dyn Trait`, or return `Err` if it is not packed
+    /// as `U`. It never panics.
+    pub fn try_as_dyn<U>(&self) -> Result<&U, TypeMismatch>
+    where U: ?Sized + 'static {
+        if !self.is_dyn::<U>() {
+            return Err(TypeMismatch::new::<U>(self));
+        }
+        Ok(unsafe { self.as_dyn_unchecked::<U>() })
+    }
+
+    /// Borrow as `&dyn Trait` without checking the type.
+    ///
+    /// # Safety
+    ///
+    /// The `VBox` must be packed as `U`.
+    pub(crate) unsafe fn as_dyn_unchecked<U>(&self) -> &U
+    where U: ?Sized + 'static {
+        self.tracker.consume();
+        &*from_raw_parts::<U>(self.data, self.vtable)
+    }
+
+    /// Borrow the payload as `&mut dyn Trait`. Do not use it directly. Use
+    /// [`with_vbox!`] instead.
+    pub fn as_dyn_mut<U>(&mut self) -> &mut U
+    where U: ?Sized + 'static {
+        self.check_type::<U>();
+        unsafe { self.as_dyn_mut_unchecked::<U>() }
+    }
+
+    /// Borrow the payload as `&mut dyn Trait`, or return `Err` if it is not
+    /// packed as `U`. It never panics.
+    pub fn try_as_dyn_mut<U>(&mut self) -> Result<&mut U, TypeMismatch>
+    where U: ?Sized + 'static {
+        if !self.is_dyn::<U>() {
+            return Err(TypeMismatch::new::<U>(self));
+        }
+        Ok(unsafe { self.as_dyn_mut_unchecked::<U>() })
+    }
+
+    /// Borrow as `&mut dyn Trait` without checking the type.
+    ///
+    /// # Safety
+    ///
+    /// The `VBox` must be packed as `U`.
+    pub(crate) unsafe fn as_dyn_mut_unchecked<U>(&mut self) -> &mut U
+    where U: ?Sized + 'static {
+        self.tracker.consume();
+        &mut *from_raw_parts::<U>(self.data, self.vtable)
+    }
+
+    /// Returns the data pointer of the payload.
+    ///
+    /// It is informational only, e.g., for debugging or deduplication. Use
+    /// [`VBox::into_raw()`] to take the ownership of the payload.
+    pub fn data_ptr(&self) -> *const () {
+        self.data
+    }
+
+    /// Returns the vtable pointer of `dyn Trait`.
+    ///
+    /// It is informational only. The same trait implementation may have
+    /// different vtable pointers in different codegen units, and vice versa.
+    pub fn vtable_ptr(&self) -> *const () {
+        self.vtable as *const ()
+    }
+
+    /// Returns the size in bytes of the payload, captured when packing.
+    ///
+    /// It does not include the heap memory the payload may own, e.g., the
+    /// buffer of a `String`.
+    pub fn size_of_payload(&self) -> usize {
+        self.layout.size()
+    }
+
+    /// Returns the alignment in bytes of the payload, captured when packing.
+    pub fn align(&self) -> usize {
+        self.layout.align()
+    }
+
+    /// Format the payload with `Debug`, if it is built with
+    /// [`into_vbox_debug!`], or with [`into_vbox!`] where it implements
+    /// `Debug`, without knowing `dyn Trait`.
+    ///
+    /// It is useful for a dead-letter handler or a logger to show the content
+    /// of a message it can not unpack.
+    ///
+    /// ```
+    /// # use std::fmt::Display;
+    /// # use vbox::{into_vbox, into_vbox_debug, VBox};
+    /// let vbox: VBox = into_vbox_debug!(dyn Display, "foo");
+    /// assert_eq!(Some(r#""foo""#.to_string()), vbox.debug_string());
+    ///
+    /// let vbox: VBox = into_vbox!(dyn Display, "foo");
+    /// assert_eq!(Some(r#""foo""#.to_string()), vbox.debug_string());
+    ///
+    /// fn pack<T: Display + Send + 'static>(v: T) -> VBox {
+    ///     // `T` is not known to implement `Debug` here
+    ///     into_vbox!(dyn Display, v)
+    /// }
+    /// assert_eq!(None, pack("foo").debug_string());
+    /// ```
+    pub fn debug_string(&self) -> Option<String> {
+        self.inspect(|args| args.to_string())
+    }
+
+    /// Call `f` with the payload formatted with `Debug`, if its `Debug`
+    /// implementation is captured, without allocating a `String`.
+    ///
+    /// It returns `None` if the `Debug` implementation is not captured.
+    ///
+    /// ```
+    /// # use std::fmt::Display;
+    /// # use vbox::{into_vbox_debug, VBox};
+    /// let vbox: VBox = into_vbox_debug!(dyn Display, 3u64);
+    ///
+    /// let mut out = String::new();
+    /// vbox.inspect(|args| std::fmt::write(&mut out, args).unwrap());
+    /// assert_eq!("3", out);
+    /// ```
+    pub fn inspect<R>(
+        &self,
+        f: impl FnOnce(fmt::Arguments<'_>) -> R,
+    ) -> Option<R> {
+        let debug_fn = self.hooks?.debug_fn?;
+
+        struct Payload {
+            data: *const (),
+            debug_fn: DebugFn,
+        }
+
+        impl fmt::Debug for Payload {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                unsafe { (self.debug_fn)(self.data, f) }
+            }
+        }
+
+        let payload = Payload {
+            data: self.data,
+            debug_fn,
+        };
+        Some(f(format_args!("{:?}", payload)))
+    }
+
+    /// Format the payload with `Display`, if it implements `Display` where it
+    /// is packed with [`into_vbox!`], without knowing `dyn Trait`.
+    ///
+    /// ```
+    /// # use std::any::Any;
+    /// # use vbox::{into_vbox, VBox};
+    /// let vbox: VBox = into_vbox!(dyn Any + Send, 3u64);
+    /// assert_eq!(Some("3".to_string()), vbox.display_string());
+    ///
+    /// let vbox: VBox = into_vbox!(dyn Any + Send, vec![3u64]);
+    /// assert_eq!(None, vbox.display_string());
+    /// ```
+    pub fn display_string(&self) -> Option<String> {
+        let display_fn = self.hooks?.display_fn?;
+
+        struct Payload {
+            data: *const (),
+            display_fn: DebugFn,
+        }
+
+        impl fmt::Display for Payload {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                unsafe { (self.display_fn)(self.data, f) }
+            }
+        }
+
+        let payload = Payload {
+            data: self.data,
+            display_fn,
+        };
+        Some(payload.to_string())
+    }
+
+    /// Clone the payload into a new `VBox` packed as the same `dyn Trait`, if
+    /// the `Clone` implementation is captured, and returns `None` otherwise.
+    ///
+    /// [`into_vbox!`] captures it only if the payload implements both `Clone`
+    /// and `Debug` where it is packed. A closure is not known to be `Clone`
+    /// when the macro is expanded, and requiring `Debug`, which no closure
+    /// implements, is how closures are ruled out. To capture `Clone` for a
+    /// payload that is not `Debug`, opt in explicitly with
+    /// [`VBoxBuilder::with_clone()`]:
+    ///
+    /// ```
+    /// # use std::any::Any;
+    /// # use vbox::{into_vbox, vbox_builder};
+    /// #[derive(Clone)]
+    /// struct Token(u64);
+    ///
+    /// assert!(into_vbox!(dyn Any + Send, Token(1)).try_clone().is_none());
+    ///
+    /// let vbox = vbox_builder!(dyn Any + Send, Token(1)).with_clone().build();
+    /// let copy = vbox.try_clone().unwrap();
+    /// assert_eq!(1, copy.into_inner::<Token>().unwrap().0);
+    /// # vbox.discard();
+    /// ```
+    ///
+    /// ```
+    /// # use std::fmt::Debug;
+    /// # use vbox::{from_vbox, into_vbox, VBox};
+    /// let vbox: VBox = into_vbox!(dyn Debug + Send, vec![1u64, 2]);
+    ///
+    /// let copy = vbox.try_clone().unwrap();
+    /// assert_eq!("[1, 2]", format!("{:?}", from_vbox!(dyn Debug + Send, copy)));
+    /// assert_eq!("[1, 2]", format!("{:?}", from_vbox!(dyn Debug + Send, vbox)));
+    /// ```
+    pub fn try_clone(&self) -> Option<VBox> {
+        let clone_fn = self.hooks?.clone_fn?;
+        Some(unsafe { clone_fn(self) })
+    }
+
+    /// Returns `true` if the `VBox` is packed as `U`, i.e., `dyn Trait`.
+    ///
+    /// ```
+    /// # use std::fmt::{Debug, Display};
+    /// # use vbox::{into_vbox, VBox};
+    /// let vbox: VBox = into_vbox!(dyn Debug, 10u64);
+    ///
+    /// assert!(vbox.is_dyn::<dyn Debug>());
+    /// assert!(!vbox.is_dyn::<dyn Display>());
+    /// ```
+    pub fn is_dyn<U>(&self) -> bool
+    where U: ?Sized + 'static {
+        match self.type_check() {
+            TypeCheck::TypeId => self.type_id == TypeId::of::<U>(),
+            TypeCheck::TypeName => {
+                (self.type_name)() == std::any::type_name::<U>()
+                    && self.vtable_layout_matches::<U>()
+            }
+        }
+    }
+
+    /// Returns `true` if the size and the alignment in the vtable, read as
+    /// the vtable of `U`, agree with the layout recorded when packing.
+    ///
+    /// It is a sanity check for [`TypeCheck::TypeName`], which does not trust
+    /// the `TypeId`: a `dyn Trait` of the same name in another build may be
+    /// packed with a vtable of a different shape.
+    fn vtable_layout_matches<U>(&self) -> bool
+    where U: ?Sized + 'static {
+        if mem::size_of::<*mut U>() != mem::size_of::<(*mut (), *const ())>() {
+            return false;
+        }
+
+        let payload = unsafe { &*from_raw_parts::<U>(self.data, self.vtable) };
+        Layout::for_value(payload) == self.layout
+    }
+
+    /// Returns `true` if the payload is of the concrete type `T`.
+    ///
+    /// It always returns `false` if the `VBox` is built with
+    /// [`into_vbox_dyn!`], in which case the concrete type is unknown.
+    pub fn is<T: 'static>(&self) -> bool {
+        match (self.type_check(), self.concrete_type_name) {
+            (TypeCheck::TypeName, Some(name)) => {
+                name() == std::any::type_name::<T>()
+                    && self.layout == Layout::new::<T>()
+            }
+            _ => self.concrete_type_id == Some(TypeId::of::<T>()),
+        }
+    }
+
+    /// Returns `true` if both `VBox`es are packed as the same `dyn Trait` with
+    /// the same implementation, i.e., from the same concrete type.
+    ///
+    /// It compares the recorded type ids rather than the vtable pointers: the
+    /// same implementation may have vtables at different addresses, e.g., in
+    /// different codegen units, and identical vtables of different types may
+    /// be merged into one, e.g., by LTO. The vtable pointers are compared only
+    /// if the concrete type of either is unknown, e.g., built with
+    /// [`into_vbox_dyn!`].
+    ///
+    /// ```
+    /// # use std::fmt::Debug;
+    /// # use vbox::{into_vbox, VBox};
+    /// let a: VBox = into_vbox!(dyn Debug, 1u64);
+    /// let b: VBox = into_vbox!(dyn Debug, 2u64);
+    /// let c: VBox = into_vbox!(dyn Debug, 3u32);
+    ///
+    /// assert!(a.same_impl(&b));
+    /// assert!(!a.same_impl(&c));
+    /// ```
+    pub fn same_impl(&self, other: &VBox) -> bool {
+        self.impl_key() == other.impl_key()
+    }
+
+    /// Identifies the implementation of `dyn Trait`: the type id of `dyn
+    /// Trait`, and the type id of the concrete type if it is known, or the
+    /// vtable pointer otherwise.
+    pub(crate) fn impl_key(&self) -> ImplKey {
+        let concrete = match self.concrete_type_id {
+            Some(type_id) => Ok(type_id),
+            None => Err(self.vtable),
+        };
+        (self.type_id, concrete)
+    }
+
+    /// Describes the implementation of `dyn Trait` for diagnostics, e.g.,
+    /// `u64 as dyn Display`.
+    pub(crate) fn impl_name(&self) -> String {
+        match self.concrete_type_name {
+            Some(name) => format!("{} as {}", name(), (self.type_name)()),
+            None => (self.type_name)().to_string(),
+        }
+    }
+
+    /// Set how this `VBox` checks the type to unpack, overriding
+    /// [`TypeCheck::global()`], e.g., for a `VBox` passed across a dynamically
+    /// loaded plugin.
+    ///
+    /// # Safety
+    ///
+    /// Under [`TypeCheck::TypeName`], a type passes the check for another type
+    /// of the same name and layout, and unpacking it as the other is undefined
+    /// behavior. The caller must ensure that the types unpacked from this
+    /// `VBox` are told apart by their names, e.g., that the host and the
+    /// plugins are built with the same version of the crates whose types they
+    /// exchange.
+    pub unsafe fn set_type_check(&mut self, type_check: TypeCheck) {
+        self.type_check = Some(type_check);
+    }
+
+    /// Returns how this `VBox` checks the type to unpack.
+    pub fn type_check(&self) -> TypeCheck {
+        self.type_check.unwrap_or_else(TypeCheck::global)
+    }
+
+    /// Set what this `VBox` does on a type mismatch, overriding
+    /// [`MismatchPolicy::global()`], e.g., for a `VBox` unpacked in a callback
+    /// called from C.
+    pub fn set_mismatch_policy(&mut self, policy: MismatchPolicy) {
+        self.mismatch_policy = Some(policy);
+    }
+
+    /// Returns what this `VBox` does on a type mismatch.
+    pub fn mismatch_policy(&self) -> MismatchPolicy {
+        self.mismatch_policy.unwrap_or_else(MismatchPolicy::global)
+    }
+
+    /// Set a user defined tag, e.g., the kind of the message, that is read
+    /// without unpacking the `VBox`.
+    ///
+    /// It is kept when the payload is replaced, and by [`VBox::try_clone()`].
+    pub fn set_tag(&mut self, tag: u32) {
+        self.tag = tag;
+    }
+
+    /// Returns the tag set with [`VBox::set_tag()`] or
+    /// [`VBoxBuilder::tag()`], or 0 if not set.
+    pub fn tag(&self) -> u32 {
+        self.tag
+    }
+
+    /// Consume the `VBox` and return the payload as the concrete type `T`.
+    ///
+    /// It is useful when the consumer knows the concrete type, e.g., the
+    /// producer and the consumer are in the same crate. No dynamic dispatch is
+    /// involved after unpacking.
+    ///
+    /// If the payload is not a `T`, the `VBox` is returned intact in `Err`.
+    ///
+    /// ```
+    /// # use std::fmt::Debug;
+    /// # use vbox::{into_vbox, VBox};
+    /// let vbox: VBox = into_vbox!(dyn Debug, 10u64);
+    ///
+    /// let vbox = vbox.into_inner::<u32>().unwrap_err();
+    /// assert_eq!(10u64, vbox.into_inner::<u64>().unwrap());
+    /// ```
+    #[cfg_attr(any(feature = "log", feature = "timeline"), track_caller)]
+    pub fn into_inner<T: 'static>(self) -> Result<T, Self> {
+        if !self.is::<T>() {
+            return Err(self);
+        }
+
+        #[cfg(feature = "log")]
+        log::debug!(
+            "VBox unpack: {}{}",
+            std::any::type_name::<T>(),
+            config::Site(std::panic::Location::caller())
+        );
+
+        #[cfg(feature = "stats")]
+        stats::unpacked(
+            self.type_id,
+            self.type_name,
+            self.concrete_type_id,
+            self.layout.size(),
+        );
+
+        #[cfg(feature = "timeline")]
+        timeline::record(
+            timeline::Kind::Unpack,
+            Some(std::any::type_name::<T>()),
+            (self.type_name)(),
+        );
+
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            let value = ptr::read(this.data as *mut T);
+            free_payload(this.data, this.layout);
+            Ok(value)
+        }
+    }
+
+    /// Check that `dyn Trait` to unpack is the one this `VBox` is built from.
+    ///
+    /// It is checked in release build too: the auto traits are part of the
+    /// type, and unpacking `dyn Handler + Send` as `dyn Handler + Send + Sync`
+    /// would claim a `Sync` the payload does not have.
+    fn check_type<U>(&self)
+    where U: ?Sized + 'static {
+        if self.is_dyn::<U>() {
+            return;
+        }
+
+        let mismatch = TypeMismatch::new::<U>(self);
+
+        if self.mismatch_policy() == MismatchPolicy::Abort {
+            // Not even the handler may unwind.
+            let _ =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    config::mismatch(&mismatch)
+                }));
+            let _ = writeln!(
+                std::io::stderr(),
+                "VBox type mismatch, aborting: packed as: {}, expected: {}",
+                self.impl_name(),
+                mismatch.expected,
+            );
+            std::process::abort();
+        }
+
+        config::mismatch(&mismatch);
+        panic!(
+            "expected type_id: {:?}({}), actual type_id: {:?}({})",
+            TypeId::of::<U>(),
+            std::any::type_name::<U>(),
+            self.type_id,
+            (self.type_name)(),
+        );
+    }
+}
+
+/// The raw parts of a [`VBox`], returned by [`VBox::into_raw()`].
+///
+/// It does not own the payload: dropping it neither drops the payload nor
+/// releases the memory. Use [`VBox::from_raw()`] to take the ownership back.
+#[derive(Debug, Clone, Copy)]
+pub struct RawVBox {
+    data: *mut (),
+    vtable: usize,
+    type_id: TypeId,
+    type_name: fn() -> &'static str,
+    concrete_type_id: Option<TypeId>,
+    concrete_type_name: Option<fn() -> &'static str>,
+    layout: Layout,
+    drop_fn: unsafe fn(*mut (), usize),
+    hooks: Option<&'static Hooks>,
+    type_check: Option<TypeCheck>,
+    mismatch_policy: Option<MismatchPolicy>,
+    tag: u32,
+}
+
+impl RawVBox {
+    /// Returns the data pointer of the payload.
+    pub fn data(&self) -> *mut () {
+        self.data
+    }
 
-use std::any::Any;
-use std::any::TypeId;
+    /// Returns the vtable pointer of the payload.
+    pub fn vtable(&self) -> *const () {
+        self.vtable as *const ()
+    }
+}
 
-/// A type erased Box of trait object that stores the vtable pointer.
-///
-/// This is just like a `Box<dyn Trait>` but erases type `Trait` so that the
-/// channel for sending it does not need to have `Trait` as one of its type
-/// parameters. Only the sending end and the receiving end need to agree on the
-/// type parameters.
+impl From<Box<dyn Any + Send>> for VBox {
+    fn from(boxed: Box<dyn Any + Send>) -> Self {
+        VBox::from_any(boxed)
+    }
+}
+
+impl From<Box<dyn Any + Send + Sync>> for VBox {
+    fn from(boxed: Box<dyn Any + Send + Sync>) -> Self {
+        VBox::from_any_sync(boxed)
+    }
+}
+
+impl TryFrom<VBox> for Box<dyn Any + Send> {
+    type Error = VBox;
+
+    fn try_from(vbox: VBox) -> Result<Self, Self::Error> {
+        vbox.into_any()
+    }
+}
+
+impl TryFrom<VBox> for Box<dyn Any + Send + Sync> {
+    type Error = VBox;
+
+    fn try_from(vbox: VBox) -> Result<Self, Self::Error> {
+        vbox.into_any_sync()
+    }
+}
+
+impl fmt::Debug for VBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("VBox");
+        d.field("vtable", &format_args!("{:#x}", self.vtable))
+            .field("type_id", &self.type_id)
+            .field("type_name", &(self.type_name)())
+            .field("concrete_type_id", &self.concrete_type_id);
+        if self.tag != 0 {
+            d.field("tag", &self.tag);
+        }
+        self.inspect(|payload| d.field("payload", &payload));
+        if let Some(summary) = self.summary() {
+            d.field("summary", &summary);
+        }
+        if self.has_fields() {
+            d.field("fields", &self.fields());
+        }
+        d.finish()
+    }
+}
+
+impl Drop for VBox {
+    fn drop(&mut self) {
+        #[cfg(feature = "log")]
+        log::debug!("VBox drop: {}", (self.type_name)());
+
+        self.tracker.check((self.type_name)());
+
+        #[cfg(feature = "stats")]
+        stats::dropped(
+            self.type_id,
+            self.type_name,
+            self.concrete_type_id,
+            self.layout.size(),
+        );
+
+        unsafe {
+            (self.drop_fn)(self.data, self.vtable);
+            free_payload(self.data, self.layout);
+        }
+    }
+}
+
+/// Empties a `VBox` whose payload is being dropped in place, if it is dropped,
+/// i.e., when dropping the payload panics.
 ///
-/// Internally, it stores the trait object's data pointer in a `Box<dyn Any>`,
-/// so that the `Drop::drop()` will be called when the wrapper is dropped.
-/// And it stores the vtable pointer in another `usize` to make sure it is
-/// `Send`.
-pub struct VBox {
-    /// The data pointer.
-    ///
-    /// Wrap it in a `Box` to make sure it is dropped when `VBox` is dropped.
-    data: Box<dyn Any + Send>,
+/// The allocation is released, and the `VBox` is left with a `()` packed as
+/// `dyn Any + Send`, which can be dropped or unpacked as usual.
+struct EmptyOnUnwind<'a>(&'a mut VBox);
 
-    /// The vtable pointer.
-    ///
-    /// Stored in `usize` to make sure it is `Send`.
-    vtable: usize,
+impl Drop for EmptyOnUnwind<'_> {
+    fn drop(&mut self) {
+        let vbox = &mut *self.0;
+        unsafe { free_payload(vbox.data, vbox.layout) };
 
-    /// Type id of `&dyn Trait`, for debugging.
-    type_id: TypeId,
+        let (data, vtable) = into_raw_parts::<dyn Any + Send>(Box::new(()));
+
+        vbox.data = data;
+        vbox.vtable = vtable;
+        vbox.type_id = TypeId::of::<dyn Any + Send>();
+        vbox.type_name = std::any::type_name::<dyn Any + Send>;
+        vbox.concrete_type_id = Some(TypeId::of::<()>());
+        vbox.concrete_type_name = Some(std::any::type_name::<()>);
+        vbox.layout = Layout::new::<()>();
+        vbox.drop_fn = drop_in_place_raw_parts::<dyn Any + Send>;
+        vbox.hooks = None;
+
+        // The old payload is counted as dropped before it is dropped.
+        #[cfg(feature = "stats")]
+        stats::packed(vbox.type_id, vbox.type_name, vbox.concrete_type_id, 0);
+    }
 }
 
-impl VBox {
-    /// Create a new VBox. Do not use it directly. Use [`into_vbox!`] instead.
-    pub fn new(
-        data: Box<dyn Any + Send>,
-        vtable: usize,
-        type_id: TypeId,
-    ) -> Self {
-        VBox {
-            data,
-            vtable,
-            type_id,
+/// Move `value` into a new allocation, which is taken from the per-thread
+/// cache if the `recycle` feature is enabled.
+fn new_box<T>(value: T) -> Box<T> {
+    #[cfg(feature = "recycle")]
+    {
+        let layout = Layout::new::<T>();
+        if layout.size() != 0 {
+            let p = recycle::alloc(layout) as *mut T;
+            unsafe {
+                ptr::write(p, value);
+                return Box::from_raw(p);
+            }
         }
     }
 
-    /// Unpack the `VBox` and return the fields to rebuild the original trait
-    /// object. Do not use it directly. Use [`from_vbox!`] instead.
-    pub fn unpack(self) -> (Box<dyn Any + Send>, usize, TypeId) {
-        (self.data, self.vtable, self.type_id)
+    Box::new(value)
+}
+
+/// Release the allocation of a payload that is already dropped or moved out,
+/// to the per-thread cache if the `recycle` feature is enabled.
+///
+/// # Safety
+///
+/// `data` must be allocated by the global allocator with `layout`, or be
+/// dangling if `layout` has a zero size.
+unsafe fn free_payload(data: *mut (), layout: Layout) {
+    if layout.size() == 0 {
+        return;
+    }
+
+    #[cfg(feature = "recycle")]
+    recycle::free(data as *mut u8, layout);
+
+    #[cfg(not(feature = "recycle"))]
+    std::alloc::dealloc(data as *mut u8, layout);
+}
+
+/// Allocate memory for a payload of `layout`, from the per-thread cache if the
+/// `recycle` feature is enabled. A zero-sized payload is not allocated.
+///
+/// # Safety
+///
+/// The returned memory is uninitialized.
+unsafe fn alloc_payload(layout: Layout) -> *mut () {
+    if layout.size() == 0 {
+        return layout.align() as *mut ();
     }
+
+    #[cfg(feature = "recycle")]
+    let p = recycle::alloc(layout);
+
+    #[cfg(not(feature = "recycle"))]
+    let p = {
+        let p = std::alloc::alloc(layout);
+        if p.is_null() {
+            std::alloc::handle_alloc_error(layout);
+        }
+        p
+    };
+
+    p as *mut ()
+}
+
+/// Assert that `*mut U` is a fat pointer with two words.
+fn assert_fat_pointer<U: ?Sized>() {
+    assert_eq!(
+        mem::size_of::<*mut U>(),
+        mem::size_of::<(*mut (), *const ())>(),
+        "expect a trait object, got: {}",
+        std::any::type_name::<U>()
+    );
+}
+
+/// Split a `Box<dyn Trait>` into the data pointer and the vtable pointer.
+fn into_raw_parts<U: ?Sized>(boxed: Box<U>) -> (*mut (), usize) {
+    split_raw_parts(Box::into_raw(boxed))
+}
+
+/// Split a `*mut dyn Trait` into the data pointer and the vtable pointer.
+fn split_raw_parts<U: ?Sized>(fat_ptr: *mut U) -> (*mut (), usize) {
+    assert_fat_pointer::<U>();
+
+    let (data, vtable): (*mut (), *const ()) =
+        unsafe { mem::transmute_copy(&fat_ptr) };
+    (data, vtable as usize)
+}
+
+/// Put the data pointer and the vtable pointer together to rebuild the fat
+/// pointer for the trait object.
+///
+/// # Safety
+///
+/// `data` and `vtable` must be split from a `*mut U`.
+unsafe fn from_raw_parts<U: ?Sized>(data: *mut (), vtable: usize) -> *mut U {
+    assert_fat_pointer::<U>();
+
+    let vtable = vtable as *const ();
+    mem::transmute_copy(&(data, vtable))
+}
+
+/// Rebuild the `*mut dyn Trait` and drop the payload in place.
+///
+/// # Safety
+///
+/// `data` and `vtable` must be split from a `*mut U` that points to a valid
+/// value.
+unsafe fn drop_in_place_raw_parts<U: ?Sized>(data: *mut (), vtable: usize) {
+    ptr::drop_in_place(from_raw_parts::<U>(data, vtable));
+}
+
+/// Format the `T` at `data` with `Debug`.
+///
+/// # Safety
+///
+/// `data` must point to a valid `T`.
+unsafe fn debug_raw<T: fmt::Debug>(
+    data: *const (),
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    fmt::Debug::fmt(&*(data as *const T), f)
 }
 
 /// Create a [`VBox`] from a user defined type `T`.
 ///
 /// The built `VBox` is another form of `Box<dyn Trait>`, where `T: Trait`.
 ///
+/// Generic traits and associated type bindings are part of the trait identity,
+/// e.g., a `VBox` built with `dyn Handler<Request = A>` can not be unpacked as
+/// `dyn Handler<Request = B>`.
+///
+/// The `Debug`, `Clone` and `Display` implementations of `T` are captured if
+/// `T` has them, so that a `VBox` can be inspected with
+/// [`VBox::debug_string()`] and [`VBox::display_string()`], or cloned with
+/// [`VBox::try_clone()`], without unpacking it. They are detected where the
+/// macro is expanded: in a generic function, only the bounds of the type
+/// parameter are known, and nothing is captured. `Clone` is only captured if
+/// `T` implements `Debug` too, which rules out closures.
+///
+/// ```
+/// # use std::any::Any;
+/// # use vbox::{into_vbox, VBox};
+/// let vbox: VBox = into_vbox!(dyn Any + Send, String::from("foo"));
+///
+/// assert_eq!(Some(r#""foo""#.to_string()), vbox.debug_string());
+/// assert_eq!(Some("foo".to_string()), vbox.display_string());
+/// assert!(vbox.try_clone().is_some());
+/// ```
+///
+/// Higher-ranked trait objects can be written as `for<'a> dyn Trait<'a>` or
+/// `dyn for<'a> Trait<'a>`, in which case a closure passed in is inferred to be
+/// higher-ranked. Extra bounds are only accepted in the latter form, e.g.,
+/// `dyn for<'a> Visitor<'a> + Send`, and a closure is not inferred then. The
+/// other macros accept both forms too.
+///
+/// ```
+/// # use vbox::{from_vbox, into_vbox, VBox};
+/// let vbox: VBox = into_vbox!(for<'a> dyn Fn(&'a str) -> &'a str, |s| s.trim());
+///
+/// let f = from_vbox!(for<'a> dyn Fn(&'a str) -> &'a str, vbox);
+/// assert_eq!("foo", f(" foo "));
+/// ```
+///
 /// See: [crate doc](crate)
 #[macro_export]
 macro_rules! into_vbox {
-    ($t: ty, $v: expr) => {{
-        let type_id = {
-            let trait_obj_ref: &$t = &$v;
-            ::std::any::Any::type_id(trait_obj_ref)
-        };
+    (for<$($lt: lifetime),+> dyn $tr: path, $v: expr) => {{
+        fn constrain<F: for<$($lt),+> $tr>(f: F) -> F {
+            f
+        }
+
+        let value = constrain($v);
+        let value = $crate::assert::payload_must_be_send(value);
+        let value = $crate::assert::payload_must_be_static(value);
+        let caps = $crate::into_vbox!(@probe value);
+        unsafe {
+            $crate::VBox::new_auto(value, |b| -> ::std::boxed::Box<dyn for<$($lt),+> $tr> {
+                b
+            }, caps)
+        }
+    }};
+
+    (dyn for<$($lt: lifetime),+> $tr: path, $v: expr) => {
+        $crate::into_vbox!(for<$($lt),+> dyn $tr, $v)
+    };
 
-        let vtable = {
-            let fat_ptr: *const $t = &$v;
-            let (_data, vtable): (*const (), *const ()) =
-                unsafe { ::std::mem::transmute(fat_ptr) };
-            vtable as usize
+    (@probe $value: ident) => {{
+        #[allow(unused_imports)]
+        use $crate::probe::{
+            CaptureClone as _, CaptureDebug as _, CaptureDisplay as _,
+            SkipClone as _, SkipDebug as _, SkipDisplay as _,
         };
 
-        VBox::new(Box::new($v), vtable, type_id)
+        let probe = $crate::probe::Probe::of(&$value);
+        (
+            (&&probe).debug_cap(),
+            (&&probe).clone_cap(),
+            (&&probe).display_cap(),
+        )
+    }};
+
+    ($t: ty, $v: expr) => {{
+        let value = $crate::assert::payload_must_be_send($v);
+        let value = $crate::assert::payload_must_be_static(value);
+        let caps = $crate::into_vbox!(@probe value);
+        unsafe {
+            $crate::VBox::new_auto(value, |b| -> ::std::boxed::Box<$t> { b }, caps)
+        }
+    }};
+}
+
+/// Create a [`VBox`] from a user defined type `T` just like [`into_vbox!`],
+/// and capture the `Debug` implementation of `T`, so that the payload can be
+/// inspected with [`VBox::debug_string()`] without unpacking it.
+///
+/// ```
+/// # use vbox::{from_vbox, into_vbox_debug, VBox};
+/// trait Command {}
+///
+/// #[derive(Debug)]
+/// struct Ping(u64);
+/// impl Command for Ping {}
+///
+/// let vbox: VBox = into_vbox_debug!(dyn Command, Ping(1));
+/// assert_eq!(Some("Ping(1)".to_string()), vbox.debug_string());
+///
+/// let _cmd = from_vbox!(dyn Command, vbox);
+/// ```
+#[macro_export]
+macro_rules! into_vbox_debug {
+    (for<$($lt: lifetime),+> dyn $tr: path, $v: expr) => {
+        $crate::into_vbox_debug!(dyn for<$($lt),+> $tr, $v)
+    };
+
+    ($t: ty, $v: expr) => {{
+        let value = $crate::assert::payload_must_be_send($v);
+        let value = $crate::assert::payload_must_be_static(value);
+        unsafe {
+            $crate::VBox::new_debug(value, |b| -> ::std::boxed::Box<$t> { b })
+        }
+    }};
+}
+
+/// Create a [`VBox`] from a user defined type `T` just like [`into_vbox!`],
+/// and capture the [`AsyncDispose`] implementation of `T`, so that the
+/// payload can be cleaned up asynchronously with [`VBox::dispose()`] without
+/// unpacking it.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use std::sync::mpsc;
+/// # use vbox::{into_vbox_disposable, AsyncDispose, BoxFuture};
+/// #[derive(Debug)]
+/// struct Conn(mpsc::Sender<&'static str>);
+///
+/// impl AsyncDispose for Conn {
+///     fn dispose(self) -> BoxFuture<'static, ()> {
+///         Box::pin(async move { self.0.send("goodbye").unwrap() })
+///     }
+/// }
+///
+/// let (tx, rx) = mpsc::channel();
+/// let vbox = into_vbox_disposable!(dyn Debug + Send, Conn(tx));
+///
+/// futures::executor::block_on(vbox.dispose());
+/// assert_eq!("goodbye", rx.recv().unwrap());
+/// ```
+#[macro_export]
+macro_rules! into_vbox_disposable {
+    (for<$($lt: lifetime),+> dyn $tr: path, $v: expr) => {
+        $crate::into_vbox_disposable!(dyn for<$($lt),+> $tr, $v)
+    };
+
+    ($t: ty, $v: expr) => {{
+        let value = $crate::assert::payload_must_be_send($v);
+        let value = $crate::assert::payload_must_be_static(value);
+        unsafe {
+            $crate::VBox::new_disposable(value, |b| -> ::std::boxed::Box<$t> { b })
+        }
+    }};
+}
+
+/// Create a [`VBox`] from a user defined type `T` just like [`into_vbox!`],
+/// and capture the [`RecordFields`] implementation of `T`, so that a logger
+/// can record the fields of the payload with [`VBox::fields()`] without
+/// unpacking it.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{into_vbox_fields, RecordFields};
+/// struct Put(u64, &'static str);
+///
+/// impl RecordFields for Put {
+///     fn record_fields(&self, record: &mut dyn FnMut(&str, &dyn Debug)) {
+///         record("id", &self.0);
+///         record("key", &self.1);
+///     }
+/// }
+///
+/// let vbox = into_vbox_fields!(dyn Send, Put(1, "k"));
+///
+/// let mut names = vec![];
+/// vbox.record_fields(|name, _| names.push(name.to_string()));
+/// assert_eq!(vec!["id", "key"], names);
+/// ```
+#[macro_export]
+macro_rules! into_vbox_fields {
+    (for<$($lt: lifetime),+> dyn $tr: path, $v: expr) => {
+        $crate::into_vbox_fields!(dyn for<$($lt),+> $tr, $v)
+    };
+
+    ($t: ty, $v: expr) => {{
+        let value = $crate::assert::payload_must_be_send($v);
+        let value = $crate::assert::payload_must_be_static(value);
+        unsafe {
+            $crate::VBox::new_fields(value, |b| -> ::std::boxed::Box<$t> { b })
+        }
+    }};
+}
+
+/// Start building a [`VBox`] from a user defined type `T`, packed as `dyn
+/// Trait`, with a [`VBoxBuilder`], to choose the optional capabilities and
+/// metadata one by one.
+///
+/// ```
+/// # use std::fmt::Display;
+/// # use vbox::{from_vbox, vbox_builder, TypeCheck};
+/// let builder = vbox_builder!(dyn Display + Send, 3u64).with_debug();
+/// // Safety: no other type is named `core::fmt::Display` or `u64`.
+/// let vbox = unsafe { builder.type_check(TypeCheck::TypeName) }.build();
+///
+/// assert_eq!(Some("3".to_string()), vbox.debug_string());
+/// assert_eq!(TypeCheck::TypeName, vbox.type_check());
+/// assert_eq!("3", from_vbox!(dyn Display + Send, vbox).to_string());
+/// ```
+#[macro_export]
+macro_rules! vbox_builder {
+    (for<$($lt: lifetime),+> dyn $tr: path, $v: expr) => {
+        $crate::vbox_builder!(dyn for<$($lt),+> $tr, $v)
+    };
+
+    ($t: ty, $v: expr) => {{
+        let value = $crate::assert::payload_must_be_send($v);
+        let value = $crate::assert::payload_must_be_static(value);
+        unsafe {
+            $crate::VBox::builder(value).erase(|b| -> ::std::boxed::Box<$t> { b })
+        }
+    }};
+}
+
+/// Create a [`VBox`] from an existing `Box<dyn Trait>`, without knowing the
+/// concrete type inside it.
+///
+/// The vtable is extracted from the fat pointer of the given box. The trait
+/// object must be `Send`, e.g., `Box<dyn Trait + Send>`.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{from_vbox, into_vbox_dyn, VBox};
+/// let b: Box<dyn Debug + Send> = Box::new(10u64);
+///
+/// let vbox: VBox = into_vbox_dyn!(dyn Debug + Send, b);
+/// let unpacked = from_vbox!(dyn Debug + Send, vbox);
+///
+/// assert_eq!("10", format!("{:?}", unpacked));
+/// ```
+#[macro_export]
+macro_rules! into_vbox_dyn {
+    ($t: ty, $v: expr) => {{
+        let boxed: ::std::boxed::Box<$t> = $v;
+        $crate::VBox::from_box(boxed)
+    }};
+}
+
+/// Create a [`PackedVBox`] from a user defined type `T`, with the control data
+/// and the payload in one allocation.
+///
+/// The `Debug`, `Clone` and `Display` implementations of `T` are captured as
+/// [`into_vbox!`] does.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{into_packed_vbox, PackedVBox};
+/// let packed: PackedVBox = into_packed_vbox!(dyn Debug + Send, 10u64);
+///
+/// assert_eq!(Some("10".to_string()), packed.debug_string());
+/// assert_eq!(10u64, packed.into_inner::<u64>().unwrap());
+/// ```
+#[macro_export]
+macro_rules! into_packed_vbox {
+    ($t: ty, $v: expr) => {{
+        let value = $crate::assert::payload_must_be_send($v);
+        let value = $crate::assert::payload_must_be_static(value);
+        let caps = $crate::into_vbox!(@probe value);
+        unsafe {
+            $crate::PackedVBox::new(value, |p| -> *mut $t { p }, caps)
+        }
     }};
 }
 
@@ -107,34 +2376,341 @@ macro_rules! into_vbox {
 /// `VBox.vtable`. Then it puts them together to reconstruct the fat pointer for
 /// the trait object.
 ///
+/// It panics if `dyn Trait` is not the one the `VBox` is packed as. The auto
+/// traits are part of it: a `VBox` packed as `dyn Trait + Send + Sync` can not
+/// be unpacked as `dyn Trait + Send`.
+///
 /// See: [crate doc](crate)
 #[macro_export]
 macro_rules! from_vbox {
+    (for<$($lt: lifetime),+> dyn $tr: path, $v: expr) => {
+        $crate::from_vbox!(dyn for<$($lt),+> $tr, $v)
+    };
+
     ($t: ty, $v: expr) => {{
-        let (data, vtable, type_id) = $v.unpack();
+        let ret: ::std::boxed::Box<$t> = $crate::VBox::unpack::<$t>($v);
+        ret
+    }};
+}
 
-        let any_fat_ptr: *const dyn ::core::any::Any = Box::into_raw(data);
-        let (data_ptr, _vtable): (*const (), *const ()) =
-            unsafe { ::std::mem::transmute(any_fat_ptr) };
+/// Unpack a `Vec` of [`VBox`]es packed as the same `dyn Trait` into a
+/// `Vec<Box<dyn Trait>>`, e.g., for a batch-processing stage.
+///
+/// The type is checked once per group of `VBox`es sharing a vtable instead of
+/// once per `VBox`, and all of them are checked before any is unpacked. It
+/// panics if any of them is not packed as `dyn Trait`. Use
+/// [`VBox::try_unpack_vec()`] to get them back instead of panicking.
+///
+/// ```
+/// # use std::fmt::Display;
+/// # use vbox::{from_vbox_vec, into_vbox, VBox};
+/// let vboxes: Vec<VBox> = (0..3u64).map(|i| into_vbox!(dyn Display, i)).collect();
+///
+/// let displays: Vec<Box<dyn Display>> = from_vbox_vec!(dyn Display, vboxes);
+/// let s = displays.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+/// assert_eq!(vec!["0", "1", "2"], s);
+/// ```
+#[macro_export]
+macro_rules! from_vbox_vec {
+    (for<$($lt: lifetime),+> dyn $tr: path, $v: expr) => {
+        $crate::from_vbox_vec!(dyn for<$($lt),+> $tr, $v)
+    };
 
-        let vtable_ptr = vtable as *const ();
+    ($t: ty, $v: expr) => {{
+        let ret: ::std::vec::Vec<::std::boxed::Box<$t>> =
+            $crate::VBox::unpack_vec::<$t>($v);
+        ret
+    }};
+}
 
-        let fat_ptr: *mut $t =
-            unsafe { ::std::mem::transmute((data_ptr, vtable_ptr)) };
+/// Consume [`VBox`] and rebuild the original `Box<dyn Trait>`, or return an
+/// [`UnpackError`] with the `VBox` intact if it is not packed as `dyn Trait`.
+///
+/// It is the panic-free form of [`from_vbox!`].
+///
+/// ```
+/// # use std::fmt::{Debug, Display};
+/// # use vbox::{into_vbox, try_from_vbox, VBox};
+/// let vbox: VBox = into_vbox!(dyn Debug, 10u64);
+///
+/// let Err(err) = try_from_vbox!(dyn Display, vbox) else {
+///     panic!("expect Err");
+/// };
+/// assert!(err.to_string().starts_with("can not unpack VBox: packed as: dyn core::fmt::Debug"));
+///
+/// let d = try_from_vbox!(dyn Debug, err.into_inner()).unwrap();
+/// assert_eq!("10", format!("{:?}", d));
+/// ```
+#[macro_export]
+macro_rules! try_from_vbox {
+    (for<$($lt: lifetime),+> dyn $tr: path, $v: expr) => {
+        $crate::try_from_vbox!(dyn for<$($lt),+> $tr, $v)
+    };
 
-        let ret = unsafe { Box::from_raw(fat_ptr) };
+    ($t: ty, $v: expr) => {{
+        let ret: ::std::result::Result<::std::boxed::Box<$t>, $crate::UnpackError> =
+            $crate::VBox::try_unpack::<$t>($v);
+        ret
+    }};
+}
 
-        {
-            let trait_obj_ref = &*ret;
-            debug_assert_eq!(
-                ::std::any::Any::type_id(trait_obj_ref),
-                type_id,
-                "expected type_id: {:?}, actual type_id: {:?}",
-                ::std::any::Any::type_id(trait_obj_ref),
-                type_id
-            );
-        }
+/// Consume [`VBox`] and leak the payload as a `&'static mut dyn Trait`, e.g.,
+/// for register-once handlers that live until the program exits.
+///
+/// The payload will never be dropped.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{into_vbox, leak_vbox, VBox};
+/// let vbox: VBox = into_vbox!(dyn Debug + Sync, 10u64);
+///
+/// let handler: &'static (dyn Debug + Sync) = leak_vbox!(dyn Debug + Sync, vbox);
+///
+/// assert_eq!("10", format!("{:?}", handler));
+/// ```
+#[macro_export]
+macro_rules! leak_vbox {
+    (for<$($lt: lifetime),+> dyn $tr: path, $v: expr) => {
+        $crate::leak_vbox!(dyn for<$($lt),+> $tr, $v)
+    };
 
+    ($t: ty, $v: expr) => {{
+        let ret: &'static mut $t = $crate::VBox::leak::<$t>($v);
         ret
     }};
 }
+
+/// Unpack a [`VBox`] as one trait object, transform it with a closure, and pack
+/// the result as another trait object, in one step.
+///
+/// The closure receives the unpacked `Box<dyn A>` and returns a value that
+/// implements `B`.
+///
+/// ```
+/// # use std::fmt::{Debug, Display};
+/// # use vbox::{from_vbox, into_vbox, map_vbox, VBox};
+/// let vbox: VBox = into_vbox!(dyn Debug, 10u64);
+///
+/// let vbox = map_vbox!(dyn Debug -> dyn Display, vbox, |a| format!("<{:?}>", a));
+///
+/// let unpacked = from_vbox!(dyn Display, vbox);
+/// assert_eq!("<10>", unpacked.to_string());
+/// ```
+#[macro_export]
+macro_rules! map_vbox {
+    (dyn $($rest: tt)+) => {
+        $crate::map_vbox!(@from [dyn] $($rest)+)
+    };
+
+    (for<$($lt: lifetime),+> dyn $($rest: tt)+) => {
+        $crate::map_vbox!(@from [dyn for<$($lt),+>] $($rest)+)
+    };
+
+    (@from [$($from: tt)+] -> $to: ty, $v: expr, $f: expr) => {{
+        fn call<A: ?::std::marker::Sized, R>(
+            a: ::std::boxed::Box<A>,
+            f: impl ::std::ops::FnOnce(::std::boxed::Box<A>) -> R,
+        ) -> R {
+            f(a)
+        }
+
+        let vbox: $crate::VBox = $v;
+        let unpacked = $crate::VBox::unpack::<$($from)+>(vbox);
+        let mapped = call(unpacked, $f);
+        let mapped = $crate::assert::payload_must_be_send(mapped);
+        let mapped = $crate::assert::payload_must_be_static(mapped);
+        unsafe { $crate::VBox::new(mapped, |b| -> ::std::boxed::Box<$to> { b }) }
+    }};
+
+    (@from [$($from: tt)+] $next: tt $($rest: tt)+) => {
+        $crate::map_vbox!(@from [$($from)+ $next] $($rest)+)
+    };
+}
+
+/// Borrow the payload of a [`VBox`] as `&mut dyn Trait` and run a closure with
+/// it, without consuming the `VBox`.
+///
+/// The `VBox` stays intact after the closure returns, no matter how the closure
+/// returns. The return value of the closure is returned.
+///
+/// ```
+/// # use vbox::{from_vbox, into_vbox, with_vbox, VBox};
+/// let mut vbox: VBox = into_vbox!(dyn Iterator<Item = u64>, 1..3u64);
+///
+/// let first = with_vbox!(dyn Iterator<Item = u64>, &mut vbox, |it| it.next());
+/// assert_eq!(Some(1), first);
+///
+/// let rest: Vec<_> = from_vbox!(dyn Iterator<Item = u64>, vbox).collect();
+/// assert_eq!(vec![2], rest);
+/// ```
+#[macro_export]
+macro_rules! with_vbox {
+    (for<$($lt: lifetime),+> dyn $tr: path, $v: expr, $f: expr) => {
+        $crate::with_vbox!(dyn for<$($lt),+> $tr, $v, $f)
+    };
+
+    ($t: ty, $v: expr, $f: expr) => {{
+        fn call<U: ?::std::marker::Sized, R>(
+            u: &mut U,
+            f: impl ::std::ops::FnOnce(&mut U) -> R,
+        ) -> R {
+            f(u)
+        }
+
+        let vbox: &mut $crate::VBox = $v;
+        call($crate::VBox::as_dyn_mut::<$t>(vbox), $f)
+    }};
+}
+
+/// Replace the payload of a [`VBox`] with a new value, packed as `dyn Trait`.
+///
+/// If the new value has the same layout as the old payload, e.g., a repeated
+/// command of the same type, the old payload is dropped and the new value is
+/// written into the same allocation. Otherwise a new allocation is made.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{from_vbox, into_vbox, replace_vbox, VBox};
+/// let mut vbox: VBox = into_vbox!(dyn Debug, 10u64);
+///
+/// replace_vbox!(dyn Debug, &mut vbox, 20i64);
+///
+/// assert_eq!("20", format!("{:?}", from_vbox!(dyn Debug, vbox)));
+/// ```
+#[macro_export]
+macro_rules! replace_vbox {
+    (for<$($lt: lifetime),+> dyn $tr: path, $v: expr, $new: expr) => {
+        $crate::replace_vbox!(dyn for<$($lt),+> $tr, $v, $new)
+    };
+
+    ($t: ty, $v: expr, $new: expr) => {{
+        let vbox: &mut $crate::VBox = $v;
+        let value = $crate::assert::payload_must_be_send($new);
+        let value = $crate::assert::payload_must_be_static(value);
+        unsafe {
+            $crate::VBox::replace(vbox, value, |b| -> ::std::boxed::Box<$t> {
+                b
+            })
+        }
+    }};
+}
+
+/// Try to take the payload of a [`VBox`] as each of the candidate concrete
+/// types in order, and evaluate the arm of the first match.
+///
+/// It evaluates to `Ok(..)` with the value of the matched arm, or `Err(vbox)`
+/// with the `VBox` intact if none matches.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{downcast_first, into_vbox, VBox};
+/// let vbox: VBox = into_vbox!(dyn Debug, String::from("foo"));
+///
+/// let got = downcast_first!(vbox,
+///     |n: u64| format!("u64: {}", n),
+///     |s: String| format!("String: {}", s),
+/// );
+/// assert_eq!("String: foo", got.unwrap());
+/// ```
+#[macro_export]
+macro_rules! downcast_first {
+    ($v: expr, $(|$x: ident : $t: ty| $body: expr),+ $(,)?) => {{
+        let vbox: $crate::VBox = $v;
+        $crate::downcast_first!(@try vbox, $(|$x: $t| $body),+)
+    }};
+
+    (
+        @try $vbox: ident,
+        |$x: ident : $t: ty| $body: expr
+        $(, |$xs: ident : $ts: ty| $bodies: expr)*
+    ) => {
+        match $crate::VBox::into_inner::<$t>($vbox) {
+            ::std::result::Result::Ok($x) => ::std::result::Result::Ok($body),
+            ::std::result::Result::Err($vbox) => {
+                $crate::downcast_first!(@try $vbox, $(|$xs: $ts| $bodies),*)
+            }
+        }
+    };
+
+    (@try $vbox: ident,) => {
+        ::std::result::Result::Err($vbox)
+    };
+}
+
+/// Define an enum with one variant per trait object, for a channel that
+/// carries a known, finite set of message traits.
+///
+/// Each variant holds a `Box<dyn Trait>`, thus `match` on it is checked for
+/// exhaustiveness, while the concrete payload types are still erased. The trait
+/// objects must be `Send`, e.g., `dyn Command + Send`.
+///
+/// It also implements:
+/// - `From<Box<dyn Trait>>` for the enum, for each variant;
+/// - `From<Enum>` for [`VBox`];
+/// - `TryFrom<VBox>` for the enum, which picks the variant by the trait object
+///   the `VBox` is packed as, and returns the `VBox` intact in `Err` if none
+///   matches.
+///
+/// ```
+/// # use std::fmt::{Debug, Display};
+/// # use vbox::{into_vbox, vbox_enum, VBox};
+/// vbox_enum! {
+///     pub enum Msg {
+///         Debug(dyn Debug + Send),
+///         Display(dyn Display + Send),
+///     }
+/// }
+///
+/// let vbox: VBox = into_vbox!(dyn Display + Send, 10u64);
+///
+/// match Msg::try_from(vbox).unwrap() {
+///     Msg::Debug(d) => panic!("unexpected: {:?}", d),
+///     Msg::Display(d) => assert_eq!("10", d.to_string()),
+/// }
+/// ```
+#[macro_export]
+macro_rules! vbox_enum {
+    (
+        $(#[$meta: meta])*
+        $vis: vis enum $name: ident {
+            $($variant: ident($t: ty)),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $($variant(::std::boxed::Box<$t>)),+
+        }
+
+        $(
+            impl ::std::convert::From<::std::boxed::Box<$t>> for $name {
+                fn from(b: ::std::boxed::Box<$t>) -> Self {
+                    $name::$variant(b)
+                }
+            }
+        )+
+
+        impl ::std::convert::From<$name> for $crate::VBox {
+            fn from(e: $name) -> Self {
+                match e {
+                    $($name::$variant(b) => $crate::VBox::from_box(b)),+
+                }
+            }
+        }
+
+        impl ::std::convert::TryFrom<$crate::VBox> for $name {
+            type Error = $crate::VBox;
+
+            fn try_from(
+                vbox: $crate::VBox,
+            ) -> ::std::result::Result<Self, $crate::VBox> {
+                $(
+                    if $crate::VBox::is_dyn::<$t>(&vbox) {
+                        let b = $crate::VBox::unpack::<$t>(vbox);
+                        return ::std::result::Result::Ok($name::$variant(b));
+                    }
+                )+
+                ::std::result::Result::Err(vbox)
+            }
+        }
+    };
+}
@@ -0,0 +1,144 @@
+use std::any::TypeId;
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::VBox;
+
+/// A type erased Box of trait object, for a payload that is not `Send`.
+///
+/// It is the same as [`VBox`] except that it does not require the payload to
+/// be `Send`, and thus it is neither `Send` nor `Sync` itself. It is meant for
+/// single threaded event plumbing, e.g., erasing wrappers of `JsValue` on
+/// `wasm32`, which are not `Send`.
+///
+/// Use [`into_local_vbox!`](crate::into_local_vbox) and
+/// [`from_local_vbox!`](crate::from_local_vbox) to pack and unpack it.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use std::rc::Rc;
+/// # use vbox::{from_local_vbox, into_local_vbox, LocalVBox};
+/// let lvbox: LocalVBox = into_local_vbox!(dyn Debug, Rc::new(10u64));
+///
+/// let unpacked: Box<dyn Debug> = from_local_vbox!(dyn Debug, lvbox);
+/// assert_eq!("10", format!("{:?}", unpacked));
+/// ```
+pub struct LocalVBox {
+    inner: VBox,
+
+    /// Make it `!Send` and `!Sync`.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl LocalVBox {
+    /// Create a new LocalVBox. Do not use it directly. Use
+    /// [`into_local_vbox!`](crate::into_local_vbox) instead.
+    ///
+    /// # Safety
+    ///
+    /// `coerce` must be an unsizing coercion from `Box<T>` to `Box<dyn
+    /// Trait>`, i.e., it must return the very same box it is given.
+    pub unsafe fn new<T, U>(value: T, coerce: fn(Box<T>) -> Box<U>) -> Self
+    where
+        T: 'static,
+        U: ?Sized + 'static,
+    {
+        let concrete_type_id = Some(TypeId::of::<T>());
+        let inner = VBox::from_box_unchecked(
+            coerce(Box::new(value)),
+            concrete_type_id,
+            Some(std::any::type_name::<T>),
+        );
+        Self::wrap(inner)
+    }
+
+    /// Create a new LocalVBox from an existing `Box<dyn Trait>`, without
+    /// knowing the concrete type inside it.
+    pub fn from_box<U>(boxed: Box<U>) -> Self
+    where U: ?Sized + 'static {
+        // The `VBox` is never sent, it is wrapped in a `!Send` LocalVBox.
+        Self::wrap(unsafe { VBox::from_box_unchecked(boxed, None, None) })
+    }
+
+    fn wrap(inner: VBox) -> Self {
+        LocalVBox {
+            inner,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Unpack the `LocalVBox` and rebuild the original trait object. Do not use
+    /// it directly. Use [`from_local_vbox!`](crate::from_local_vbox) instead.
+    pub fn unpack<U>(self) -> Box<U>
+    where U: ?Sized + 'static {
+        self.inner.unpack::<U>()
+    }
+
+    /// Borrow the payload as `&dyn Trait`.
+    pub fn as_dyn<U>(&self) -> &U
+    where U: ?Sized + 'static {
+        self.inner.as_dyn::<U>()
+    }
+
+    /// Borrow the payload as `&mut dyn Trait`.
+    pub fn as_dyn_mut<U>(&mut self) -> &mut U
+    where U: ?Sized + 'static {
+        self.inner.as_dyn_mut::<U>()
+    }
+
+    /// Returns `true` if the payload is of the concrete type `T`.
+    ///
+    /// See [`VBox::is()`].
+    pub fn is<T: 'static>(&self) -> bool {
+        self.inner.is::<T>()
+    }
+
+    /// Consume the `LocalVBox` and return the payload as the concrete type `T`.
+    ///
+    /// If the payload is not a `T`, the `LocalVBox` is returned intact in
+    /// `Err`.
+    pub fn into_inner<T: 'static>(self) -> Result<T, Self> {
+        self.inner.into_inner::<T>().map_err(Self::wrap)
+    }
+}
+
+/// Convert a `VBox` into a `LocalVBox`.
+///
+/// The opposite is not possible, since the payload may not be `Send`.
+impl From<VBox> for LocalVBox {
+    fn from(vbox: VBox) -> Self {
+        Self::wrap(vbox)
+    }
+}
+
+impl fmt::Debug for LocalVBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("LocalVBox").field(&self.inner).finish()
+    }
+}
+
+/// Create a [`LocalVBox`] from a user defined type `T` that is not necessarily
+/// `Send`.
+///
+/// See: [`into_vbox!`](crate::into_vbox)
+#[macro_export]
+macro_rules! into_local_vbox {
+    ($t: ty, $v: expr) => {{
+        let value = $crate::assert::payload_must_be_static($v);
+        unsafe {
+            $crate::LocalVBox::new(value, |b| -> ::std::boxed::Box<$t> { b })
+        }
+    }};
+}
+
+/// Consume [`LocalVBox`] and reconstruct the original trait object: `Box<dyn
+/// Trait>`.
+///
+/// See: [`from_vbox!`](crate::from_vbox)
+#[macro_export]
+macro_rules! from_local_vbox {
+    ($t: ty, $v: expr) => {{
+        let ret: ::std::boxed::Box<$t> = $crate::LocalVBox::unpack::<$t>($v);
+        ret
+    }};
+}
@@ -0,0 +1,244 @@
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::VBox;
+
+/// The bound of an [`LruCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LruCapacity {
+    /// At most this many entries.
+    Entries(usize),
+
+    /// At most this many bytes of payload in total, counted with
+    /// [`VBox::size_of_payload()`].
+    Bytes(usize),
+}
+
+struct Entry {
+    vbox: VBox,
+
+    /// The payload bytes counted when it is inserted. The `VBox` can be
+    /// replaced through [`LruCache::get_vbox()`], thus it is not recounted.
+    size: usize,
+
+    /// When it is used last, the key in the recency index.
+    tick: u64,
+}
+
+/// A cache that maps keys to [`VBox`]es and evicts the least recently used
+/// ones when it exceeds a capacity in entries or in payload bytes, so that a
+/// cache of erased values of different types is bounded in memory.
+///
+/// The evicted entries are returned to the caller by the methods that evict,
+/// as [`TtlCache::sweep()`](crate::TtlCache::sweep) does.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{into_vbox, LruCache, LruCapacity};
+/// let mut cache = LruCache::new(LruCapacity::Bytes(16));
+/// cache.insert("a", into_vbox!(dyn Debug, 1u64));
+/// cache.insert("b", into_vbox!(dyn Debug, 2u64));
+///
+/// // "a" becomes the most recently used.
+/// assert!(cache.get::<dyn Debug, _>("a").is_some());
+///
+/// let evicted = cache.insert("c", into_vbox!(dyn Debug, 3u64));
+/// assert_eq!(vec!["b"], evicted.iter().map(|(k, _)| *k).collect::<Vec<_>>());
+/// assert_eq!(16, cache.bytes());
+/// ```
+pub struct LruCache<K> {
+    capacity: LruCapacity,
+    entries: HashMap<K, Entry>,
+
+    /// Keys from the least recently used to the most.
+    recency: BTreeMap<u64, K>,
+
+    /// Total payload bytes of the entries.
+    bytes: usize,
+
+    next_tick: u64,
+}
+
+impl<K> LruCache<K>
+where K: Hash + Eq + Clone
+{
+    /// Create an empty cache bounded by `capacity`.
+    pub fn new(capacity: LruCapacity) -> Self {
+        LruCache {
+            capacity,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            bytes: 0,
+            next_tick: 0,
+        }
+    }
+
+    /// Returns the bound of the cache.
+    pub fn capacity(&self) -> LruCapacity {
+        self.capacity
+    }
+
+    /// Change the bound of the cache, and return the entries evicted to fit in
+    /// it, from the least recently used.
+    pub fn set_capacity(&mut self, capacity: LruCapacity) -> Vec<(K, VBox)> {
+        self.capacity = capacity;
+        self.evict()
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there is no entry.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the total payload bytes of the entries, as they are counted
+    /// when inserted.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Insert a `VBox` as the most recently used entry, and return the
+    /// replaced entry under the same key, if any, followed by the entries
+    /// evicted to fit in the capacity, from the least recently used.
+    ///
+    /// A payload larger than the capacity in bytes is evicted at once.
+    pub fn insert(&mut self, key: K, vbox: VBox) -> Vec<(K, VBox)> {
+        let mut out = Vec::new();
+
+        if let Some(old) = self.remove_entry(&key) {
+            out.push(old);
+        }
+
+        let tick = self.tick();
+        let size = vbox.size_of_payload();
+        self.bytes += size;
+        self.recency.insert(tick, key.clone());
+        self.entries.insert(key, Entry { vbox, size, tick });
+
+        out.extend(self.evict());
+        out
+    }
+
+    /// Returns `true` if an entry is stored under the key, without marking it
+    /// as used.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.contains_key(key)
+    }
+
+    /// Borrow the `VBox` stored under the key, and mark it as the most
+    /// recently used.
+    ///
+    /// If the `VBox` is replaced with one of a different payload size, the
+    /// entry is still counted with the size it is inserted with. Insert it
+    /// again to recount.
+    pub fn get_vbox<Q>(&mut self, key: &Q) -> Option<&mut VBox>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let tick = self.tick();
+        let entry = self.entries.get_mut(key)?;
+
+        let k = self.recency.remove(&entry.tick).unwrap();
+        self.recency.insert(tick, k);
+        entry.tick = tick;
+
+        Some(&mut entry.vbox)
+    }
+
+    /// Borrow the payload stored under the key as `&dyn Trait`, and mark it
+    /// as the most recently used.
+    ///
+    /// It panics if the `VBox` is not packed as `dyn Trait`.
+    pub fn get<U, Q>(&mut self, key: &Q) -> Option<&U>
+    where
+        U: ?Sized + 'static,
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_vbox(key).map(|vbox| vbox.as_dyn::<U>())
+    }
+
+    /// Borrow the `VBox` stored under the key, without marking it as used.
+    pub fn peek_vbox<Q>(&self, key: &Q) -> Option<&VBox>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.get(key).map(|e| &e.vbox)
+    }
+
+    /// Remove the entry stored under the key, if any.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<VBox>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, vbox)| vbox)
+    }
+
+    /// Remove the least recently used entry, if any.
+    pub fn pop_lru(&mut self) -> Option<(K, VBox)> {
+        let (_, key) = self.recency.pop_first()?;
+        let entry = self.entries.remove(&key).unwrap();
+        self.bytes -= entry.size;
+        Some((key, entry.vbox))
+    }
+
+    fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, VBox)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.entries.remove(key)?;
+        let key = self.recency.remove(&entry.tick).unwrap();
+        self.bytes -= entry.size;
+        Some((key, entry.vbox))
+    }
+
+    fn evict(&mut self) -> Vec<(K, VBox)> {
+        let mut evicted = Vec::new();
+
+        while self.exceeds() {
+            let Some(e) = self.pop_lru() else {
+                break;
+            };
+            evicted.push(e);
+        }
+
+        evicted
+    }
+
+    fn exceeds(&self) -> bool {
+        match self.capacity {
+            LruCapacity::Entries(n) => self.entries.len() > n,
+            LruCapacity::Bytes(n) => self.bytes > n,
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.next_tick += 1;
+        self.next_tick
+    }
+}
+
+impl<K> fmt::Debug for LruCache<K>
+where K: fmt::Debug + Hash + Eq
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.recency.values().map(|k| (k, &self.entries[k].vbox)))
+            .finish()
+    }
+}
@@ -0,0 +1,109 @@
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+
+use crate::VBox;
+
+/// Typed access to a standard map of [`VBox`]es, i.e., `HashMap<K, VBox>` or
+/// `BTreeMap<K, VBox>`, with the check of `dyn Trait` built in.
+///
+/// A value is inserted with [`insert_erased!`](crate::insert_erased).
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use std::fmt::{Debug, Display};
+/// # use vbox::{insert_erased, VBox, VBoxMapExt};
+/// let mut m: HashMap<String, VBox> = HashMap::new();
+/// insert_erased!(m, "a".to_string(), dyn Debug, 1u64);
+/// insert_erased!(m, "b".to_string(), dyn Display, "b");
+///
+/// assert_eq!("1", format!("{:?}", m.get_as::<dyn Debug>("a").unwrap()));
+///
+/// let b = m.remove_as::<dyn Display>("b").unwrap();
+/// assert_eq!("b", b.to_string());
+/// ```
+pub trait VBoxMapExt<Q: ?Sized> {
+    /// Borrow the payload stored under the key as `&dyn Trait`.
+    ///
+    /// It panics if the `VBox` is not packed as `dyn Trait`.
+    fn get_as<U>(&self, key: &Q) -> Option<&U>
+    where U: ?Sized + 'static;
+
+    /// Borrow the payload stored under the key as `&mut dyn Trait`.
+    ///
+    /// It panics if the `VBox` is not packed as `dyn Trait`.
+    fn get_as_mut<U>(&mut self, key: &Q) -> Option<&mut U>
+    where U: ?Sized + 'static;
+
+    /// Remove the `VBox` stored under the key and unpack it as `dyn Trait`.
+    ///
+    /// It panics if the `VBox` is not packed as `dyn Trait`, in which case the
+    /// `VBox` is left in the map.
+    fn remove_as<U>(&mut self, key: &Q) -> Option<Box<U>>
+    where U: ?Sized + 'static;
+}
+
+impl<K, Q, S> VBoxMapExt<Q> for HashMap<K, VBox, S>
+where
+    K: Borrow<Q> + Hash + Eq,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    fn get_as<U>(&self, key: &Q) -> Option<&U>
+    where U: ?Sized + 'static {
+        self.get(key).map(|v| v.as_dyn::<U>())
+    }
+
+    fn get_as_mut<U>(&mut self, key: &Q) -> Option<&mut U>
+    where U: ?Sized + 'static {
+        self.get_mut(key).map(|v| v.as_dyn_mut::<U>())
+    }
+
+    fn remove_as<U>(&mut self, key: &Q) -> Option<Box<U>>
+    where U: ?Sized + 'static {
+        self.get(key)?.check_type::<U>();
+        self.remove(key).map(|v| v.unpack::<U>())
+    }
+}
+
+impl<K, Q> VBoxMapExt<Q> for BTreeMap<K, VBox>
+where
+    K: Borrow<Q> + Ord,
+    Q: Ord + ?Sized,
+{
+    fn get_as<U>(&self, key: &Q) -> Option<&U>
+    where U: ?Sized + 'static {
+        self.get(key).map(|v| v.as_dyn::<U>())
+    }
+
+    fn get_as_mut<U>(&mut self, key: &Q) -> Option<&mut U>
+    where U: ?Sized + 'static {
+        self.get_mut(key).map(|v| v.as_dyn_mut::<U>())
+    }
+
+    fn remove_as<U>(&mut self, key: &Q) -> Option<Box<U>>
+    where U: ?Sized + 'static {
+        self.get(key)?.check_type::<U>();
+        self.remove(key).map(|v| v.unpack::<U>())
+    }
+}
+
+/// Pack a value as `dyn Trait` and insert it into a standard map of
+/// [`VBox`](crate::VBox)es, returning the `VBox` replaced, if any.
+///
+/// ```
+/// # use std::collections::BTreeMap;
+/// # use std::fmt::Debug;
+/// # use vbox::{insert_erased, VBox};
+/// let mut m: BTreeMap<u64, VBox> = BTreeMap::new();
+/// assert!(insert_erased!(m, 1, dyn Debug, "one").is_none());
+/// assert!(insert_erased!(m, 1, dyn Debug, "uno").is_some());
+/// ```
+#[macro_export]
+macro_rules! insert_erased {
+    ($map: expr, $key: expr, $t: ty, $v: expr) => {
+        $map.insert($key, $crate::into_vbox!($t, $v))
+    };
+}
@@ -0,0 +1,73 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::VBox;
+
+/// The error returned when a [`VBox`] is not packed as the expected trait
+/// object, e.g., by [`VBox::try_as_dyn()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeMismatch {
+    /// The name of the expected trait object.
+    pub expected: &'static str,
+
+    /// The name of the trait object the `VBox` is packed as.
+    pub actual: &'static str,
+}
+
+impl TypeMismatch {
+    pub(crate) fn new<U>(vbox: &VBox) -> Self
+    where U: ?Sized + 'static {
+        TypeMismatch {
+            expected: std::any::type_name::<U>(),
+            actual: (vbox.type_name)(),
+        }
+    }
+}
+
+impl fmt::Display for TypeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "packed as: {}, expected: {}", self.actual, self.expected)
+    }
+}
+
+impl Error for TypeMismatch {}
+
+/// The error returned by [`VBox::try_unpack()`], with the `VBox` returned
+/// intact.
+///
+/// `B` is the container that could not be unpacked, e.g., a
+/// [`PackedVBox`](crate::PackedVBox) for
+/// [`PackedVBox::try_unpack()`](crate::PackedVBox::try_unpack).
+#[derive(Debug)]
+pub struct UnpackError<B = VBox> {
+    mismatch: TypeMismatch,
+    vbox: B,
+}
+
+impl<B> UnpackError<B> {
+    pub(crate) fn new(mismatch: TypeMismatch, vbox: B) -> Self {
+        UnpackError { mismatch, vbox }
+    }
+
+    /// Returns what is expected and what the `VBox` is packed as.
+    pub fn mismatch(&self) -> TypeMismatch {
+        self.mismatch
+    }
+
+    /// Returns the `VBox` that could not be unpacked.
+    pub fn into_inner(self) -> B {
+        self.vbox
+    }
+}
+
+impl<B> fmt::Display for UnpackError<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "can not unpack VBox: {}", self.mismatch)
+    }
+}
+
+impl<B: fmt::Debug> Error for UnpackError<B> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.mismatch)
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
+
+/// What a [`VBox`](crate::VBox) does when it is unpacked as a type it is not
+/// packed as, e.g., by [`from_vbox!`](crate::from_vbox).
+///
+/// Unwinding out of an `extern "C"` function is undefined behavior, or aborts
+/// without a hint of the cause. A `VBox` that is unpacked inside a callback
+/// called from C should use [`MismatchPolicy::Abort`], which prints the
+/// mismatch and aborts the process before any unwinding starts.
+///
+/// It is set globally with [`MismatchPolicy::set_global()`], or per `VBox`
+/// with [`VBox::set_mismatch_policy()`](crate::VBox::set_mismatch_policy),
+/// which takes precedence. The panic-free forms such as
+/// [`VBox::try_unpack()`](crate::VBox::try_unpack) are not affected.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{from_vbox, into_vbox, MismatchPolicy, VBox};
+/// let mut vbox: VBox = into_vbox!(dyn Debug + Send, 10u64);
+/// vbox.set_mismatch_policy(MismatchPolicy::Abort);
+///
+/// extern "C" fn callback(vbox: *mut VBox) {
+///     let vbox = unsafe { Box::from_raw(vbox) };
+///     // Aborts instead of unwinding across the FFI boundary
+///     let d = from_vbox!(dyn Debug + Send, *vbox);
+///     assert_eq!("10", format!("{:?}", d));
+/// }
+///
+/// callback(Box::into_raw(Box::new(vbox)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MismatchPolicy {
+    /// Panic. It is the default.
+    #[default]
+    Panic,
+
+    /// Print the mismatch to stderr and call `std::process::abort()`.
+    Abort,
+}
+
+static GLOBAL: AtomicU8 = AtomicU8::new(MismatchPolicy::Panic as u8);
+
+impl MismatchPolicy {
+    /// Set the policy for every `VBox` that does not set its own.
+    pub fn set_global(policy: MismatchPolicy) {
+        GLOBAL.store(policy as u8, Ordering::Relaxed);
+    }
+
+    /// Returns the policy for every `VBox` that does not set its own.
+    pub fn global() -> MismatchPolicy {
+        match GLOBAL.load(Ordering::Relaxed) {
+            x if x == MismatchPolicy::Abort as u8 => MismatchPolicy::Abort,
+            _ => MismatchPolicy::Panic,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! A process wide hook called on every pack of a [`VBox`](crate::VBox), to
+//! enforce policies centrally, e.g., "no payloads over 64 KiB on this
+//! channel".
+//!
+//! It is enabled by the `pack-hook` feature. The hook receives a
+//! [`PackInfo`] and returns `Err` to reject the payload, in which case packing
+//! panics with the returned message.
+//!
+//! A tag, such as the name of a channel, can be attached to the packs made in
+//! a closure with [`with_tag()`].
+//!
+//! ```
+//! # use std::fmt::Debug;
+//! # use vbox::{into_vbox, pack_hook};
+//! pack_hook::set_hook(|info| {
+//!     if info.tag == Some("small") && info.size > 8 {
+//!         return Err(format!("{} bytes is too large", info.size));
+//!     }
+//!     Ok(())
+//! });
+//!
+//! let _ok = pack_hook::with_tag("small", || into_vbox!(dyn Debug, 1u64));
+//!
+//! let res = std::panic::catch_unwind(|| {
+//!     pack_hook::with_tag("small", || into_vbox!(dyn Debug, [0u64; 2]))
+//! });
+//! assert!(res.is_err());
+//!
+//! pack_hook::clear_hook();
+//! ```
+
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use crate::tag;
+pub use crate::tag::with_tag;
+
+/// The information of a payload being packed.
+#[derive(Debug, Clone, Copy)]
+pub struct PackInfo<'a> {
+    /// Name of `dyn Trait` the payload is packed as.
+    pub type_name: &'static str,
+
+    /// Size in bytes of the payload.
+    pub size: usize,
+
+    /// Alignment in bytes of the payload.
+    pub align: usize,
+
+    /// The tag set with [`with_tag()`], if any.
+    pub tag: Option<&'a str>,
+}
+
+type Hook = dyn Fn(&PackInfo<'_>) -> Result<(), String> + Send + Sync;
+
+static HOOK: RwLock<Option<Arc<Hook>>> = RwLock::new(None);
+
+/// Set the hook, replacing the existing one.
+pub fn set_hook<F>(f: F)
+where F: Fn(&PackInfo<'_>) -> Result<(), String> + Send + Sync + 'static {
+    *HOOK.write().unwrap() = Some(Arc::new(f));
+}
+
+/// Remove the hook.
+pub fn clear_hook() {
+    *HOOK.write().unwrap() = None;
+}
+
+/// Call the hook, and panic if it rejects the payload.
+pub(crate) fn check(type_name: &'static str, size: usize, align: usize) {
+    let hook = HOOK.read().unwrap().clone();
+    let Some(hook) = hook else {
+        return;
+    };
+
+    let info = PackInfo {
+        type_name,
+        size,
+        align,
+        tag: tag::current(),
+    };
+
+    if let Err(e) = hook(&info) {
+        panic!("VBox pack is rejected: {}: {:?}", e, info);
+    }
+}
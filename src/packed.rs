@@ -0,0 +1,417 @@
+use std::alloc;
+use std::alloc::Layout;
+use std::any::TypeId;
+use std::fmt;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::ptr::NonNull;
+
+#[cfg(feature = "pack-hook")] use crate::pack_hook;
+use crate::probe::CloneCap;
+use crate::probe::DebugCap;
+use crate::probe::DisplayCap;
+#[cfg(feature = "stats")] use crate::stats;
+#[cfg(feature = "timeline")] use crate::timeline;
+use crate::AutoHooks;
+use crate::RawVBox;
+use crate::TypeMismatch;
+use crate::UnpackError;
+use crate::VBox;
+
+/// A [`VBox`] whose control data and payload are stored in one allocation.
+///
+/// A `VBox` keeps its control data inline, so that a slot of a channel buffer
+/// holds it, and the payload in a separate allocation. A `PackedVBox` is a
+/// single thin pointer to one allocation that holds the control data followed
+/// by the payload, so that draining a queue of them touches one cache line per
+/// message instead of two, at the cost of one more indirection to read the
+/// control data.
+///
+/// It is chosen at pack time with
+/// [`into_packed_vbox!`](crate::into_packed_vbox). Converting from or to a
+/// `VBox` moves the payload into a new allocation.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{from_vbox, into_packed_vbox, PackedVBox, VBox};
+/// let packed: PackedVBox = into_packed_vbox!(dyn Debug + Send, 10u64);
+/// assert_eq!(std::mem::size_of::<usize>(), std::mem::size_of::<PackedVBox>());
+///
+/// assert_eq!("10", format!("{:?}", packed.as_dyn::<dyn Debug + Send>()));
+///
+/// let vbox = VBox::from(packed);
+/// assert_eq!("10", format!("{:?}", from_vbox!(dyn Debug + Send, vbox)));
+/// ```
+pub struct PackedVBox {
+    /// Points to the control data, followed by the payload at
+    /// `header.data`.
+    header: NonNull<RawVBox>,
+}
+
+/// A `PackedVBox` can only be built from a `Send` payload.
+unsafe impl Send for PackedVBox {}
+
+impl PackedVBox {
+    /// Create a new `PackedVBox`. Do not use it directly. Use
+    /// [`into_packed_vbox!`](crate::into_packed_vbox) instead.
+    ///
+    /// # Safety
+    ///
+    /// `coerce` must be an unsizing coercion from `*mut T` to `*mut dyn
+    /// Trait`, i.e., it must return the very same pointer it is given.
+    #[doc(hidden)]
+    #[cfg_attr(any(feature = "log", feature = "timeline"), track_caller)]
+    pub unsafe fn new<T, U, D, C, P>(
+        value: T,
+        coerce: fn(*mut T) -> *mut U,
+        _caps: (D, C, P),
+    ) -> Self
+    where
+        T: Send + 'static,
+        U: ?Sized + 'static,
+        D: DebugCap,
+        C: CloneCap,
+        P: DisplayCap,
+    {
+        let layout = Layout::new::<T>();
+
+        #[cfg(feature = "log")]
+        log::debug!(
+            "VBox pack: {}{}",
+            std::any::type_name::<U>(),
+            crate::config::Site(std::panic::Location::caller())
+        );
+
+        #[cfg(feature = "pack-hook")]
+        pack_hook::check(
+            std::any::type_name::<U>(),
+            layout.size(),
+            layout.align(),
+        );
+
+        #[cfg(feature = "stats")]
+        {
+            stats::name_type::<T>();
+            stats::packed(
+                TypeId::of::<U>(),
+                std::any::type_name::<U>,
+                Some(TypeId::of::<T>()),
+                layout.size(),
+            );
+        }
+
+        #[cfg(feature = "timeline")]
+        timeline::record(
+            timeline::Kind::Pack,
+            Some(std::any::type_name::<T>()),
+            std::any::type_name::<U>(),
+        );
+
+        let (header, data) = alloc_block(layout);
+        ptr::write(data as *mut T, value);
+
+        let (data, vtable) = crate::split_raw_parts(coerce(data as *mut T));
+
+        ptr::write(header.as_ptr(), RawVBox {
+            data,
+            vtable,
+            type_id: TypeId::of::<U>(),
+            type_name: std::any::type_name::<U>,
+            concrete_type_id: Some(TypeId::of::<T>()),
+            concrete_type_name: Some(std::any::type_name::<T>),
+            layout,
+            drop_fn: crate::drop_in_place_raw_parts::<U>,
+            hooks: AutoHooks::<D, C, P>::HOOKS,
+            type_check: None,
+            mismatch_policy: None,
+            tag: 0,
+        });
+
+        PackedVBox { header }
+    }
+
+    /// Returns `true` if it is packed as `dyn Trait` `U`, see
+    /// [`VBox::is_dyn()`].
+    pub fn is_dyn<U>(&self) -> bool
+    where U: ?Sized + 'static {
+        self.view().is_dyn::<U>()
+    }
+
+    /// Returns `true` if the payload is of the concrete type `T`, see
+    /// [`VBox::is()`].
+    pub fn is<T: 'static>(&self) -> bool {
+        self.view().is::<T>()
+    }
+
+    /// Borrow the payload as `&dyn Trait`.
+    ///
+    /// It panics, or aborts, on a type mismatch, as [`VBox::as_dyn()`] does.
+    pub fn as_dyn<U>(&self) -> &U
+    where U: ?Sized + 'static {
+        self.view().check_type::<U>();
+        unsafe { &*self.dyn_ptr::<U>() }
+    }
+
+    /// Borrow the payload as `&dyn Trait`, or return `Err` if it is not packed
+    /// as `U`. It never panics.
+    pub fn try_as_dyn<U>(&self) -> Result<&U, TypeMismatch>
+    where U: ?Sized + 'static {
+        let view = self.view();
+        if !view.is_dyn::<U>() {
+            return Err(TypeMismatch::new::<U>(&view));
+        }
+        Ok(unsafe { &*self.dyn_ptr::<U>() })
+    }
+
+    /// Borrow the payload as `&mut dyn Trait`.
+    ///
+    /// It panics, or aborts, on a type mismatch, as [`VBox::as_dyn_mut()`]
+    /// does.
+    pub fn as_dyn_mut<U>(&mut self) -> &mut U
+    where U: ?Sized + 'static {
+        self.view().check_type::<U>();
+        unsafe { &mut *self.dyn_ptr::<U>() }
+    }
+
+    /// Borrow the payload as `&mut dyn Trait`, or return `Err` if it is not
+    /// packed as `U`. It never panics.
+    pub fn try_as_dyn_mut<U>(&mut self) -> Result<&mut U, TypeMismatch>
+    where U: ?Sized + 'static {
+        let view = self.view();
+        if !view.is_dyn::<U>() {
+            return Err(TypeMismatch::new::<U>(&view));
+        }
+        Ok(unsafe { &mut *self.dyn_ptr::<U>() })
+    }
+
+    /// Unpack it and rebuild the original trait object, as
+    /// [`VBox::unpack()`] does.
+    ///
+    /// A `Box<dyn Trait>` owns an allocation of the payload only, thus the
+    /// payload is moved out of the block, as converting to a `VBox` does.
+    ///
+    /// It panics, or aborts, on a type mismatch.
+    #[cfg_attr(any(feature = "log", feature = "timeline"), track_caller)]
+    pub fn unpack<U>(self) -> Box<U>
+    where U: ?Sized + 'static {
+        self.view().check_type::<U>();
+        unsafe { self.into_vbox().unpack_unchecked::<U>() }
+    }
+
+    /// Unpack it and rebuild the original trait object, or return it intact
+    /// in `Err` if it is not packed as `U`. It never panics.
+    #[cfg_attr(any(feature = "log", feature = "timeline"), track_caller)]
+    pub fn try_unpack<U>(self) -> Result<Box<U>, UnpackError<PackedVBox>>
+    where U: ?Sized + 'static {
+        let view = self.view();
+        if !view.is_dyn::<U>() {
+            return Err(UnpackError::new(TypeMismatch::new::<U>(&view), self));
+        }
+        Ok(unsafe { self.into_vbox().unpack_unchecked::<U>() })
+    }
+
+    /// Move the payload out into a [`VBox`], the same as `VBox::from()`.
+    pub fn into_vbox(self) -> VBox {
+        VBox::from(self)
+    }
+
+    /// Consume it and return the payload as the concrete type `T`, or return
+    /// it intact in `Err` if the payload is not a `T`.
+    #[cfg_attr(any(feature = "log", feature = "timeline"), track_caller)]
+    pub fn into_inner<T: 'static>(self) -> Result<T, Self> {
+        if !self.is::<T>() {
+            return Err(self);
+        }
+
+        let header = *self.header();
+
+        #[cfg(feature = "log")]
+        log::debug!(
+            "VBox unpack: {}{}",
+            std::any::type_name::<T>(),
+            crate::config::Site(std::panic::Location::caller())
+        );
+
+        #[cfg(feature = "stats")]
+        stats::unpacked(
+            header.type_id,
+            header.type_name,
+            header.concrete_type_id,
+            header.layout.size(),
+        );
+
+        #[cfg(feature = "timeline")]
+        timeline::record(
+            timeline::Kind::Unpack,
+            Some(std::any::type_name::<T>()),
+            (header.type_name)(),
+        );
+
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            let value = ptr::read(header.data as *mut T);
+            free_block(this.header, header.layout);
+            Ok(value)
+        }
+    }
+
+    /// Returns the data pointer of the payload, which is in the same
+    /// allocation as the control data.
+    ///
+    /// It is informational only, as [`VBox::data_ptr()`].
+    pub fn data_ptr(&self) -> *const () {
+        self.header().data
+    }
+
+    /// Returns the size in bytes of the payload, captured when packing.
+    pub fn size_of_payload(&self) -> usize {
+        self.header().layout.size()
+    }
+
+    /// Returns the tag, see [`VBox::tag()`].
+    pub fn tag(&self) -> u32 {
+        self.header().tag
+    }
+
+    /// Format the payload with the `Debug` implementation captured when
+    /// packing, see [`VBox::debug_string()`].
+    pub fn debug_string(&self) -> Option<String> {
+        self.view().debug_string()
+    }
+
+    fn header(&self) -> &RawVBox {
+        unsafe { self.header.as_ref() }
+    }
+
+    /// A `VBox` that shares the control data and the payload, to reuse its
+    /// checks. It must not be dropped or unpacked.
+    fn view(&self) -> ManuallyDrop<VBox> {
+        ManuallyDrop::new(unsafe { VBox::from_raw(*self.header()) })
+    }
+
+    /// Rebuild the fat pointer to the payload.
+    ///
+    /// # Safety
+    ///
+    /// It must be packed as `U`.
+    unsafe fn dyn_ptr<U>(&self) -> *mut U
+    where U: ?Sized + 'static {
+        let header = self.header();
+        crate::from_raw_parts::<U>(header.data, header.vtable)
+    }
+}
+
+impl From<VBox> for PackedVBox {
+    /// Move the payload into one allocation with the control data.
+    ///
+    /// The tag and the other per-`VBox` settings are kept.
+    fn from(vbox: VBox) -> Self {
+        let mut raw = vbox.into_raw();
+
+        let (header, data) = alloc_block(raw.layout);
+        unsafe {
+            ptr::copy_nonoverlapping(
+                raw.data as *const u8,
+                data,
+                raw.layout.size(),
+            );
+            crate::free_payload(raw.data, raw.layout);
+
+            raw.data = data as *mut ();
+            ptr::write(header.as_ptr(), raw);
+        }
+
+        PackedVBox { header }
+    }
+}
+
+impl From<PackedVBox> for VBox {
+    /// Move the payload out into an allocation of its own.
+    fn from(packed: PackedVBox) -> Self {
+        let packed = ManuallyDrop::new(packed);
+        let mut raw = *packed.header();
+
+        unsafe {
+            let data = crate::alloc_payload(raw.layout);
+            ptr::copy_nonoverlapping(
+                raw.data as *const u8,
+                data as *mut u8,
+                raw.layout.size(),
+            );
+            free_block(packed.header, raw.layout);
+
+            raw.data = data;
+            VBox::from_raw(raw)
+        }
+    }
+}
+
+impl fmt::Debug for PackedVBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = self.header();
+        let mut d = f.debug_struct("PackedVBox");
+        d.field("type_name", &(header.type_name)())
+            .field("concrete_type_id", &header.concrete_type_id);
+        if header.tag != 0 {
+            d.field("tag", &header.tag);
+        }
+        if let Some(payload) = self.debug_string() {
+            d.field("payload", &format_args!("{}", payload));
+        }
+        d.finish()
+    }
+}
+
+impl Drop for PackedVBox {
+    fn drop(&mut self) {
+        let header = *self.header();
+
+        #[cfg(feature = "log")]
+        log::debug!("VBox drop: {}", (header.type_name)());
+
+        #[cfg(feature = "stats")]
+        stats::dropped(
+            header.type_id,
+            header.type_name,
+            header.concrete_type_id,
+            header.layout.size(),
+        );
+
+        unsafe {
+            (header.drop_fn)(header.data, header.vtable);
+            free_block(self.header, header.layout);
+        }
+    }
+}
+
+/// Returns the layout of an allocation holding a `RawVBox` followed by a
+/// payload of `payload`, and the offset of the payload.
+fn block_layout(payload: Layout) -> (Layout, usize) {
+    let (layout, offset) =
+        Layout::new::<RawVBox>().extend(payload).expect("payload is too large");
+    (layout.pad_to_align(), offset)
+}
+
+/// Allocate an uninitialized block for a payload of `payload` and return the
+/// pointers to the header and to the payload.
+fn alloc_block(payload: Layout) -> (NonNull<RawVBox>, *mut u8) {
+    let (layout, offset) = block_layout(payload);
+
+    let block = unsafe { alloc::alloc(layout) };
+    let Some(header) = NonNull::new(block as *mut RawVBox) else {
+        alloc::handle_alloc_error(layout);
+    };
+
+    let data = unsafe { block.add(offset) };
+    (header, data)
+}
+
+/// Release a block whose payload is already dropped or moved out.
+///
+/// # Safety
+///
+/// `header` must be returned by [`alloc_block()`] with `payload`.
+unsafe fn free_block(header: NonNull<RawVBox>, payload: Layout) {
+    let (layout, _) = block_layout(payload);
+    alloc::dealloc(header.as_ptr() as *mut u8, layout);
+}
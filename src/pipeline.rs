@@ -0,0 +1,168 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::VBox;
+use crate::VError;
+
+/// The erased form of a stage, as packed in a [`Pipeline`].
+type StageFn = dyn Fn(VBox) -> Result<VBox, VError> + Send + Sync;
+
+/// An ordered list of erased stages, each a `Fn(VBox) -> Result<VBox,
+/// VError>`, that a [`VBox`] is passed through, e.g., on send or on receive of
+/// a channel.
+///
+/// A stage may inspect the `VBox` and pass it on, e.g., logging or metrics,
+/// replace it with another one, e.g., enrichment, or reject it with an error,
+/// e.g., validation. Since stages are erased, a pipeline is a plain value that
+/// can be attached to any channel without adding a type parameter to it.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use std::sync::mpsc;
+/// # use vbox::{into_vbox, Pipeline, VBox, VError};
+/// let pipeline = Pipeline::new()
+///     .stage("validate", |vbox: VBox| {
+///         if vbox.is::<u64>() {
+///             Ok(vbox)
+///         } else {
+///             Err(VError::new(std::fmt::Error))
+///         }
+///     })
+///     .stage("double", |vbox: VBox| {
+///         let n = vbox.into_inner::<u64>().unwrap();
+///         Ok(into_vbox!(dyn Debug + Send, n * 2))
+///     });
+///
+/// let (tx, rx) = mpsc::channel();
+/// tx.send(pipeline.run(into_vbox!(dyn Debug + Send, 2u64)).unwrap()).unwrap();
+///
+/// let got: VBox = rx.recv().unwrap();
+/// assert_eq!(4u64, got.into_inner::<u64>().unwrap());
+///
+/// let err = pipeline.run(into_vbox!(dyn Debug + Send, "x")).unwrap_err();
+/// assert_eq!("validate", err.stage());
+/// ```
+#[derive(Default)]
+pub struct Pipeline {
+    /// Stage names and stages packed as `dyn Fn(VBox) -> Result<VBox, VError>
+    /// + Send + Sync`.
+    stages: Vec<(String, VBox)>,
+}
+
+// Every stage is packed as `dyn Fn(..) + Send + Sync`.
+unsafe impl Sync for Pipeline {}
+
+/// The error returned by [`Pipeline::run()`]: the error of the stage that
+/// rejected the `VBox`.
+#[derive(Debug)]
+pub struct PipelineError {
+    stage: String,
+    error: VError,
+}
+
+impl PipelineError {
+    /// Returns the name of the stage that failed.
+    pub fn stage(&self) -> &str {
+        &self.stage
+    }
+
+    /// Returns the error returned by the stage.
+    pub fn into_error(self) -> VError {
+        self.error
+    }
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pipeline stage '{}' failed: {}", self.stage, self.error)
+    }
+}
+
+impl Error for PipelineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl Pipeline {
+    /// Create a pipeline without any stage, which passes a `VBox` through
+    /// unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a stage, and return the pipeline, to build a pipeline in one
+    /// expression.
+    pub fn stage(
+        mut self,
+        name: impl Into<String>,
+        f: impl Fn(VBox) -> Result<VBox, VError> + Send + Sync + 'static,
+    ) -> Self {
+        self.push(name, f);
+        self
+    }
+
+    /// Append a stage.
+    pub fn push(
+        &mut self,
+        name: impl Into<String>,
+        f: impl Fn(VBox) -> Result<VBox, VError> + Send + Sync + 'static,
+    ) {
+        self.push_vbox(name, crate::into_vbox!(StageFn, f));
+    }
+
+    /// Append an erased stage.
+    ///
+    /// The `stage` must be packed as `dyn Fn(VBox) -> Result<VBox, VError> +
+    /// Send + Sync`.
+    pub fn push_vbox(&mut self, name: impl Into<String>, stage: VBox) {
+        stage.check_type::<StageFn>();
+        self.stages.push((name.into(), stage));
+    }
+
+    /// Append all stages of `other` after the stages of this pipeline.
+    pub fn then(mut self, other: Pipeline) -> Self {
+        self.stages.extend(other.stages);
+        self
+    }
+
+    /// Returns the names of the stages, in order.
+    pub fn names(&self) -> Vec<&str> {
+        self.stages.iter().map(|(n, _)| n.as_str()).collect()
+    }
+
+    /// Returns the number of stages.
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Returns `true` if there is no stage.
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Pass `vbox` through every stage in order, and return the output of the
+    /// last stage.
+    ///
+    /// It stops at the first stage that returns an error. The `VBox` is
+    /// consumed by that stage.
+    // The error carries a `VError`, which is as large as a `VBox`.
+    #[allow(clippy::result_large_err)]
+    pub fn run(&self, vbox: VBox) -> Result<VBox, PipelineError> {
+        let mut vbox = vbox;
+        for (name, stage) in self.stages.iter() {
+            let f = stage.as_dyn::<StageFn>();
+            vbox = f(vbox).map_err(|error| PipelineError {
+                stage: name.clone(),
+                error,
+            })?;
+        }
+        Ok(vbox)
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pipeline").field("stages", &self.names()).finish()
+    }
+}
@@ -0,0 +1,198 @@
+//! Policies of checking the type to unpack a [`VBox`], so that one codebase
+//! can mix strict boundaries with audited hot paths.
+//!
+//! A [`PolicyVBox<P>`] is a `VBox` that checks `dyn Trait` with the policy `P`
+//! when it is unpacked or borrowed:
+//!
+//! - [`Checked`]: always check, the same as `VBox`.
+//! - [`Logging`]: always check, and log the check.
+//! - [`DebugChecked`]: check in debug builds only.
+//! - [`Unchecked`]: never check.
+//!
+//! A policy that does not always check is not [`Verified`], and a `PolicyVBox`
+//! of it can only be built with the unsafe [`PolicyVBox::new_unchecked()`].
+//!
+//! ```
+//! # use std::fmt::Debug;
+//! # use vbox::into_vbox;
+//! # use vbox::policy::{Checked, PolicyVBox, Unchecked};
+//! let strict: PolicyVBox<Checked> = PolicyVBox::new(into_vbox!(dyn Debug, 1u64));
+//! assert_eq!("1", format!("{:?}", strict.unpack::<dyn Debug>()));
+//!
+//! // The producer is audited to pack only `dyn Debug`.
+//! let fast: PolicyVBox<Unchecked> =
+//!     unsafe { PolicyVBox::new_unchecked(into_vbox!(dyn Debug, 2u64)) };
+//! assert_eq!("2", format!("{:?}", fast.unpack::<dyn Debug>()));
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::VBox;
+
+/// Check that a `VBox` is packed as `U`, i.e., `dyn Trait`, before it is
+/// unpacked or borrowed.
+pub trait CheckPolicy {
+    /// Check the type, and panic if it does not match.
+    fn check<U>(vbox: &VBox)
+    where U: ?Sized + 'static;
+}
+
+/// A policy that always panics if the type does not match.
+///
+/// # Safety
+///
+/// [`CheckPolicy::check()`] must panic if `vbox` is not packed as `U`.
+pub unsafe trait Verified: CheckPolicy {}
+
+/// Always check the type, the same as [`VBox`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Checked;
+
+impl CheckPolicy for Checked {
+    fn check<U>(vbox: &VBox)
+    where U: ?Sized + 'static {
+        vbox.check_type::<U>();
+    }
+}
+
+unsafe impl Verified for Checked {}
+
+/// Always check the type, and log the check if the `log` feature is enabled: a
+/// mismatch is logged as an error, and a match is logged at the trace level.
+///
+/// Without the `log` feature it behaves as [`Checked`], and a mismatch is
+/// reported by the panic message only.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Logging;
+
+impl CheckPolicy for Logging {
+    fn check<U>(vbox: &VBox)
+    where U: ?Sized + 'static {
+        if vbox.is_dyn::<U>() {
+            #[cfg(feature = "log")]
+            log::trace!(
+                "VBox type check passed: {}",
+                std::any::type_name::<U>()
+            );
+            return;
+        }
+
+        #[cfg(feature = "log")]
+        log::error!(
+            "VBox type check failed: expected: {}, actual: {}",
+            std::any::type_name::<U>(),
+            (vbox.type_name)()
+        );
+
+        vbox.check_type::<U>();
+    }
+}
+
+unsafe impl Verified for Logging {}
+
+/// Check the type in debug builds only.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DebugChecked;
+
+impl CheckPolicy for DebugChecked {
+    fn check<U>(vbox: &VBox)
+    where U: ?Sized + 'static {
+        #[cfg(debug_assertions)]
+        vbox.check_type::<U>();
+        #[cfg(not(debug_assertions))]
+        let _ = vbox;
+    }
+}
+
+/// Never check the type.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unchecked;
+
+impl CheckPolicy for Unchecked {
+    fn check<U>(_vbox: &VBox)
+    where U: ?Sized + 'static {
+    }
+}
+
+/// A [`VBox`] that checks the type with the policy `P` when it is unpacked or
+/// borrowed.
+///
+/// See the [module doc](crate::policy).
+pub struct PolicyVBox<P: CheckPolicy> {
+    inner: VBox,
+    _p: PhantomData<fn() -> P>,
+}
+
+impl<P: CheckPolicy> PolicyVBox<P> {
+    /// Wrap a `VBox` with a policy that always checks the type.
+    pub fn new(vbox: VBox) -> Self
+    where P: Verified {
+        Self::wrap(vbox)
+    }
+
+    /// Wrap a `VBox` with any policy.
+    ///
+    /// # Safety
+    ///
+    /// If `P` does not check the type, the caller must ensure that the `VBox`
+    /// is unpacked or borrowed only as the `dyn Trait` it is packed as.
+    pub unsafe fn new_unchecked(vbox: VBox) -> Self {
+        Self::wrap(vbox)
+    }
+
+    fn wrap(inner: VBox) -> Self {
+        PolicyVBox {
+            inner,
+            _p: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped `VBox`, which always checks the type.
+    pub fn into_vbox(self) -> VBox {
+        self.inner
+    }
+
+    /// Unpack and rebuild the original trait object, checked by `P`.
+    #[cfg_attr(any(feature = "log", feature = "timeline"), track_caller)]
+    pub fn unpack<U>(self) -> Box<U>
+    where U: ?Sized + 'static {
+        P::check::<U>(&self.inner);
+        unsafe { self.inner.unpack_unchecked::<U>() }
+    }
+
+    /// Borrow the payload as `&dyn Trait`, checked by `P`.
+    pub fn as_dyn<U>(&self) -> &U
+    where U: ?Sized + 'static {
+        P::check::<U>(&self.inner);
+        unsafe { self.inner.as_dyn_unchecked::<U>() }
+    }
+
+    /// Borrow the payload as `&mut dyn Trait`, checked by `P`.
+    pub fn as_dyn_mut<U>(&mut self) -> &mut U
+    where U: ?Sized + 'static {
+        P::check::<U>(&self.inner);
+        unsafe { self.inner.as_dyn_mut_unchecked::<U>() }
+    }
+
+    /// Returns `true` if it is packed as `U`, i.e., `dyn Trait`.
+    pub fn is_dyn<U>(&self) -> bool
+    where U: ?Sized + 'static {
+        self.inner.is_dyn::<U>()
+    }
+}
+
+impl<P: Verified> From<VBox> for PolicyVBox<P> {
+    fn from(vbox: VBox) -> Self {
+        Self::new(vbox)
+    }
+}
+
+impl<P: CheckPolicy> fmt::Debug for PolicyVBox<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PolicyVBox")
+            .field("policy", &std::any::type_name::<P>())
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Instant;
+
+use crate::Dump;
+use crate::DumpEntry;
+use crate::VBox;
+
+/// A mailbox of erased messages tagged with priority levels, where a message of
+/// a higher priority is delivered first.
+///
+/// To protect lower priority messages from starvation, a message that has been
+/// overtaken by more than `starvation_limit` deliveries is delivered before
+/// any other, see [`PriorityMailbox::with_starvation_limit()`].
+///
+/// Messages of the same priority are delivered in FIFO order.
+///
+/// ```
+/// # use vbox::{PriorityMailbox, VBox};
+/// struct Vote;
+///
+/// let mut mailbox = PriorityMailbox::new();
+/// mailbox.push(0, VBox::unit());
+/// mailbox.push(10, VBox::signal(Vote));
+///
+/// assert!(mailbox.pop().unwrap().is_signal::<Vote>());
+/// assert!(mailbox.pop().unwrap().is_unit());
+/// assert!(mailbox.pop().is_none());
+/// ```
+pub struct PriorityMailbox {
+    /// Queues of messages by priority.
+    ///
+    /// A message is stored along with the value of `delivered` and the time
+    /// when it is pushed.
+    queues: BTreeMap<u8, VecDeque<(u64, Instant, VBox)>>,
+
+    /// The number of messages delivered so far.
+    delivered: u64,
+
+    starvation_limit: u64,
+}
+
+impl Default for PriorityMailbox {
+    fn default() -> Self {
+        PriorityMailbox {
+            queues: BTreeMap::new(),
+            delivered: 0,
+            starvation_limit: Self::DEFAULT_STARVATION_LIMIT,
+        }
+    }
+}
+
+impl PriorityMailbox {
+    /// The default number of deliveries a message can be overtaken by.
+    pub const DEFAULT_STARVATION_LIMIT: u64 = 64;
+
+    /// Create an empty mailbox with the default starvation limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of deliveries a message can be overtaken by, before it
+    /// is delivered regardless of its priority.
+    ///
+    /// `u64::MAX` disables starvation protection.
+    pub fn with_starvation_limit(mut self, limit: u64) -> Self {
+        self.starvation_limit = limit;
+        self
+    }
+
+    /// Push a message with a priority, a greater value is a higher priority.
+    pub fn push(&mut self, priority: u8, message: VBox) {
+        let q = self.queues.entry(priority).or_default();
+        q.push_back((self.delivered, Instant::now(), message));
+    }
+
+    /// Pop the next message to deliver, along with its priority.
+    pub fn pop_with_priority(&mut self) -> Option<(u8, VBox)> {
+        let priority = self.starved().or_else(|| self.highest())?;
+
+        let q = self.queues.get_mut(&priority).unwrap();
+        let (_, _, message) = q.pop_front().unwrap();
+        if q.is_empty() {
+            self.queues.remove(&priority);
+        }
+
+        self.delivered += 1;
+        Some((priority, message))
+    }
+
+    /// Pop the next message to deliver.
+    pub fn pop(&mut self) -> Option<VBox> {
+        self.pop_with_priority().map(|(_, message)| message)
+    }
+
+    /// Returns the number of messages in the mailbox.
+    pub fn len(&self) -> usize {
+        self.queues.values().map(|q| q.len()).sum()
+    }
+
+    /// Returns `true` if there is no message in the mailbox.
+    pub fn is_empty(&self) -> bool {
+        self.queues.is_empty()
+    }
+
+    /// Returns a snapshot of the pending messages, tagged with their
+    /// priorities, from the highest priority to the lowest.
+    ///
+    /// The order does not take starvation protection into account.
+    pub fn dump(&self) -> Dump {
+        let now = Instant::now();
+        let entries = self
+            .queues
+            .iter()
+            .rev()
+            .flat_map(|(p, q)| {
+                q.iter().map(move |(_, at, m)| {
+                    let tag = format!("priority {}", p);
+                    DumpEntry::new(m, Some(tag), Some(now - *at))
+                })
+            })
+            .collect();
+        Dump::new("PriorityMailbox", entries)
+    }
+
+    /// Returns the highest priority that has a message.
+    fn highest(&self) -> Option<u8> {
+        self.queues.keys().next_back().copied()
+    }
+
+    /// Returns the priority of the longest waiting message, if it has been
+    /// overtaken by more than `starvation_limit` deliveries.
+    fn starved(&self) -> Option<u8> {
+        let (priority, pushed_at) = self
+            .queues
+            .iter()
+            .map(|(p, q)| (*p, q.front().unwrap().0))
+            .min_by_key(|(_, pushed_at)| *pushed_at)?;
+
+        if self.delivered - pushed_at > self.starvation_limit {
+            Some(priority)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Debug for PriorityMailbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lens = self.queues.iter().map(|(p, q)| (p, q.len()));
+        f.debug_struct("PriorityMailbox")
+            .field("queues", &BTreeMap::from_iter(lens))
+            .field("delivered", &self.delivered)
+            .field("starvation_limit", &self.starvation_limit)
+            .finish()
+    }
+}
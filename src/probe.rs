@@ -0,0 +1,187 @@
+//! Autoref specialization used by [`into_vbox!`](crate::into_vbox) to capture
+//! the `Debug`, `Clone` and `Display` implementations of the payload, if it
+//! has them.
+//!
+//! For each capability, the method is implemented on `&Probe<T>` if `T` has
+//! it, and on `Probe<T>` otherwise. Called on `&&Probe<T>`, method resolution
+//! picks the former if it applies, which needs no auto-deref. The result is
+//! [`Yes<T>`] or [`No`], which provides the captured function as an associated
+//! const.
+//!
+//! The choice is made where the macro is expanded. In a generic function, only
+//! the bounds of a type parameter are known. `Clone` is only captured along
+//! with `Debug`, to rule out closures, see the impl of [`CaptureClone`]. A
+//! payload that is `Clone` but not `Debug` opts in with
+//! [`VBoxBuilder::with_clone()`](crate::VBoxBuilder::with_clone).
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::dispose::dispose_raw;
+use crate::dispose::DisposeFn;
+use crate::fields::record_fields_raw;
+use crate::fields::FieldsFn;
+use crate::AsyncDispose;
+use crate::DebugFn;
+use crate::RecordFields;
+use crate::VBox;
+
+/// Clone the payload of a `VBox` into a new `VBox` with the same vtable.
+pub(crate) type CloneFn = unsafe fn(&VBox) -> VBox;
+
+/// Stands for the payload type `T` in method resolution.
+pub struct Probe<T>(PhantomData<T>);
+
+impl<T> Probe<T> {
+    pub fn of(_value: &T) -> Self {
+        Probe(PhantomData)
+    }
+}
+
+/// `T` has the capability.
+pub struct Yes<T>(PhantomData<T>);
+
+/// The payload does not have the capability.
+pub struct No;
+
+pub trait DebugCap {
+    const DEBUG_FN: Option<DebugFn>;
+}
+
+impl<T: fmt::Debug> DebugCap for Yes<T> {
+    const DEBUG_FN: Option<DebugFn> = Some(crate::debug_raw::<T>);
+}
+
+impl DebugCap for No {
+    const DEBUG_FN: Option<DebugFn> = None;
+}
+
+pub trait CloneCap {
+    const CLONE_FN: Option<CloneFn>;
+}
+
+impl<T: Clone + Send + 'static> CloneCap for Yes<T> {
+    const CLONE_FN: Option<CloneFn> = Some(clone_raw::<T>);
+}
+
+impl CloneCap for No {
+    const CLONE_FN: Option<CloneFn> = None;
+}
+
+pub trait DisplayCap {
+    const DISPLAY_FN: Option<DebugFn>;
+}
+
+impl<T: fmt::Display> DisplayCap for Yes<T> {
+    const DISPLAY_FN: Option<DebugFn> = Some(display_raw::<T>);
+}
+
+impl DisplayCap for No {
+    const DISPLAY_FN: Option<DebugFn> = None;
+}
+
+pub trait DisposeCap {
+    const DISPOSE_FN: Option<DisposeFn>;
+}
+
+impl<T: AsyncDispose> DisposeCap for Yes<T> {
+    const DISPOSE_FN: Option<DisposeFn> = Some(dispose_raw::<T>);
+}
+
+impl DisposeCap for No {
+    const DISPOSE_FN: Option<DisposeFn> = None;
+}
+
+pub trait FieldsCap {
+    const FIELDS_FN: Option<FieldsFn>;
+}
+
+impl<T: RecordFields> FieldsCap for Yes<T> {
+    const FIELDS_FN: Option<FieldsFn> = Some(record_fields_raw::<T>);
+}
+
+impl FieldsCap for No {
+    const FIELDS_FN: Option<FieldsFn> = None;
+}
+
+pub trait CaptureDebug<T> {
+    fn debug_cap(&self) -> Yes<T>;
+}
+
+impl<T: fmt::Debug> CaptureDebug<T> for &Probe<T> {
+    fn debug_cap(&self) -> Yes<T> {
+        Yes(PhantomData)
+    }
+}
+
+pub trait SkipDebug {
+    fn debug_cap(&self) -> No;
+}
+
+impl<T> SkipDebug for Probe<T> {
+    fn debug_cap(&self) -> No {
+        No
+    }
+}
+
+pub trait CaptureClone<T> {
+    fn clone_cap(&self) -> Yes<T>;
+}
+
+// A closure is not known to be `Clone` or not in the function that defines it,
+// until its captures are inferred, which is after the method is resolved.
+// Requiring `Debug`, which no closure implements, rules closures out.
+impl<T: Clone + fmt::Debug + Send + 'static> CaptureClone<T> for &Probe<T> {
+    fn clone_cap(&self) -> Yes<T> {
+        Yes(PhantomData)
+    }
+}
+
+pub trait SkipClone {
+    fn clone_cap(&self) -> No;
+}
+
+impl<T> SkipClone for Probe<T> {
+    fn clone_cap(&self) -> No {
+        No
+    }
+}
+
+pub trait CaptureDisplay<T> {
+    fn display_cap(&self) -> Yes<T>;
+}
+
+impl<T: fmt::Display> CaptureDisplay<T> for &Probe<T> {
+    fn display_cap(&self) -> Yes<T> {
+        Yes(PhantomData)
+    }
+}
+
+pub trait SkipDisplay {
+    fn display_cap(&self) -> No;
+}
+
+impl<T> SkipDisplay for Probe<T> {
+    fn display_cap(&self) -> No {
+        No
+    }
+}
+
+/// Clone the `T` at the data pointer and pack it as the same `dyn Trait`, with
+/// the same tag.
+unsafe fn clone_raw<T: Clone + Send + 'static>(vbox: &VBox) -> VBox {
+    let value = (*(vbox.data as *const T)).clone();
+    let mut copy = vbox
+        .pack_like(value)
+        .unwrap_or_else(|_| unreachable!("the payload is a T"));
+    copy.tag = vbox.tag;
+    copy
+}
+
+/// Formats the `T` at the data pointer with `Display`.
+unsafe fn display_raw<T: fmt::Display>(
+    data: *const (),
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    fmt::Display::fmt(&*(data as *const T), f)
+}
@@ -0,0 +1,568 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::future;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+use std::time::Instant;
+
+use crate::Dump;
+use crate::DumpEntry;
+use crate::VBox;
+
+/// A bounded queue of erased messages, with async backpressure.
+///
+/// It is cheap to clone, all clones share the same queue and any of them can
+/// send or receive. When the queue is full, [`VQueue::send()`] waits until
+/// there is room. After [`VQueue::close()`], sending fails, while the messages
+/// already in the queue can still be received.
+///
+/// It does not depend on any async runtime.
+///
+/// With [`VQueue::set_watermarks()`], callbacks are called when the depth of
+/// the queue crosses a high or a low watermark, and a [`Backpressure`] handle
+/// tells producers to throttle or shed load in between.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{from_vbox, into_vbox, VQueue};
+/// futures::executor::block_on(async {
+///     let q = VQueue::bounded(1);
+///
+///     q.send(into_vbox!(dyn Debug, 1u64)).await.unwrap();
+///     q.close();
+///     assert!(q.send(into_vbox!(dyn Debug, 2u64)).await.is_err());
+///
+///     let got = q.recv().await.unwrap();
+///     assert_eq!("1", format!("{:?}", from_vbox!(dyn Debug, got)));
+///     assert!(q.recv().await.is_none());
+/// });
+/// ```
+#[derive(Clone)]
+pub struct VQueue {
+    shared: Arc<Mutex<Shared>>,
+}
+
+struct Shared {
+    /// Messages along with when they are sent.
+    queue: VecDeque<(Instant, VBox)>,
+    capacity: usize,
+    closed: bool,
+
+    /// The total size of the payloads in the queue.
+    bytes: usize,
+
+    watermarks: Option<Watermarks>,
+
+    /// Whether the depth has reached the high watermark and not yet fallen to
+    /// the low watermark.
+    high: bool,
+
+    /// Tasks waiting for the depth to fall to the low watermark.
+    low_wakers: Vec<Waker>,
+
+    /// Tasks waiting for room to send.
+    send_wakers: Vec<Waker>,
+
+    /// Tasks waiting for a message to receive.
+    recv_wakers: Vec<Waker>,
+}
+
+/// What the depth of a queue is measured in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthUnit {
+    /// The number of messages.
+    Messages,
+
+    /// The total size of the payloads in bytes, see
+    /// [`VBox::size_of_payload()`].
+    Bytes,
+}
+
+type WatermarkFn = dyn Fn(usize) + Send + Sync;
+
+/// A high and a low watermark of the depth of a [`VQueue`], with the
+/// callbacks to call when they are crossed.
+///
+/// The high callback is called with the depth when the depth rises to `high`,
+/// and the low callback when it then falls to `low`. Between them the queue is
+/// under pressure, see [`Backpressure::is_high()`]. The callbacks are called
+/// without holding the lock of the queue, on the thread that sends or
+/// receives.
+pub struct Watermarks {
+    unit: DepthUnit,
+    low: usize,
+    high: usize,
+    on_high: Option<Arc<WatermarkFn>>,
+    on_low: Option<Arc<WatermarkFn>>,
+}
+
+impl Watermarks {
+    /// Watermarks measured in `unit`.
+    ///
+    /// It panics if `low` is greater than or equal to `high`.
+    pub fn new(unit: DepthUnit, low: usize, high: usize) -> Self {
+        assert!(low < high, "low watermark must be less than high");
+        Watermarks {
+            unit,
+            low,
+            high,
+            on_high: None,
+            on_low: None,
+        }
+    }
+
+    /// Watermarks measured in the number of messages.
+    pub fn messages(low: usize, high: usize) -> Self {
+        Self::new(DepthUnit::Messages, low, high)
+    }
+
+    /// Watermarks measured in the total size of the payloads.
+    pub fn bytes(low: usize, high: usize) -> Self {
+        Self::new(DepthUnit::Bytes, low, high)
+    }
+
+    /// Set the callback called when the depth rises to the high watermark.
+    pub fn on_high(
+        mut self,
+        f: impl Fn(usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_high = Some(Arc::new(f));
+        self
+    }
+
+    /// Set the callback called when the depth falls back to the low watermark.
+    pub fn on_low(mut self, f: impl Fn(usize) + Send + Sync + 'static) -> Self {
+        self.on_low = Some(Arc::new(f));
+        self
+    }
+}
+
+impl fmt::Debug for Watermarks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watermarks")
+            .field("unit", &self.unit)
+            .field("low", &self.low)
+            .field("high", &self.high)
+            .finish()
+    }
+}
+
+/// A callback to call after the lock is released, and its argument.
+type Crossed = Option<(Arc<WatermarkFn>, usize)>;
+
+/// The error returned by [`VQueue::try_send()`]. The message is returned.
+pub enum TrySendError {
+    /// The queue is full.
+    Full(VBox),
+
+    /// The queue is closed.
+    Closed(VBox),
+}
+
+/// The error returned by [`VQueue::try_recv()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// The queue is empty.
+    Empty,
+
+    /// The queue is empty and closed.
+    Closed,
+}
+
+impl TrySendError {
+    /// Returns the message that failed to send.
+    pub fn into_inner(self) -> VBox {
+        match self {
+            TrySendError::Full(v) => v,
+            TrySendError::Closed(v) => v,
+        }
+    }
+}
+
+impl fmt::Debug for TrySendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => write!(f, "Full(..)"),
+            TrySendError::Closed(_) => write!(f, "Closed(..)"),
+        }
+    }
+}
+
+impl fmt::Display for TrySendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => write!(f, "VQueue is full"),
+            TrySendError::Closed(_) => write!(f, "VQueue is closed"),
+        }
+    }
+}
+
+impl Error for TrySendError {}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "VQueue is empty"),
+            TryRecvError::Closed => write!(f, "VQueue is empty and closed"),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
+
+impl Shared {
+    fn push(&mut self, message: VBox) -> Crossed {
+        self.bytes += message.size_of_payload();
+        self.queue.push_back((Instant::now(), message));
+        self.wake_receivers();
+        self.check_watermarks()
+    }
+
+    fn pop(&mut self) -> Option<(VBox, Crossed)> {
+        let (_, message) = self.queue.pop_front()?;
+        self.bytes -= message.size_of_payload();
+        self.wake_senders();
+        Some((message, self.check_watermarks()))
+    }
+
+    fn depth(&self, unit: DepthUnit) -> usize {
+        match unit {
+            DepthUnit::Messages => self.queue.len(),
+            DepthUnit::Bytes => self.bytes,
+        }
+    }
+
+    fn check_watermarks(&mut self) -> Crossed {
+        let w = self.watermarks.as_ref()?;
+        let depth = self.depth(w.unit);
+
+        if !self.high && depth >= w.high {
+            self.high = true;
+            let f = w.on_high.clone();
+            return f.map(|f| (f, depth));
+        }
+
+        if self.high && depth <= w.low {
+            self.high = false;
+            let f = w.on_low.clone();
+            self.low_wakers.drain(..).for_each(Waker::wake);
+            return f.map(|f| (f, depth));
+        }
+
+        None
+    }
+
+    fn wake_senders(&mut self) {
+        self.send_wakers.drain(..).for_each(Waker::wake);
+    }
+
+    fn wake_receivers(&mut self) {
+        self.recv_wakers.drain(..).for_each(Waker::wake);
+    }
+}
+
+/// Add `waker` to `wakers`, unless it would wake the same task as one of them,
+/// so that a future polled again, e.g., in a `select!` loop, does not grow the
+/// list.
+pub(crate) fn register_waker(wakers: &mut Vec<Waker>, waker: &Waker) {
+    if !wakers.iter().any(|w| w.will_wake(waker)) {
+        wakers.push(waker.clone());
+    }
+}
+
+impl VQueue {
+    /// Create a queue that holds at most `capacity` messages.
+    ///
+    /// # Panics
+    ///
+    /// It panics if `capacity` is 0.
+    pub fn bounded(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be greater than 0");
+
+        let shared = Shared {
+            queue: VecDeque::with_capacity(capacity),
+            capacity,
+            closed: false,
+            bytes: 0,
+            watermarks: None,
+            high: false,
+            low_wakers: Vec::new(),
+            send_wakers: Vec::new(),
+            recv_wakers: Vec::new(),
+        };
+        VQueue {
+            shared: Arc::new(Mutex::new(shared)),
+        }
+    }
+
+    /// Send a message, waiting until there is room in the queue.
+    ///
+    /// If the queue is closed, the message is returned in `Err`.
+    pub async fn send(&self, message: VBox) -> Result<(), VBox> {
+        let mut message = Some(message);
+        future::poll_fn(|cx| self.poll_send(cx, &mut message)).await
+    }
+
+    fn poll_send(
+        &self,
+        cx: &mut Context<'_>,
+        message: &mut Option<VBox>,
+    ) -> Poll<Result<(), VBox>> {
+        let mut shared = self.shared.lock().unwrap();
+
+        if shared.closed {
+            return Poll::Ready(Err(message.take().unwrap()));
+        }
+
+        if shared.queue.len() < shared.capacity {
+            let crossed = shared.push(message.take().unwrap());
+            drop(shared);
+            call(crossed);
+            return Poll::Ready(Ok(()));
+        }
+
+        register_waker(&mut shared.send_wakers, cx.waker());
+        Poll::Pending
+    }
+
+    /// Send a message if there is room in the queue, without waiting.
+    pub fn try_send(&self, message: VBox) -> Result<(), TrySendError> {
+        let mut shared = self.shared.lock().unwrap();
+
+        if shared.closed {
+            return Err(TrySendError::Closed(message));
+        }
+        if shared.queue.len() >= shared.capacity {
+            return Err(TrySendError::Full(message));
+        }
+
+        let crossed = shared.push(message);
+        drop(shared);
+        call(crossed);
+        Ok(())
+    }
+
+    /// Receive a message, waiting until there is one.
+    ///
+    /// It returns `None` if the queue is closed and there is no message left.
+    pub async fn recv(&self) -> Option<VBox> {
+        future::poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Option<VBox>> {
+        let mut shared = self.shared.lock().unwrap();
+
+        if let Some((message, crossed)) = shared.pop() {
+            drop(shared);
+            call(crossed);
+            return Poll::Ready(Some(message));
+        }
+        if shared.closed {
+            return Poll::Ready(None);
+        }
+
+        register_waker(&mut shared.recv_wakers, cx.waker());
+        Poll::Pending
+    }
+
+    /// Receive a message if there is one, without waiting.
+    pub fn try_recv(&self) -> Result<VBox, TryRecvError> {
+        let mut shared = self.shared.lock().unwrap();
+
+        if let Some((message, crossed)) = shared.pop() {
+            drop(shared);
+            call(crossed);
+            return Ok(message);
+        }
+        if shared.closed {
+            return Err(TryRecvError::Closed);
+        }
+        Err(TryRecvError::Empty)
+    }
+
+    /// Close the queue. Sending fails after it, and waiting senders and
+    /// receivers are woken up.
+    ///
+    /// Messages already in the queue can still be received.
+    pub fn close(&self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.closed = true;
+        shared.wake_senders();
+        shared.wake_receivers();
+        shared.low_wakers.drain(..).for_each(Waker::wake);
+    }
+
+    /// Returns `true` if [`VQueue::close()`] is called on this queue or any of
+    /// its clones.
+    pub fn is_closed(&self) -> bool {
+        self.shared.lock().unwrap().closed
+    }
+
+    /// Returns the number of messages in the queue.
+    pub fn len(&self) -> usize {
+        self.shared.lock().unwrap().queue.len()
+    }
+
+    /// Returns `true` if there is no message in the queue.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum number of messages the queue holds.
+    pub fn capacity(&self) -> usize {
+        self.shared.lock().unwrap().capacity
+    }
+
+    /// Returns the total size of the payloads in the queue.
+    pub fn bytes(&self) -> usize {
+        self.shared.lock().unwrap().bytes
+    }
+
+    /// Returns a snapshot of the pending messages, with their types, sizes and
+    /// how long they have been queued.
+    ///
+    /// ```
+    /// # use std::fmt::Debug;
+    /// # use vbox::{into_vbox, VQueue};
+    /// let q = VQueue::bounded(4);
+    /// q.try_send(into_vbox!(dyn Debug + Send, 1u64)).unwrap();
+    ///
+    /// let dump = q.dump();
+    /// assert_eq!("u64", dump.entries[0].type_name);
+    /// assert!(dump.to_string().starts_with("VQueue: 1 pending, 8 bytes"));
+    /// ```
+    pub fn dump(&self) -> Dump {
+        let shared = self.shared.lock().unwrap();
+        let now = Instant::now();
+        let entries = shared
+            .queue
+            .iter()
+            .map(|(at, m)| DumpEntry::new(m, None, Some(now - *at)))
+            .collect();
+        Dump::new("VQueue", entries)
+    }
+
+    /// Set the watermarks of the depth, replacing the existing ones.
+    ///
+    /// The current depth is checked against the new watermarks right away.
+    pub fn set_watermarks(&self, watermarks: Watermarks) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.watermarks = Some(watermarks);
+        shared.high = false;
+        let crossed = shared.check_watermarks();
+        drop(shared);
+        call(crossed);
+    }
+
+    /// Remove the watermarks. Waiters of [`Backpressure::wait_low()`] are
+    /// woken up.
+    pub fn clear_watermarks(&self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.watermarks = None;
+        shared.high = false;
+        shared.low_wakers.drain(..).for_each(Waker::wake);
+    }
+
+    /// Returns a handle for producers to watch the pressure of the queue.
+    pub fn backpressure(&self) -> Backpressure {
+        Backpressure {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+fn call(crossed: Crossed) {
+    if let Some((f, depth)) = crossed {
+        f(depth);
+    }
+}
+
+/// A handle to watch the pressure of a [`VQueue`], returned by
+/// [`VQueue::backpressure()`], so that a producer can throttle or shed load.
+///
+/// The queue is under pressure after its depth rises to the high watermark,
+/// until it falls to the low watermark. Without watermarks, it is never under
+/// pressure.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{into_vbox, VQueue, Watermarks};
+/// let q = VQueue::bounded(10);
+/// q.set_watermarks(Watermarks::messages(1, 3));
+/// let bp = q.backpressure();
+///
+/// for i in 0..3u64 {
+///     q.try_send(into_vbox!(dyn Debug + Send, i)).unwrap();
+/// }
+/// assert!(bp.is_high());
+///
+/// // Shed load until the consumer catches up.
+/// q.try_recv().unwrap().discard();
+/// assert!(bp.is_high());
+/// q.try_recv().unwrap().discard();
+/// assert!(!bp.is_high());
+/// ```
+#[derive(Clone)]
+pub struct Backpressure {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Backpressure {
+    /// Returns `true` if the queue is under pressure.
+    pub fn is_high(&self) -> bool {
+        self.shared.lock().unwrap().high
+    }
+
+    /// Returns the depth in the unit of the watermarks, or in messages if no
+    /// watermark is set.
+    pub fn depth(&self) -> usize {
+        let shared = self.shared.lock().unwrap();
+        let unit = shared
+            .watermarks
+            .as_ref()
+            .map(|w| w.unit)
+            .unwrap_or(DepthUnit::Messages);
+        shared.depth(unit)
+    }
+
+    /// Wait until the queue is not under pressure, e.g., before a producer
+    /// sends the next batch.
+    pub async fn wait_low(&self) {
+        future::poll_fn(|cx| {
+            let mut shared = self.shared.lock().unwrap();
+            if !shared.high || shared.closed {
+                return Poll::Ready(());
+            }
+            register_waker(&mut shared.low_wakers, cx.waker());
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+impl fmt::Debug for Backpressure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shared = self.shared.lock().unwrap();
+        f.debug_struct("Backpressure")
+            .field("high", &shared.high)
+            .field("len", &shared.queue.len())
+            .field("bytes", &shared.bytes)
+            .finish()
+    }
+}
+
+impl fmt::Debug for VQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shared = self.shared.lock().unwrap();
+        f.debug_struct("VQueue")
+            .field("len", &shared.queue.len())
+            .field("capacity", &shared.capacity)
+            .field("bytes", &shared.bytes)
+            .field("closed", &shared.closed)
+            .finish()
+    }
+}
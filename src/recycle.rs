@@ -0,0 +1,134 @@
+//! A per-thread cache of the freed payload allocations of [`VBox`]es, reused
+//! by the next pack with the same layout on the same thread.
+//!
+//! It is enabled by the `recycle` feature. When a `VBox` is dropped, or its
+//! payload is taken with [`VBox::into_inner()`], the allocation is kept in a
+//! cache of the current thread, keyed by the [`Layout`] of the payload, instead
+//! of being returned to the allocator. [`into_vbox!`] takes an allocation from
+//! the cache before asking the allocator.
+//!
+//! It removes most of the allocator traffic in a ping-pong pattern, where two
+//! threads exchange messages of a few types through channels: each thread frees
+//! what the other packed, and packs into what it freed.
+//!
+//! At most [`capacity()`] allocations are kept per layout on each thread, and
+//! payloads larger than [`MAX_SIZE`] bytes are never cached. The cache of a
+//! thread is released when the thread exits, or with [`clear()`].
+//!
+//! [`VBox`]: crate::VBox
+//! [`VBox::into_inner()`]: crate::VBox::into_inner
+//! [`into_vbox!`]: crate::into_vbox
+
+use std::alloc;
+use std::alloc::Layout;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// The default number of allocations to keep per layout on each thread.
+pub const DEFAULT_CAPACITY: usize = 32;
+
+/// Payloads larger than this are not cached.
+pub const MAX_SIZE: usize = 4096;
+
+static CAPACITY: AtomicUsize = AtomicUsize::new(DEFAULT_CAPACITY);
+
+struct Cache {
+    free: HashMap<Layout, Vec<*mut u8>>,
+}
+
+impl Cache {
+    fn release(&mut self) {
+        for (layout, ptrs) in self.free.drain() {
+            for p in ptrs {
+                unsafe { alloc::dealloc(p, layout) };
+            }
+        }
+    }
+}
+
+impl Drop for Cache {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+thread_local! {
+    static CACHE: RefCell<Cache> = RefCell::new(Cache { free: HashMap::new() });
+}
+
+/// Set the number of allocations to keep per layout on each thread.
+///
+/// The allocations already kept over the new capacity are released when they
+/// are reused, or with [`clear()`].
+pub fn set_capacity(capacity: usize) {
+    CAPACITY.store(capacity, Ordering::Relaxed);
+}
+
+/// Returns the number of allocations to keep per layout on each thread.
+pub fn capacity() -> usize {
+    CAPACITY.load(Ordering::Relaxed)
+}
+
+/// Returns the number of allocations kept by the current thread.
+pub fn cached() -> usize {
+    CACHE
+        .try_with(|c| c.borrow().free.values().map(|v| v.len()).sum())
+        .unwrap_or_default()
+}
+
+/// Release all allocations kept by the current thread to the allocator.
+pub fn clear() {
+    let _ = CACHE.try_with(|c| c.borrow_mut().release());
+}
+
+/// Take an allocation for `layout` from the cache, or allocate one.
+///
+/// `layout` must have a non-zero size.
+pub(crate) fn alloc(layout: Layout) -> *mut u8 {
+    let cached = CACHE
+        .try_with(|c| {
+            c.borrow_mut().free.get_mut(&layout).and_then(|v| v.pop())
+        })
+        .ok()
+        .flatten();
+
+    if let Some(p) = cached {
+        return p;
+    }
+
+    let p = unsafe { alloc::alloc(layout) };
+    if p.is_null() {
+        alloc::handle_alloc_error(layout);
+    }
+    p
+}
+
+/// Keep an allocation in the cache, or release it to the allocator if the cache
+/// is full.
+///
+/// # Safety
+///
+/// `p` must be allocated by the global allocator with `layout`, which has a
+/// non-zero size, and must not be used after this call.
+pub(crate) unsafe fn free(p: *mut u8, layout: Layout) {
+    if layout.size() <= MAX_SIZE {
+        let kept = CACHE.try_with(|c| {
+            let mut c = c.borrow_mut();
+            let ptrs = c.free.entry(layout).or_default();
+            if ptrs.len() < capacity() {
+                ptrs.push(p);
+                true
+            } else {
+                false
+            }
+        });
+
+        if kept == Ok(true) {
+            return;
+        }
+    }
+
+    alloc::dealloc(p, layout);
+}
@@ -0,0 +1,130 @@
+//! A global registry of named, erased singletons.
+//!
+//! A value is registered once with [`set()`] and lives until the program
+//! exits. It is retrieved as `&'static dyn Trait` with
+//! [`get_as!`](crate::get_as).
+//!
+//! The trait object must be `Sync`, e.g., `dyn Clock + Sync`, since it is
+//! shared by all threads.
+//!
+//! ```
+//! # use vbox::{get_as, into_vbox, registry};
+//! trait Clock {
+//!     fn now(&self) -> u64;
+//! }
+//!
+//! struct FixedClock;
+//! impl Clock for FixedClock {
+//!     fn now(&self) -> u64 {
+//!         42
+//!     }
+//! }
+//!
+//! registry::set("clock", into_vbox!(dyn Clock + Sync, FixedClock)).unwrap();
+//!
+//! let clock = get_as!("clock", dyn Clock + Sync).unwrap();
+//! assert_eq!(42, clock.now());
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use crate::VBox;
+
+/// The error returned by [`get()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GetError {
+    /// Nothing is registered under the name.
+    NotFound { name: String },
+
+    /// The registered value is not packed as the expected trait object.
+    TypeMismatch {
+        name: String,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for GetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GetError::NotFound { name } => {
+                write!(f, "nothing is registered under: {}", name)
+            }
+            GetError::TypeMismatch { name, expected } => {
+                write!(f, "{} is not registered as: {}", name, expected)
+            }
+        }
+    }
+}
+
+impl Error for GetError {}
+
+fn entries() -> &'static Mutex<HashMap<String, VBox>> {
+    static ENTRIES: OnceLock<Mutex<HashMap<String, VBox>>> = OnceLock::new();
+    ENTRIES.get_or_init(Default::default)
+}
+
+/// Register `value` under `name`.
+///
+/// A name can only be set once. If it is already set, `value` is returned in
+/// `Err`.
+pub fn set(name: impl Into<String>, value: VBox) -> Result<(), VBox> {
+    let mut entries = entries().lock().unwrap();
+
+    let name = name.into();
+    if entries.contains_key(&name) {
+        return Err(value);
+    }
+
+    entries.insert(name, value);
+    Ok(())
+}
+
+/// Returns `true` if a value is registered under `name`.
+pub fn contains(name: &str) -> bool {
+    entries().lock().unwrap().contains_key(name)
+}
+
+/// Get the value registered under `name` as `&'static dyn Trait`. Use
+/// [`get_as!`](crate::get_as) for a better readability.
+pub fn get<U>(name: &str) -> Result<&'static U, GetError>
+where U: ?Sized + Sync + 'static {
+    let entries = entries().lock().unwrap();
+
+    let Some(vbox) = entries.get(name) else {
+        return Err(GetError::NotFound {
+            name: name.to_string(),
+        });
+    };
+
+    if !vbox.is_dyn::<U>() {
+        return Err(GetError::TypeMismatch {
+            name: name.to_string(),
+            expected: std::any::type_name::<U>(),
+        });
+    }
+
+    // An entry is never removed and the payload never moves, thus it lives
+    // until the program exits.
+    let reference: *const U = vbox.as_dyn::<U>();
+    Ok(unsafe { &*reference })
+}
+
+/// Get the value registered in the global [`registry`](crate::registry) as
+/// `&'static dyn Trait`.
+///
+/// It returns a [`GetError`] if nothing is registered under the name, or it is
+/// not packed as `dyn Trait`.
+#[macro_export]
+macro_rules! get_as {
+    ($name: expr, $t: ty) => {{
+        let ret: ::std::result::Result<
+            &'static $t,
+            $crate::registry::GetError,
+        > = $crate::registry::get::<$t>($name);
+        ret
+    }};
+}
@@ -0,0 +1,64 @@
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::thread;
+
+use crate::VBox;
+use crate::VOnce;
+use crate::VPanic;
+
+/// Run `job` on a new thread and return a future of its erased result.
+///
+/// If the job panics, the panic is caught and returned as a [`VPanic`], which
+/// can be re-raised with [`VPanic::resume()`].
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{from_vbox, into_vbox, run_remote};
+/// let res = futures::executor::block_on(run_remote(|| into_vbox!(dyn Debug, 3u64)));
+/// assert_eq!("3", format!("{:?}", from_vbox!(dyn Debug, res.unwrap())));
+///
+/// let res = futures::executor::block_on(run_remote(|| panic!("oops")));
+/// assert_eq!(Some("oops"), res.unwrap_err().message());
+/// ```
+pub fn run_remote<F>(job: F) -> impl Future<Output = Result<VBox, VPanic>>
+where F: FnOnce() -> VBox + Send + 'static {
+    run_remote_on(
+        |task| {
+            thread::spawn(task);
+        },
+        job,
+    )
+}
+
+/// Run `job` with a user provided `spawn`, e.g., to submit it to a thread pool
+/// or an executor, and return a future of its erased result.
+///
+/// `spawn` receives the task to run. If the task is dropped without running,
+/// the future resolves to a [`VPanic`] with a message that says so.
+pub fn run_remote_on<S, F>(
+    spawn: S,
+    job: F,
+) -> impl Future<Output = Result<VBox, VPanic>>
+where
+    S: FnOnce(Box<dyn FnOnce() + Send>),
+    F: FnOnce() -> VBox + Send + 'static,
+{
+    let (tx, rx) = VOnce::channel();
+
+    spawn(Box::new(move || {
+        let res = VPanic::catch(AssertUnwindSafe(job));
+        // The caller may have dropped the future, no one waits for the result.
+        let _ = tx.send(crate::into_vbox!(dyn std::any::Any + Send, res));
+    }));
+
+    async move {
+        let Ok(res) = rx.await else {
+            return Err(VPanic::new(Box::new(
+                "the job is dropped before it completes",
+            )));
+        };
+
+        let res = res.into_inner::<Result<VBox, VPanic>>();
+        res.expect("run_remote sends a Result<VBox, VPanic>")
+    }
+}
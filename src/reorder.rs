@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use crate::Envelope;
+
+/// Assigns monotonically increasing sequence numbers to the [`Envelope`]s of
+/// a stream, starting from 0.
+///
+/// It can be shared by producers, e.g., in an `Arc`, in which case the order
+/// is the order in which they call [`Sequencer::stamp()`].
+#[derive(Debug, Default)]
+pub struct Sequencer {
+    next: AtomicU64,
+}
+
+impl Sequencer {
+    /// Create a sequencer starting from 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the next sequence number.
+    pub fn next_seq(&self) -> u64 {
+        self.next.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Set the next sequence number on the envelope.
+    pub fn stamp(&self, envelope: Envelope) -> Envelope {
+        envelope.with_seq(self.next_seq())
+    }
+}
+
+/// Restores the order of sequenced [`Envelope`]s delivered out of order, e.g.,
+/// through the shards of a [`ShardedQueue`](crate::ShardedQueue) with work
+/// stealing.
+///
+/// Envelopes are pushed as they arrive and popped in the order of their
+/// [`Envelope::seq`], without gaps: an envelope is held until all the
+/// envelopes before it are popped.
+///
+/// ```
+/// # use vbox::{Envelope, ReorderBuffer, Sequencer, VBox};
+/// let seq = Sequencer::new();
+/// let a = seq.stamp(Envelope::new(VBox::unit()));
+/// let b = seq.stamp(Envelope::new(VBox::unit()));
+///
+/// let mut buf = ReorderBuffer::new();
+/// buf.push(b).unwrap();
+/// assert!(buf.pop().is_none());
+///
+/// buf.push(a).unwrap();
+/// assert_eq!(Some(0), buf.pop().unwrap().seq);
+/// assert_eq!(Some(1), buf.pop().unwrap().seq);
+/// ```
+#[derive(Default)]
+pub struct ReorderBuffer {
+    /// The sequence number of the next envelope to pop.
+    expected: u64,
+
+    /// Envelopes arrived ahead of `expected`.
+    held: BTreeMap<u64, Envelope>,
+}
+
+impl ReorderBuffer {
+    /// Create a buffer expecting the sequence to start from 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a buffer expecting the sequence to start from `seq`, e.g., when
+    /// a consumer resumes a stream.
+    pub fn starting_at(seq: u64) -> Self {
+        ReorderBuffer {
+            expected: seq,
+            held: BTreeMap::new(),
+        }
+    }
+
+    /// Hold an envelope until it is its turn.
+    ///
+    /// It returns the envelope in `Err` if it has no sequence number, or its
+    /// turn has passed, or another envelope with the same sequence number is
+    /// held. The envelope is boxed to keep the `Result` small.
+    pub fn push(&mut self, envelope: Envelope) -> Result<(), Box<Envelope>> {
+        let Some(seq) = envelope.seq else {
+            return Err(Box::new(envelope));
+        };
+
+        if seq < self.expected || self.held.contains_key(&seq) {
+            return Err(Box::new(envelope));
+        }
+
+        self.held.insert(seq, envelope);
+        Ok(())
+    }
+
+    /// Pop the envelope whose turn it is, if it has arrived.
+    pub fn pop(&mut self) -> Option<Envelope> {
+        let envelope = self.held.remove(&self.expected)?;
+        self.expected += 1;
+        Some(envelope)
+    }
+
+    /// Pop all the envelopes that are ready, in order.
+    pub fn drain_ready(&mut self) -> Vec<Envelope> {
+        std::iter::from_fn(|| self.pop()).collect()
+    }
+
+    /// Give up waiting for the envelopes before `seq`, e.g., after a timeout,
+    /// and return the held envelopes before it, in order.
+    ///
+    /// It does nothing if `seq` is not ahead of the expected one.
+    pub fn skip_to(&mut self, seq: u64) -> Vec<Envelope> {
+        if seq <= self.expected {
+            return vec![];
+        }
+        let ahead = self.held.split_off(&seq);
+        let skipped = std::mem::replace(&mut self.held, ahead);
+        self.expected = seq;
+        skipped.into_values().collect()
+    }
+
+    /// Returns the sequence number of the next envelope to pop.
+    pub fn expected(&self) -> u64 {
+        self.expected
+    }
+
+    /// Returns the ranges of the sequence numbers missing before the last held
+    /// envelope, in order.
+    ///
+    /// There is at most one range more than the envelopes held, no matter how
+    /// far ahead an envelope is.
+    pub fn missing(&self) -> Vec<Range<u64>> {
+        let mut ranges = vec![];
+        let mut start = self.expected;
+
+        for &seq in self.held.keys() {
+            if seq > start {
+                ranges.push(start..seq);
+            }
+            start = seq.saturating_add(1);
+        }
+        ranges
+    }
+
+    /// Returns the number of envelopes held.
+    pub fn len(&self) -> usize {
+        self.held.len()
+    }
+
+    /// Returns `true` if no envelope is held.
+    pub fn is_empty(&self) -> bool {
+        self.held.is_empty()
+    }
+}
+
+impl fmt::Debug for ReorderBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReorderBuffer")
+            .field("expected", &self.expected)
+            .field("held", &self.held.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
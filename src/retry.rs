@@ -0,0 +1,195 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::hash::Hasher;
+use std::thread;
+use std::time::Duration;
+
+use crate::VBox;
+use crate::VError;
+
+/// The erased form of an operation, as accepted by [`Retry::run_vbox()`].
+type OpFn = dyn FnMut() -> Result<VBox, VError> + Send;
+
+/// Runs a fallible operation that returns an erased value, and retries it
+/// with exponential backoff and jitter until it succeeds or the attempts run
+/// out.
+///
+/// The delay before the `n`-th retry is `initial_backoff * multiplier^(n-1)`,
+/// capped at `max_backoff`, then reduced by a random fraction up to `jitter`,
+/// so that clients failing together do not retry together.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use std::time::Duration;
+/// # use vbox::{into_vbox, Retry, VError};
+/// let mut calls = 0;
+/// let res = Retry::new()
+///     .max_attempts(5)
+///     .initial_backoff(Duration::from_millis(1))
+///     .run(|| {
+///         calls += 1;
+///         if calls < 3 {
+///             Err(VError::new(std::fmt::Error))
+///         } else {
+///             Ok(into_vbox!(dyn Debug + Send, calls))
+///         }
+///     });
+///
+/// assert_eq!(3, res.unwrap().into_inner::<i32>().unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Retry {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: f64,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Retry {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.5,
+        }
+    }
+}
+
+impl Retry {
+    /// Create a policy of 3 attempts, with backoff starting at 100 ms,
+    /// doubling each time up to 10 s, and a jitter of 0.5.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of attempts, including the first one.
+    ///
+    /// It panics if `n` is 0.
+    pub fn max_attempts(mut self, n: u32) -> Self {
+        assert!(n > 0, "max_attempts must be at least 1");
+        self.max_attempts = n;
+        self
+    }
+
+    /// Set the delay before the first retry.
+    pub fn initial_backoff(mut self, d: Duration) -> Self {
+        self.initial_backoff = d;
+        self
+    }
+
+    /// Set the upper bound of the delay before a retry, before jitter.
+    pub fn max_backoff(mut self, d: Duration) -> Self {
+        self.max_backoff = d;
+        self
+    }
+
+    /// Set the factor the delay grows by after each retry.
+    ///
+    /// It panics if `m` is less than 1.0.
+    pub fn multiplier(mut self, m: f64) -> Self {
+        assert!(m >= 1.0, "multiplier must be at least 1.0");
+        self.multiplier = m;
+        self
+    }
+
+    /// Set the maximum fraction, in `[0.0, 1.0]`, a delay is randomly reduced
+    /// by. `0.0` disables jitter.
+    ///
+    /// It panics if `j` is out of range.
+    pub fn jitter(mut self, j: f64) -> Self {
+        assert!((0.0..=1.0).contains(&j), "jitter must be in [0.0, 1.0]");
+        self.jitter = j;
+        self
+    }
+
+    /// Returns the delay before the `retry`-th retry, starting from 1, before
+    /// jitter is applied.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exp = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let secs =
+            self.initial_backoff.as_secs_f64() * self.multiplier.powi(exp);
+        let max = self.max_backoff.as_secs_f64();
+
+        if secs.is_finite() && secs < max {
+            Duration::from_secs_f64(secs)
+        } else {
+            self.max_backoff
+        }
+    }
+
+    /// Run `f` until it returns `Ok`, sleeping between attempts, and return
+    /// the value of the first success, or the error of the last attempt.
+    pub fn run<F>(&self, f: F) -> Result<VBox, VError>
+    where F: FnMut() -> Result<VBox, VError> {
+        self.run_with_sleep(f, thread::sleep)
+    }
+
+    /// Run an erased operation like [`Retry::run()`].
+    ///
+    /// The `op` must be packed as `dyn FnMut() -> Result<VBox, VError> +
+    /// Send`.
+    pub fn run_vbox(&self, op: VBox) -> Result<VBox, VError> {
+        let op = op.unpack::<OpFn>();
+        self.run(op)
+    }
+
+    /// Run `f` like [`Retry::run()`], but call `sleep` with the delay instead
+    /// of blocking the thread, e.g., to record the delays in a test.
+    pub fn run_with_sleep<F, S>(
+        &self,
+        mut f: F,
+        mut sleep: S,
+    ) -> Result<VBox, VError>
+    where
+        F: FnMut() -> Result<VBox, VError>,
+        S: FnMut(Duration),
+    {
+        let mut rng = Rng::new();
+        let mut attempt = 1;
+
+        loop {
+            let err = match f() {
+                Ok(v) => return Ok(v),
+                Err(e) => e,
+            };
+
+            if attempt >= self.max_attempts {
+                return Err(err);
+            }
+
+            sleep(self.jittered(self.backoff(attempt), &mut rng));
+            attempt += 1;
+        }
+    }
+
+    fn jittered(&self, d: Duration, rng: &mut Rng) -> Duration {
+        if self.jitter == 0.0 {
+            return d;
+        }
+        d.mul_f64(1.0 - self.jitter * rng.next_f64())
+    }
+}
+
+/// A xorshift generator seeded from the randomly keyed std hasher; jitter does
+/// not need more.
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Self {
+        let seed = RandomState::new().build_hasher().finish();
+        Rng(seed | 1)
+    }
+
+    /// Returns a number in `[0.0, 1.0)`.
+    fn next_f64(&mut self) -> f64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
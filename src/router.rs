@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::VBox;
+
+/// A [`VBox`] paired with a user defined tag, e.g., an enum or an integer that
+/// identifies the kind of the message in a multiplexed channel.
+#[derive(Debug)]
+pub struct TaggedVBox<K> {
+    /// The tag to route the message by.
+    pub tag: K,
+
+    /// The message body.
+    pub vbox: VBox,
+}
+
+impl<K> TaggedVBox<K> {
+    /// Tag a `VBox`.
+    pub fn new(tag: K, vbox: VBox) -> Self {
+        TaggedVBox { tag, vbox }
+    }
+
+    /// Returns the tag and the `VBox`.
+    pub fn into_parts(self) -> (K, VBox) {
+        (self.tag, self.vbox)
+    }
+}
+
+/// Routes [`TaggedVBox`]es to erased handlers by tag.
+///
+/// A handler is a `FnMut(VBox)`, it receives the `VBox` of a message with the
+/// tag it is registered for.
+///
+/// ```
+/// # use std::sync::mpsc;
+/// # use vbox::{Router, TaggedVBox, VBox};
+/// #[derive(Debug, PartialEq, Eq, Hash)]
+/// enum Kind {
+///     Ping,
+///     Data,
+/// }
+///
+/// let (tx, rx) = mpsc::channel();
+///
+/// let mut router = Router::new();
+/// router.on(Kind::Ping, move |vbox| tx.send(vbox.is_unit()).unwrap());
+///
+/// router.route(TaggedVBox::new(Kind::Ping, VBox::unit())).unwrap();
+/// assert!(rx.recv().unwrap());
+///
+/// let unrouted = router.route(TaggedVBox::new(Kind::Data, VBox::unit()));
+/// assert!(unrouted.is_err());
+/// ```
+pub struct Router<K> {
+    /// Handlers packed as `dyn FnMut(VBox) + Send`.
+    handlers: HashMap<K, VBox>,
+}
+
+impl<K> Default for Router<K> {
+    fn default() -> Self {
+        Router {
+            handlers: HashMap::new(),
+        }
+    }
+}
+
+impl<K> Router<K>
+where K: Eq + Hash
+{
+    /// Create a router without any handler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the handler for `tag`, replacing the existing one.
+    pub fn on(&mut self, tag: K, f: impl FnMut(VBox) + Send + 'static) {
+        let f = crate::into_vbox!(dyn FnMut(VBox) + Send, f);
+        self.handlers.insert(tag, f);
+    }
+
+    /// Register an erased handler for `tag`, replacing the existing one.
+    ///
+    /// The `handler` must be packed as `dyn FnMut(VBox) + Send`, otherwise it
+    /// is returned in `Err` right away, rather than failing at route time.
+    pub fn register(&mut self, tag: K, handler: VBox) -> Result<(), VBox> {
+        if !handler.is_dyn::<dyn FnMut(VBox) + Send>() {
+            return Err(handler);
+        }
+        self.handlers.insert(tag, handler);
+        Ok(())
+    }
+
+    /// Remove the handler for `tag`, and return it.
+    pub fn unregister(&mut self, tag: &K) -> Option<VBox> {
+        self.handlers.remove(tag)
+    }
+
+    /// Returns `true` if a handler is registered for `tag`.
+    pub fn contains(&self, tag: &K) -> bool {
+        self.handlers.contains_key(tag)
+    }
+
+    /// Feed the message to the handler registered for its tag.
+    ///
+    /// If there is no handler for the tag, the message is returned in `Err`.
+    pub fn route(&mut self, msg: TaggedVBox<K>) -> Result<(), TaggedVBox<K>> {
+        let Some(handler) = self.handlers.get_mut(&msg.tag) else {
+            return Err(msg);
+        };
+
+        let f = handler.as_dyn_mut::<dyn FnMut(VBox) + Send>();
+        f(msg.vbox);
+        Ok(())
+    }
+}
+
+impl<K> fmt::Debug for Router<K>
+where K: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Router")
+            .field("tags", &self.handlers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+type FilterFn<K> = dyn Fn(&TaggedVBox<K>) -> bool + Send;
+
+type SubscriberFn<K> = dyn FnMut(&TaggedVBox<K>) + Send;
+
+/// Selects the messages a subscriber of a [`Fanout`] receives, by tag, by the
+/// `dyn Trait` the `VBox` is packed as, or by a user predicate.
+///
+/// Filters are combined with [`RouteFilter::and()`] and [`RouteFilter::or()`].
+pub struct RouteFilter<K> {
+    f: Box<FilterFn<K>>,
+}
+
+impl<K> RouteFilter<K>
+where K: 'static
+{
+    /// Match every message.
+    pub fn all() -> Self {
+        Self::predicate(|_| true)
+    }
+
+    /// Match the messages with the tag `tag`.
+    pub fn tag(tag: K) -> Self
+    where K: PartialEq + Send {
+        Self::predicate(move |msg| msg.tag == tag)
+    }
+
+    /// Match the messages packed as `U`, i.e., `dyn Trait`.
+    pub fn dyn_trait<U>() -> Self
+    where U: ?Sized + 'static {
+        Self::predicate(|msg| msg.vbox.is_dyn::<U>())
+    }
+
+    /// Match the messages for which `f` returns `true`, e.g., by the concrete
+    /// type or by the size of the payload.
+    pub fn predicate(
+        f: impl Fn(&TaggedVBox<K>) -> bool + Send + 'static,
+    ) -> Self {
+        RouteFilter { f: Box::new(f) }
+    }
+
+    /// Match the messages matched by both filters.
+    pub fn and(self, other: Self) -> Self {
+        Self::predicate(move |msg| (self.f)(msg) && (other.f)(msg))
+    }
+
+    /// Match the messages matched by either filter.
+    pub fn or(self, other: Self) -> Self {
+        Self::predicate(move |msg| (self.f)(msg) || (other.f)(msg))
+    }
+
+    /// Returns `true` if `msg` is matched.
+    pub fn matches(&self, msg: &TaggedVBox<K>) -> bool {
+        (self.f)(msg)
+    }
+}
+
+impl<K> fmt::Debug for RouteFilter<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RouteFilter")
+    }
+}
+
+/// Identifies a subscription of a [`Fanout`], to unsubscribe it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubscriptionId(u64);
+
+/// Delivers each [`TaggedVBox`] of a multiplexed stream to every subscriber
+/// whose [`RouteFilter`] matches it.
+///
+/// Unlike [`Router`], a message may have any number of receivers. They borrow
+/// the message in turn, in the order they subscribed, so the payload is never
+/// copied; the publisher keeps the ownership.
+///
+/// ```
+/// # use std::fmt::{Debug, Display};
+/// # use std::sync::mpsc;
+/// # use vbox::{into_vbox, Fanout, RouteFilter, TaggedVBox};
+/// let (tx, rx) = mpsc::channel();
+///
+/// let mut fanout = Fanout::new();
+///
+/// let t = tx.clone();
+/// fanout.subscribe(RouteFilter::tag("audit"), move |msg| {
+///     t.send(format!("audit {:?}", msg.vbox.as_dyn::<dyn Debug>())).unwrap()
+/// });
+/// fanout.subscribe(RouteFilter::dyn_trait::<dyn Display>(), move |msg| {
+///     tx.send(format!("display {}", msg.vbox.as_dyn::<dyn Display>())).unwrap()
+/// });
+///
+/// let msg = TaggedVBox::new("audit", into_vbox!(dyn Debug, 1u64));
+/// assert_eq!(1, fanout.publish(&msg));
+///
+/// let msg = TaggedVBox::new("data", into_vbox!(dyn Display, 2u64));
+/// assert_eq!(1, fanout.publish(&msg));
+///
+/// assert_eq!(vec!["audit 1", "display 2"], rx.try_iter().collect::<Vec<_>>());
+/// ```
+pub struct Fanout<K> {
+    next_id: u64,
+    subscribers: Vec<Subscriber<K>>,
+}
+
+struct Subscriber<K> {
+    id: SubscriptionId,
+    filter: RouteFilter<K>,
+    handler: Box<SubscriberFn<K>>,
+}
+
+impl<K> Default for Fanout<K> {
+    fn default() -> Self {
+        Fanout {
+            next_id: 0,
+            subscribers: Vec::new(),
+        }
+    }
+}
+
+impl<K> Fanout<K>
+where K: 'static
+{
+    /// Create a fanout without any subscriber.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to the messages matched by `filter`.
+    pub fn subscribe(
+        &mut self,
+        filter: RouteFilter<K>,
+        f: impl FnMut(&TaggedVBox<K>) + Send + 'static,
+    ) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+
+        self.subscribers.push(Subscriber {
+            id,
+            filter,
+            handler: Box::new(f),
+        });
+        id
+    }
+
+    /// Remove a subscription. It returns `false` if it is already removed.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let len = self.subscribers.len();
+        self.subscribers.retain(|s| s.id != id);
+        self.subscribers.len() != len
+    }
+
+    /// Returns the number of subscriptions.
+    pub fn len(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    /// Returns `true` if there is no subscription.
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+
+    /// Deliver `msg` to every matching subscriber, and return the number of
+    /// subscribers it is delivered to.
+    pub fn publish(&mut self, msg: &TaggedVBox<K>) -> usize {
+        let mut n = 0;
+        for s in self.subscribers.iter_mut() {
+            if s.filter.matches(msg) {
+                (s.handler)(msg);
+                n += 1;
+            }
+        }
+        n
+    }
+}
+
+impl<K> fmt::Debug for Fanout<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fanout")
+            .field("subscribers", &self.subscribers.len())
+            .finish()
+    }
+}
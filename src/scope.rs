@@ -0,0 +1,136 @@
+use std::fmt;
+use std::future;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::Poll;
+use std::thread;
+
+use crate::VBox;
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A scope to spawn erased tasks on threads, created by [`scope()`].
+pub struct Scope<'scope, 'env: 'scope> {
+    inner: &'scope thread::Scope<'scope, 'env>,
+}
+
+/// Run `f` with a [`Scope`] to spawn erased tasks on threads, and wait for all
+/// the tasks to finish before returning.
+///
+/// If a task panics, the panic is propagated after all tasks finish.
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use std::sync::atomic::{AtomicU64, Ordering};
+/// # use vbox::into_vbox;
+/// let cnt = Arc::new(AtomicU64::new(0));
+///
+/// vbox::scope(|s| {
+///     for _ in 0..3 {
+///         let c = cnt.clone();
+///         s.spawn_erased(into_vbox!(dyn FnOnce() + Send, move || {
+///             c.fetch_add(1, Ordering::Relaxed);
+///         }));
+///     }
+/// });
+///
+/// assert_eq!(3, cnt.load(Ordering::Relaxed));
+/// ```
+pub fn scope<F, R>(f: F) -> R
+where F: for<'scope, 'env> FnOnce(&Scope<'scope, 'env>) -> R {
+    thread::scope(|s| f(&Scope { inner: s }))
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Spawn a task on a new thread.
+    pub fn spawn(&self, f: impl FnOnce() + Send + 'static) {
+        self.inner.spawn(f);
+    }
+
+    /// Spawn an erased task on a new thread.
+    ///
+    /// The `task` must be packed as `dyn FnOnce() + Send`.
+    pub fn spawn_erased(&self, task: VBox) {
+        let f = task.unpack::<dyn FnOnce() + Send>();
+        self.inner.spawn(f);
+    }
+}
+
+impl<'scope, 'env> fmt::Debug for Scope<'scope, 'env> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scope").finish()
+    }
+}
+
+/// A scope to spawn erased futures, created by [`async_scope()`].
+///
+/// The futures are polled concurrently by the future returned by
+/// [`async_scope()`], no async runtime is required.
+#[derive(Default)]
+pub struct AsyncScope {
+    /// Futures spawned, to be polled after the closure returns.
+    spawned: Mutex<Vec<BoxFuture>>,
+}
+
+/// Run `f` with an [`AsyncScope`] to spawn erased futures, and return a future
+/// that completes when all the spawned futures complete.
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use std::sync::atomic::{AtomicU64, Ordering};
+/// # use std::future::Future;
+/// # use vbox::into_vbox;
+/// let cnt = Arc::new(AtomicU64::new(0));
+///
+/// let c = cnt.clone();
+/// let fu = vbox::async_scope(move |s| {
+///     s.spawn_erased(into_vbox!(dyn Future<Output = ()> + Send, async move {
+///         c.fetch_add(1, Ordering::Relaxed);
+///     }));
+/// });
+///
+/// futures::executor::block_on(fu);
+/// assert_eq!(1, cnt.load(Ordering::Relaxed));
+/// ```
+pub async fn async_scope<F, R>(f: F) -> R
+where F: FnOnce(&AsyncScope) -> R {
+    let scope = AsyncScope::default();
+    let ret = f(&scope);
+
+    let mut running = scope.spawned.into_inner().unwrap();
+    future::poll_fn(|cx| {
+        running.retain_mut(|fu| fu.as_mut().poll(cx).is_pending());
+
+        if running.is_empty() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await;
+
+    ret
+}
+
+impl AsyncScope {
+    /// Spawn a future in this scope.
+    pub fn spawn(&self, fu: impl Future<Output = ()> + Send + 'static) {
+        self.spawned.lock().unwrap().push(Box::pin(fu));
+    }
+
+    /// Spawn an erased future in this scope.
+    ///
+    /// The `task` must be packed as `dyn Future<Output = ()> + Send`.
+    pub fn spawn_erased(&self, task: VBox) {
+        let fu = task.unpack::<dyn Future<Output = ()> + Send>();
+        self.spawned.lock().unwrap().push(Box::into_pin(fu));
+    }
+}
+
+impl fmt::Debug for AsyncScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let spawned = self.spawned.lock().unwrap().len();
+        f.debug_struct("AsyncScope").field("spawned", &spawned).finish()
+    }
+}
@@ -0,0 +1,696 @@
+//! A bridge between an embedded scripting engine, such as `rhai` or `mlua`,
+//! and host functions: Rust closures are erased, registered by name, and
+//! called with `VBox`-packed arguments and results.
+//!
+//! The bridge does not depend on any engine. An engine adapter converts the
+//! script values into `VBox`es of Rust values, e.g., `i64` or `String`, calls
+//! [`ScriptBridge::call()`] with the name the script called, and converts the
+//! result back. The adapters for `rhai` and `mlua` are provided by the
+//! features of the same names: `register_rhai()` and `register_lua()`. Opaque
+//! host objects travel through the script as `VBox`es unchanged.
+//!
+//! ```
+//! # use std::any::Any;
+//! # use vbox::into_vbox;
+//! # use vbox::script::ScriptBridge;
+//! let mut bridge = ScriptBridge::new();
+//! bridge.register_fn("add", |a: i64, b: i64| a + b);
+//! bridge.register_fn("greet", |name: String| format!("hello {}", name));
+//!
+//! let args = vec![into_vbox!(dyn Any + Send, 1i64), into_vbox!(dyn Any + Send, 2i64)];
+//! let sum = bridge.call("add", args).unwrap();
+//! assert_eq!(3, sum.into_inner::<i64>().unwrap());
+//!
+//! let err = bridge.call("add", vec![]).unwrap_err();
+//! assert_eq!("add: expected 2 arguments, got 0", err.to_string());
+//! ```
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+#[cfg(any(feature = "rhai", feature = "mlua"))] use std::sync::Arc;
+
+use crate::VBox;
+use crate::VError;
+
+/// The erased form of a host function, as registered in a [`ScriptBridge`].
+type HostFn = dyn Fn(Vec<VBox>) -> Result<VBox, ScriptError> + Send + Sync;
+
+/// The error returned by [`ScriptBridge::call()`].
+#[derive(Debug)]
+pub enum ScriptError {
+    /// No function is registered under the name.
+    NotFound { name: String },
+
+    /// The function is called with a wrong number of arguments.
+    Arity {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+
+    /// An argument is not of the type the function takes.
+    ArgType {
+        name: String,
+        index: usize,
+        expected: &'static str,
+    },
+
+    /// The function returned an error, boxed to keep the `Result` small.
+    Failed { name: String, error: Box<VError> },
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::NotFound { name } => {
+                write!(f, "no host function: {}", name)
+            }
+            ScriptError::Arity {
+                name,
+                expected,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "{}: expected {} arguments, got {}",
+                    name, expected, actual
+                )
+            }
+            ScriptError::ArgType {
+                name,
+                index,
+                expected,
+            } => {
+                write!(f, "{}: argument {} is not: {}", name, index, expected)
+            }
+            ScriptError::Failed { name, error } => {
+                write!(f, "{} failed: {}", name, error)
+            }
+        }
+    }
+}
+
+impl Error for ScriptError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ScriptError::Failed { error, .. } => Some(&**error),
+            _ => None,
+        }
+    }
+}
+
+/// The arguments of a host function registered with
+/// [`ScriptBridge::register_fn()`], unpacked from `VBox`es by concrete type.
+///
+/// It is implemented for functions of up to 4 arguments.
+pub trait ScriptArgs<Args>: Send + Sync + 'static {
+    /// Returns the number of arguments.
+    fn arity(&self) -> usize;
+
+    /// Unpack the arguments and call the function, returning the result
+    /// packed as `dyn Any + Send`.
+    ///
+    /// The number of `args` must be the arity. `Err` contains the index of
+    /// the argument of a wrong type and the expected type name.
+    fn call_args(&self, args: Vec<VBox>)
+        -> Result<VBox, (usize, &'static str)>;
+}
+
+macro_rules! impl_script_args {
+    ($n: expr; $($a: ident),*) => {
+        impl<F, R, $($a),*> ScriptArgs<($($a,)*)> for F
+        where
+            F: Fn($($a),*) -> R + Send + Sync + 'static,
+            R: Send + 'static,
+            $($a: 'static),*
+        {
+            fn arity(&self) -> usize {
+                $n
+            }
+
+            #[allow(unused_mut, unused_variables, non_snake_case)]
+            fn call_args(
+                &self,
+                args: Vec<VBox>,
+            ) -> Result<VBox, (usize, &'static str)> {
+                let mut it = args.into_iter().enumerate();
+                $(
+                    let $a = {
+                        let (i, vbox) = it.next().unwrap();
+                        match vbox.into_inner::<$a>() {
+                            Ok(v) => v,
+                            Err(vbox) => {
+                                vbox.discard();
+                                return Err((i, std::any::type_name::<$a>()));
+                            }
+                        }
+                    };
+                )*
+                let ret = self($($a),*);
+                Ok(crate::into_vbox!(dyn Any + Send, ret))
+            }
+        }
+    };
+}
+
+impl_script_args!(0;);
+impl_script_args!(1; A1);
+impl_script_args!(2; A1, A2);
+impl_script_args!(3; A1, A2, A3);
+impl_script_args!(4; A1, A2, A3, A4);
+
+/// A table of host functions registered by name, see the [module
+/// doc](self).
+///
+/// It can be shared between threads, e.g., by several script engines.
+#[derive(Default)]
+pub struct ScriptBridge {
+    /// Host functions packed as `dyn Fn(Vec<VBox>) -> Result<VBox,
+    /// ScriptError> + Send + Sync`.
+    fns: BTreeMap<String, VBox>,
+}
+
+// Every function is packed as `dyn Fn(..) + Send + Sync`.
+unsafe impl Sync for ScriptBridge {}
+
+impl ScriptBridge {
+    /// Create a bridge without any function.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a function taking and returning `VBox`es as they are, e.g.,
+    /// to take an opaque host object of any type, and return `true` if it
+    /// replaces a function of the same name.
+    ///
+    /// An error returned by `f` is reported as [`ScriptError::Failed`].
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        f: impl Fn(Vec<VBox>) -> Result<VBox, VError> + Send + Sync + 'static,
+    ) -> bool {
+        let name = name.into();
+        let n = name.clone();
+        let host = move |args: Vec<VBox>| {
+            f(args).map_err(|error| ScriptError::Failed {
+                name: n.clone(),
+                error: Box::new(error),
+            })
+        };
+        self.fns.insert(name, crate::into_vbox!(HostFn, host)).is_some()
+    }
+
+    /// Register a function whose arguments are unpacked by concrete type, and
+    /// whose result is packed as `dyn Any + Send`, and return `true` if it
+    /// replaces a function of the same name.
+    ///
+    /// A call with a wrong number of arguments or an argument of a wrong type
+    /// fails without calling `f`.
+    pub fn register_fn<Args, F>(
+        &mut self,
+        name: impl Into<String>,
+        f: F,
+    ) -> bool
+    where
+        F: ScriptArgs<Args>,
+    {
+        let name = name.into();
+        let n = name.clone();
+        let host = move |args: Vec<VBox>| {
+            if args.len() != f.arity() {
+                return Err(ScriptError::Arity {
+                    name: n.clone(),
+                    expected: f.arity(),
+                    actual: args.len(),
+                });
+            }
+            f.call_args(args).map_err(|(index, expected)| {
+                ScriptError::ArgType {
+                    name: n.clone(),
+                    index,
+                    expected,
+                }
+            })
+        };
+        self.fns.insert(name, crate::into_vbox!(HostFn, host)).is_some()
+    }
+
+    /// Remove the function registered under `name`, and return `true` if
+    /// there was one.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.fns.remove(name).is_some()
+    }
+
+    /// Call the function registered under `name`.
+    pub fn call(
+        &self,
+        name: &str,
+        args: Vec<VBox>,
+    ) -> Result<VBox, ScriptError> {
+        let Some(f) = self.fns.get(name) else {
+            return Err(ScriptError::NotFound {
+                name: name.to_string(),
+            });
+        };
+        let f = unsafe { f.as_dyn_unchecked::<HostFn>() };
+        f(args)
+    }
+
+    /// Returns `true` if a function is registered under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.fns.contains_key(name)
+    }
+
+    /// Returns the names of the functions, in order, e.g., to declare them
+    /// to a script engine.
+    pub fn names(&self) -> Vec<&str> {
+        self.fns.keys().map(|n| n.as_str()).collect()
+    }
+
+    /// Returns the number of functions.
+    pub fn len(&self) -> usize {
+        self.fns.len()
+    }
+
+    /// Returns `true` if there is no function.
+    pub fn is_empty(&self) -> bool {
+        self.fns.is_empty()
+    }
+}
+
+impl fmt::Debug for ScriptBridge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScriptBridge").field("fns", &self.names()).finish()
+    }
+}
+
+/// The most arguments a function is registered with in a `rhai` engine by
+/// [`register_rhai()`], the same as [`ScriptArgs`] supports.
+#[cfg(feature = "rhai")]
+pub const RHAI_MAX_ARGS: usize = 4;
+
+/// Register every function of `bridge` in a `rhai` engine, under the same
+/// name.
+///
+/// The script values `()`, `bool`, `i64`, `f64`, `char` and strings are
+/// passed as `VBox`es of `()`, `bool`, `i64`, `f64`, `char` and `String`, and
+/// a result of one of these types, or `&'static str`, is converted back. Any
+/// other argument or result fails the call with a runtime error, as does a
+/// [`ScriptError`].
+///
+/// `rhai` has no variadic function, thus every name is registered with 0 to
+/// [`RHAI_MAX_ARGS`] arguments of any type, and the bridge reports a call with
+/// a wrong number of arguments. A function registered in the bridge after this
+/// call is not visible to the engine.
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use vbox::script::register_rhai;
+/// # use vbox::script::ScriptBridge;
+/// let mut bridge = ScriptBridge::new();
+/// bridge.register_fn("add", |a: i64, b: i64| a + b);
+///
+/// let mut engine = rhai::Engine::new();
+/// register_rhai(&mut engine, &Arc::new(bridge));
+///
+/// assert_eq!(3, engine.eval::<i64>("add(1, 2)").unwrap());
+/// ```
+#[cfg(feature = "rhai")]
+pub fn register_rhai(engine: &mut rhai::Engine, bridge: &Arc<ScriptBridge>) {
+    use std::any::TypeId;
+
+    use rhai::Dynamic;
+
+    for name in bridge.names() {
+        for arity in 0..=RHAI_MAX_ARGS {
+            let bridge = bridge.clone();
+            let n = name.to_string();
+            let arg_types = vec![TypeId::of::<Dynamic>(); arity];
+
+            engine.register_raw_fn(name, arg_types, move |_ctx, args| {
+                let args = args
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(i, a)| from_rhai(&n, i, std::mem::take(*a)))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let ret = bridge.call(&n, args).map_err(|e| e.to_string())?;
+                into_rhai(&n, ret)
+            });
+        }
+    }
+}
+
+#[cfg(feature = "rhai")]
+fn from_rhai(
+    name: &str,
+    index: usize,
+    v: rhai::Dynamic,
+) -> Result<VBox, Box<rhai::EvalAltResult>> {
+    let vbox = if v.is_unit() {
+        crate::into_vbox!(dyn Any + Send, ())
+    } else if let Ok(b) = v.as_bool() {
+        crate::into_vbox!(dyn Any + Send, b)
+    } else if let Ok(i) = v.as_int() {
+        crate::into_vbox!(dyn Any + Send, i)
+    } else if let Ok(f) = v.as_float() {
+        crate::into_vbox!(dyn Any + Send, f)
+    } else if let Ok(c) = v.as_char() {
+        crate::into_vbox!(dyn Any + Send, c)
+    } else if v.is_string() {
+        let s = v.into_immutable_string().unwrap().to_string();
+        crate::into_vbox!(dyn Any + Send, s)
+    } else {
+        return Err(format!(
+            "{}: argument {} of type {} can not be passed to the host",
+            name,
+            index,
+            v.type_name()
+        )
+        .into());
+    };
+    Ok(vbox)
+}
+
+#[cfg(feature = "rhai")]
+fn into_rhai(
+    name: &str,
+    ret: VBox,
+) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>> {
+    use rhai::Dynamic;
+
+    let res = ret
+        .into_inner::<()>()
+        .map(|_| Dynamic::UNIT)
+        .or_else(|v| v.into_inner::<bool>().map(Dynamic::from))
+        .or_else(|v| v.into_inner::<i64>().map(Dynamic::from))
+        .or_else(|v| v.into_inner::<f64>().map(Dynamic::from))
+        .or_else(|v| v.into_inner::<char>().map(Dynamic::from))
+        .or_else(|v| v.into_inner::<String>().map(Dynamic::from))
+        .or_else(|v| v.into_inner::<&'static str>().map(Dynamic::from));
+
+    res.map_err(|v| {
+        let err = unsupported_result(name, &v);
+        v.discard();
+        err.into()
+    })
+}
+
+/// Register every function of `bridge` as a global function of a Lua state,
+/// under the same name.
+///
+/// The Lua values `nil`, booleans, integers, numbers and strings are passed as
+/// `VBox`es of `()`, `bool`, `i64`, `f64` and `String`, and a result of one of
+/// these types, or `&'static str`, is converted back. Any other argument or
+/// result fails the call with a runtime error, as does a [`ScriptError`].
+///
+/// A [`HostObject`] result is stored in Lua as a userdata. When the script
+/// passes it to a host function, the payload is moved into a new `HostObject`
+/// argument, and the one in Lua is left taken.
+///
+/// A function registered in the bridge after this call is not visible to the
+/// Lua state.
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use vbox::script::register_lua;
+/// # use vbox::script::ScriptBridge;
+/// let mut bridge = ScriptBridge::new();
+/// bridge.register_fn("add", |a: i64, b: i64| a + b);
+///
+/// let lua = mlua::Lua::new();
+/// register_lua(&lua, &Arc::new(bridge)).unwrap();
+///
+/// assert_eq!(3, lua.load("return add(1, 2)").eval::<i64>().unwrap());
+/// ```
+#[cfg(feature = "mlua")]
+pub fn register_lua(
+    lua: &mlua::Lua,
+    bridge: &Arc<ScriptBridge>,
+) -> mlua::Result<()> {
+    let globals = lua.globals();
+
+    for name in bridge.names() {
+        let bridge = bridge.clone();
+        let n = name.to_string();
+
+        let f = lua.create_function(move |lua, args: mlua::MultiValue| {
+            let args = args
+                .into_iter()
+                .enumerate()
+                .map(|(i, a)| from_lua(&n, i, a))
+                .collect::<mlua::Result<Vec<_>>>()?;
+
+            let ret = bridge
+                .call(&n, args)
+                .map_err(|e| mlua::Error::runtime(e.to_string()))?;
+            into_lua(lua, &n, ret)
+        })?;
+
+        globals.set(name, f)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "mlua")]
+fn from_lua(name: &str, index: usize, v: mlua::Value) -> mlua::Result<VBox> {
+    use mlua::Value;
+
+    let vbox = match v {
+        Value::Nil => crate::into_vbox!(dyn Any + Send, ()),
+        Value::Boolean(b) => crate::into_vbox!(dyn Any + Send, b),
+        Value::Integer(i) => crate::into_vbox!(dyn Any + Send, i),
+        Value::Number(f) => crate::into_vbox!(dyn Any + Send, f),
+        Value::String(s) => {
+            let s = s.to_str()?.to_string();
+            crate::into_vbox!(dyn Any + Send, s)
+        }
+        Value::UserData(ud) if ud.is::<HostObject>() => {
+            let Some(vbox) = ud.borrow_mut::<HostObject>()?.take_vbox() else {
+                return Err(mlua::Error::runtime(format!(
+                    "{}: argument {} is a taken host object",
+                    name, index
+                )));
+            };
+            crate::into_vbox!(dyn Any + Send, HostObject::new(vbox))
+        }
+        v => {
+            return Err(mlua::Error::runtime(format!(
+                "{}: argument {} of type {} can not be passed to the host",
+                name,
+                index,
+                v.type_name()
+            )));
+        }
+    };
+    Ok(vbox)
+}
+
+#[cfg(feature = "mlua")]
+fn into_lua<'lua>(
+    lua: &'lua mlua::Lua,
+    name: &str,
+    ret: VBox,
+) -> mlua::Result<mlua::Value<'lua>> {
+    use mlua::Value;
+
+    let ret = match ret.into_inner::<()>() {
+        Ok(()) => return Ok(Value::Nil),
+        Err(v) => v,
+    };
+
+    let res = ret
+        .into_inner::<bool>()
+        .map(Value::Boolean)
+        .or_else(|v| v.into_inner::<i64>().map(Value::Integer))
+        .or_else(|v| v.into_inner::<f64>().map(Value::Number));
+
+    let ret = match res {
+        Ok(v) => return Ok(v),
+        Err(v) => v,
+    };
+
+    let ret = match ret.into_inner::<String>() {
+        Ok(s) => return lua.create_string(s).map(Value::String),
+        Err(v) => v,
+    };
+
+    let ret = match ret.into_inner::<&'static str>() {
+        Ok(s) => return lua.create_string(s).map(Value::String),
+        Err(v) => v,
+    };
+
+    match ret.into_inner::<HostObject>() {
+        Ok(obj) => lua.create_userdata(obj).map(Value::UserData),
+        Err(v) => {
+            let err = unsupported_result(name, &v);
+            v.discard();
+            Err(mlua::Error::runtime(err))
+        }
+    }
+}
+
+#[cfg(any(feature = "rhai", feature = "mlua"))]
+fn unsupported_result(name: &str, ret: &VBox) -> String {
+    format!(
+        "{}: result of type {} can not be passed to the script",
+        name,
+        ret.concrete_type_name.map_or((ret.type_name)(), |f| f())
+    )
+}
+
+/// The error returned when a script passes a [`HostObject`] back to Rust.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostObjectError {
+    /// The payload has been taken out of the object.
+    Taken,
+
+    /// The payload is not packed as the expected trait object.
+    TypeMismatch {
+        expected: &'static str,
+        actual: &'static str,
+    },
+}
+
+impl fmt::Display for HostObjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostObjectError::Taken => write!(f, "host object is taken"),
+            HostObjectError::TypeMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "host object is packed as: {}, expected: {}",
+                    actual, expected
+                )
+            }
+        }
+    }
+}
+
+impl Error for HostObjectError {}
+
+/// An opaque host object stored in a script value, e.g., as the `UserData`
+/// of an `mlua` value, that is passed back to Rust and unpacked with the usual
+/// check of `dyn Trait`.
+///
+/// An engine adapter stores it in the script value as is. With the `mlua`
+/// feature it implements `mlua::UserData`, with only a `__tostring` metamethod
+/// that shows [`HostObject::type_name()`]. A script can hold and pass it
+/// around, but not look inside.
+///
+/// ```
+/// # use std::fmt::Display;
+/// # use vbox::into_vbox;
+/// # use vbox::script::HostObject;
+/// let mut obj = HostObject::new(into_vbox!(dyn Display, 3u64));
+///
+/// assert_eq!(Ok("3".to_string()), obj.with::<dyn Display, _>(|d| d.to_string()));
+/// assert!(obj.take::<dyn std::fmt::Debug>().is_err());
+///
+/// let d = obj.take::<dyn Display>().unwrap();
+/// assert_eq!("3", d.to_string());
+/// assert!(obj.is_taken());
+/// ```
+#[derive(Debug)]
+pub struct HostObject {
+    vbox: Option<VBox>,
+}
+
+#[cfg(feature = "mlua")]
+impl mlua::UserData for HostObject {
+    fn add_methods<'lua, M: mlua::UserDataMethods<'lua, Self>>(
+        methods: &mut M,
+    ) {
+        methods.add_meta_method(mlua::MetaMethod::ToString, |_, this, ()| {
+            Ok(format!(
+                "HostObject({})",
+                this.type_name().unwrap_or("taken")
+            ))
+        });
+    }
+}
+
+impl From<VBox> for HostObject {
+    fn from(vbox: VBox) -> Self {
+        HostObject::new(vbox)
+    }
+}
+
+impl HostObject {
+    /// Wrap a `VBox` to hand it to a script.
+    pub fn new(vbox: VBox) -> Self {
+        HostObject { vbox: Some(vbox) }
+    }
+
+    /// Returns the name of the trait object the payload is packed as, or
+    /// `None` if it is taken.
+    pub fn type_name(&self) -> Option<&'static str> {
+        self.vbox.as_ref().map(|v| (v.type_name)())
+    }
+
+    /// Returns `true` if the payload is packed as `U`, i.e., `dyn Trait`.
+    pub fn is_dyn<U>(&self) -> bool
+    where U: ?Sized + 'static {
+        self.vbox.as_ref().is_some_and(|v| v.is_dyn::<U>())
+    }
+
+    /// Returns `true` if the payload is taken out, e.g., a script passed the
+    /// object to a host function that consumed it.
+    pub fn is_taken(&self) -> bool {
+        self.vbox.is_none()
+    }
+
+    /// Call `f` with the payload borrowed as `&dyn Trait`.
+    pub fn with<U, R>(
+        &self,
+        f: impl FnOnce(&U) -> R,
+    ) -> Result<R, HostObjectError>
+    where
+        U: ?Sized + 'static,
+    {
+        let vbox = self.checked::<U>()?;
+        Ok(f(unsafe { vbox.as_dyn_unchecked::<U>() }))
+    }
+
+    /// Call `f` with the payload borrowed as `&mut dyn Trait`.
+    pub fn with_mut<U, R>(
+        &mut self,
+        f: impl FnOnce(&mut U) -> R,
+    ) -> Result<R, HostObjectError>
+    where
+        U: ?Sized + 'static,
+    {
+        self.checked::<U>()?;
+        let vbox = self.vbox.as_mut().unwrap();
+        Ok(f(unsafe { vbox.as_dyn_mut_unchecked::<U>() }))
+    }
+
+    /// Take the payload out as `Box<dyn Trait>`, leaving the object empty.
+    ///
+    /// On a type mismatch the payload is left in the object.
+    pub fn take<U>(&mut self) -> Result<Box<U>, HostObjectError>
+    where U: ?Sized + 'static {
+        self.checked::<U>()?;
+        let vbox = self.vbox.take().unwrap();
+        Ok(unsafe { vbox.unpack_unchecked::<U>() })
+    }
+
+    /// Take the `VBox` out, leaving the object empty.
+    pub fn take_vbox(&mut self) -> Option<VBox> {
+        self.vbox.take()
+    }
+
+    fn checked<U>(&self) -> Result<&VBox, HostObjectError>
+    where U: ?Sized + 'static {
+        let vbox = self.vbox.as_ref().ok_or(HostObjectError::Taken)?;
+        if !vbox.is_dyn::<U>() {
+            return Err(HostObjectError::TypeMismatch {
+                expected: std::any::type_name::<U>(),
+                actual: (vbox.type_name)(),
+            });
+        }
+        Ok(vbox)
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::Dump;
+use crate::DumpEntry;
+use crate::VBox;
+
+/// A non-blocking MPMC queue of [`VBox`]es, sharded into per-worker queues with
+/// work stealing.
+///
+/// Producers push to a shard chosen by the concrete type of the message, or by
+/// a user provided key such as a tag. Worker `i` pops from shard `i`, and
+/// steals from the other shards when its own shard is empty. Since a producer
+/// and a consumer contend only on one shard, it scales better than a single
+/// queue for fan-in on many cores.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use std::sync::Arc;
+/// # use vbox::{into_vbox, ShardedQueue};
+/// let q = Arc::new(ShardedQueue::new(4));
+///
+/// q.push_by_key(&"user-1", into_vbox!(dyn Debug, 1u64));
+/// q.push(into_vbox!(dyn Debug, 2u64));
+///
+/// let workers: Vec<_> = (0..4)
+///     .map(|i| {
+///         let q = q.clone();
+///         std::thread::spawn(move || {
+///             let mut n = 0;
+///             while let Some(_vbox) = q.pop(i) {
+///                 n += 1;
+///             }
+///             n
+///         })
+///     })
+///     .collect();
+///
+/// let total: u64 = workers.into_iter().map(|w| w.join().unwrap()).sum();
+/// assert_eq!(2, total);
+/// ```
+pub struct ShardedQueue {
+    /// Messages along with when they are pushed, by shard.
+    shards: Vec<Mutex<VecDeque<(Instant, VBox)>>>,
+}
+
+impl ShardedQueue {
+    /// Create a queue with `n` shards, usually one per worker thread.
+    ///
+    /// It panics if `n` is zero.
+    pub fn new(n: usize) -> Self {
+        assert!(n > 0, "number of shards must be positive");
+
+        ShardedQueue {
+            shards: (0..n).map(|_| Mutex::new(VecDeque::new())).collect(),
+        }
+    }
+
+    /// Returns the number of shards.
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Push a message to the shard chosen by its implementation, see
+    /// [`VBox::same_impl()`], so that messages of the same concrete type tend
+    /// to be handled by the same worker.
+    pub fn push(&self, vbox: VBox) {
+        let key = vbox.impl_key();
+        self.push_by_key(&key, vbox);
+    }
+
+    /// Push a message to the shard chosen by the hash of `key`, e.g., a tag
+    /// or a session id, so that messages with the same key go to the same
+    /// shard.
+    ///
+    /// It does not keep them in order: [`ShardedQueue::pop()`] steals from the
+    /// back of another shard, thus a message may be handled before, or at the
+    /// same time as, an earlier one with the same key. Workers that need the
+    /// order should only use [`ShardedQueue::pop_local()`].
+    pub fn push_by_key<K: Hash + ?Sized>(&self, key: &K, vbox: VBox) {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let shard = (hasher.finish() % self.shards.len() as u64) as usize;
+        self.push_to(shard, vbox);
+    }
+
+    /// Push a message to shard `shard`.
+    ///
+    /// It panics if `shard` is out of range.
+    pub fn push_to(&self, shard: usize, vbox: VBox) {
+        self.shards[shard].lock().unwrap().push_back((Instant::now(), vbox));
+    }
+
+    /// Pop a message for worker `shard`: from the front of its own shard, or
+    /// steal one from the back of another shard if its own is empty.
+    ///
+    /// It returns `None` if all shards are empty.
+    pub fn pop(&self, shard: usize) -> Option<VBox> {
+        if let Some(vbox) = self.pop_local(shard) {
+            return Some(vbox);
+        }
+        self.steal(shard)
+    }
+
+    /// Pop a message from the front of shard `shard`, without stealing.
+    pub fn pop_local(&self, shard: usize) -> Option<VBox> {
+        self.shards[shard].lock().unwrap().pop_front().map(|(_, vbox)| vbox)
+    }
+
+    /// Steal a message from the back of a shard other than `shard`, starting
+    /// from the next one.
+    fn steal(&self, shard: usize) -> Option<VBox> {
+        let n = self.shards.len();
+
+        (1..n).find_map(|i| {
+            let victim = (shard + i) % n;
+            self.shards[victim].lock().unwrap().pop_back().map(|(_, vbox)| vbox)
+        })
+    }
+
+    /// Returns a snapshot of the pending messages, tagged with their shards,
+    /// shard by shard.
+    ///
+    /// The shards are locked one at a time, so it is not an atomic snapshot of
+    /// the whole queue.
+    pub fn dump(&self) -> Dump {
+        let mut entries = vec![];
+        for (i, shard) in self.shards.iter().enumerate() {
+            let shard = shard.lock().unwrap();
+            let now = Instant::now();
+            entries.extend(shard.iter().map(|(at, m)| {
+                DumpEntry::new(m, Some(format!("shard {}", i)), Some(now - *at))
+            }));
+        }
+        Dump::new("ShardedQueue", entries)
+    }
+
+    /// Returns the total number of messages in all shards.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|s| s.lock().unwrap().len()).sum()
+    }
+
+    /// Returns `true` if all shards are empty.
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|s| s.lock().unwrap().is_empty())
+    }
+}
+
+impl fmt::Debug for ShardedQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lens: Vec<_> =
+            self.shards.iter().map(|s| s.lock().unwrap().len()).collect();
+        f.debug_struct("ShardedQueue").field("shards", &lens).finish()
+    }
+}
@@ -0,0 +1,517 @@
+//! Pass [`VBox`]es of plain-old-data payloads between processes through a ring
+//! buffer in shared memory.
+//!
+//! A payload type implements [`Pod`] and is registered in a [`PodRegistry`]
+//! under a name, with the `dyn Trait` to pack it as. The name identifies the
+//! type in every process, since a `TypeId` is only meaningful within one
+//! compilation. A [`ShmRing`] copies the bytes of a registered payload into a
+//! region of memory, e.g., mapped with `mmap()` by the caller, and the other
+//! end rebuilds the `VBox` from them.
+//!
+//! ```
+//! # use std::fmt::Debug;
+//! # use vbox::{from_vbox, into_vbox, register_pod};
+//! # use vbox::shm::{PodRegistry, ShmRing};
+//! #[derive(Debug, Clone, Copy)]
+//! #[repr(C)]
+//! struct Tick {
+//!     seq: u64,
+//!     price: f64,
+//! }
+//! // No padding, and any bytes are a valid `Tick`: a `u64` and an `f64`.
+//! unsafe impl vbox::shm::Pod for Tick {}
+//!
+//! // Both processes register the same names.
+//! let registry = PodRegistry::new();
+//! register_pod!(registry, "tick", Tick => dyn Debug + Send);
+//!
+//! // The region is usually shared memory mapped by both processes.
+//! let mut region = vec![0u64; 128];
+//! let mut ring = unsafe { ShmRing::init(region.as_mut_ptr() as *mut u8, 1024) };
+//!
+//! ring.push(&registry, into_vbox!(dyn Debug + Send, Tick { seq: 1, price: 2.5 })).unwrap();
+//!
+//! let vbox = ring.pop(&registry).unwrap().unwrap();
+//! let tick = from_vbox!(dyn Debug + Send, vbox);
+//! assert_eq!("Tick { seq: 1, price: 2.5 }", format!("{:?}", tick));
+//! ```
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::RwLock;
+
+use crate::VBox;
+
+/// A type whose value is fully described by its bytes, thus can be copied to
+/// another process.
+///
+/// # Safety
+///
+/// The type must be `Copy`, have a stable layout such as `#[repr(C)]`, have no
+/// padding bytes, and must not contain pointers or references. Every bit
+/// pattern must be a valid value of the type, since the bytes are read as is
+/// from the other process: a `bool`, a `char` or an enum is not `Pod`. Every
+/// process must be built with the same definition of it.
+pub unsafe trait Pod: Copy + Send + 'static {}
+
+macro_rules! impl_pod {
+    ($($t: ty),*) => {
+        $(unsafe impl Pod for $t {})*
+    };
+}
+
+impl_pod!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// Rebuild a `VBox` from the bytes of a payload.
+type Decode = Box<dyn Fn(&[u8]) -> VBox + Send + Sync>;
+
+struct Entry {
+    id: u64,
+    size: usize,
+    decode: Decode,
+}
+
+/// A registry of [`Pod`] payload types, identified by names that are the same
+/// in every process.
+///
+/// Use [`register_pod!`](crate::register_pod) to register a type.
+#[derive(Default)]
+pub struct PodRegistry {
+    /// Registered types by the type id of the concrete type, to encode.
+    by_type: RwLock<HashMap<TypeId, u64>>,
+
+    /// Registered types by the id derived from the name, to decode.
+    by_id: RwLock<HashMap<u64, Entry>>,
+}
+
+impl PodRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the concrete type `T` under `name`, with a function that
+    /// packs a `T` into a `VBox`. Use [`register_pod!`](crate::register_pod)
+    /// for a better readability.
+    ///
+    /// It panics if another type is registered under the same name.
+    pub fn register<T: Pod>(&self, name: &str, pack: fn(T) -> VBox) {
+        let id = name_id(name);
+
+        let mut by_id = self.by_id.write().unwrap();
+        if let Some(existing) = by_id.get(&id) {
+            let registered =
+                self.by_type.read().unwrap().get(&TypeId::of::<T>())
+                    == Some(&existing.id);
+            assert!(registered, "another type is registered as: {}", name);
+        }
+
+        let decode = move |bytes: &[u8]| {
+            assert_eq!(mem::size_of::<T>(), bytes.len());
+            // `T` is `Pod`, every bit pattern of the right size is a valid
+            // `T`.
+            let value =
+                unsafe { ptr::read_unaligned(bytes.as_ptr() as *const T) };
+            pack(value)
+        };
+
+        by_id.insert(id, Entry {
+            id,
+            size: mem::size_of::<T>(),
+            decode: Box::new(decode),
+        });
+        self.by_type.write().unwrap().insert(TypeId::of::<T>(), id);
+    }
+
+    /// Returns `true` if the concrete type of `vbox` is registered.
+    pub fn supports(&self, vbox: &VBox) -> bool {
+        self.id_of(vbox).is_some()
+    }
+
+    fn id_of(&self, vbox: &VBox) -> Option<u64> {
+        let type_id = vbox.concrete_type_id?;
+        self.by_type.read().unwrap().get(&type_id).copied()
+    }
+}
+
+impl fmt::Debug for PodRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let types = self.by_id.read().unwrap().len();
+        f.debug_struct("PodRegistry").field("types", &types).finish()
+    }
+}
+
+/// Register a [`Pod`] type `T` in a [`PodRegistry`] under a name, to be packed
+/// as `dyn Trait` when it is received.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::register_pod;
+/// # use vbox::shm::PodRegistry;
+/// let registry = PodRegistry::new();
+/// register_pod!(registry, "u64", u64 => dyn Debug + Send);
+/// ```
+#[macro_export]
+macro_rules! register_pod {
+    ($registry: expr, $name: expr, $t: ty => $u: ty) => {{
+        let registry: &$crate::shm::PodRegistry = &$registry;
+        registry.register::<$t>($name, |v: $t| $crate::into_vbox!($u, v));
+    }};
+}
+
+/// The error returned by [`ShmRing::pop()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopError {
+    /// The type of the record is not registered in this process. The record
+    /// is dropped.
+    UnknownPod {
+        /// The id derived from the name the type is registered as.
+        id: u64,
+    },
+
+    /// The record does not fit in the buffer, i.e., the shared memory is
+    /// corrupted by the other process. Nothing is popped.
+    Corrupted {
+        /// The offset of the record in the buffer.
+        offset: usize,
+
+        /// The size of the payload in the record.
+        size: u64,
+    },
+}
+
+impl fmt::Display for PopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PopError::UnknownPod { id } => {
+                write!(f, "no type is registered with id: {:#x}", id)
+            }
+            PopError::Corrupted { offset, size } => {
+                write!(
+                    f,
+                    "corrupted record at offset {} with size {}",
+                    offset, size
+                )
+            }
+        }
+    }
+}
+
+impl Error for PopError {}
+
+/// Derive a stable id from a name with FNV-1a. 0 is reserved to mark the end
+/// of the buffer.
+fn name_id(name: &str) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for b in name.bytes() {
+        h ^= b as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    h.max(1)
+}
+
+const MAGIC: u64 = 0x7662_6f78_7368_6d31; // "vboxshm1"
+
+/// Marks the rest of the buffer is skipped, the next record is at the start.
+const WRAP: u64 = 0;
+
+/// The header at the start of the region.
+#[repr(C)]
+struct Header {
+    magic: AtomicU64,
+
+    /// Size in bytes of the buffer after the header.
+    capacity: AtomicU64,
+
+    /// Total bytes written, updated by the producer.
+    head: AtomicU64,
+
+    /// Total bytes read, updated by the consumer.
+    tail: AtomicU64,
+}
+
+/// Size in bytes of the header of a record: the type id and the size.
+const RECORD_HEADER: usize = 16;
+
+/// A single producer, single consumer ring buffer of [`Pod`] payloads in a
+/// region of memory shared by two processes.
+///
+/// Each record is the id of the payload type, the size and the bytes of the
+/// payload, aligned to 8 bytes.
+///
+/// [`ShmRing::push()`] and [`ShmRing::pop()`] take `&mut self`, thus a
+/// `ShmRing` is used by one thread at a time. Each process attaches its own
+/// `ShmRing` to the region.
+pub struct ShmRing {
+    header: *const Header,
+    buf: *mut u8,
+    capacity: usize,
+}
+
+/// The region is shared on purpose. The positions are synchronized with
+/// atomics.
+unsafe impl Send for ShmRing {}
+unsafe impl Sync for ShmRing {}
+
+impl ShmRing {
+    /// Size in bytes of the header at the start of the region.
+    pub const HEADER_SIZE: usize = 64;
+
+    /// Initialize an empty ring in the region, by one of the processes.
+    ///
+    /// # Safety
+    ///
+    /// `region` must be aligned to 8 bytes and valid for `len` bytes as long
+    /// as the ring is used. Only one `ShmRing` may push to the region and only
+    /// one may pop from it.
+    ///
+    /// # Panics
+    ///
+    /// It panics if `len` is too small to hold the header and a record.
+    pub unsafe fn init(region: *mut u8, len: usize) -> Self {
+        assert!(
+            len >= Self::HEADER_SIZE + RECORD_HEADER,
+            "region is too small: {}",
+            len
+        );
+        assert_eq!(0, region as usize % 8, "region must be aligned to 8 bytes");
+
+        let capacity = (len - Self::HEADER_SIZE) / 8 * 8;
+
+        let header = region as *mut Header;
+        ptr::write(header, Header {
+            magic: AtomicU64::new(0),
+            capacity: AtomicU64::new(capacity as u64),
+            head: AtomicU64::new(0),
+            tail: AtomicU64::new(0),
+        });
+        (*header).magic.store(MAGIC, Ordering::Release);
+
+        Self::attach(region, len)
+    }
+
+    /// Attach to a ring initialized by another process with
+    /// [`ShmRing::init()`].
+    ///
+    /// # Safety
+    ///
+    /// The same as [`ShmRing::init()`].
+    ///
+    /// # Panics
+    ///
+    /// It panics if the region is not initialized as a ring, or `len` is less
+    /// than it is initialized with, or the capacity in the header is invalid.
+    pub unsafe fn attach(region: *mut u8, len: usize) -> Self {
+        let header = region as *const Header;
+        assert_eq!(
+            MAGIC,
+            (*header).magic.load(Ordering::Acquire),
+            "region is not initialized as a ShmRing"
+        );
+
+        let capacity = (*header).capacity.load(Ordering::Relaxed) as usize;
+        assert!(
+            capacity >= RECORD_HEADER && round_up(capacity) == capacity,
+            "invalid capacity of the ring: {}",
+            capacity
+        );
+        assert!(
+            Self::HEADER_SIZE + capacity <= len,
+            "region is smaller than the ring: {} < {}",
+            len,
+            Self::HEADER_SIZE + capacity
+        );
+
+        ShmRing {
+            header,
+            buf: region.add(Self::HEADER_SIZE),
+            capacity,
+        }
+    }
+
+    /// Returns the size in bytes of the buffer.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of bytes used by the records not yet popped.
+    ///
+    /// The positions are written by both processes; if they are corrupted,
+    /// the result is clamped to `0..=capacity`.
+    pub fn len(&self) -> usize {
+        let h = self.header();
+        let head = h.head.load(Ordering::Acquire);
+        let tail = h.tail.load(Ordering::Acquire);
+        head.saturating_sub(tail).min(self.capacity as u64) as usize
+    }
+
+    /// Returns `true` if there is no record to pop.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copy the payload of `vbox` into the ring, by the producer.
+    ///
+    /// The `VBox` is returned intact in `Err` if its concrete type is not
+    /// registered, the record is larger than the buffer, the ring is full, or
+    /// the positions in the header are corrupted by the other process, e.g.,
+    /// the consumer is ahead of the producer.
+    pub fn push(
+        &mut self,
+        registry: &PodRegistry,
+        vbox: VBox,
+    ) -> Result<(), VBox> {
+        let Some(id) = registry.id_of(&vbox) else {
+            return Err(vbox);
+        };
+
+        let size = vbox.size_of_payload();
+        let need = RECORD_HEADER + round_up(size);
+        if need > self.capacity {
+            return Err(vbox);
+        }
+
+        let h = self.header();
+        let head = h.head.load(Ordering::Relaxed);
+        let tail = h.tail.load(Ordering::Acquire);
+
+        // `tail` is written by the consumer, check it before using it.
+        let used = match head.checked_sub(tail) {
+            Some(used) if used <= self.capacity as u64 => used as usize,
+            _ => return Err(vbox),
+        };
+        let free = self.capacity - used;
+
+        let offset = (head % self.capacity as u64) as usize;
+        if round_up(offset) != offset {
+            return Err(vbox);
+        }
+        let till_end = self.capacity - offset;
+
+        // A record is never split: skip the rest of the buffer if it does not
+        // fit.
+        let skip = if till_end < need { till_end } else { 0 };
+        if skip + need > free {
+            return Err(vbox);
+        }
+
+        unsafe {
+            if skip >= RECORD_HEADER {
+                self.write_u64(offset, WRAP);
+            }
+
+            let offset = (offset + skip) % self.capacity;
+            self.write_u64(offset, id);
+            self.write_u64(offset + 8, size as u64);
+            ptr::copy_nonoverlapping(
+                vbox.data_ptr() as *const u8,
+                self.buf.add(offset + RECORD_HEADER),
+                size,
+            );
+        }
+
+        // The payload is `Pod`, there is nothing to drop.
+        vbox.discard();
+
+        h.head.store(head + (skip + need) as u64, Ordering::Release);
+        Ok(())
+    }
+
+    /// Pop a record and rebuild the `VBox`, by the consumer.
+    ///
+    /// It returns `Ok(None)` if the ring is empty. If the type of the record
+    /// is not registered, the record is dropped and an error is returned.
+    ///
+    /// The positions and the record are written by the other process, and
+    /// are checked before the payload is read: a record that does not fit in
+    /// the buffer is reported as [`PopError::Corrupted`].
+    pub fn pop(
+        &mut self,
+        registry: &PodRegistry,
+    ) -> Result<Option<VBox>, PopError> {
+        let h = self.header();
+        let mut tail = h.tail.load(Ordering::Relaxed);
+        let head = h.head.load(Ordering::Acquire);
+
+        if tail == head {
+            return Ok(None);
+        }
+
+        let mut offset = (tail % self.capacity as u64) as usize;
+        if round_up(offset) != offset {
+            return Err(PopError::Corrupted { offset, size: 0 });
+        }
+        let till_end = self.capacity - offset;
+
+        let wrapped = till_end < RECORD_HEADER
+            || unsafe { self.read_u64(offset) } == WRAP;
+        if wrapped {
+            tail += till_end as u64;
+            offset = 0;
+        }
+
+        let (id, size) =
+            unsafe { (self.read_u64(offset), self.read_u64(offset + 8)) };
+
+        // `offset + RECORD_HEADER <= capacity` is guaranteed above.
+        let room = self.capacity - offset - RECORD_HEADER;
+        if size > room as u64 {
+            return Err(PopError::Corrupted { offset, size });
+        }
+        let size = size as usize;
+
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                self.buf.add(offset + RECORD_HEADER),
+                size,
+            )
+        };
+
+        let res = {
+            let by_id = registry.by_id.read().unwrap();
+            match by_id.get(&id) {
+                Some(entry) if entry.size == size => {
+                    Ok(Some((entry.decode)(bytes)))
+                }
+                _ => Err(PopError::UnknownPod { id }),
+            }
+        };
+
+        tail += (RECORD_HEADER + round_up(size)) as u64;
+        h.tail.store(tail, Ordering::Release);
+        res
+    }
+
+    fn header(&self) -> &Header {
+        unsafe { &*self.header }
+    }
+
+    unsafe fn write_u64(&self, offset: usize, v: u64) {
+        ptr::write(self.buf.add(offset) as *mut u64, v);
+    }
+
+    unsafe fn read_u64(&self, offset: usize) -> u64 {
+        ptr::read(self.buf.add(offset) as *const u64)
+    }
+}
+
+impl fmt::Debug for ShmRing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShmRing")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+fn round_up(n: usize) -> usize {
+    n.div_ceil(8) * 8
+}
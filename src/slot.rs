@@ -0,0 +1,256 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::VBox;
+
+/// A place holding at most one value, usually a [`VBox`], that can be loaned
+/// out and must be returned.
+///
+/// [`Slot::loan()`] takes the value out together with a [`Loan`] token. The
+/// token must be passed back with either [`Slot::restore()`] or
+/// [`Slot::discard()`]. If the token is dropped instead, e.g., by a user
+/// callback that drops the message, the slot reports it with
+/// [`Slot::is_lost()`], so that a dispatch loop can detect a message that is
+/// never returned.
+///
+/// Every loan has a generation that is unique in the process. A [`Loan`] that
+/// is not the outstanding loan of the slot, i.e., from another slot or from an
+/// earlier loan, is reported by [`Slot::check_loan()`] as a [`LoanError`].
+///
+/// ```
+/// # use vbox::{Slot, VBox};
+/// let mut slot: Slot = Slot::new(VBox::unit());
+///
+/// let (vbox, loan) = slot.loan().unwrap();
+/// assert!(slot.is_loaned());
+///
+/// slot.restore(loan, vbox);
+/// assert!(slot.is_occupied());
+///
+/// let (_vbox, loan) = slot.loan().unwrap();
+/// drop(loan);
+/// assert!(slot.is_lost());
+/// ```
+pub struct Slot<T = VBox> {
+    value: Option<T>,
+
+    /// The generation of the outstanding [`Loan`] and the flag shared with
+    /// it, set if it is dropped without being returned.
+    loan: Option<(u64, Arc<AtomicBool>)>,
+}
+
+/// The token of a value loaned out of a [`Slot`].
+///
+/// Pass it back with [`Slot::restore()`] or [`Slot::discard()`]. Dropping it
+/// marks the slot as lost.
+#[must_use = "a Loan must be passed back to the Slot with restore() or discard()"]
+pub struct Loan {
+    generation: u64,
+    lost: Arc<AtomicBool>,
+    returned: bool,
+}
+
+/// The error returned by [`Slot::check_loan()`] for a [`Loan`] that is not the
+/// outstanding loan of the slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoanError {
+    /// The slot has no outstanding loan.
+    NotLoaned { loan: u64 },
+
+    /// The slot has another outstanding loan: the loan is from another slot,
+    /// or it is stale.
+    Mismatch { loan: u64, current: u64 },
+}
+
+impl fmt::Display for LoanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoanError::NotLoaned { loan } => {
+                write!(
+                    f,
+                    "the Loan is not from this Slot: loan #{}, not loaned",
+                    loan
+                )
+            }
+            LoanError::Mismatch { loan, current } => {
+                write!(
+                    f,
+                    "the Loan is not from this Slot: loan #{}, current #{}",
+                    loan, current
+                )
+            }
+        }
+    }
+}
+
+impl Error for LoanError {}
+
+/// The generation of the next loan of any slot, unique in the process.
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+impl<T> Default for Slot<T> {
+    fn default() -> Self {
+        Slot {
+            value: None,
+            loan: None,
+        }
+    }
+}
+
+impl<T> Slot<T> {
+    /// Create a slot holding `value`.
+    pub fn new(value: T) -> Self {
+        Slot {
+            value: Some(value),
+            loan: None,
+        }
+    }
+
+    /// Create an empty slot.
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if the slot holds a value.
+    pub fn is_occupied(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// Returns `true` if the value is loaned out and not yet returned.
+    pub fn is_loaned(&self) -> bool {
+        self.loan.is_some()
+    }
+
+    /// Returns `true` if the value is loaned out and the [`Loan`] is dropped
+    /// without being returned.
+    pub fn is_lost(&self) -> bool {
+        self.loan
+            .as_ref()
+            .map(|(_, lost)| lost.load(Ordering::Relaxed))
+            .unwrap_or(false)
+    }
+
+    /// Forget a lost loan so that the slot can be used again, and return
+    /// `true` if there was one.
+    pub fn clear_lost(&mut self) -> bool {
+        if !self.is_lost() {
+            return false;
+        }
+        self.loan = None;
+        true
+    }
+
+    /// Put a value into an empty slot.
+    ///
+    /// If the slot holds a value or it is loaned out, `value` is returned in
+    /// `Err`.
+    pub fn put(&mut self, value: T) -> Result<(), T> {
+        if self.value.is_some() || self.loan.is_some() {
+            return Err(value);
+        }
+        self.value = Some(value);
+        Ok(())
+    }
+
+    /// Take the value out for good.
+    pub fn take(&mut self) -> Option<T> {
+        self.value.take()
+    }
+
+    /// Take the value out temporarily, with a [`Loan`] to return it.
+    ///
+    /// It returns `None` if the slot is empty.
+    pub fn loan(&mut self) -> Option<(T, Loan)> {
+        let value = self.value.take()?;
+
+        let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+        let lost = Arc::new(AtomicBool::new(false));
+        self.loan = Some((generation, lost.clone()));
+
+        let loan = Loan {
+            generation,
+            lost,
+            returned: false,
+        };
+        Some((value, loan))
+    }
+
+    /// Returns `Ok` if `loan` is the outstanding loan of this slot, i.e., it
+    /// can be passed to [`Slot::restore()`] or [`Slot::discard()`].
+    pub fn check_loan(&self, loan: &Loan) -> Result<(), LoanError> {
+        match self.loan {
+            None => Err(LoanError::NotLoaned {
+                loan: loan.generation,
+            }),
+            Some((current, _)) if current != loan.generation => {
+                Err(LoanError::Mismatch {
+                    loan: loan.generation,
+                    current,
+                })
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Return the loaned value.
+    ///
+    /// It panics with the [`LoanError`] if `loan` is not the outstanding loan
+    /// of this slot.
+    pub fn restore(&mut self, loan: Loan, value: T) {
+        self.end_loan(loan);
+        self.value = Some(value);
+    }
+
+    /// End the loan without returning the value, e.g., the message is
+    /// consumed by the callback on purpose.
+    ///
+    /// It panics with the [`LoanError`] if `loan` is not the outstanding loan
+    /// of this slot.
+    pub fn discard(&mut self, loan: Loan) {
+        self.end_loan(loan);
+    }
+
+    fn end_loan(&mut self, mut loan: Loan) {
+        if let Err(e) = self.check_loan(&loan) {
+            panic!("{}", e);
+        }
+
+        loan.returned = true;
+        self.loan = None;
+    }
+}
+
+impl Drop for Loan {
+    fn drop(&mut self) {
+        if !self.returned {
+            self.lost.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Slot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Slot")
+            .field("occupied", &self.is_occupied())
+            .field("loaned", &self.is_loaned())
+            .field("lost", &self.is_lost())
+            .finish()
+    }
+}
+
+impl Loan {
+    /// Returns the generation of the loan, unique in the process.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+impl fmt::Debug for Loan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Loan").field("generation", &self.generation).finish()
+    }
+}
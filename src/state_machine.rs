@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use crate::VBox;
+
+/// What a transition handler of a [`StateMachine`] decides to do next.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Next<K> {
+    /// Transit to another state.
+    Goto(K),
+
+    /// Stay in the current state.
+    Stay,
+
+    /// Stop the state machine. No more event will be handled.
+    Done,
+}
+
+/// A state machine whose states are keys and whose transitions are erased
+/// handlers.
+///
+/// A handler is a `FnMut(&mut Ctx, VBox) -> Next<K>`, it receives the shared
+/// context and an erased event, and decides the next state. Handlers are
+/// stored erased, so that states can be assembled at runtime, e.g., provided by
+/// plugins as [`VBox`]es.
+///
+/// ```
+/// # use std::any::Any;
+/// # use vbox::{into_vbox, Next, StateMachine, VBox};
+/// let mut sm = StateMachine::<&str, Vec<u64>>::new("idle");
+///
+/// sm.on("idle", |_ctx, _ev| Next::Goto("running"));
+/// sm.on("running", |ctx, ev| match ev.into_inner::<u64>() {
+///     Ok(n) => {
+///         ctx.push(n);
+///         Next::Stay
+///     }
+///     Err(_) => Next::Done,
+/// });
+///
+/// let mut ctx = Vec::new();
+/// sm.handle(&mut ctx, VBox::unit()).unwrap();
+/// sm.handle(&mut ctx, into_vbox!(dyn Any + Send, 5u64)).unwrap();
+/// assert_eq!(Some(&"running"), sm.state());
+///
+/// sm.handle(&mut ctx, VBox::unit()).unwrap();
+/// assert!(sm.is_done());
+/// assert_eq!(vec![5], ctx);
+/// ```
+pub struct StateMachine<K, Ctx> {
+    /// Handlers packed as `dyn FnMut(&mut Ctx, VBox) -> Next<K> + Send`.
+    handlers: HashMap<K, VBox>,
+
+    /// The current state, `None` if the state machine is done.
+    state: Option<K>,
+
+    _p: PhantomData<fn(&mut Ctx)>,
+}
+
+impl<K, Ctx> StateMachine<K, Ctx>
+where
+    K: Eq + Hash + 'static,
+    Ctx: 'static,
+{
+    /// Create a state machine in the `initial` state, without any handler.
+    pub fn new(initial: K) -> Self {
+        StateMachine {
+            handlers: HashMap::new(),
+            state: Some(initial),
+            _p: PhantomData,
+        }
+    }
+
+    /// Register the transition handler for `state`, replacing the existing
+    /// one.
+    pub fn on(
+        &mut self,
+        state: K,
+        f: impl FnMut(&mut Ctx, VBox) -> Next<K> + Send + 'static,
+    ) {
+        let f =
+            crate::into_vbox!(dyn FnMut(&mut Ctx, VBox) -> Next<K> + Send, f);
+        self.handlers.insert(state, f);
+    }
+
+    /// Register an erased transition handler for `state`, replacing the
+    /// existing one.
+    ///
+    /// The `handler` must be packed as `dyn FnMut(&mut Ctx, VBox) -> Next<K> +
+    /// Send`, otherwise it is returned in `Err` right away, rather than failing
+    /// when an event is handled.
+    pub fn register(&mut self, state: K, handler: VBox) -> Result<(), VBox> {
+        if !handler.is_dyn::<dyn FnMut(&mut Ctx, VBox) -> Next<K> + Send>() {
+            return Err(handler);
+        }
+        self.handlers.insert(state, handler);
+        Ok(())
+    }
+
+    /// Returns the current state, or `None` if the state machine is done.
+    pub fn state(&self) -> Option<&K> {
+        self.state.as_ref()
+    }
+
+    /// Returns `true` if a handler returned [`Next::Done`].
+    pub fn is_done(&self) -> bool {
+        self.state.is_none()
+    }
+
+    /// Feed an event to the handler of the current state and transit to the
+    /// state it returns.
+    ///
+    /// If the state machine is done or there is no handler for the current
+    /// state, the event is returned in `Err`.
+    pub fn handle(&mut self, ctx: &mut Ctx, event: VBox) -> Result<(), VBox> {
+        let Some(state) = &self.state else {
+            return Err(event);
+        };
+
+        let Some(handler) = self.handlers.get_mut(state) else {
+            return Err(event);
+        };
+
+        let f =
+            handler.as_dyn_mut::<dyn FnMut(&mut Ctx, VBox) -> Next<K> + Send>();
+
+        match f(ctx, event) {
+            Next::Goto(next) => self.state = Some(next),
+            Next::Stay => {}
+            Next::Done => self.state = None,
+        }
+        Ok(())
+    }
+}
+
+impl<K, Ctx> fmt::Debug for StateMachine<K, Ctx>
+where K: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateMachine")
+            .field("state", &self.state)
+            .field("states", &self.handlers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
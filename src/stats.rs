@@ -0,0 +1,214 @@
+//! Counters of how many [`VBox`](crate::VBox)es are packed, unpacked and
+//! currently live, and how many bytes of payload they hold, per `dyn Trait` and
+//! per concrete type, for capacity planning and leak hunting.
+//!
+//! It is enabled by the `stats` feature. Every pack, unpack and drop updates a
+//! process wide registry, which is queried with [`by_trait()`], [`by_type()`],
+//! [`of_trait()`] and [`of_type()`].
+//!
+//! The concrete type is counted only if it is known when packing, e.g., with
+//! [`into_vbox!`](crate::into_vbox), but not with
+//! [`into_vbox_dyn!`](crate::into_vbox_dyn).
+//!
+//! [`by_type_bytes()`] and [`by_trait_bytes()`] answer which message type
+//! holds the most memory, without a heap profiler.
+//!
+//! ```
+//! # use std::fmt::Debug;
+//! # use vbox::{from_vbox, into_vbox, stats};
+//! struct Ping(#[allow(dead_code)] u64);
+//! impl Debug for Ping {
+//!     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+//!         write!(f, "Ping")
+//!     }
+//! }
+//!
+//! let a = into_vbox!(dyn Debug + Sync, Ping(1));
+//! let b = into_vbox!(dyn Debug + Sync, Ping(2));
+//! let _ = from_vbox!(dyn Debug + Sync, a);
+//!
+//! let ping = stats::of_type::<Ping>();
+//! assert_eq!((2, 1, 0), (ping.packed, ping.unpacked, ping.dropped));
+//! assert_eq!(1, ping.live());
+//!
+//! assert_eq!(8, ping.live_bytes);
+//! # drop(b);
+//! ```
+
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// The counters of a `dyn Trait` or a concrete type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counters {
+    /// Name of `dyn Trait` or the concrete type.
+    pub name: &'static str,
+
+    /// Number of values packed into a `VBox`.
+    pub packed: u64,
+
+    /// Number of values unpacked from a `VBox`.
+    pub unpacked: u64,
+
+    /// Number of values dropped inside a `VBox`, without being unpacked.
+    pub dropped: u64,
+
+    /// Total size in bytes of the values still packed in a `VBox`.
+    pub live_bytes: u64,
+}
+
+impl Counters {
+    fn new(name: &'static str) -> Self {
+        Counters {
+            name,
+            packed: 0,
+            unpacked: 0,
+            dropped: 0,
+            live_bytes: 0,
+        }
+    }
+
+    /// Returns the number of values still packed in a `VBox`.
+    ///
+    /// A `VBox` converted with [`VBox::into_raw()`](crate::VBox::into_raw) is
+    /// still live. It saturates at 0 if the counters are [`reset()`] while some
+    /// values are live.
+    pub fn live(&self) -> u64 {
+        self.packed.saturating_sub(self.unpacked).saturating_sub(self.dropped)
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    traits: HashMap<TypeId, Counters>,
+    types: HashMap<TypeId, Counters>,
+}
+
+static REGISTRY: Mutex<Option<Registry>> = Mutex::new(None);
+
+fn with_registry<R>(f: impl FnOnce(&mut Registry) -> R) -> R {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    f(registry.get_or_insert_with(Default::default))
+}
+
+fn sorted(counters: impl Iterator<Item = Counters>) -> Vec<Counters> {
+    let mut v: Vec<_> = counters.collect();
+    v.sort_by_key(|c| c.name);
+    v
+}
+
+/// Returns the counters of every `dyn Trait` that is packed, sorted by name.
+pub fn by_trait() -> Vec<Counters> {
+    with_registry(|r| sorted(r.traits.values().copied()))
+}
+
+/// Returns the counters of every concrete type that is packed, sorted by name.
+pub fn by_type() -> Vec<Counters> {
+    with_registry(|r| sorted(r.types.values().copied()))
+}
+
+/// Returns the counters of every `dyn Trait` that holds some bytes, from the
+/// most bytes to the least.
+pub fn by_trait_bytes() -> Vec<Counters> {
+    with_registry(|r| by_bytes(r.traits.values().copied()))
+}
+
+/// Returns the counters of every concrete type that holds some bytes, from the
+/// most bytes to the least.
+pub fn by_type_bytes() -> Vec<Counters> {
+    with_registry(|r| by_bytes(r.types.values().copied()))
+}
+
+fn by_bytes(counters: impl Iterator<Item = Counters>) -> Vec<Counters> {
+    let mut v: Vec<_> = counters.filter(|c| c.live_bytes > 0).collect();
+    v.sort_by(|a, b| b.live_bytes.cmp(&a.live_bytes).then(a.name.cmp(b.name)));
+    v
+}
+
+/// Returns the counters of `dyn Trait` `U`.
+pub fn of_trait<U: ?Sized + 'static>() -> Counters {
+    let name = std::any::type_name::<U>();
+    with_registry(|r| {
+        r.traits.get(&TypeId::of::<U>()).copied().unwrap_or(Counters::new(name))
+    })
+}
+
+/// Returns the counters of concrete type `T`.
+pub fn of_type<T: 'static>() -> Counters {
+    let name = std::any::type_name::<T>();
+    with_registry(|r| {
+        r.types.get(&TypeId::of::<T>()).copied().unwrap_or(Counters::new(name))
+    })
+}
+
+/// Reset all of the counters.
+pub fn reset() {
+    with_registry(|r| *r = Registry::default());
+}
+
+/// Record the name of a concrete type, so that it is counted from now on.
+pub(crate) fn name_type<T: 'static>() {
+    with_registry(|r| {
+        r.types
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Counters::new(std::any::type_name::<T>()));
+    })
+}
+
+fn update(
+    type_id: TypeId,
+    type_name: fn() -> &'static str,
+    concrete_type_id: Option<TypeId>,
+    f: impl Fn(&mut Counters),
+) {
+    if !crate::config::metrics() {
+        return;
+    }
+
+    with_registry(|r| {
+        f(r.traits
+            .entry(type_id)
+            .or_insert_with(|| Counters::new(type_name())));
+
+        if let Some(c) = concrete_type_id.and_then(|id| r.types.get_mut(&id)) {
+            f(c);
+        }
+    })
+}
+
+pub(crate) fn packed(
+    type_id: TypeId,
+    type_name: fn() -> &'static str,
+    concrete_type_id: Option<TypeId>,
+    size: usize,
+) {
+    update(type_id, type_name, concrete_type_id, |c| {
+        c.packed += 1;
+        c.live_bytes += size as u64;
+    });
+}
+
+pub(crate) fn unpacked(
+    type_id: TypeId,
+    type_name: fn() -> &'static str,
+    concrete_type_id: Option<TypeId>,
+    size: usize,
+) {
+    update(type_id, type_name, concrete_type_id, |c| {
+        c.unpacked += 1;
+        c.live_bytes = c.live_bytes.saturating_sub(size as u64);
+    });
+}
+
+pub(crate) fn dropped(
+    type_id: TypeId,
+    type_name: fn() -> &'static str,
+    concrete_type_id: Option<TypeId>,
+    size: usize,
+) {
+    update(type_id, type_name, concrete_type_id, |c| {
+        c.dropped += 1;
+        c.live_bytes = c.live_bytes.saturating_sub(size as u64);
+    });
+}
@@ -0,0 +1,182 @@
+//! Adapt a [`Stream`] of [`VBox`]es, such as the receiver of a channel, into a
+//! stream of `Box<dyn Trait>`, unpacking each item with the type checked.
+//!
+//! It is enabled by the `stream` feature.
+//!
+//! Any receiver that implements [`Stream<Item = VBox>`](Stream), e.g., an
+//! `async_channel::Receiver<VBox>` or a
+//! `futures::channel::mpsc::Receiver<VBox>`, becomes a typed stream with
+//! [`VBoxStreamExt::unpack()`] or [`VBoxStreamExt::try_unpack()`], so that the
+//! consumer uses the usual `StreamExt` combinators.
+//!
+//! ```
+//! # use std::fmt::Debug;
+//! # use futures::channel::mpsc;
+//! # use futures::StreamExt;
+//! # use vbox::into_vbox;
+//! # use vbox::stream::VBoxStreamExt;
+//! let (mut tx, rx) = mpsc::unbounded();
+//! tx.unbounded_send(into_vbox!(dyn Debug + Send, 1u64)).unwrap();
+//! tx.unbounded_send(into_vbox!(dyn Debug + Send, "a")).unwrap();
+//! drop(tx);
+//!
+//! let got = futures::executor::block_on(
+//!     rx.unpack::<dyn Debug + Send>()
+//!         .map(|d| format!("{:?}", d))
+//!         .collect::<Vec<_>>(),
+//! );
+//! assert_eq!(vec!["1", "\"a\""], got);
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+pub use futures_core::Stream;
+
+use crate::VBox;
+
+/// Converts a [`Stream`] of [`VBox`]es into a stream of `Box<U>`, where `U` is
+/// `dyn Trait`.
+pub trait VBoxStreamExt: Stream<Item = VBox> + Sized {
+    /// Unpack each item as `U`.
+    ///
+    /// Polling the returned stream panics if an item is not packed as `U`.
+    fn unpack<U>(self) -> Unpack<Self, U>
+    where U: ?Sized + 'static {
+        Unpack {
+            inner: self,
+            _p: PhantomData,
+        }
+    }
+
+    /// Unpack each item as `U`, yielding `Err` with the `VBox` of an item that
+    /// is not packed as `U`.
+    fn try_unpack<U>(self) -> TryUnpack<Self, U>
+    where U: ?Sized + 'static {
+        TryUnpack {
+            inner: self,
+            _p: PhantomData,
+        }
+    }
+}
+
+impl<S> VBoxStreamExt for S where S: Stream<Item = VBox> {}
+
+/// The stream returned by [`VBoxStreamExt::unpack()`].
+pub struct Unpack<S, U>
+where U: ?Sized + 'static
+{
+    inner: S,
+    _p: PhantomData<fn() -> Box<U>>,
+}
+
+impl<S, U> Unpack<S, U>
+where U: ?Sized + 'static
+{
+    /// Returns the stream of `VBox`es.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn project(self: Pin<&mut Self>) -> Pin<&mut S> {
+        // `inner` is never moved out of a pinned `Unpack`.
+        unsafe { self.map_unchecked_mut(|s| &mut s.inner) }
+    }
+}
+
+impl<S, U> Stream for Unpack<S, U>
+where
+    S: Stream<Item = VBox>,
+    U: ?Sized + 'static,
+{
+    type Item = Box<U>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Box<U>>> {
+        self.project().poll_next(cx).map(|v| v.map(|v| v.unpack::<U>()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<S, U> fmt::Debug for Unpack<S, U>
+where
+    S: fmt::Debug,
+    U: ?Sized + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Unpack")
+            .field("type", &std::any::type_name::<U>())
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+/// The stream returned by [`VBoxStreamExt::try_unpack()`].
+pub struct TryUnpack<S, U>
+where U: ?Sized + 'static
+{
+    inner: S,
+    _p: PhantomData<fn() -> Box<U>>,
+}
+
+impl<S, U> TryUnpack<S, U>
+where U: ?Sized + 'static
+{
+    /// Returns the stream of `VBox`es.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn project(self: Pin<&mut Self>) -> Pin<&mut S> {
+        // `inner` is never moved out of a pinned `TryUnpack`.
+        unsafe { self.map_unchecked_mut(|s| &mut s.inner) }
+    }
+}
+
+impl<S, U> Stream for TryUnpack<S, U>
+where
+    S: Stream<Item = VBox>,
+    U: ?Sized + 'static,
+{
+    type Item = Result<Box<U>, VBox>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Box<U>, VBox>>> {
+        self.project().poll_next(cx).map(|v| {
+            v.map(|v| {
+                if v.is_dyn::<U>() {
+                    Ok(v.unpack::<U>())
+                } else {
+                    Err(v)
+                }
+            })
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<S, U> fmt::Debug for TryUnpack<S, U>
+where
+    S: fmt::Debug,
+    U: ?Sized + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TryUnpack")
+            .field("type", &std::any::type_name::<U>())
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
@@ -0,0 +1,29 @@
+//! A tag, such as the name of a channel, attached to the `VBox`es packed or
+//! unpacked on the current thread. It is shared by the `pack-hook` and the
+//! `timeline` features.
+
+use std::cell::Cell;
+
+thread_local! {
+    static TAG: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+/// Call `f` with `tag` attached to the `VBox`es it packs or unpacks on this
+/// thread.
+pub fn with_tag<R>(tag: &'static str, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<&'static str>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            TAG.with(|t| t.set(self.0));
+        }
+    }
+
+    let _restore = Restore(TAG.with(|t| t.replace(Some(tag))));
+    f()
+}
+
+/// Returns the tag set with [`with_tag()`] on this thread, if any.
+pub(crate) fn current() -> Option<&'static str> {
+    TAG.with(|t| t.get())
+}
@@ -0,0 +1,176 @@
+//! Fixtures for testing code that sends [`VBox`]es around, e.g., the channel
+//! code of a downstream crate.
+//!
+//! It is enabled by the `test-util` feature, which is meant to be enabled only
+//! in `[dev-dependencies]`.
+//!
+//! ```
+//! # use vbox::{from_vbox, into_vbox};
+//! # use vbox::test_util::{assert_drop_once, assert_packed_as, Plus};
+//! assert_drop_once(|token| {
+//!     let vbox = into_vbox!(dyn Plus, token);
+//!     assert_packed_as::<dyn Plus>(&vbox);
+//!
+//!     let p = from_vbox!(dyn Plus, vbox);
+//!     assert_eq!(3, p.plus(3));
+//! });
+//! ```
+
+use std::fmt;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use crate::VBox;
+
+/// A canned trait called by `&self`.
+pub trait Plus {
+    fn plus(&self, n: u64) -> u64;
+}
+
+/// A canned trait called by `&mut self`.
+pub trait Handle {
+    fn handle(&mut self, msg: &str);
+}
+
+/// Counts how many [`DropToken`]s it created are dropped.
+#[derive(Debug, Clone, Default)]
+pub struct DropCounter {
+    dropped: Arc<AtomicU64>,
+}
+
+impl DropCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a payload that increases the counter when it is dropped.
+    pub fn token(&self) -> DropToken {
+        DropToken {
+            dropped: self.dropped.clone(),
+        }
+    }
+
+    /// Returns the number of dropped tokens.
+    pub fn count(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// A payload that increases its [`DropCounter`] when it is dropped.
+///
+/// It implements [`Plus`], which returns the argument, and `Debug`.
+pub struct DropToken {
+    dropped: Arc<AtomicU64>,
+}
+
+impl Plus for DropToken {
+    fn plus(&self, n: u64) -> u64 {
+        n
+    }
+}
+
+impl fmt::Debug for DropToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DropToken").finish()
+    }
+}
+
+impl Drop for DropToken {
+    fn drop(&mut self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Records the calls made to the handlers it creates, in order.
+#[derive(Debug, Clone, Default)]
+pub struct CallRecorder {
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl CallRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a call.
+    pub fn record(&self, call: impl ToString) {
+        self.calls.lock().unwrap().push(call.to_string());
+    }
+
+    /// Returns the recorded calls.
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Create a `FnMut(VBox)` handler that records `name` each time it is
+    /// called, e.g., for a [`Router`](crate::Router).
+    pub fn handler(
+        &self,
+        name: impl ToString,
+    ) -> impl FnMut(VBox) + Send + 'static {
+        let recorder = self.clone();
+        let name = name.to_string();
+        move |_vbox| recorder.record(&name)
+    }
+
+    /// Create a [`Handle`] implementation that records `name: msg` for each
+    /// message.
+    pub fn handle(&self, name: impl ToString) -> RecordingHandle {
+        RecordingHandle {
+            name: name.to_string(),
+            recorder: self.clone(),
+        }
+    }
+}
+
+/// A [`Handle`] implementation that records the messages to a
+/// [`CallRecorder`].
+#[derive(Debug)]
+pub struct RecordingHandle {
+    name: String,
+    recorder: CallRecorder,
+}
+
+impl Handle for RecordingHandle {
+    fn handle(&mut self, msg: &str) {
+        self.recorder.record(format!("{}: {}", self.name, msg));
+    }
+}
+
+/// Assert that `vbox` is packed as `U`, i.e., `dyn Trait`.
+#[track_caller]
+pub fn assert_packed_as<U>(vbox: &VBox)
+where U: ?Sized + 'static {
+    assert!(
+        vbox.is_dyn::<U>(),
+        "expected VBox packed as {}, got: {:?}",
+        std::any::type_name::<U>(),
+        vbox
+    );
+}
+
+/// Assert that `vbox` is not packed as `U`, i.e., `dyn Trait`.
+#[track_caller]
+pub fn assert_not_packed_as<U>(vbox: &VBox)
+where U: ?Sized + 'static {
+    assert!(
+        !vbox.is_dyn::<U>(),
+        "expected VBox not packed as {}",
+        std::any::type_name::<U>(),
+    );
+}
+
+/// Call `f` with a [`DropToken`] and assert that it is dropped exactly once
+/// when `f` returns, e.g., after it is packed, sent and unpacked.
+#[track_caller]
+pub fn assert_drop_once(f: impl FnOnce(DropToken)) {
+    let counter = DropCounter::new();
+    f(counter.token());
+    assert_eq!(
+        1,
+        counter.count(),
+        "the payload must be dropped exactly once"
+    );
+}
@@ -0,0 +1,170 @@
+//! Record a timeline of every pack and unpack of a [`VBox`](crate::VBox), to
+//! diagnose ordering bugs in a topology of channels.
+//!
+//! It is enabled by the `timeline` feature. Each pack and unpack appends an
+//! [`Event`] to a process wide buffer, which keeps the latest
+//! [`capacity()`] events. The buffer is read with [`events()`], or formatted
+//! with [`dump()`].
+//!
+//! A tag, such as the name of a channel, can be attached to the packs and
+//! unpacks made in a closure with [`with_tag()`].
+//!
+//! ```
+//! # use std::fmt::Debug;
+//! # use vbox::{from_vbox, into_vbox, timeline};
+//! let vbox = timeline::with_tag("requests", || into_vbox!(dyn Debug, 1u64));
+//! let _ = from_vbox!(dyn Debug, vbox);
+//!
+//! println!("{}", timeline::dump());
+//! // +0.000000s pack   u64 as dyn core::fmt::Debug [requests] at src/main.rs:3:45
+//! // +0.000012s unpack dyn core::fmt::Debug at src/main.rs:4:9
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic::Location;
+use std::sync::Mutex;
+use std::time::Instant;
+
+pub use crate::tag::with_tag;
+
+/// The default number of events to keep.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// What happens to a `VBox`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Pack,
+    Unpack,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Pack => f.pad("pack"),
+            Kind::Unpack => f.pad("unpack"),
+        }
+    }
+}
+
+/// A pack or an unpack of a `VBox`.
+#[derive(Debug, Clone, Copy)]
+pub struct Event {
+    /// When it happens.
+    pub at: Instant,
+
+    pub kind: Kind,
+
+    /// Name of the concrete type of the payload, if it is known, e.g., it is
+    /// unknown when unpacking as `dyn Trait`.
+    pub type_name: Option<&'static str>,
+
+    /// Name of `dyn Trait` the payload is packed as.
+    pub trait_name: &'static str,
+
+    /// The tag set with [`with_tag()`] on the thread, if any.
+    pub tag: Option<&'static str>,
+
+    /// Where it happens.
+    pub site: &'static Location<'static>,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<6} ", self.kind)?;
+
+        if let Some(type_name) = self.type_name {
+            write!(f, "{} as ", type_name)?;
+        }
+        write!(f, "{}", self.trait_name)?;
+
+        if let Some(tag) = self.tag {
+            write!(f, " [{}]", tag)?;
+        }
+        write!(f, " at {}", self.site)
+    }
+}
+
+struct Buffer {
+    events: VecDeque<Event>,
+    capacity: usize,
+}
+
+static BUFFER: Mutex<Buffer> = Mutex::new(Buffer {
+    events: VecDeque::new(),
+    capacity: DEFAULT_CAPACITY,
+});
+
+fn with_buffer<R>(f: impl FnOnce(&mut Buffer) -> R) -> R {
+    let mut buffer = BUFFER.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut buffer)
+}
+
+/// Set the number of events to keep. The oldest events are discarded if there
+/// are more than `capacity`. A `capacity` of 0 stops recording.
+pub fn set_capacity(capacity: usize) {
+    with_buffer(|b| {
+        b.capacity = capacity;
+        while b.events.len() > capacity {
+            b.events.pop_front();
+        }
+    })
+}
+
+/// Returns the number of events to keep.
+pub fn capacity() -> usize {
+    with_buffer(|b| b.capacity)
+}
+
+/// Returns the recorded events, the oldest first.
+pub fn events() -> Vec<Event> {
+    with_buffer(|b| b.events.iter().copied().collect())
+}
+
+/// Remove all of the recorded events.
+pub fn clear() {
+    with_buffer(|b| b.events.clear())
+}
+
+/// Format the recorded events, one per line, the oldest first.
+///
+/// Each line starts with the time elapsed since the oldest event.
+pub fn dump() -> String {
+    let events = events();
+    let Some(first) = events.first() else {
+        return String::new();
+    };
+
+    let mut out = String::new();
+    for ev in events.iter() {
+        let elapsed = ev.at.duration_since(first.at);
+        out.push_str(&format!("+{:.6}s {}\n", elapsed.as_secs_f64(), ev));
+    }
+    out
+}
+
+#[track_caller]
+pub(crate) fn record(
+    kind: Kind,
+    type_name: Option<&'static str>,
+    trait_name: &'static str,
+) {
+    let event = Event {
+        at: Instant::now(),
+        kind,
+        type_name,
+        trait_name,
+        tag: crate::tag::current(),
+        site: Location::caller(),
+    };
+
+    with_buffer(|b| {
+        if b.capacity == 0 {
+            return;
+        }
+        if b.events.len() >= b.capacity {
+            b.events.pop_front();
+        }
+        b.events.push_back(event);
+    })
+}
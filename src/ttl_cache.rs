@@ -0,0 +1,240 @@
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::VBox;
+
+struct Entry {
+    vbox: VBox,
+
+    /// `None` if it never expires, i.e., the time-to-live overflows `Instant`.
+    expires_at: Option<Instant>,
+
+    /// Distinguishes entries that expire at the same instant in the expiry
+    /// index.
+    seq: u64,
+}
+
+/// A cache that maps keys to [`VBox`]es, each of which expires after a
+/// time-to-live, so that erased values of different types, e.g., computation
+/// results or connection handles, are cached in one place.
+///
+/// An expired entry is no longer returned by [`TtlCache::get()`], but it is
+/// not dropped until [`TtlCache::sweep()`] is called, which returns the
+/// expired entries to the caller. A driver calls `sweep()` periodically, or
+/// sleeps until [`TtlCache::next_expiry()`].
+///
+/// A time-to-live too large to be represented, e.g., `Duration::MAX`, means
+/// the entry never expires.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use std::time::{Duration, Instant};
+/// # use vbox::{into_vbox, TtlCache};
+/// let mut cache = TtlCache::new(Duration::from_secs(60));
+/// cache.insert("answer", into_vbox!(dyn Debug, 42u64));
+///
+/// assert_eq!("42", format!("{:?}", cache.get::<dyn Debug, _>("answer").unwrap()));
+///
+/// let later = Instant::now() + Duration::from_secs(61);
+/// let expired = cache.sweep_at(later);
+/// assert_eq!(vec!["answer"], expired.iter().map(|(k, _)| *k).collect::<Vec<_>>());
+/// ```
+pub struct TtlCache<K> {
+    default_ttl: Duration,
+    entries: HashMap<K, Entry>,
+
+    /// Keys by the time they expire, except the ones that never expire.
+    expiry: BTreeMap<(Instant, u64), K>,
+
+    next_seq: u64,
+}
+
+impl<K> TtlCache<K>
+where K: Hash + Eq + Clone
+{
+    /// Create an empty cache, in which an entry expires after `default_ttl` if
+    /// no other time-to-live is given.
+    pub fn new(default_ttl: Duration) -> Self {
+        TtlCache {
+            default_ttl,
+            entries: HashMap::new(),
+            expiry: BTreeMap::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// Returns the time-to-live of an entry inserted with
+    /// [`TtlCache::insert()`].
+    pub fn default_ttl(&self) -> Duration {
+        self.default_ttl
+    }
+
+    /// Returns the number of entries, including the expired ones not yet
+    /// swept.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there is no entry.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Insert a `VBox` that expires after the default time-to-live, and return
+    /// the `VBox` replaced, if it is not expired.
+    pub fn insert(&mut self, key: K, vbox: VBox) -> Option<VBox> {
+        self.insert_with_ttl(key, vbox, self.default_ttl)
+    }
+
+    /// Insert a `VBox` that expires after `ttl`, and return the `VBox`
+    /// replaced, if it is not expired.
+    ///
+    /// A replaced `VBox` that is expired is dropped, as [`TtlCache::remove()`]
+    /// does.
+    pub fn insert_with_ttl(
+        &mut self,
+        key: K,
+        vbox: VBox,
+        ttl: Duration,
+    ) -> Option<VBox> {
+        let now = Instant::now();
+        let expires_at = now.checked_add(ttl);
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        if let Some(at) = expires_at {
+            self.expiry.insert((at, seq), key.clone());
+        }
+
+        let old = self.entries.insert(key, Entry {
+            vbox,
+            expires_at,
+            seq,
+        })?;
+        self.unindex(&old);
+        Self::unexpired(old, now)
+    }
+
+    /// Returns `true` if an unexpired entry is stored under the key.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_vbox(key).is_some()
+    }
+
+    /// Borrow the `VBox` stored under the key, if it is not expired.
+    pub fn get_vbox<Q>(&self, key: &Q) -> Option<&VBox>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.entries.get(key)?;
+        if entry.is_expired(Instant::now()) {
+            return None;
+        }
+        Some(&entry.vbox)
+    }
+
+    /// Borrow the payload stored under the key as `&dyn Trait`, if it is not
+    /// expired.
+    ///
+    /// It panics if the `VBox` is not packed as `dyn Trait`.
+    pub fn get<U, Q>(&self, key: &Q) -> Option<&U>
+    where
+        U: ?Sized + 'static,
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_vbox(key).map(|vbox| vbox.as_dyn::<U>())
+    }
+
+    /// Remove the entry stored under the key, and return the `VBox` if it is
+    /// not expired.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<VBox>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.entries.remove(key)?;
+        self.unindex(&entry);
+        Self::unexpired(entry, Instant::now())
+    }
+
+    /// Returns the time the first entry expires, so that a driver can sleep
+    /// until then before calling [`TtlCache::sweep()`].
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.expiry.keys().next().map(|(at, _)| *at)
+    }
+
+    /// Remove the entries that are expired now, and return them in the order
+    /// they expire.
+    pub fn sweep(&mut self) -> Vec<(K, VBox)> {
+        self.sweep_at(Instant::now())
+    }
+
+    /// Remove the entries that are expired at `now`, and return them in the
+    /// order they expire.
+    pub fn sweep_at(&mut self, now: Instant) -> Vec<(K, VBox)> {
+        let mut expired = Vec::new();
+
+        while let Some(entry) = self.expiry.first_entry() {
+            if entry.key().0 > now {
+                break;
+            }
+
+            let key = entry.remove();
+            let e = self.entries.remove(&key).unwrap();
+            expired.push((key, e.vbox));
+        }
+
+        expired
+    }
+
+    /// Remove the entry from the expiry index.
+    fn unindex(&mut self, entry: &Entry) {
+        if let Some(at) = entry.expires_at {
+            self.expiry.remove(&(at, entry.seq));
+        }
+    }
+
+    /// Returns the `VBox` of a removed entry if it is not expired at `now`,
+    /// otherwise drops it.
+    fn unexpired(entry: Entry, now: Instant) -> Option<VBox> {
+        if entry.is_expired(now) {
+            entry.vbox.discard();
+            return None;
+        }
+        Some(entry.vbox)
+    }
+}
+
+impl Entry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+}
+
+impl<K> fmt::Debug for TtlCache<K>
+where K: fmt::Debug + Hash + Eq
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let never = self.entries.iter().filter(|(_, e)| e.expires_at.is_none());
+
+        f.debug_map()
+            .entries(
+                self.expiry
+                    .values()
+                    .map(|k| (k, &self.entries[k]))
+                    .chain(never)
+                    .map(|(k, e)| (k, (&e.vbox, e.expires_at))),
+            )
+            .finish()
+    }
+}
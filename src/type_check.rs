@@ -0,0 +1,71 @@
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
+
+/// How a [`VBox`](crate::VBox) checks the type to unpack against the type it
+/// is packed as.
+///
+/// `TypeId` is only meaningful within one compilation: a `VBox` passed to or
+/// from a dynamically loaded plugin may carry a `TypeId` that disagrees with
+/// the one of the same type in the host. [`TypeCheck::TypeName`] compares the
+/// fully qualified type names instead, and the layout for a concrete type, or
+/// the size and alignment in the vtable for a `dyn Trait`.
+///
+/// Choosing [`TypeCheck::TypeName`] is `unsafe`: a type passes the check for
+/// another type of the same name and layout.
+///
+/// It is set globally with [`TypeCheck::set_global()`], or per `VBox` with
+/// [`VBox::set_type_check()`](crate::VBox::set_type_check), which takes
+/// precedence.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{from_vbox, into_vbox, TypeCheck, VBox};
+/// let mut vbox: VBox = into_vbox!(dyn Debug, 10u64);
+/// // Safety: no other type is named `core::fmt::Debug` or `u64`.
+/// unsafe { vbox.set_type_check(TypeCheck::TypeName) };
+///
+/// assert!(vbox.is::<u64>());
+/// assert_eq!("10", format!("{:?}", from_vbox!(dyn Debug, vbox)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TypeCheck {
+    /// Compare `TypeId`s. It is the default.
+    #[default]
+    TypeId,
+
+    /// Compare the names returned by `std::any::type_name()`, and the layout
+    /// for a concrete type, or the size and alignment in the vtable for a
+    /// `dyn Trait`.
+    ///
+    /// Type names are not guaranteed to be unique, e.g., two versions of a
+    /// crate may produce the same name for different types. The concrete type
+    /// falls back to `TypeId` if its name is unknown, e.g., for a `VBox` built
+    /// with [`VBox::from_any()`](crate::VBox::from_any).
+    TypeName,
+}
+
+static GLOBAL: AtomicU8 = AtomicU8::new(TypeCheck::TypeId as u8);
+
+impl TypeCheck {
+    /// Set the mode for every `VBox` that does not set its own.
+    ///
+    /// # Safety
+    ///
+    /// Under [`TypeCheck::TypeName`], a type passes the check for another type
+    /// of the same name and layout, and unpacking it as the other is undefined
+    /// behavior. The caller must ensure that the types unpacked from any
+    /// `VBox` are told apart by their names, e.g., that the host and the
+    /// plugins are built with the same version of the crates whose types they
+    /// exchange.
+    pub unsafe fn set_global(mode: TypeCheck) {
+        GLOBAL.store(mode as u8, Ordering::Relaxed);
+    }
+
+    /// Returns the mode for every `VBox` that does not set its own.
+    pub fn global() -> TypeCheck {
+        match GLOBAL.load(Ordering::Relaxed) {
+            x if x == TypeCheck::TypeName as u8 => TypeCheck::TypeName,
+            _ => TypeCheck::TypeId,
+        }
+    }
+}
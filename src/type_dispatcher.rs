@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::TypeKey;
+use crate::VBox;
+
+/// Routes [`VBox`]es to typed handlers by the concrete type of the payload.
+///
+/// A handler is a `FnMut(Box<T>)` registered for the concrete type `T`. It
+/// receives the payload of a `VBox` that is built from a `T`, whatever `dyn
+/// Trait` it is packed as. A `VBox` without a handler for its type, or built
+/// with [`into_vbox_dyn!`](crate::into_vbox_dyn) thus without a known concrete
+/// type, is returned unhandled.
+///
+/// Unlike [`Dispatcher`](crate::Dispatcher), which routes by name to erased
+/// handlers, it is a typed front-end over an erased transport.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use std::sync::mpsc;
+/// # use vbox::{into_vbox, TypeDispatcher};
+/// struct Ping(u64);
+/// struct Pong(u64);
+///
+/// let (tx, rx) = mpsc::channel();
+///
+/// let mut d = TypeDispatcher::new();
+/// d.on(move |p: Box<Ping>| tx.send(p.0).unwrap());
+///
+/// d.dispatch(into_vbox!(dyn Send, Ping(3))).unwrap();
+/// assert_eq!(3, rx.recv().unwrap());
+///
+/// let unhandled = d.dispatch(into_vbox!(dyn Send, Pong(4))).unwrap_err();
+/// assert!(unhandled.is::<Pong>());
+/// ```
+#[derive(Default)]
+pub struct TypeDispatcher {
+    /// Handlers packed as `dyn FnMut(VBox) + Send`, that unpack the payload
+    /// of the type they are registered for.
+    handlers: HashMap<TypeKey, VBox>,
+}
+
+impl TypeDispatcher {
+    /// Create a dispatcher without any handler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the handler for payloads of type `T`, replacing the existing
+    /// one.
+    pub fn on<T, F>(&mut self, mut f: F)
+    where
+        T: 'static,
+        F: FnMut(Box<T>) + Send + 'static,
+    {
+        let handler = move |vbox: VBox| match vbox.into_inner::<T>() {
+            Ok(v) => f(Box::new(v)),
+            Err(_) => unreachable!("dispatched to the handler of another type"),
+        };
+
+        self.handlers.insert(
+            TypeKey::of::<T>(),
+            crate::into_vbox!(dyn FnMut(VBox) + Send, handler),
+        );
+    }
+
+    /// Remove the handler for payloads of type `T`, and return it.
+    ///
+    /// The returned handler is packed as `dyn FnMut(VBox) + Send`.
+    pub fn unregister<T: 'static>(&mut self) -> Option<VBox> {
+        self.handlers.remove(&TypeKey::of::<T>())
+    }
+
+    /// Returns `true` if a handler is registered for payloads of type `T`.
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.handlers.contains_key(&TypeKey::of::<T>())
+    }
+
+    /// Returns the number of registered handlers.
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    /// Returns `true` if no handler is registered.
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Feed the payload of `vbox` to the handler registered for its concrete
+    /// type.
+    ///
+    /// If there is no handler for it, `vbox` is returned in `Err`.
+    pub fn dispatch(&mut self, vbox: VBox) -> Result<(), VBox> {
+        let Some(handler) = self.handlers.get_mut(&TypeKey::of_vbox(&vbox))
+        else {
+            return Err(vbox);
+        };
+
+        let f = handler.as_dyn_mut::<dyn FnMut(VBox) + Send>();
+        f(vbox);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for TypeDispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypeDispatcher")
+            .field("len", &self.handlers.len())
+            .finish()
+    }
+}
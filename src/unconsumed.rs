@@ -0,0 +1,131 @@
+//! Detect a [`VBox`](crate::VBox) that is dropped without being unpacked,
+//! which often means a lost message.
+//!
+//! It is enabled by the `debug-unconsumed` feature. A `VBox` is consumed when
+//! it is unpacked, borrowed as `dyn Trait`, replaced, or explicitly discarded
+//! with [`VBox::discard()`](crate::VBox::discard). Dropping it otherwise is
+//! reported with its type name and the site where it is created, according
+//! to the [`Action`] set with [`set_action()`].
+
+#[cfg(feature = "debug-unconsumed")]
+mod enabled {
+    use std::panic::Location;
+    use std::ptr;
+    use std::sync::atomic::AtomicPtr;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::atomic::AtomicU8;
+    use std::sync::atomic::Ordering;
+
+    /// What to do when an unconsumed `VBox` is dropped.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Action {
+        /// Print a warning, with `log::warn!()` if the `log` feature is
+        /// enabled, or to stderr otherwise. It is the default.
+        Warn,
+
+        /// Panic, unless the thread is already panicking.
+        Panic,
+
+        /// Only increase the counter returned by [`count()`].
+        Count,
+    }
+
+    static ACTION: AtomicU8 = AtomicU8::new(Action::Warn as u8);
+    static COUNT: AtomicU64 = AtomicU64::new(0);
+
+    /// Set the action to take when an unconsumed `VBox` is dropped.
+    pub fn set_action(action: Action) {
+        ACTION.store(action as u8, Ordering::Relaxed);
+    }
+
+    /// Returns the action to take when an unconsumed `VBox` is dropped.
+    pub fn action() -> Action {
+        match ACTION.load(Ordering::Relaxed) {
+            x if x == Action::Panic as u8 => Action::Panic,
+            x if x == Action::Count as u8 => Action::Count,
+            _ => Action::Warn,
+        }
+    }
+
+    /// Returns the number of unconsumed `VBox`es dropped so far, no matter what
+    /// the action is.
+    pub fn count() -> u64 {
+        COUNT.load(Ordering::Relaxed)
+    }
+
+    /// Per-`VBox` state: where it is created, or null if it is consumed.
+    ///
+    /// It is a single pointer to keep `VBox` small.
+    pub(crate) struct Tracker {
+        created_at: AtomicPtr<Location<'static>>,
+    }
+
+    impl Tracker {
+        #[track_caller]
+        pub(crate) fn new() -> Self {
+            let created_at: *const Location<'static> = Location::caller();
+            Tracker {
+                created_at: AtomicPtr::new(created_at as *mut _),
+            }
+        }
+
+        pub(crate) fn consume(&self) {
+            self.created_at.store(ptr::null_mut(), Ordering::Relaxed);
+        }
+
+        pub(crate) fn check(&self, type_name: &str) {
+            let created_at = self.created_at.load(Ordering::Relaxed);
+            if created_at.is_null() {
+                return;
+            }
+            // Non-null pointers are always from `Location::caller()`.
+            let created_at = unsafe { &*created_at };
+
+            COUNT.fetch_add(1, Ordering::Relaxed);
+
+            let msg = format!(
+                "VBox dropped without being unpacked: {}, created at {}",
+                type_name, created_at
+            );
+
+            match action() {
+                Action::Warn => {
+                    #[cfg(feature = "log")]
+                    log::warn!("{}", msg);
+                    #[cfg(not(feature = "log"))]
+                    eprintln!("{}", msg);
+                }
+                Action::Panic => {
+                    if !std::thread::panicking() {
+                        panic!("{}", msg);
+                    }
+                }
+                Action::Count => {}
+            }
+        }
+    }
+}
+
+#[cfg(feature = "debug-unconsumed")] pub use enabled::action;
+#[cfg(feature = "debug-unconsumed")] pub use enabled::count;
+#[cfg(feature = "debug-unconsumed")] pub use enabled::set_action;
+#[cfg(feature = "debug-unconsumed")] pub use enabled::Action;
+#[cfg(feature = "debug-unconsumed")] pub(crate) use enabled::Tracker;
+
+/// A no-op tracker when the feature is disabled.
+#[cfg(not(feature = "debug-unconsumed"))]
+pub(crate) struct Tracker;
+
+#[cfg(not(feature = "debug-unconsumed"))]
+impl Tracker {
+    #[inline(always)]
+    pub(crate) fn new() -> Self {
+        Tracker
+    }
+
+    #[inline(always)]
+    pub(crate) fn consume(&self) {}
+
+    #[inline(always)]
+    pub(crate) fn check(&self, _type_name: &str) {}
+}
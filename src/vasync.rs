@@ -0,0 +1,152 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::VBox;
+
+/// The future returned by a method of an `#[async_trait]` trait, the same as
+/// `futures::future::BoxFuture`.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A [`VBox`] of a trait object whose methods return a [`BoxFuture`], such as
+/// a trait generated by `#[async_trait]`, to call and await its methods on the
+/// other side of a channel.
+///
+/// An `#[async_trait]` trait object is packed with [`into_vbox!`] as usual,
+/// usually as `dyn Trait + Send + Sync`. A method is then called with a closure
+/// that receives the unpacked `&dyn Trait`, and the closure returns the future
+/// of the method, e.g., `|s| s.get(key)`.
+///
+/// [`VAsync::into_call()`] moves the trait object into the returned future, so
+/// that the future is `'static` and can be spawned.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{into_vbox, BoxFuture, VAsync};
+/// // What `#[async_trait]` generates for `async fn get(&self, key: u64) -> String`.
+/// trait Store: Send + Sync {
+///     fn get<'a>(&'a self, key: u64) -> BoxFuture<'a, String>;
+/// }
+///
+/// struct Mem;
+/// impl Store for Mem {
+///     fn get<'a>(&'a self, key: u64) -> BoxFuture<'a, String> {
+///         Box::pin(async move { format!("v{}", key) })
+///     }
+/// }
+///
+/// let store = VAsync::new(into_vbox!(dyn Store, Mem));
+///
+/// let fu = store.into_call::<dyn Store, _, _>(|s| s.get(1));
+/// assert_eq!("v1", futures::executor::block_on(fu));
+/// ```
+///
+/// [`into_vbox!`]: crate::into_vbox
+pub struct VAsync {
+    inner: VBox,
+}
+
+impl VAsync {
+    /// Wrap a `VBox` of a trait object with async methods.
+    pub fn new(vbox: VBox) -> Self {
+        VAsync { inner: vbox }
+    }
+
+    /// Returns the wrapped `VBox`.
+    pub fn into_vbox(self) -> VBox {
+        self.inner
+    }
+
+    /// Returns `true` if it is packed as `U`, i.e., `dyn Trait`.
+    pub fn is_dyn<U>(&self) -> bool
+    where U: ?Sized + 'static {
+        self.inner.is_dyn::<U>()
+    }
+
+    /// Call an async method with `&dyn Trait`, and return its future, which
+    /// borrows `self`.
+    ///
+    /// It panics if it is not packed as `dyn Trait`.
+    pub fn call<'a, U, R, F>(&'a self, f: F) -> BoxFuture<'a, R>
+    where
+        U: ?Sized + 'static,
+        F: FnOnce(&'a U) -> BoxFuture<'a, R>,
+    {
+        f(self.inner.as_dyn::<U>())
+    }
+
+    /// Call an async method with `&mut dyn Trait`, and return its future,
+    /// which borrows `self`.
+    ///
+    /// It panics if it is not packed as `dyn Trait`.
+    pub fn call_mut<'a, U, R, F>(&'a mut self, f: F) -> BoxFuture<'a, R>
+    where
+        U: ?Sized + 'static,
+        F: FnOnce(&'a mut U) -> BoxFuture<'a, R>,
+    {
+        f(self.inner.as_dyn_mut::<U>())
+    }
+
+    /// Unpack the trait object and call an async method with it. The trait
+    /// object is moved into the returned future, and is dropped when the
+    /// future completes.
+    ///
+    /// It panics if it is not packed as `dyn Trait`.
+    pub fn into_call<U, R, F>(self, f: F) -> BoxFuture<'static, R>
+    where
+        U: ?Sized + Send + Sync + 'static,
+        R: 'static,
+        F: for<'a> FnOnce(&'a U) -> BoxFuture<'a, R> + Send + 'static,
+    {
+        let obj = self.inner.unpack::<U>();
+        Box::pin(async move { f(&*obj).await })
+    }
+}
+
+impl From<VBox> for VAsync {
+    fn from(vbox: VBox) -> Self {
+        Self::new(vbox)
+    }
+}
+
+impl fmt::Debug for VAsync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VAsync").field(&self.inner).finish()
+    }
+}
+
+/// Borrow the payload of a [`VBox`] as `&dyn Trait` of an `#[async_trait]`
+/// trait, call an async method with a closure, and return the future of the
+/// method, like [`with_vbox!`](crate::with_vbox) for a sync method.
+///
+/// ```
+/// # use vbox::{into_vbox, with_vbox_async, BoxFuture};
+/// trait Greet: Send + Sync {
+///     fn greet<'a>(&'a self, name: &'a str) -> BoxFuture<'a, String>;
+/// }
+///
+/// struct Hello;
+/// impl Greet for Hello {
+///     fn greet<'a>(&'a self, name: &'a str) -> BoxFuture<'a, String> {
+///         Box::pin(async move { format!("hello {}", name) })
+///     }
+/// }
+///
+/// let vbox = into_vbox!(dyn Greet, Hello);
+/// let fu = with_vbox_async!(dyn Greet, &vbox, |g| g.greet("world"));
+/// assert_eq!("hello world", futures::executor::block_on(fu));
+/// ```
+#[macro_export]
+macro_rules! with_vbox_async {
+    ($t: ty, $v: expr, $f: expr) => {{
+        fn call<'a, U: ?::std::marker::Sized, R>(
+            u: &'a U,
+            f: impl ::std::ops::FnOnce(&'a U) -> $crate::BoxFuture<'a, R>,
+        ) -> $crate::BoxFuture<'a, R> {
+            f(u)
+        }
+
+        let vbox: &$crate::VBox = $v;
+        call($crate::VBox::as_dyn::<$t>(vbox), $f)
+    }};
+}
@@ -0,0 +1,105 @@
+use std::any::Any;
+use std::fmt;
+
+use crate::VBox;
+
+/// A complete method invocation, i.e., the receiver, the method and the
+/// arguments, erased into one object that is executed by the consumer.
+///
+/// It is built with [`VCall::on()`], and run with [`VCall::run()`], e.g., by a
+/// thread that owns the resources but can not name the methods to call.
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use std::sync::atomic::{AtomicU64, Ordering};
+/// # use vbox::VCall;
+/// let counter = Arc::new(AtomicU64::new(1));
+///
+/// let call = VCall::on(counter.clone())
+///     .with_args(2u64)
+///     .method(|c: Arc<AtomicU64>, n| c.fetch_add(n, Ordering::Relaxed));
+///
+/// let ret = std::thread::spawn(move || call.run()).join().unwrap();
+///
+/// assert_eq!(1u64, ret.into_inner::<u64>().unwrap());
+/// assert_eq!(3, counter.load(Ordering::Relaxed));
+/// ```
+pub struct VCall {
+    /// Packed as `dyn FnOnce() -> VBox + Send`.
+    call: VBox,
+
+    /// The type name of the method, for debugging.
+    method: &'static str,
+}
+
+/// A builder of [`VCall`] with the receiver and the arguments bound.
+pub struct VCallBuilder<Recv, Args> {
+    receiver: Recv,
+    args: Args,
+}
+
+impl VCall {
+    /// Start building a call on `receiver`.
+    pub fn on<Recv>(receiver: Recv) -> VCallBuilder<Recv, ()>
+    where Recv: Send + 'static {
+        VCallBuilder { receiver, args: () }
+    }
+
+    /// Execute the call and return the output of the method, packed as `dyn
+    /// Any + Send`.
+    pub fn run(self) -> VBox {
+        let f = self.call.unpack::<dyn FnOnce() -> VBox + Send>();
+        f()
+    }
+}
+
+impl<Recv, Args> VCallBuilder<Recv, Args>
+where
+    Recv: Send + 'static,
+    Args: Send + 'static,
+{
+    /// Bind the arguments, replacing the previously bound ones.
+    ///
+    /// Multiple arguments can be bound as a tuple.
+    pub fn with_args<A>(self, args: A) -> VCallBuilder<Recv, A>
+    where A: Send + 'static {
+        VCallBuilder {
+            receiver: self.receiver,
+            args,
+        }
+    }
+
+    /// Bind the method and build the [`VCall`].
+    pub fn method<F, R>(self, f: F) -> VCall
+    where
+        F: FnOnce(Recv, Args) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let VCallBuilder { receiver, args } = self;
+
+        let call = move || -> VBox {
+            let ret = f(receiver, args);
+            crate::into_vbox!(dyn Any + Send, ret)
+        };
+
+        VCall {
+            call: crate::into_vbox!(dyn FnOnce() -> VBox + Send, call),
+            method: std::any::type_name::<F>(),
+        }
+    }
+}
+
+impl fmt::Debug for VCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VCall").field("method", &self.method).finish()
+    }
+}
+
+impl<Recv, Args> fmt::Debug for VCallBuilder<Recv, Args> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VCallBuilder")
+            .field("receiver", &std::any::type_name::<Recv>())
+            .field("args", &std::any::type_name::<Args>())
+            .finish()
+    }
+}
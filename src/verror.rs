@@ -0,0 +1,113 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::VBox;
+
+/// An erased error, i.e., a [`VBox`] packed as `dyn Error + Send + Sync`, that
+/// implements [`Error`] itself.
+///
+/// A `VError` wrapping another `VError` is transparent: `Display`, `Debug` and
+/// `source()` are delegated to the innermost error, so that a `source()` chain
+/// walks through any number of erased layers. Use [`VError::find()`] to get a
+/// concrete error type out of the chain.
+///
+/// ```
+/// # use std::error::Error;
+/// # use std::fmt;
+/// # use vbox::VError;
+/// #[derive(Debug)]
+/// struct Io;
+/// impl fmt::Display for Io {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         write!(f, "io")
+///     }
+/// }
+/// impl Error for Io {}
+///
+/// let err = VError::new(VError::new(Io));
+///
+/// assert_eq!("io", err.to_string());
+/// assert!(err.find::<Io>().is_some());
+/// ```
+pub struct VError {
+    /// Packed as `dyn Error + Send + Sync`.
+    inner: VBox,
+}
+
+// The payload is packed as `dyn Error + Send + Sync`.
+unsafe impl Sync for VError {}
+
+impl VError {
+    /// Erase an error.
+    pub fn new<E>(e: E) -> Self
+    where E: Error + Send + Sync + 'static {
+        VError {
+            inner: crate::into_vbox!(dyn Error + Send + Sync, e),
+        }
+    }
+
+    /// Wrap a `VBox` packed as `dyn Error + Send + Sync`.
+    ///
+    /// It panics if the `VBox` is packed as another trait object.
+    pub fn from_vbox(vbox: VBox) -> Self {
+        assert!(
+            vbox.is_dyn::<dyn Error + Send + Sync>(),
+            "VError must be packed as dyn Error + Send + Sync, got: {:?}",
+            vbox
+        );
+        VError { inner: vbox }
+    }
+
+    /// Returns the `VBox` packed as `dyn Error + Send + Sync`.
+    pub fn into_vbox(self) -> VBox {
+        self.inner
+    }
+
+    /// Returns the innermost error, skipping the `VError` layers.
+    pub fn as_error(&self) -> &(dyn Error + 'static) {
+        peel(self.inner.as_dyn::<dyn Error + Send + Sync>())
+    }
+
+    /// Iterate the error and its sources, skipping the `VError` layers.
+    pub fn chain(&self) -> impl Iterator<Item = &(dyn Error + 'static)> {
+        let mut next = Some(self.as_error());
+        std::iter::from_fn(move || {
+            let cur = next?;
+            next = cur.source().map(peel);
+            Some(cur)
+        })
+    }
+
+    /// Returns the first error of type `E` in the chain of sources, including
+    /// the error itself.
+    pub fn find<E>(&self) -> Option<&E>
+    where E: Error + 'static {
+        self.chain().find_map(|e| e.downcast_ref::<E>())
+    }
+}
+
+/// Skip the `VError` layers and return the error they wrap.
+fn peel<'a>(mut e: &'a (dyn Error + 'static)) -> &'a (dyn Error + 'static) {
+    while let Some(v) = e.downcast_ref::<VError>() {
+        e = v.inner.as_dyn::<dyn Error + Send + Sync>();
+    }
+    e
+}
+
+impl fmt::Debug for VError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_error(), f)
+    }
+}
+
+impl fmt::Display for VError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_error(), f)
+    }
+}
+
+impl Error for VError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.as_error().source().map(peel)
+    }
+}
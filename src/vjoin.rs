@@ -0,0 +1,125 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+use crate::BoxFuture;
+use crate::VBox;
+
+/// The future returned by [`join_vbox()`] and [`vjoin!`](macro@crate::vjoin).
+///
+/// It polls every unfinished future each time it is woken up, and resolves
+/// to their outputs in the order of the input.
+pub struct JoinAll<T> {
+    futures: Vec<Option<BoxFuture<'static, T>>>,
+    outputs: Vec<Option<T>>,
+    remaining: usize,
+}
+
+// The futures are pinned in their own boxes.
+impl<T> Unpin for JoinAll<T> {}
+
+/// Drive `futures`, each a [`VBox`] packed as `dyn Future<Output = T> + Send`,
+/// concurrently on the current task, and return their outputs in order.
+///
+/// It panics right away if any of them is packed as another trait.
+///
+/// ```
+/// # use std::future::Future;
+/// # use vbox::{into_vbox, join_vbox};
+/// let futs = vec![
+///     into_vbox!(dyn Future<Output = u64> + Send, async { 1u64 }),
+///     into_vbox!(dyn Future<Output = u64> + Send, async { 2u64 }),
+/// ];
+///
+/// let got = futures::executor::block_on(join_vbox::<u64>(futs));
+/// assert_eq!(vec![1, 2], got);
+/// ```
+pub fn join_vbox<T: 'static>(futures: Vec<VBox>) -> JoinAll<T> {
+    let futures = futures
+        .into_iter()
+        .map(|f| {
+            let f = f.unpack::<dyn Future<Output = T> + Send>();
+            Some(Box::into_pin(f))
+        })
+        .collect::<Vec<_>>();
+
+    let n = futures.len();
+    JoinAll {
+        futures,
+        outputs: (0..n).map(|_| None).collect(),
+        remaining: n,
+    }
+}
+
+impl<T> Future for JoinAll<T> {
+    type Output = Vec<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Vec<T>> {
+        let this = self.get_mut();
+
+        for (slot, output) in
+            this.futures.iter_mut().zip(this.outputs.iter_mut())
+        {
+            let Some(fu) = slot else {
+                continue;
+            };
+            if let Poll::Ready(v) = fu.as_mut().poll(cx) {
+                *output = Some(v);
+                *slot = None;
+                this.remaining -= 1;
+            }
+        }
+
+        if this.remaining > 0 {
+            return Poll::Pending;
+        }
+
+        let outputs = std::mem::take(&mut this.outputs);
+        Poll::Ready(outputs.into_iter().map(|v| v.unwrap()).collect())
+    }
+}
+
+impl<T> fmt::Debug for JoinAll<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinAll")
+            .field("total", &self.futures.len())
+            .field("remaining", &self.remaining)
+            .finish()
+    }
+}
+
+/// Join a `Vec<VBox>` of erased futures, and return a future of the `Vec` of
+/// their outputs in order.
+///
+/// - `vjoin!(futs)`: every `VBox` is packed as `dyn Future<Output = VBox> +
+///   Send`, i.e., the outputs are erased too, and it yields a `Vec<VBox>`.
+/// - `vjoin!(futs, T)`: every `VBox` is packed as `dyn Future<Output = T> +
+///   Send`, and it yields a `Vec<T>`.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use std::future::Future;
+/// # use vbox::{into_vbox, vjoin, VBox};
+/// let work = (0..3u64)
+///     .map(|i| {
+///         into_vbox!(dyn Future<Output = VBox> + Send, async move {
+///             into_vbox!(dyn Debug + Send, i * 10)
+///         })
+///     })
+///     .collect::<Vec<_>>();
+///
+/// let got = futures::executor::block_on(vjoin!(work));
+/// let got = got.into_iter().map(|v| v.into_inner::<u64>().unwrap()).collect::<Vec<_>>();
+/// assert_eq!(vec![0, 10, 20], got);
+/// ```
+#[macro_export]
+macro_rules! vjoin {
+    ($futs: expr) => {
+        $crate::join_vbox::<$crate::VBox>($futs)
+    };
+    ($futs: expr, $t: ty) => {
+        $crate::join_vbox::<$t>($futs)
+    };
+}
@@ -0,0 +1,218 @@
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+
+use crate::VBox;
+
+/// The sending half of an erased oneshot channel, for sending back a response.
+///
+/// It is created with [`VOnce::channel()`]. If it is dropped without sending,
+/// the receiving end gets a [`Canceled`] error.
+pub struct VOnce {
+    shared: Arc<Shared>,
+    sent: bool,
+}
+
+/// The receiving half of an erased oneshot channel.
+///
+/// The response can be received by blocking with [`VOnceReceiver::recv()`], or
+/// by awaiting it, since it is a `Future`.
+///
+/// Dropping it signals the [`VOnce`] that the request is canceled, see
+/// [`VOnce::is_canceled()`].
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{into_vbox, VOnce};
+/// let (tx, rx) = VOnce::channel();
+///
+/// std::thread::spawn(move || tx.send(into_vbox!(dyn Debug, 5u64)));
+///
+/// let got = futures::executor::block_on(rx).unwrap();
+/// assert_eq!(5u64, got.into_inner::<u64>().unwrap());
+/// ```
+pub struct VOnceReceiver {
+    shared: Arc<Shared>,
+}
+
+/// The error returned by [`VOnceReceiver`] when the [`VOnce`] is dropped
+/// without sending a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Canceled;
+
+impl fmt::Display for Canceled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "VOnce is dropped without sending a response")
+    }
+}
+
+impl Error for Canceled {}
+
+struct Shared {
+    state: Mutex<State>,
+    cond: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    value: Option<VBox>,
+    sender_dropped: bool,
+    receiver_dropped: bool,
+
+    /// The task awaiting the response.
+    waker: Option<Waker>,
+}
+
+impl State {
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl VOnce {
+    /// Create a oneshot channel and return the sending and receiving halves.
+    pub fn channel() -> (VOnce, VOnceReceiver) {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::default()),
+            cond: Condvar::new(),
+        });
+
+        let tx = VOnce {
+            shared: shared.clone(),
+            sent: false,
+        };
+        let rx = VOnceReceiver { shared };
+        (tx, rx)
+    }
+
+    /// Send a response to the receiving end.
+    ///
+    /// If the receiving end is already dropped, the value is returned in `Err`.
+    pub fn send(mut self, value: VBox) -> Result<(), VBox> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.receiver_dropped {
+            return Err(value);
+        }
+
+        state.value = Some(value);
+        state.wake();
+        self.sent = true;
+        self.shared.cond.notify_all();
+        Ok(())
+    }
+
+    /// Returns `true` if the receiving end is dropped, i.e., no one is waiting
+    /// for the response any more.
+    ///
+    /// A responder may check it to skip the work for a canceled request.
+    pub fn is_canceled(&self) -> bool {
+        self.shared.state.lock().unwrap().receiver_dropped
+    }
+}
+
+impl Drop for VOnce {
+    fn drop(&mut self) {
+        if self.sent {
+            return;
+        }
+
+        let mut state =
+            self.shared.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.sender_dropped = true;
+        state.wake();
+        self.shared.cond.notify_all();
+    }
+}
+
+impl fmt::Debug for VOnce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VOnce").field("sent", &self.sent).finish()
+    }
+}
+
+impl VOnceReceiver {
+    /// Block until the response is sent, or the [`VOnce`] is dropped.
+    pub fn recv(self) -> Result<VBox, Canceled> {
+        let mut state = self.shared.state.lock().unwrap();
+        loop {
+            if let Some(value) = state.value.take() {
+                return Ok(value);
+            }
+            if state.sender_dropped {
+                return Err(Canceled);
+            }
+            state = self.shared.cond.wait(state).unwrap();
+        }
+    }
+
+    /// Block until the response is sent, and unpack it as `Box<dyn Trait>`.
+    ///
+    /// ```
+    /// # use std::fmt::Debug;
+    /// # use vbox::{into_vbox, VOnce};
+    /// let (tx, rx) = VOnce::channel();
+    /// tx.send(into_vbox!(dyn Debug, 5u64)).unwrap();
+    ///
+    /// let got = rx.recv_as::<dyn Debug>().unwrap();
+    /// assert_eq!("5", format!("{:?}", got));
+    /// ```
+    pub fn recv_as<U>(self) -> Result<Box<U>, Canceled>
+    where U: ?Sized + 'static {
+        self.recv().map(VBox::unpack::<U>)
+    }
+
+    /// Return the response if it is already sent, without blocking.
+    ///
+    /// It returns `Ok(None)` if the response is not yet sent.
+    pub fn try_recv(&mut self) -> Result<Option<VBox>, Canceled> {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(value) = state.value.take() {
+            return Ok(Some(value));
+        }
+        if state.sender_dropped {
+            return Err(Canceled);
+        }
+        Ok(None)
+    }
+}
+
+impl Future for VOnceReceiver {
+    type Output = Result<VBox, Canceled>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(value) = state.value.take() {
+            return Poll::Ready(Ok(value));
+        }
+        if state.sender_dropped {
+            return Poll::Ready(Err(Canceled));
+        }
+
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for VOnceReceiver {
+    fn drop(&mut self) {
+        let mut state =
+            self.shared.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.receiver_dropped = true;
+    }
+}
+
+impl fmt::Debug for VOnceReceiver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VOnceReceiver").finish()
+    }
+}
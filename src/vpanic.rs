@@ -0,0 +1,93 @@
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+use std::panic;
+use std::panic::UnwindSafe;
+
+use crate::VBox;
+
+/// The payload of a caught panic, i.e., the `Box<dyn Any + Send>` returned by
+/// [`std::panic::catch_unwind()`].
+///
+/// It extracts the message if the payload is a `&str` or a `String`, keeps the
+/// original payload, and re-raises it with [`VPanic::resume()`], so that a
+/// panic in a job can be shipped back to the thread that waits for the job.
+///
+/// ```
+/// # use vbox::VPanic;
+/// let res = VPanic::catch(|| -> u64 { panic!("bad {}", "input") });
+///
+/// let p = res.unwrap_err();
+/// assert_eq!(Some("bad input"), p.message());
+/// ```
+pub struct VPanic {
+    payload: Box<dyn Any + Send>,
+}
+
+impl VPanic {
+    /// Wrap a panic payload.
+    pub fn new(payload: Box<dyn Any + Send>) -> Self {
+        VPanic { payload }
+    }
+
+    /// Call `f` and catch the panic it raises.
+    pub fn catch<R>(f: impl FnOnce() -> R + UnwindSafe) -> Result<R, VPanic> {
+        panic::catch_unwind(f).map_err(VPanic::new)
+    }
+
+    /// Returns the panic message if the payload is a `&str` or a `String`,
+    /// which is the case for `panic!()` with or without format arguments.
+    pub fn message(&self) -> Option<&str> {
+        if let Some(s) = self.payload.downcast_ref::<&'static str>() {
+            return Some(s);
+        }
+        if let Some(s) = self.payload.downcast_ref::<String>() {
+            return Some(s);
+        }
+        None
+    }
+
+    /// Borrow the original payload.
+    pub fn payload(&self) -> &(dyn Any + Send) {
+        &*self.payload
+    }
+
+    /// Returns the original payload.
+    pub fn into_payload(self) -> Box<dyn Any + Send> {
+        self.payload
+    }
+
+    /// Pack the original payload into a `VBox` as `dyn Any + Send`.
+    pub fn into_vbox(self) -> VBox {
+        VBox::from_any(self.payload)
+    }
+
+    /// Re-raise the panic with the original payload, with
+    /// [`std::panic::resume_unwind()`].
+    pub fn resume(self) -> ! {
+        panic::resume_unwind(self.payload)
+    }
+}
+
+impl From<Box<dyn Any + Send>> for VPanic {
+    fn from(payload: Box<dyn Any + Send>) -> Self {
+        VPanic::new(payload)
+    }
+}
+
+impl fmt::Debug for VPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VPanic").field("message", &self.message()).finish()
+    }
+}
+
+impl fmt::Display for VPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.message() {
+            Some(msg) => write!(f, "panicked: {}", msg),
+            None => write!(f, "panicked with a non-string payload"),
+        }
+    }
+}
+
+impl Error for VPanic {}
@@ -0,0 +1,128 @@
+//! Transport a `Result<T, E>` as a pair of erased values.
+
+use crate::VBox;
+
+/// A `Result` with both sides erased, e.g., the response of a request that is
+/// either a reply packed as `dyn Reply` or an error packed as `dyn Error +
+/// Send`.
+///
+/// It is built with [`into_vresult!`](crate::into_vresult), unpacked with
+/// [`from_vresult!`](crate::from_vresult), and transformed with
+/// [`map_ok_vbox!`](crate::map_ok_vbox) and
+/// [`map_err_vbox!`](crate::map_err_vbox).
+///
+/// ```
+/// # use std::error::Error;
+/// # use std::fmt::Display;
+/// # use vbox::{from_vresult, into_vresult, VResult};
+/// let res: Result<u64, std::fmt::Error> = Ok(3);
+/// let vres: VResult = into_vresult!(dyn Display, dyn Error + Send, res);
+///
+/// let res = from_vresult!(dyn Display, dyn Error + Send, vres);
+/// assert_eq!("3", res.unwrap().to_string());
+/// ```
+pub type VResult = Result<VBox, VBox>;
+
+/// Pack a `Result<T, E>` into a [`VResult`], with `T` packed as the first
+/// trait object and `E` packed as the second.
+#[macro_export]
+macro_rules! into_vresult {
+    ($ok: ty, $err: ty, $v: expr) => {{
+        let ret: $crate::VResult = match $v {
+            ::std::result::Result::Ok(x) => {
+                ::std::result::Result::Ok($crate::into_vbox!($ok, x))
+            }
+            ::std::result::Result::Err(e) => {
+                ::std::result::Result::Err($crate::into_vbox!($err, e))
+            }
+        };
+        ret
+    }};
+}
+
+/// Unpack a [`VResult`] into `Result<Box<dyn Ok>, Box<dyn Err>>`.
+///
+/// It panics if either side is not packed as the given trait object, just like
+/// [`from_vbox!`](crate::from_vbox).
+#[macro_export]
+macro_rules! from_vresult {
+    ($ok: ty, $err: ty, $v: expr) => {{
+        let vres: $crate::VResult = $v;
+        match vres {
+            ::std::result::Result::Ok(x) => {
+                ::std::result::Result::Ok($crate::from_vbox!($ok, x))
+            }
+            ::std::result::Result::Err(e) => {
+                ::std::result::Result::Err($crate::from_vbox!($err, e))
+            }
+        }
+    }};
+}
+
+/// Transform the `Ok` side of a [`VResult`] with
+/// [`map_vbox!`](crate::map_vbox), leaving the `Err` side intact.
+///
+/// ```
+/// # use std::fmt::{Debug, Display};
+/// # use vbox::{from_vbox, into_vbox, map_ok_vbox, VResult};
+/// let vres: VResult = Ok(into_vbox!(dyn Debug, 10u64));
+///
+/// let vres = map_ok_vbox!(dyn Debug -> dyn Display, vres, |a| format!("<{:?}>", a));
+///
+/// assert_eq!("<10>", from_vbox!(dyn Display, vres.unwrap()).to_string());
+/// ```
+#[macro_export]
+macro_rules! map_ok_vbox {
+    (dyn $($rest: tt)+) => {
+        $crate::map_ok_vbox!(@from [dyn] $($rest)+)
+    };
+
+    (@from [$($from: tt)+] -> $to: ty, $v: expr, $f: expr) => {{
+        let vres: $crate::VResult = $v;
+        match vres {
+            ::std::result::Result::Ok(x) => ::std::result::Result::Ok(
+                $crate::map_vbox!(@from [$($from)+] -> $to, x, $f),
+            ),
+            ::std::result::Result::Err(e) => ::std::result::Result::Err(e),
+        }
+    }};
+
+    (@from [$($from: tt)+] $next: tt $($rest: tt)+) => {
+        $crate::map_ok_vbox!(@from [$($from)+ $next] $($rest)+)
+    };
+}
+
+/// Transform the `Err` side of a [`VResult`] with
+/// [`map_vbox!`](crate::map_vbox), leaving the `Ok` side intact.
+///
+/// ```
+/// # use std::error::Error;
+/// # use std::fmt::{Debug, Display};
+/// # use vbox::{from_vbox, into_vbox, map_err_vbox, VResult};
+/// let vres: VResult = Err(into_vbox!(dyn Error + Send, std::fmt::Error));
+///
+/// let vres = map_err_vbox!(dyn Error + Send -> dyn Display, vres, |e| format!("failed: {}", e));
+///
+/// let err = from_vbox!(dyn Display, vres.unwrap_err());
+/// assert_eq!("failed: an error occurred when formatting an argument", err.to_string());
+/// ```
+#[macro_export]
+macro_rules! map_err_vbox {
+    (dyn $($rest: tt)+) => {
+        $crate::map_err_vbox!(@from [dyn] $($rest)+)
+    };
+
+    (@from [$($from: tt)+] -> $to: ty, $v: expr, $f: expr) => {{
+        let vres: $crate::VResult = $v;
+        match vres {
+            ::std::result::Result::Ok(x) => ::std::result::Result::Ok(x),
+            ::std::result::Result::Err(e) => ::std::result::Result::Err(
+                $crate::map_vbox!(@from [$($from)+] -> $to, e, $f),
+            ),
+        }
+    }};
+
+    (@from [$($from: tt)+] $next: tt $($rest: tt)+) => {
+        $crate::map_err_vbox!(@from [$($from)+ $next] $($rest)+)
+    };
+}
@@ -0,0 +1,184 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+
+use crate::BoxFuture;
+use crate::VBox;
+
+/// A [`VBox`] packed as `dyn Future<Output = T> + Send`, unpacked into a
+/// named, `Unpin` future, so that it can be used in `futures::select!` (after
+/// `.fuse()`) or `tokio::select!` without knowing the concrete future type.
+///
+/// ```
+/// # use std::future::Future;
+/// # use futures::FutureExt;
+/// # use vbox::{into_vbox, VFuture};
+/// let a = VFuture::<u64>::new(into_vbox!(dyn Future<Output = u64> + Send, async { 1 }));
+/// let b = VFuture::<u64>::new(into_vbox!(
+///     dyn Future<Output = u64> + Send,
+///     futures::future::pending::<u64>()
+/// ));
+///
+/// let got = futures::executor::block_on(async {
+///     let (mut a, mut b) = (a.fuse(), b.fuse());
+///     futures::select! {
+///         v = a => v,
+///         v = b => v + 100,
+///     }
+/// });
+/// assert_eq!(1, got);
+/// ```
+pub struct VFuture<T> {
+    inner: BoxFuture<'static, T>,
+}
+
+impl<T: 'static> VFuture<T> {
+    /// Unpack a `VBox` packed as `dyn Future<Output = T> + Send`.
+    ///
+    /// It panics if it is packed as another trait.
+    pub fn new(vbox: VBox) -> Self {
+        let f = vbox.unpack::<dyn Future<Output = T> + Send>();
+        VFuture {
+            inner: Box::into_pin(f),
+        }
+    }
+}
+
+impl<T> Future for VFuture<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        self.inner.as_mut().poll(cx)
+    }
+}
+
+impl<T> fmt::Debug for VFuture<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VFuture")
+            .field("output", &std::any::type_name::<T>())
+            .finish()
+    }
+}
+
+/// The output of [`select_either()`]: the output of the future that completes
+/// first, and the other future, which is not completed.
+#[derive(Debug)]
+pub enum VEither<A, B> {
+    /// The first future completes first.
+    Left(A, VFuture<B>),
+
+    /// The second future completes first.
+    Right(B, VFuture<A>),
+}
+
+/// The future returned by [`select_either()`].
+#[derive(Debug)]
+pub struct SelectEither<A, B> {
+    inner: Option<(VFuture<A>, VFuture<B>)>,
+}
+
+/// Wait for either of two erased futures, packed as `dyn Future<Output = A> +
+/// Send` and `dyn Future<Output = B> + Send`, to complete. The first one is
+/// polled first.
+///
+/// It panics right away if either is packed as another trait.
+///
+/// ```
+/// # use std::future::Future;
+/// # use vbox::{into_vbox, select_either, VEither};
+/// let fast = into_vbox!(dyn Future<Output = u64> + Send, async { 1u64 });
+/// let slow = into_vbox!(dyn Future<Output = String> + Send, futures::future::pending());
+///
+/// match futures::executor::block_on(select_either::<u64, String>(fast, slow)) {
+///     VEither::Left(v, _slow) => assert_eq!(1, v),
+///     VEither::Right(..) => unreachable!(),
+/// }
+/// ```
+pub fn select_either<A: 'static, B: 'static>(
+    a: VBox,
+    b: VBox,
+) -> SelectEither<A, B> {
+    SelectEither {
+        inner: Some((VFuture::new(a), VFuture::new(b))),
+    }
+}
+
+impl<A, B> Future for SelectEither<A, B> {
+    type Output = VEither<A, B>;
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<VEither<A, B>> {
+        let (a, b) = self.inner.as_mut().expect("polled after completion");
+
+        if let Poll::Ready(v) = Pin::new(&mut *a).poll(cx) {
+            let (_, b) = self.inner.take().unwrap();
+            return Poll::Ready(VEither::Left(v, b));
+        }
+        if let Poll::Ready(v) = Pin::new(&mut *b).poll(cx) {
+            let (a, _) = self.inner.take().unwrap();
+            return Poll::Ready(VEither::Right(v, a));
+        }
+        Poll::Pending
+    }
+}
+
+/// The future returned by [`select_vbox()`].
+#[derive(Debug)]
+pub struct SelectVBox<T> {
+    inner: Vec<VFuture<T>>,
+}
+
+/// Wait for the first of `futures`, each packed as `dyn Future<Output = T> +
+/// Send`, to complete, and return its output, its index, and the other
+/// futures in order, like `futures::future::select_all()`.
+///
+/// It panics right away if `futures` is empty or any of them is packed as
+/// another trait.
+///
+/// ```
+/// # use std::future::Future;
+/// # use vbox::{into_vbox, select_vbox};
+/// let futs = vec![
+///     into_vbox!(dyn Future<Output = u64> + Send, futures::future::pending()),
+///     into_vbox!(dyn Future<Output = u64> + Send, async { 2u64 }),
+/// ];
+///
+/// let (v, index, rest) = futures::executor::block_on(select_vbox::<u64>(futs));
+/// assert_eq!((2, 1, 1), (v, index, rest.len()));
+/// ```
+pub fn select_vbox<T: 'static>(futures: Vec<VBox>) -> SelectVBox<T> {
+    assert!(!futures.is_empty(), "select_vbox() with no future");
+    SelectVBox {
+        inner: futures.into_iter().map(VFuture::new).collect(),
+    }
+}
+
+impl<T> Future for SelectVBox<T> {
+    type Output = (T, usize, Vec<VFuture<T>>);
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Self::Output> {
+        let ready =
+            self.inner.iter_mut().enumerate().find_map(
+                |(i, f)| match Pin::new(f).poll(cx) {
+                    Poll::Ready(v) => Some((i, v)),
+                    Poll::Pending => None,
+                },
+            );
+
+        match ready {
+            Some((i, v)) => {
+                let mut rest = std::mem::take(&mut self.inner);
+                drop(rest.remove(i));
+                Poll::Ready((v, i, rest))
+            }
+            None => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::VBox;
+
+/// A key of a [`VBox`] stored in a [`VSlab`].
+///
+/// It is a `u64` made of the index of the entry and a generation, which is
+/// bumped every time the entry is removed: a key of a removed `VBox` does
+/// not refer to another `VBox` stored later in the same entry.
+///
+/// The generation is a `u32` that wraps around: after 2^32 reuses of one
+/// entry, a key that is kept that long refers to the `VBox` stored in the entry
+/// again.
+///
+/// It is `Copy` and `Send`, and can be put into a message with
+/// [`SlabKey::to_u64()`] instead of the `VBox` itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SlabKey(u64);
+
+impl SlabKey {
+    fn new(index: u32, generation: u32) -> Self {
+        SlabKey(((generation as u64) << 32) | index as u64)
+    }
+
+    /// Build a key from the `u64` returned by [`SlabKey::to_u64()`].
+    pub fn from_u64(v: u64) -> Self {
+        SlabKey(v)
+    }
+
+    /// Returns the key as a `u64`.
+    pub fn to_u64(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the index of the entry in the slab.
+    pub fn index(self) -> u32 {
+        self.0 as u32
+    }
+
+    /// Returns the generation of the entry when the key is created.
+    pub fn generation(self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+}
+
+impl fmt::Display for SlabKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}v{}", self.index(), self.generation())
+    }
+}
+
+/// The error returned by [`VSlab::get()`] and the other typed accessors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlabError {
+    /// Nothing is stored under the key, e.g., it is removed.
+    NotFound { key: SlabKey },
+
+    /// The `VBox` the key refers to is removed, and the entry is reused by
+    /// another `VBox`, which the key must not be used for.
+    Stale { key: SlabKey, current: SlabKey },
+
+    /// The stored `VBox` is not packed as the expected trait object.
+    TypeMismatch {
+        key: SlabKey,
+        expected: &'static str,
+        actual: &'static str,
+    },
+}
+
+impl fmt::Display for SlabError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlabError::NotFound { key } => {
+                write!(f, "nothing is stored under: {}", key)
+            }
+            SlabError::Stale { key, current } => {
+                write!(
+                    f,
+                    "{} is stale, the entry is reused by: {}",
+                    key, current
+                )
+            }
+            SlabError::TypeMismatch {
+                key,
+                expected,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "{} is stored as: {}, expected: {}",
+                    key, actual, expected
+                )
+            }
+        }
+    }
+}
+
+impl Error for SlabError {}
+
+struct Entry {
+    generation: u32,
+    vbox: Option<VBox>,
+}
+
+/// A slab that owns [`VBox`]es and hands out [`SlabKey`]s to them, so that a
+/// system refers to an erased object in messages by a small key instead of
+/// moving the `VBox` around.
+///
+/// A key is checked against the generation of the entry when it is used, and
+/// the trait object is checked as [`from_vbox!`](crate::from_vbox) does. A key
+/// kept after its `VBox` is removed gets [`SlabError::Stale`] once the entry is
+/// reused, instead of the `VBox` stored later.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{into_vbox, VSlab};
+/// let mut slab = VSlab::new();
+/// let key = slab.insert(into_vbox!(dyn Debug, 1u64));
+///
+/// assert_eq!("1", format!("{:?}", slab.get::<dyn Debug>(key).unwrap()));
+///
+/// let vbox = slab.remove(key).unwrap();
+/// assert!(slab.get::<dyn Debug>(key).is_err());
+/// # drop(vbox);
+/// ```
+#[derive(Default)]
+pub struct VSlab {
+    entries: Vec<Entry>,
+
+    /// Indexes of the vacant entries.
+    free: Vec<u32>,
+}
+
+impl VSlab {
+    /// Create an empty slab.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of stored `VBox`es.
+    pub fn len(&self) -> usize {
+        self.entries.len() - self.free.len()
+    }
+
+    /// Returns `true` if there is no `VBox` stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Store a `VBox` and return the key to it.
+    ///
+    /// # Panics
+    ///
+    /// It panics if more than `u32::MAX` `VBox`es are stored at the same time.
+    pub fn insert(&mut self, vbox: VBox) -> SlabKey {
+        if let Some(index) = self.free.pop() {
+            let entry = &mut self.entries[index as usize];
+            entry.vbox = Some(vbox);
+            return SlabKey::new(index, entry.generation);
+        }
+
+        let index = u32::try_from(self.entries.len()).expect("VSlab is full");
+        self.entries.push(Entry {
+            generation: 0,
+            vbox: Some(vbox),
+        });
+        SlabKey::new(index, 0)
+    }
+
+    /// Returns `true` if a `VBox` is stored under the key.
+    pub fn contains(&self, key: SlabKey) -> bool {
+        self.get_vbox(key).is_some()
+    }
+
+    /// Borrow the `VBox` stored under the key, if any.
+    pub fn get_vbox(&self, key: SlabKey) -> Option<&VBox> {
+        let entry = self.entries.get(key.index() as usize)?;
+        if entry.generation != key.generation() {
+            return None;
+        }
+        entry.vbox.as_ref()
+    }
+
+    /// Mutably borrow the `VBox` stored under the key, if any.
+    pub fn get_vbox_mut(&mut self, key: SlabKey) -> Option<&mut VBox> {
+        let entry = self.entries.get_mut(key.index() as usize)?;
+        if entry.generation != key.generation() {
+            return None;
+        }
+        entry.vbox.as_mut()
+    }
+
+    /// Borrow the payload stored under the key as `&dyn Trait`.
+    pub fn get<U>(&self, key: SlabKey) -> Result<&U, SlabError>
+    where U: ?Sized + 'static {
+        let vbox = self.lookup(key)?;
+        check_type::<U>(key, vbox)?;
+        Ok(vbox.as_dyn::<U>())
+    }
+
+    /// Borrow the payload stored under the key as `&mut dyn Trait`.
+    pub fn get_mut<U>(&mut self, key: SlabKey) -> Result<&mut U, SlabError>
+    where U: ?Sized + 'static {
+        self.lookup(key)?;
+        let vbox = self.get_vbox_mut(key).unwrap();
+        check_type::<U>(key, vbox)?;
+        Ok(vbox.as_dyn_mut::<U>())
+    }
+
+    /// Remove the `VBox` stored under the key, if any.
+    ///
+    /// The key, and any copy of it, no longer refers to anything.
+    pub fn remove(&mut self, key: SlabKey) -> Option<VBox> {
+        let entry = self.entries.get_mut(key.index() as usize)?;
+        if entry.generation != key.generation() {
+            return None;
+        }
+
+        let vbox = entry.vbox.take()?;
+        entry.generation = entry.generation.wrapping_add(1);
+        self.free.push(key.index());
+        Some(vbox)
+    }
+
+    /// Remove the `VBox` stored under the key and unpack it as `dyn Trait`.
+    ///
+    /// If it is not packed as `dyn Trait`, it is left in the slab.
+    pub fn remove_as<U>(&mut self, key: SlabKey) -> Result<Box<U>, SlabError>
+    where U: ?Sized + 'static {
+        let vbox = self.lookup(key)?;
+        check_type::<U>(key, vbox)?;
+
+        let vbox = self.remove(key).unwrap();
+        Ok(vbox.unpack::<U>())
+    }
+
+    /// Borrow the `VBox` stored under the key, or tell why there is none.
+    fn lookup(&self, key: SlabKey) -> Result<&VBox, SlabError> {
+        if let Some(vbox) = self.get_vbox(key) {
+            return Ok(vbox);
+        }
+
+        match self.entries.get(key.index() as usize) {
+            Some(Entry {
+                generation,
+                vbox: Some(_),
+            }) if is_older(key.generation(), *generation) => {
+                Err(SlabError::Stale {
+                    key,
+                    current: SlabKey::new(key.index(), *generation),
+                })
+            }
+            _ => Err(SlabError::NotFound { key }),
+        }
+    }
+
+    /// Iterate over the keys and the stored `VBox`es.
+    pub fn iter(&self) -> impl Iterator<Item = (SlabKey, &VBox)> + '_ {
+        self.entries.iter().enumerate().filter_map(|(i, e)| {
+            let vbox = e.vbox.as_ref()?;
+            Some((SlabKey::new(i as u32, e.generation), vbox))
+        })
+    }
+}
+
+/// Returns `true` if generation `a` is before `b`, taking the wrap around into
+/// account: `b` is less than 2^31 generations after `a`.
+fn is_older(a: u32, b: u32) -> bool {
+    (b.wrapping_sub(a) as i32) > 0
+}
+
+fn check_type<U>(key: SlabKey, vbox: &VBox) -> Result<(), SlabError>
+where U: ?Sized + 'static {
+    if vbox.is_dyn::<U>() {
+        return Ok(());
+    }
+
+    Err(SlabError::TypeMismatch {
+        key,
+        expected: std::any::type_name::<U>(),
+        actual: (vbox.type_name)(),
+    })
+}
+
+impl fmt::Debug for VSlab {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
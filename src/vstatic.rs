@@ -0,0 +1,272 @@
+use std::any::TypeId;
+use std::fmt;
+use std::mem;
+
+/// A type erased `&'static dyn Trait` that can be built in a const context.
+///
+/// It stores the data pointer and the vtable pointer, both of which are
+/// captured at compile time. It is `Copy` and never allocates, e.g., for
+/// interrupt tables and routing arrays of statically known handlers.
+///
+/// Use [`vstatic!`](macro@crate::vstatic) to build it, and [`VStatic::get()`]
+/// to get the trait object back.
+///
+/// ```
+/// # use vbox::{vstatic, VStatic};
+/// trait Handler {
+///     fn handle(&self) -> u64;
+/// }
+///
+/// struct Ping;
+/// impl Handler for Ping {
+///     fn handle(&self) -> u64 {
+///         1
+///     }
+/// }
+///
+/// static HANDLER: VStatic = vstatic!(dyn Handler, &Ping);
+///
+/// let h: &'static dyn Handler = HANDLER.get::<dyn Handler>();
+/// assert_eq!(1, h.handle());
+/// ```
+#[derive(Clone, Copy)]
+pub struct VStatic {
+    data: *const (),
+    vtable: *const (),
+
+    /// Returns the type id of `dyn Trait`, to check the type to unpack.
+    ///
+    /// `TypeId::of()` is not const, the function is called when unpacking.
+    type_id: fn() -> TypeId,
+}
+
+/// A `VStatic` can only be built from a `Sync` payload.
+unsafe impl Send for VStatic {}
+unsafe impl Sync for VStatic {}
+
+/// Reinterpret a `&dyn Trait` as the data pointer and the vtable pointer.
+union Repr<U: ?Sized + 'static> {
+    reference: &'static U,
+    parts: (*const (), *const ()),
+}
+
+impl VStatic {
+    /// Create a new VStatic. Do not use it directly. Use
+    /// [`vstatic!`](macro@crate::vstatic) instead.
+    ///
+    /// # Safety
+    ///
+    /// The value `reference` points to must be `Sync`.
+    pub const unsafe fn new<U>(reference: &'static U) -> Self
+    where U: ?Sized + 'static {
+        assert!(
+            mem::size_of::<&U>() == mem::size_of::<(*const (), *const ())>(),
+            "expect a trait object"
+        );
+
+        let (data, vtable) = Repr { reference }.parts;
+        VStatic {
+            data,
+            vtable,
+            type_id: TypeId::of::<U>,
+        }
+    }
+
+    /// Rebuild the original `&'static dyn Trait`.
+    ///
+    /// It panics if `U` is not the `dyn Trait` it is built from. It is checked
+    /// in release builds too, since the vtable would be misinterpreted.
+    pub fn get<U>(&self) -> &'static U
+    where U: ?Sized + 'static {
+        assert_eq!(
+            TypeId::of::<U>(),
+            (self.type_id)(),
+            "expected type_id: {:?}, actual type_id: {:?}",
+            TypeId::of::<U>(),
+            (self.type_id)()
+        );
+
+        let parts = (self.data, self.vtable);
+        unsafe { Repr::<U> { parts }.reference }
+    }
+}
+
+impl fmt::Debug for VStatic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VStatic")
+            .field("vtable", &self.vtable)
+            .field("type_id", &(self.type_id)())
+            .finish()
+    }
+}
+
+/// A type erased `&'static dyn Trait` of two words, for dispatch tables built
+/// at startup and passed by value through hot paths.
+///
+/// It is like [`VStatic`] but stores only the data pointer and the vtable
+/// pointer. Since the type is not checked in release builds,
+/// [`ErasedStaticRef::get()`] is unsafe. The type id of `dyn Trait` is stored
+/// in debug builds only, to check it. It is `Copy`, `Send` and `Sync`, and
+/// never drops anything.
+///
+/// Use [`erased_static_ref!`](crate::erased_static_ref) to build it.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{erased_static_ref, ErasedStaticRef};
+/// static NUMBER: ErasedStaticRef = erased_static_ref!(dyn Debug, &10u64);
+///
+/// let name: &'static String = Box::leak(Box::new("foo".to_string()));
+/// let table = [NUMBER, erased_static_ref!(dyn Debug, name)];
+///
+/// // Safe: built from `dyn Debug`.
+/// let name = unsafe { table[1].get::<dyn Debug>() };
+/// assert_eq!("\"foo\"", format!("{:?}", name));
+/// ```
+#[derive(Clone, Copy)]
+pub struct ErasedStaticRef {
+    data: *const (),
+    vtable: *const (),
+
+    #[cfg(debug_assertions)]
+    type_id: fn() -> TypeId,
+}
+
+/// An `ErasedStaticRef` can only be built from a `Sync` payload.
+unsafe impl Send for ErasedStaticRef {}
+unsafe impl Sync for ErasedStaticRef {}
+
+impl ErasedStaticRef {
+    /// Create a new ErasedStaticRef. Do not use it directly. Use
+    /// [`erased_static_ref!`](crate::erased_static_ref) instead.
+    ///
+    /// # Safety
+    ///
+    /// The value `reference` points to must be `Sync`.
+    pub const unsafe fn new<U>(reference: &'static U) -> Self
+    where U: ?Sized + 'static {
+        let v = VStatic::new::<U>(reference);
+        ErasedStaticRef {
+            data: v.data,
+            vtable: v.vtable,
+            #[cfg(debug_assertions)]
+            type_id: v.type_id,
+        }
+    }
+
+    /// Rebuild the original `&'static dyn Trait`.
+    ///
+    /// # Safety
+    ///
+    /// `U` must be the `dyn Trait` it is built from. It is checked in debug
+    /// builds only, to keep `ErasedStaticRef` two words. Use [`VStatic`] for a
+    /// checked one.
+    pub unsafe fn get<U>(self) -> &'static U
+    where U: ?Sized + 'static {
+        #[cfg(debug_assertions)]
+        assert_eq!(
+            TypeId::of::<U>(),
+            (self.type_id)(),
+            "expected type_id: {:?}, actual type_id: {:?}",
+            TypeId::of::<U>(),
+            (self.type_id)()
+        );
+
+        let parts = (self.data, self.vtable);
+        unsafe { Repr::<U> { parts }.reference }
+    }
+
+    /// Returns the data pointer.
+    pub fn data_ptr(self) -> *const () {
+        self.data
+    }
+
+    /// Returns the vtable pointer.
+    pub fn vtable_ptr(self) -> *const () {
+        self.vtable
+    }
+}
+
+impl From<VStatic> for ErasedStaticRef {
+    fn from(v: VStatic) -> Self {
+        ErasedStaticRef {
+            data: v.data,
+            vtable: v.vtable,
+            #[cfg(debug_assertions)]
+            type_id: v.type_id,
+        }
+    }
+}
+
+impl fmt::Debug for ErasedStaticRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErasedStaticRef")
+            .field("data", &self.data)
+            .field("vtable", &self.vtable)
+            .finish()
+    }
+}
+
+/// Create a [`VStatic`] from a `&'static T`, in a const context.
+///
+/// The concrete type `T` must be `Sync`.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{vstatic, VStatic};
+/// static ROUTES: [VStatic; 2] = [vstatic!(dyn Debug, &1u64), vstatic!(dyn Debug, &"two")];
+///
+/// assert_eq!("\"two\"", format!("{:?}", ROUTES[1].get::<dyn Debug>()));
+/// ```
+#[macro_export]
+macro_rules! vstatic {
+    (for<$($lt: lifetime),+> dyn $tr: path, $v: expr) => {
+        $crate::vstatic!(dyn for<$($lt),+> $tr, $v)
+    };
+
+    ($t: ty, $v: expr) => {{
+        const fn check_sync<T: ::std::marker::Sync + 'static>(
+            v: &'static T,
+        ) -> &'static T {
+            v
+        }
+
+        let checked = check_sync($v);
+        let reference: &'static $t = checked;
+        unsafe { $crate::VStatic::new::<$t>(reference) }
+    }};
+}
+
+/// Create an [`ErasedStaticRef`] from a `&'static T`, in a const context or at
+/// runtime.
+///
+/// The concrete type `T` must be `Sync`.
+///
+/// ```
+/// # use std::fmt::Display;
+/// # use vbox::{erased_static_ref, ErasedStaticRef};
+/// const NAMES: [ErasedStaticRef; 2] =
+///     [erased_static_ref!(dyn Display, &"foo"), erased_static_ref!(dyn Display, &2u8)];
+///
+/// let got: Vec<_> =
+///     NAMES.iter().map(|n| unsafe { n.get::<dyn Display>() }.to_string()).collect();
+/// assert_eq!(vec!["foo", "2"], got);
+/// ```
+#[macro_export]
+macro_rules! erased_static_ref {
+    (for<$($lt: lifetime),+> dyn $tr: path, $v: expr) => {
+        $crate::erased_static_ref!(dyn for<$($lt),+> $tr, $v)
+    };
+
+    ($t: ty, $v: expr) => {{
+        const fn check_sync<T: ::std::marker::Sync + 'static>(
+            v: &'static T,
+        ) -> &'static T {
+            v
+        }
+
+        let checked = check_sync($v);
+        let reference: &'static $t = checked;
+        unsafe { $crate::ErasedStaticRef::new::<$t>(reference) }
+    }};
+}
@@ -0,0 +1,164 @@
+use std::any::TypeId;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+
+use crate::from_raw_parts;
+use crate::new_box;
+use crate::VBox;
+
+/// The vtable of a concrete type `T` as `dyn Trait`, captured at compile time.
+///
+/// It is built once with [`static_vtable!`](crate::static_vtable), e.g., into
+/// a `static`, and combined with payloads of type `T` at runtime by
+/// [`VTable::pack()`], without coercing `Box<T>` to `Box<dyn Trait>` for
+/// each pack.
+///
+/// ```
+/// # use std::fmt::Debug;
+/// # use vbox::{from_vbox, static_vtable, VTable};
+/// static DEBUG_U64: VTable<dyn Debug> = static_vtable!(dyn Debug, u64);
+///
+/// let vbox = DEBUG_U64.pack(3u64);
+/// assert!(DEBUG_U64.is_vtable_of(&vbox));
+/// assert_eq!("3", format!("{:?}", from_vbox!(dyn Debug, vbox)));
+/// ```
+pub struct VTable<U: ?Sized + 'static> {
+    vtable: *const (),
+
+    /// Type id of the concrete type `T`.
+    ///
+    /// `TypeId::of()` is not const, the function is called when packing.
+    concrete_type_id: fn() -> TypeId,
+
+    /// Name of the concrete type `T`.
+    concrete_type_name: fn() -> &'static str,
+
+    _p: PhantomData<fn() -> *const U>,
+}
+
+/// A vtable pointer is immutable and refers to static memory.
+unsafe impl<U: ?Sized + 'static> Send for VTable<U> {}
+unsafe impl<U: ?Sized + 'static> Sync for VTable<U> {}
+
+impl<U: ?Sized + 'static> Clone for VTable<U> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<U: ?Sized + 'static> Copy for VTable<U> {}
+
+/// Reinterpret a `*const dyn Trait` as the data pointer and the vtable pointer.
+union Repr<U: ?Sized + 'static> {
+    ptr: *const U,
+    parts: (*const (), *const ()),
+}
+
+impl<U: ?Sized + 'static> VTable<U> {
+    /// Create a new VTable. Do not use it directly. Use
+    /// [`static_vtable!`](crate::static_vtable) instead.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a `*const T` coerced to `*const dyn Trait`.
+    pub const unsafe fn new<T>(ptr: *const U) -> Self
+    where T: Send + 'static {
+        assert!(
+            mem::size_of::<*const U>()
+                == mem::size_of::<(*const (), *const ())>(),
+            "expect a trait object"
+        );
+
+        let (_data, vtable) = Repr { ptr }.parts;
+        VTable {
+            vtable,
+            concrete_type_id: TypeId::of::<T>,
+            concrete_type_name: std::any::type_name::<T>,
+            _p: PhantomData,
+        }
+    }
+
+    /// Pack `value` into a [`VBox`] as `dyn Trait`, with the captured vtable.
+    ///
+    /// # Panics
+    ///
+    /// It panics if `T` is not the concrete type the vtable is captured for.
+    #[cfg_attr(
+        any(
+            feature = "log",
+            feature = "debug-unconsumed",
+            feature = "timeline"
+        ),
+        track_caller
+    )]
+    pub fn pack<T>(&self, value: T) -> VBox
+    where T: Send + 'static {
+        assert_eq!(
+            (self.concrete_type_id)(),
+            TypeId::of::<T>(),
+            "expected concrete type: {}, actual: {}",
+            (self.concrete_type_name)(),
+            std::any::type_name::<T>()
+        );
+
+        let data = Box::into_raw(new_box(value)) as *mut ();
+        unsafe {
+            let fat_ptr = from_raw_parts::<U>(data, self.vtable as usize);
+            VBox::from_box_unchecked(
+                Box::from_raw(fat_ptr),
+                Some(TypeId::of::<T>()),
+                Some(std::any::type_name::<T>),
+            )
+        }
+    }
+
+    /// Returns the vtable pointer.
+    pub fn as_ptr(&self) -> *const () {
+        self.vtable
+    }
+
+    /// Returns `true` if `vbox` is packed as `dyn Trait` with this vtable.
+    ///
+    /// It is informational only, as is [`VBox::vtable_ptr()`]: the same
+    /// implementation may have different vtable pointers in different codegen
+    /// units.
+    pub fn is_vtable_of(&self, vbox: &VBox) -> bool {
+        vbox.is_dyn::<U>() && vbox.vtable_ptr() == self.vtable
+    }
+}
+
+impl<U: ?Sized + 'static> fmt::Debug for VTable<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VTable")
+            .field("vtable", &self.vtable)
+            .field("type_name", &std::any::type_name::<U>())
+            .field("concrete_type_name", &(self.concrete_type_name)())
+            .finish()
+    }
+}
+
+/// Capture the vtable of a concrete type `T` as `dyn Trait` into a [`VTable`],
+/// in a const context.
+///
+/// The concrete type `T` must be `Send`.
+///
+/// ```
+/// # use std::fmt::Display;
+/// # use vbox::{from_vbox, static_vtable, VTable};
+/// const DISPLAY_STR: VTable<dyn Display> = static_vtable!(dyn Display, &'static str);
+///
+/// let vbox = DISPLAY_STR.pack("foo");
+/// assert_eq!("foo", from_vbox!(dyn Display, vbox).to_string());
+/// ```
+#[macro_export]
+macro_rules! static_vtable {
+    (for<$($lt: lifetime),+> dyn $tr: path, $T: ty) => {
+        $crate::static_vtable!(dyn for<$($lt),+> $tr, $T)
+    };
+
+    ($t: ty, $T: ty) => {{
+        let ptr: *const $t = ::std::ptr::null::<$T>();
+        unsafe { $crate::VTable::<$t>::new::<$T>(ptr) }
+    }};
+}
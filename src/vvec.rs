@@ -0,0 +1,179 @@
+use std::fmt;
+use std::slice;
+use std::vec;
+
+use crate::VBox;
+
+/// A homogeneous column of [`VBox`]es: every element is of the same concrete
+/// type, packed as the same `dyn Trait`, see [`VBox::same_impl()`].
+///
+/// The implementation is fixed by the first element and checked for the
+/// rest. It can be built with `collect()`, and extended with both `VBox`es
+/// and concrete values, which are packed the same way as the first element
+/// without naming `dyn Trait` again.
+///
+/// ```
+/// # use std::fmt::Display;
+/// # use vbox::{from_vbox_vec, into_vbox, VVec};
+/// let mut col: VVec = (0..3u64).map(|i| into_vbox!(dyn Display, i)).collect();
+///
+/// col.extend([into_vbox!(dyn Display, 3u64)]);
+/// col.extend_values([4u64, 5]);
+/// assert_eq!(6, col.len());
+///
+/// let displays = from_vbox_vec!(dyn Display, col.into_vec());
+/// assert_eq!("5", displays[5].to_string());
+/// ```
+#[derive(Default)]
+pub struct VVec {
+    items: Vec<VBox>,
+}
+
+impl VVec {
+    /// Create an empty column, whose implementation is fixed by the first
+    /// element pushed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Push a `VBox`, or return it in `Err` if it is not of the same
+    /// implementation as the elements in the column.
+    pub fn push(&mut self, vbox: VBox) -> Result<(), VBox> {
+        if let Some(first) = self.items.first() {
+            if !first.same_impl(&vbox) {
+                return Err(vbox);
+            }
+        }
+        self.items.push(vbox);
+        Ok(())
+    }
+
+    /// Pack a value the same way as the elements in the column and push it,
+    /// or return it in `Err` if it is not of their concrete type, or the
+    /// column is empty.
+    pub fn push_value<T>(&mut self, value: T) -> Result<(), T>
+    where T: Send + 'static {
+        let Some(first) = self.items.first() else {
+            return Err(value);
+        };
+        let vbox = first.pack_like(value)?;
+        self.items.push(vbox);
+        Ok(())
+    }
+
+    /// Pack values the same way as the elements in the column and push them.
+    ///
+    /// It panics if the column is empty, or a value is not of the concrete
+    /// type of the elements.
+    pub fn extend_values<T>(&mut self, values: impl IntoIterator<Item = T>)
+    where T: Send + 'static {
+        let first =
+            self.items.first().expect("VVec::extend_values() on an empty VVec");
+
+        let packed = values
+            .into_iter()
+            .map(|v| match first.pack_like(v) {
+                Ok(vbox) => vbox,
+                Err(_) => panic!(
+                    "VVec of {} can not hold {}",
+                    first.impl_name(),
+                    std::any::type_name::<T>()
+                ),
+            })
+            .collect::<Vec<_>>();
+
+        self.items.extend(packed);
+    }
+
+    /// Removes and returns the last element.
+    pub fn pop(&mut self) -> Option<VBox> {
+        self.items.pop()
+    }
+
+    /// Returns the element at `index`.
+    pub fn get(&self, index: usize) -> Option<&VBox> {
+        self.items.get(index)
+    }
+
+    /// Returns an iterator over the elements.
+    pub fn iter(&self) -> slice::Iter<'_, VBox> {
+        self.items.iter()
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if there is no element.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Consume the column and return the elements, e.g., to unpack them all
+    /// with [`from_vbox_vec!`](crate::from_vbox_vec).
+    pub fn into_vec(self) -> Vec<VBox> {
+        self.items
+    }
+
+    fn push_or_panic(&mut self, vbox: VBox) {
+        if let Err(vbox) = self.push(vbox) {
+            panic!(
+                "VVec of {} can not hold {}",
+                self.items[0].impl_name(),
+                vbox.impl_name()
+            );
+        }
+    }
+}
+
+/// Collect `VBox`es of the same implementation.
+///
+/// It panics if an element is not of the same implementation as the first
+/// one.
+impl FromIterator<VBox> for VVec {
+    fn from_iter<I: IntoIterator<Item = VBox>>(iter: I) -> Self {
+        let mut col = VVec::new();
+        col.extend(iter);
+        col
+    }
+}
+
+/// Push `VBox`es of the same implementation as the elements in the column.
+///
+/// It panics if an element is not of the same implementation.
+impl Extend<VBox> for VVec {
+    fn extend<I: IntoIterator<Item = VBox>>(&mut self, iter: I) {
+        for vbox in iter {
+            self.push_or_panic(vbox);
+        }
+    }
+}
+
+impl IntoIterator for VVec {
+    type Item = VBox;
+    type IntoIter = vec::IntoIter<VBox>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a VVec {
+    type Item = &'a VBox;
+    type IntoIter = slice::Iter<'a, VBox>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl fmt::Debug for VVec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("VVec");
+        if let Some(first) = self.items.first() {
+            d.field("impl", &first.impl_name());
+        }
+        d.field("len", &self.items.len()).finish()
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt::Debug;
+use std::sync::mpsc;
+use std::thread;
+
+use vbox::actor_message;
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::Envelope;
+use vbox::VBox;
+
+/// The message traits of a minimal actor framework, in the style of actix.
+mod framework {
+    pub trait Message: Send + 'static {
+        type Result: Send + 'static;
+    }
+
+    pub trait Handler<M: Message> {
+        fn handle(&mut self, msg: M) -> M::Result;
+    }
+
+    /// Another trait of the framework, without associated items.
+    pub trait Traced {
+        fn trace_id(&self) -> u64 {
+            0
+        }
+    }
+}
+
+use framework::Handler;
+use framework::Message;
+
+actor_message! {
+    /// A type-erased command.
+    pub struct Command(VBox);
+
+    impl Message {
+        type Result = String;
+    }
+
+    impl framework::Traced {
+        fn trace_id(&self) -> u64 {
+            7
+        }
+    }
+}
+
+actor_message! {
+    struct Request(Envelope);
+
+    impl Message {
+        type Result = ();
+    }
+}
+
+struct Printer;
+
+impl Handler<Command> for Printer {
+    fn handle(&mut self, msg: Command) -> String {
+        format!("{:?}", from_vbox!(dyn Debug + Send, msg.into_inner()))
+    }
+}
+
+impl Handler<Request> for Printer {
+    fn handle(&mut self, msg: Request) {
+        let req = msg.into_inner();
+        let body = from_vbox!(dyn Debug + Send, req.body);
+        let resp = into_vbox!(dyn Debug + Send, format!("re: {:?}", body));
+        req.reply_to.unwrap().send(resp).unwrap();
+    }
+}
+
+/// Run the handler in another thread, as an actor does.
+fn send<M>(msg: M) -> M::Result
+where
+    M: Message,
+    Printer: Handler<M>,
+{
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || tx.send(Printer.handle(msg)).unwrap());
+    rx.recv().unwrap()
+}
+
+#[test]
+fn test_actor_message_vbox() {
+    let cmd = Command::from(into_vbox!(dyn Debug + Send, 3u64));
+    assert!(cmd.is::<u64>());
+    assert_eq!(7, framework::Traced::trace_id(&cmd));
+    assert!(format!("{:?}", cmd).starts_with("Command(VBox"));
+
+    assert_eq!("3", send(cmd));
+}
+
+#[test]
+fn test_actor_message_envelope() {
+    let (env, rx) = Envelope::request(into_vbox!(dyn Debug + Send, "ping"));
+    let req = Request::from(env);
+    assert!(req.reply_to.is_some());
+
+    send(req);
+
+    let resp = rx.recv().unwrap();
+    assert_eq!(
+        "\"re: \\\"ping\\\"\"",
+        format!("{:?}", from_vbox!(dyn Debug + Send, resp))
+    );
+}
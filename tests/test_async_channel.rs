@@ -0,0 +1,77 @@
+#![cfg(feature = "async-channel")]
+
+use std::fmt::Debug;
+use std::fmt::Display;
+
+use futures::executor::block_on;
+use vbox::async_channel;
+use vbox::async_channel::TryRecvError;
+use vbox::async_channel::TrySendError;
+
+#[test]
+fn test_async_channel_bounded() {
+    let (tx, rx) = async_channel::bounded::<dyn Display + Send>(1);
+
+    tx.try_send(Box::new(1u64)).unwrap();
+    let err = tx.try_send(Box::new("full")).unwrap_err();
+    let TrySendError::Full(msg) = err else {
+        panic!("expect Full");
+    };
+    assert_eq!("full", msg.to_string());
+    assert_eq!(1, tx.len());
+
+    assert_eq!("1", rx.try_recv().unwrap().to_string());
+    assert_eq!(Err(TryRecvError::Empty), rx.try_recv().map(|_| ()));
+
+    let t = std::thread::spawn(move || {
+        for i in 0..3u64 {
+            tx.send_blocking(Box::new(i)).unwrap();
+        }
+    });
+
+    let got: Vec<_> =
+        (0..3).map(|_| rx.recv_blocking().unwrap().to_string()).collect();
+    assert_eq!(vec!["0", "1", "2"], got);
+
+    t.join().unwrap();
+    assert!(rx.recv_blocking().is_err(), "all senders are dropped");
+}
+
+#[test]
+fn test_async_channel_unbounded_async() {
+    let (tx, rx) = async_channel::unbounded::<dyn Debug + Send>();
+
+    block_on(async {
+        tx.send(Box::new(1u64)).await.unwrap();
+        tx.send(Box::new("a")).await.unwrap();
+
+        assert_eq!("1", format!("{:?}", rx.recv().await.unwrap()));
+        assert_eq!(r#""a""#, format!("{:?}", rx.recv().await.unwrap()));
+
+        rx.close();
+        let err = tx.send(Box::new(2u64)).await.unwrap_err();
+        assert_eq!("2", format!("{:?}", err.0));
+    });
+}
+
+#[test]
+fn test_async_channel_erased() {
+    let (tx, rx) = async_channel::unbounded::<dyn Debug + Send>();
+
+    // Erased endpoints do not depend on the trait.
+    let erased_tx = tx.into_erased();
+    let erased_rx = rx.into_erased();
+
+    let tx = async_channel::Sender::<dyn Debug + Send>::from_erased(erased_tx);
+    let rx = async_channel::Receiver::<dyn Debug + Send>::from_erased(
+        erased_rx.clone(),
+    );
+
+    tx.send_blocking(Box::new(5u64)).unwrap();
+    assert_eq!("5", format!("{:?}", rx.recv_blocking().unwrap()));
+
+    tx.send_blocking(Box::new(6u64)).unwrap();
+    let vbox = erased_rx.recv_blocking().unwrap();
+    assert!(vbox.is_dyn::<dyn Debug + Send>());
+    assert_eq!("6", format!("{:?}", vbox.unpack::<dyn Debug + Send>()));
+}
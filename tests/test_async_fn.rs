@@ -0,0 +1,33 @@
+use std::any::Any;
+use std::thread;
+
+use futures::executor::block_on;
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::VAsyncFnOnce;
+
+#[test]
+fn test_async_fn_once_call() {
+    let base = 10u64;
+    let f = VAsyncFnOnce::new(move |x: u64| async move { base + x });
+
+    let got: u64 = block_on(f.call(5u64));
+    assert_eq!(15, got);
+}
+
+#[test]
+fn test_async_fn_once_in_message() {
+    let f =
+        VAsyncFnOnce::new(
+            |(a, b): (u64, u64)| async move { format!("{}", a * b) },
+        );
+    let vb = into_vbox!(dyn Any + Send, f);
+
+    let h = thread::spawn(move || {
+        let any = from_vbox!(dyn Any + Send, vb);
+        let f = any.downcast::<VAsyncFnOnce>().unwrap();
+        block_on(f.call::<_, String>((3u64, 4u64)))
+    });
+
+    assert_eq!("12", h.join().unwrap());
+}
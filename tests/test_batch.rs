@@ -0,0 +1,378 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use futures::executor::block_on;
+use vbox::into_vbox;
+use vbox::BatchPool;
+use vbox::Batcher;
+use vbox::ShutdownMode;
+use vbox::VBox;
+
+#[test]
+fn test_batcher_groups_by_vtable() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    let mut batcher = Batcher::new();
+
+    let s = seen.clone();
+    batcher.on::<dyn Debug + Send>(move |d| {
+        s.lock().unwrap().push(format!("debug {:?}", d))
+    });
+    let s = seen.clone();
+    batcher.on::<dyn Display + Send>(move |d| {
+        s.lock().unwrap().push(format!("display {}", d))
+    });
+
+    batcher.push(into_vbox!(dyn Debug + Send, 1u64));
+    batcher.push(into_vbox!(dyn Display + Send, 2u64));
+    batcher.push(into_vbox!(dyn Debug + Send, "x"));
+    batcher.push(into_vbox!(dyn Debug + Send, 3u64));
+    batcher.push(into_vbox!(dyn Display + Send, 4u64));
+
+    assert_eq!(5, batcher.len());
+    assert_eq!(5, batcher.flush());
+    assert!(batcher.is_empty());
+
+    assert_eq!(
+        vec![
+            "debug 1",
+            "debug 3",
+            "display 2",
+            "display 4",
+            r#"debug "x""#,
+        ],
+        *seen.lock().unwrap()
+    );
+
+    assert_eq!(0, batcher.flush());
+}
+
+#[test]
+fn test_batcher_unhandled() {
+    let mut batcher = Batcher::new();
+
+    // Dropped without a handler for unhandled messages
+    batcher.push(into_vbox!(dyn Debug + Send, 1u64));
+    assert_eq!(1, batcher.flush());
+
+    let unhandled = Arc::new(Mutex::new(Vec::new()));
+    let u = unhandled.clone();
+    batcher.on_unhandled(move |vbox: VBox| {
+        u.lock().unwrap().push(vbox.into_inner::<u64>().unwrap())
+    });
+
+    batcher.push(into_vbox!(dyn Debug + Send, 2u64));
+    batcher.push(into_vbox!(dyn Display + Send, 3u64));
+    assert_eq!(2, batcher.flush());
+
+    assert_eq!(vec![2, 3], *unhandled.lock().unwrap());
+}
+
+#[test]
+fn test_batch_pool() {
+    let debug_cnt = Arc::new(AtomicU64::new(0));
+    let display_sum = Arc::new(AtomicU64::new(0));
+
+    let (dc, ds) = (debug_cnt.clone(), display_sum.clone());
+    let pool = BatchPool::new(4, 8, move || {
+        let mut b = Batcher::new();
+
+        let dc = dc.clone();
+        b.on::<dyn Debug + Send>(move |_| {
+            dc.fetch_add(1, Ordering::Relaxed);
+        });
+
+        let ds = ds.clone();
+        b.on::<dyn Display + Send>(move |d| {
+            ds.fetch_add(
+                d.to_string().parse::<u64>().unwrap(),
+                Ordering::Relaxed,
+            );
+        });
+        b
+    });
+
+    for i in 0..100u64 {
+        if i % 2 == 0 {
+            pool.submit(into_vbox!(dyn Debug + Send, i)).unwrap();
+        } else {
+            pool.submit(into_vbox!(dyn Display + Send, i)).unwrap();
+        }
+    }
+    pool.shutdown();
+
+    assert_eq!(50, debug_cnt.load(Ordering::Relaxed));
+    assert_eq!(
+        (0..100u64).filter(|i| i % 2 == 1).sum::<u64>(),
+        display_sum.load(Ordering::Relaxed)
+    );
+}
+
+/// A pool of one worker taking one message at a time, whose handler reports
+/// the message to `handled` and then blocks until a permit is sent to the
+/// returned sender.
+fn gated_pool(handled: mpsc::Sender<u64>) -> (BatchPool, mpsc::Sender<()>) {
+    let (permit_tx, permit_rx) = mpsc::channel::<()>();
+    let permit_rx = Arc::new(Mutex::new(permit_rx));
+
+    let pool = BatchPool::new(1, 1, move || {
+        let handled = handled.clone();
+        let permit_rx = permit_rx.clone();
+        let mut b = Batcher::new();
+        b.on::<dyn Debug + Send>(move |d| {
+            handled.send(format!("{:?}", d).parse().unwrap()).unwrap();
+            let _ = permit_rx.lock().unwrap().recv();
+        });
+        b
+    });
+    (pool, permit_tx)
+}
+
+#[test]
+fn test_batch_pool_shutdown_finish_in_flight() {
+    let (handled_tx, handled_rx) = mpsc::channel();
+    let (pool, permit_tx) = gated_pool(handled_tx);
+
+    for i in 0..5u64 {
+        pool.submit(into_vbox!(dyn Debug + Send, i)).unwrap();
+    }
+
+    // The worker is in the middle of the first message.
+    assert_eq!(0, handled_rx.recv().unwrap());
+
+    let releaser = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        let _ = permit_tx.send(());
+    });
+
+    let queued = pool.shutdown_with(ShutdownMode::FinishInFlight);
+    releaser.join().unwrap();
+
+    let queued = queued
+        .into_iter()
+        .map(|v| v.into_inner::<u64>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(vec![1, 2, 3, 4], queued);
+
+    // The worker has exited without taking more.
+    assert_eq!(Vec::<u64>::new(), handled_rx.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_batch_pool_shutdown_abort() {
+    let (handled_tx, handled_rx) = mpsc::channel();
+    let (pool, permit_tx) = gated_pool(handled_tx);
+
+    for i in 0..3u64 {
+        pool.submit(into_vbox!(dyn Debug + Send, i)).unwrap();
+    }
+    assert_eq!(0, handled_rx.recv().unwrap());
+
+    // Returns while the worker is still blocked.
+    let queued = pool.shutdown_with(ShutdownMode::Abort);
+    assert_eq!(2, queued.len());
+    for v in queued {
+        v.discard();
+    }
+
+    permit_tx.send(()).unwrap();
+    assert_eq!(Vec::<u64>::new(), handled_rx.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_batch_pool_drain() {
+    let (handled_tx, handled_rx) = mpsc::channel();
+    let (pool, permit_tx) = gated_pool(handled_tx);
+
+    block_on(pool.drain());
+
+    for i in 0..3u64 {
+        pool.submit(into_vbox!(dyn Debug + Send, i)).unwrap();
+    }
+    assert_eq!(3, pool.pending());
+
+    let releaser = thread::spawn(move || {
+        for _ in 0..3 {
+            permit_tx.send(()).unwrap();
+        }
+    });
+
+    block_on(pool.drain());
+    assert_eq!(0, pool.pending());
+    assert_eq!(vec![0, 1, 2], handled_rx.try_iter().collect::<Vec<_>>());
+
+    releaser.join().unwrap();
+    assert!(pool.shutdown_with(ShutdownMode::FinishQueued).is_empty());
+}
+
+#[test]
+fn test_batch_pool_drain_repoll_does_not_grow_wakers() {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::Context;
+
+    use futures::task::ArcWake;
+
+    struct Task;
+
+    impl ArcWake for Task {
+        fn wake_by_ref(_task: &Arc<Self>) {}
+    }
+
+    let task = Arc::new(Task);
+    let waker = futures::task::waker(task.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let (handled_tx, handled_rx) = mpsc::channel();
+    let (pool, permit_tx) = gated_pool(handled_tx);
+    pool.submit(into_vbox!(dyn Debug + Send, 1u64)).unwrap();
+
+    {
+        let mut drain = pin!(pool.drain());
+
+        // Each waker kept by the pool holds a reference to the task.
+        for _ in 0..10 {
+            assert!(drain.as_mut().poll(&mut cx).is_pending());
+        }
+        assert_eq!(3, Arc::strong_count(&task));
+    }
+
+    permit_tx.send(()).unwrap();
+    block_on(pool.drain());
+    assert_eq!(vec![1], handled_rx.try_iter().collect::<Vec<_>>());
+    assert!(pool.shutdown_with(ShutdownMode::FinishQueued).is_empty());
+}
+
+#[test]
+fn test_batch_pool_drain_after_panic() {
+    #[derive(Debug)]
+    struct Boom;
+
+    let handled = Arc::new(AtomicU64::new(0));
+
+    let h = handled.clone();
+    let mut pool = BatchPool::new(2, 1, move || {
+        let mut b = Batcher::new();
+        let h = h.clone();
+        b.on::<dyn Debug + Send>(move |d| {
+            if format!("{:?}", d) == "Boom" {
+                panic!("boom");
+            }
+            h.fetch_add(1, Ordering::Relaxed);
+        });
+        b
+    });
+
+    // Everything goes to worker 0, which panics on the first message.
+    pool.pin_dyn::<dyn Debug + Send>(0);
+
+    pool.submit(into_vbox!(dyn Debug + Send, Boom)).unwrap();
+    let mut accepted = 0;
+    for i in 0..10u64 {
+        if pool.submit(into_vbox!(dyn Debug + Send, i)).is_ok() {
+            accepted += 1;
+        }
+    }
+
+    block_on(pool.drain());
+    assert_eq!(0, pool.pending());
+    assert_eq!(0, handled.load(Ordering::Relaxed));
+
+    // The accepted ones are orphaned, the later ones are rejected.
+    let orphaned = pool.take_orphaned();
+    assert_eq!(accepted, orphaned.len());
+    assert!(pool.take_orphaned().is_empty());
+
+    let rejected = pool.submit(into_vbox!(dyn Debug + Send, 0u64));
+    assert!(rejected.is_err());
+
+    pool.shutdown();
+}
+
+#[test]
+fn test_batch_pool_pinning() {
+    use std::collections::HashSet;
+    use std::thread::ThreadId;
+
+    trait Storage: Send {
+        fn key(&self) -> u64;
+    }
+
+    struct Put(u64);
+    impl Storage for Put {
+        fn key(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[derive(Debug)]
+    struct Special;
+
+    let seen = Arc::new(Mutex::new(Vec::<(&'static str, ThreadId)>::new()));
+
+    let s = seen.clone();
+    let mut pool = BatchPool::new(4, 2, move || {
+        let mut b = Batcher::new();
+
+        let s1 = s.clone();
+        b.on::<dyn Storage>(move |st| {
+            let _ = st.key();
+            s1.lock().unwrap().push(("storage", thread::current().id()));
+        });
+
+        let s2 = s.clone();
+        b.on::<dyn Debug + Send>(move |d| {
+            let kind = if format!("{:?}", d) == "Special" {
+                "special"
+            } else {
+                "other"
+            };
+            s2.lock().unwrap().push((kind, thread::current().id()));
+            thread::sleep(Duration::from_millis(1));
+        });
+        b
+    });
+
+    pool.pin_dyn::<dyn Storage>(0);
+    pool.pin_type::<Special>(1);
+
+    let probe = into_vbox!(dyn Storage, Put(0));
+    assert_eq!(Some(0), pool.pinned_worker(&probe));
+    probe.discard();
+
+    for i in 0..40u64 {
+        pool.submit(into_vbox!(dyn Storage, Put(i))).unwrap();
+        pool.submit(into_vbox!(dyn Debug + Send, Special)).unwrap();
+        pool.submit(into_vbox!(dyn Debug + Send, i)).unwrap();
+    }
+    pool.shutdown();
+
+    let seen = seen.lock().unwrap();
+    let threads_of = |kind: &str| {
+        seen.iter()
+            .filter(|(k, _)| *k == kind)
+            .map(|(_, t)| *t)
+            .collect::<HashSet<_>>()
+    };
+
+    let storage = threads_of("storage");
+    let special = threads_of("special");
+    assert_eq!(1, storage.len());
+    assert_eq!(1, special.len());
+    assert_ne!(storage, special);
+    assert_eq!(120, seen.len());
+}
+
+#[test]
+#[should_panic(expected = "no such worker: 2")]
+fn test_batch_pool_pin_no_such_worker() {
+    let mut pool = BatchPool::new(2, 1, Batcher::new);
+    pool.pin_dyn::<dyn Debug + Send>(2);
+}
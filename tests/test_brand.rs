@@ -0,0 +1,73 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::sync::mpsc;
+use std::thread;
+
+use vbox::brand;
+use vbox::brand::Branded;
+use vbox::into_vbox;
+
+trait Command {
+    fn run(&self) -> u64;
+}
+
+struct Add(u64, u64);
+
+impl Command for Add {
+    fn run(&self) -> u64 {
+        self.0 + self.1
+    }
+}
+
+#[test]
+fn test_brand_pair() {
+    brand::pair::<dyn Command + Send, _>(|tx, rx| {
+        let msg = tx.pack(Box::new(Add(1, 2)));
+        assert_eq!(3, rx.as_dyn(&msg).run());
+        assert_eq!(3, rx.unpack(msg).run());
+    });
+}
+
+#[test]
+fn test_brand_existing_vbox() {
+    let got = brand::pair::<dyn Debug, _>(|tx, rx| {
+        let msg = tx.brand(into_vbox!(dyn Debug, 5u64)).unwrap();
+
+        let vb = tx.brand(into_vbox!(dyn Display, 6u64)).unwrap_err();
+        assert!(vb.is_dyn::<dyn Display>());
+
+        format!("{:?}", rx.unpack(msg))
+    });
+    assert_eq!("5", got);
+}
+
+#[test]
+fn test_brand_across_threads() {
+    let got = brand::pair::<dyn Command + Send, _>(|tx, rx| {
+        let (s, r) = mpsc::channel::<Branded<'_>>();
+
+        thread::scope(|scope| {
+            scope.spawn(move || {
+                for i in 0..3 {
+                    s.send(tx.pack(Box::new(Add(i, 10)))).unwrap();
+                }
+            });
+
+            scope
+                .spawn(move || {
+                    r.iter().map(|m| rx.unpack(m).run()).sum::<u64>()
+                })
+                .join()
+                .unwrap()
+        })
+    });
+    assert_eq!(33, got);
+}
+
+#[test]
+fn test_brand_into_vbox() {
+    let vb = brand::pair::<dyn Debug + Send, _>(|tx, _rx| {
+        tx.pack(Box::new("foo")).into_vbox()
+    });
+    assert!(vb.is_dyn::<dyn Debug + Send>());
+}
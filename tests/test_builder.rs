@@ -0,0 +1,129 @@
+use std::fmt;
+use std::fmt::Debug;
+use std::sync::mpsc;
+
+use futures::executor::block_on;
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::vbox_builder;
+use vbox::AsyncDispose;
+use vbox::BoxFuture;
+use vbox::RecordFields;
+use vbox::TypeCheck;
+use vbox::VBox;
+
+#[derive(Debug, Clone)]
+struct Put {
+    id: u64,
+    key: &'static str,
+}
+
+impl fmt::Display for Put {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "put {}={}", self.id, self.key)
+    }
+}
+
+impl RecordFields for Put {
+    fn record_fields(&self, record: &mut dyn FnMut(&str, &dyn Debug)) {
+        record("id", &self.id);
+        record("key", &self.key);
+    }
+}
+
+fn put(id: u64) -> Put {
+    Put { id, key: "k" }
+}
+
+#[test]
+fn test_builder_default() {
+    // Nothing captured, packed as `dyn Any + Send`
+    let vb = VBox::builder(put(1)).build();
+    assert_eq!(0, vb.tag());
+    assert_eq!(None, vb.debug_string());
+    assert_eq!(None, vb.display_string());
+    assert!(vb.try_clone().is_none());
+    assert!(!vb.has_fields());
+    assert!(!vb.is_disposable());
+
+    assert!(vb.same_impl(&into_vbox!(dyn std::any::Any + Send, put(2))));
+    assert_eq!(1, vb.into_inner::<Put>().unwrap().id);
+}
+
+#[test]
+fn test_builder_capabilities() {
+    let builder = vbox_builder!(dyn Debug + Send, put(1))
+        .with_debug()
+        .with_clone()
+        .with_display()
+        .with_fields()
+        .tag(7);
+    let vb = unsafe { builder.type_check(TypeCheck::TypeName) }.build();
+
+    assert_eq!(7, vb.tag());
+    assert_eq!(TypeCheck::TypeName, vb.type_check());
+    assert_eq!(
+        Some(r#"Put { id: 1, key: "k" }"#.to_string()),
+        vb.debug_string()
+    );
+    assert_eq!(Some("put 1=k".to_string()), vb.display_string());
+    assert_eq!(r#"id=1 key="k""#, vb.fields().to_string());
+    assert!(format!("{:?}", vb).contains("tag: 7"));
+
+    // The clone keeps the capabilities and the metadata
+    let copy = vb.try_clone().unwrap();
+    assert!(copy.same_impl(&vb));
+    assert_eq!(7, copy.tag());
+    assert_eq!(TypeCheck::TypeName, copy.type_check());
+    assert_eq!(Some("put 1=k".to_string()), copy.display_string());
+
+    let got = from_vbox!(dyn Debug + Send, copy);
+    assert_eq!(r#"Put { id: 1, key: "k" }"#, format!("{:?}", got));
+    let _ = from_vbox!(dyn Debug + Send, vb);
+
+    // Only the chosen ones
+    let vb = vbox_builder!(dyn Debug + Send, put(2)).with_display().build();
+    assert_eq!(None, vb.debug_string());
+    assert_eq!(Some("put 2=k".to_string()), vb.display_string());
+    assert!(vb.try_clone().is_none());
+    let _ = from_vbox!(dyn Debug + Send, vb);
+}
+
+#[test]
+fn test_builder_dispose() {
+    struct Conn(mpsc::Sender<&'static str>);
+
+    impl AsyncDispose for Conn {
+        fn dispose(self) -> BoxFuture<'static, ()> {
+            Box::pin(async move { self.0.send("disposed").unwrap() })
+        }
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let vb = VBox::builder(Conn(tx)).with_dispose().tag(3).build();
+    assert!(vb.is_disposable());
+    assert_eq!(3, vb.tag());
+
+    block_on(vb.dispose());
+    assert_eq!("disposed", rx.recv().unwrap());
+}
+
+#[test]
+fn test_tag() {
+    let mut vb = into_vbox!(dyn Debug + Send, 1u64);
+    assert_eq!(0, vb.tag());
+    assert!(!format!("{:?}", vb).contains("tag"));
+
+    vb.set_tag(5);
+    assert_eq!(5, vb.tag());
+
+    // Kept when the payload is replaced, and by raw parts
+    vbox::replace_vbox!(dyn Debug + Send, &mut vb, 2u64);
+    assert_eq!(5, vb.tag());
+    vbox::replace_vbox!(dyn Debug + Send, &mut vb, "a string");
+    assert_eq!(5, vb.tag());
+
+    let vb = unsafe { VBox::from_raw(vb.into_raw()) };
+    assert_eq!(5, vb.tag());
+    let _ = from_vbox!(dyn Debug + Send, vb);
+}
@@ -0,0 +1,75 @@
+use std::any::Any;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::CancelToken;
+use vbox::VBox;
+
+#[test]
+fn test_cancel_token_callbacks() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let token = CancelToken::new();
+
+    let l = log.clone();
+    token.on_cancel(move || l.lock().unwrap().push("a"));
+
+    let l = log.clone();
+    token
+        .register(into_vbox!(dyn FnOnce() + Send, move || {
+            l.lock().unwrap().push("b")
+        }))
+        .unwrap();
+
+    // Not packed as `dyn FnOnce() + Send`
+    let res = token.register(into_vbox!(dyn Fn() + Send, || {}));
+    assert!(res.unwrap_err().is_dyn::<dyn Fn() + Send>());
+
+    assert!(!token.is_canceled());
+    assert!(log.lock().unwrap().is_empty());
+
+    token.cancel();
+    assert!(token.is_canceled());
+    assert_eq!(vec!["a", "b"], *log.lock().unwrap());
+
+    // Cancel again has no effect
+    token.cancel();
+    assert_eq!(vec!["a", "b"], *log.lock().unwrap());
+
+    // Register after canceled runs at once
+    let l = log.clone();
+    token.on_cancel(move || l.lock().unwrap().push("c"));
+    assert_eq!(vec!["a", "b", "c"], *log.lock().unwrap());
+}
+
+#[test]
+fn test_cancel_token_in_message() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let token = CancelToken::new();
+
+    let vb: VBox = into_vbox!(dyn Any + Send, token.clone());
+
+    let l = log.clone();
+    let h = thread::spawn(move || {
+        let any = from_vbox!(dyn Any + Send, vb);
+        let token = any.downcast::<CancelToken>().unwrap();
+        token.on_cancel(move || l.lock().unwrap().push("downstream"));
+    });
+    h.join().unwrap();
+
+    token.cancel();
+    assert_eq!(vec!["downstream"], *log.lock().unwrap());
+}
+
+#[test]
+fn test_cancel_token_callback_accesses_token() {
+    let token = CancelToken::new();
+
+    let t = token.clone();
+    token.on_cancel(move || assert!(t.is_canceled()));
+
+    token.cancel();
+}
@@ -0,0 +1,81 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::into_vbox_dyn;
+use vbox::register_cast;
+use vbox::CastRegistry;
+use vbox::VBox;
+
+trait Named {
+    fn name(&self) -> String;
+}
+
+trait Counter {
+    fn incr(&mut self) -> u64;
+}
+
+#[derive(Debug)]
+struct Node {
+    id: u64,
+}
+
+impl Named for Node {
+    fn name(&self) -> String {
+        format!("node-{}", self.id)
+    }
+}
+
+impl Counter for Node {
+    fn incr(&mut self) -> u64 {
+        self.id += 1;
+        self.id
+    }
+}
+
+#[test]
+fn test_cast_registry_query() {
+    let registry = CastRegistry::new();
+    register_cast!(registry, Node => dyn Named);
+    register_cast!(registry, Node => dyn Counter);
+
+    let mut vb: VBox = into_vbox!(dyn Debug, Node { id: 1 });
+
+    assert!(registry.supports::<dyn Named>(&vb));
+    assert!(registry.supports::<dyn Counter>(&vb));
+    assert!(!registry.supports::<dyn Display>(&vb));
+
+    assert_eq!(
+        "node-1",
+        registry.cast_ref::<dyn Named>(&vb).unwrap().name()
+    );
+    assert_eq!(2, registry.cast_mut::<dyn Counter>(&mut vb).unwrap().incr());
+    assert!(registry.cast_ref::<dyn Display>(&vb).is_none());
+
+    let vb = registry.cast::<dyn Display>(vb).unwrap_err();
+    let vb = registry.cast::<dyn Named>(vb).unwrap();
+
+    let named = from_vbox!(dyn Named, vb);
+    assert_eq!("node-2", named.name());
+}
+
+#[test]
+fn test_cast_registry_unknown_concrete_type() {
+    let registry = CastRegistry::new();
+    register_cast!(registry, Node => dyn Named);
+
+    let b: Box<dyn Debug + Send> = Box::new(Node { id: 1 });
+    let vb = into_vbox_dyn!(dyn Debug + Send, b);
+
+    assert!(!registry.supports::<dyn Named>(&vb));
+}
+
+#[test]
+fn test_cast_registry_global() {
+    register_cast!(CastRegistry::global(), u64 => dyn Display);
+
+    let vb: VBox = into_vbox!(dyn Debug, 7u64);
+    let vb = CastRegistry::global().cast::<dyn Display>(vb).unwrap();
+    assert_eq!("7", from_vbox!(dyn Display, vb).to_string());
+}
@@ -0,0 +1,81 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::MismatchPolicy;
+use vbox::TypeCheck;
+use vbox::VBoxConfig;
+
+static MISMATCHES: AtomicU64 = AtomicU64::new(0);
+
+// The configuration is installed once per process, thus there is only one test
+// in this file.
+#[test]
+fn test_config_install() {
+    assert!(VBoxConfig::current().is_none());
+
+    // A mode set by the application is kept if the config does not set it.
+    unsafe { TypeCheck::set_global(TypeCheck::TypeName) };
+
+    VBoxConfig::new()
+        .mismatch_policy(MismatchPolicy::Panic)
+        .on_mismatch(|m| {
+            assert_eq!(
+                "dyn core::fmt::Display + core::marker::Send",
+                m.expected
+            );
+            assert_eq!("dyn core::fmt::Debug + core::marker::Send", m.actual);
+            MISMATCHES.fetch_add(1, Ordering::Relaxed);
+        })
+        .metrics(false)
+        .capture_location(false)
+        .recycle_capacity(8)
+        .timeline_capacity(16)
+        .install()
+        .unwrap();
+
+    let config = VBoxConfig::current().unwrap();
+    assert!(!config.is_metrics());
+    assert!(!config.is_capture_location());
+    assert_eq!(TypeCheck::TypeName, TypeCheck::global());
+    assert_eq!(MismatchPolicy::Panic, MismatchPolicy::global());
+    assert_eq!(
+        "VBoxConfig { type_check: None, mismatch_policy: Some(Panic), on_mismatch: true, metrics: false, capture_location: false, recycle_capacity: Some(8), timeline_capacity: Some(16) }",
+        format!("{:?}", config)
+    );
+
+    // Only once
+    assert!(VBoxConfig::new().install().is_err());
+
+    // The handler is called before the panic.
+    let vbox = into_vbox!(dyn Debug + Send, 1u64);
+    let res = std::panic::catch_unwind(move || {
+        let _ = from_vbox!(dyn Display + Send, vbox);
+    });
+    assert!(res.is_err());
+    assert_eq!(1, MISMATCHES.load(Ordering::Relaxed));
+
+    // The panic-free form does not call it.
+    let vbox = into_vbox!(dyn Debug + Send, 2u64);
+    let vbox =
+        vbox.try_unpack::<dyn Display + Send>().err().unwrap().into_inner();
+    assert_eq!(1, MISMATCHES.load(Ordering::Relaxed));
+    assert_eq!(2, vbox.into_inner::<u64>().unwrap());
+
+    #[cfg(feature = "recycle")]
+    assert_eq!(8, vbox::recycle::capacity());
+
+    #[cfg(feature = "timeline")]
+    assert_eq!(16, vbox::timeline::capacity());
+
+    #[cfg(feature = "stats")]
+    {
+        struct Untracked;
+        let vbox = into_vbox!(dyn std::any::Any + Send, Untracked);
+        vbox.discard();
+        assert_eq!(0, vbox::stats::of_type::<Untracked>().packed);
+    }
+}
@@ -0,0 +1,145 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::thread;
+
+use vbox::into_vbox;
+use vbox::DeadLetterQueue;
+use vbox::DeadLetterReason;
+use vbox::Router;
+use vbox::TaggedVBox;
+use vbox::VError;
+
+#[derive(Debug)]
+struct Invalid;
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid")
+    }
+}
+
+impl Error for Invalid {}
+
+#[test]
+fn test_dead_letter_from_dispatch_failures() {
+    let dlq = Arc::new(DeadLetterQueue::new(8));
+
+    let mut router = Router::new();
+    let d = dlq.clone();
+    router.on(1u8, move |vbox| {
+        if !vbox.is_dyn::<dyn Display + Send>() {
+            d.type_mismatch::<dyn Display + Send>(vbox);
+        }
+    });
+
+    let h = {
+        let dlq = dlq.clone();
+        thread::spawn(move || {
+            let msgs = [
+                TaggedVBox::new(1u8, into_vbox!(dyn Display + Send, 1u64)),
+                TaggedVBox::new(2u8, into_vbox!(dyn Display + Send, 2u64)),
+                TaggedVBox::new(1u8, into_vbox!(dyn Debug + Send, 3u64)),
+            ];
+            for msg in msgs {
+                if let Err(msg) = router.route(msg) {
+                    dlq.no_handler(msg.vbox);
+                }
+            }
+        })
+    };
+    h.join().unwrap();
+
+    // Rejected by validation.
+    let vbox = into_vbox!(dyn Debug + Send, "s");
+    if !vbox.is::<u64>() {
+        dlq.rejected(vbox, VError::new(Invalid));
+    }
+
+    assert_eq!(3, dlq.len());
+    assert_eq!(
+        vec![
+            "no handler".to_string(),
+            format!(
+                "packed as: {}, expected: {}",
+                std::any::type_name::<dyn Debug + Send>(),
+                std::any::type_name::<dyn Display + Send>()
+            ),
+            "rejected: invalid".to_string(),
+        ],
+        dlq.inspect(|l| l.reason.to_string())
+    );
+    assert_eq!(vec![0, 1, 2], dlq.inspect(|l| l.seq));
+
+    // Letters are intact.
+    let letters = dlq.drain();
+    assert!(dlq.is_empty());
+
+    let mut it = letters.into_iter();
+    let l = it.next().unwrap();
+    assert_eq!("2", l.vbox.as_dyn::<dyn Display + Send>().to_string());
+    l.vbox.discard();
+
+    let l = it.next().unwrap();
+    assert_eq!(3u64, l.vbox.into_inner::<u64>().unwrap());
+
+    let l = it.next().unwrap();
+    let DeadLetterReason::Rejected(e) = l.reason else {
+        panic!("expect Rejected");
+    };
+    assert!(e.find::<Invalid>().is_some());
+    assert_eq!("s", l.vbox.into_inner::<&str>().unwrap());
+}
+
+#[test]
+fn test_dead_letter_bounded() {
+    let dlq = DeadLetterQueue::new(2);
+
+    assert!(dlq.no_handler(into_vbox!(dyn Debug, 0u64)).is_none());
+    assert!(dlq.no_handler(into_vbox!(dyn Debug, 1u64)).is_none());
+
+    let evicted = dlq.no_handler(into_vbox!(dyn Debug, 2u64)).unwrap();
+    assert_eq!(0, evicted.seq);
+    assert_eq!(0u64, evicted.vbox.into_inner::<u64>().unwrap());
+
+    assert_eq!(2, dlq.len());
+    assert_eq!(3, dlq.pushed());
+    assert_eq!(1, dlq.evicted());
+
+    let l = dlq.pop().unwrap();
+    assert_eq!(1, l.seq);
+    l.vbox.discard();
+
+    for l in dlq.drain() {
+        l.vbox.discard();
+    }
+}
+
+#[test]
+fn test_dead_letter_dump() {
+    let dlq = DeadLetterQueue::new(4);
+    dlq.no_handler(into_vbox!(dyn Debug, 1u64));
+    dlq.type_mismatch::<dyn Display>(into_vbox!(dyn Debug, 2u8));
+
+    let dump = dlq.dump();
+    let entries = dump
+        .entries
+        .iter()
+        .map(|e| (e.type_name, e.tag.clone().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            ("u64", "no handler".to_string()),
+            (
+                "u8",
+                "packed as: dyn core::fmt::Debug, expected: dyn core::fmt::Display"
+                    .to_string()
+            ),
+        ],
+        entries
+    );
+    assert!(dump.entries.iter().all(|e| e.age.is_some()));
+    assert_eq!(2, dlq.len());
+}
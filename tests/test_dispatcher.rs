@@ -0,0 +1,69 @@
+use std::any::Any;
+use std::fmt::Debug;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::DispatchError;
+use vbox::Dispatcher;
+use vbox::HandlerError;
+use vbox::VBox;
+
+fn echo(args: VBox) -> Result<VBox, HandlerError> {
+    Ok(args)
+}
+
+#[test]
+fn test_dispatcher_dispatch() {
+    let mut dispatcher = Dispatcher::new();
+
+    dispatcher.register("len", |args| {
+        let s = args.into_inner::<String>().map_err(|_| "expect a String")?;
+        Ok(into_vbox!(dyn Debug, s.len()))
+    });
+    dispatcher
+        .register_vbox(
+            "echo",
+            into_vbox!(dyn Fn(VBox) -> Result<VBox, HandlerError> + Send, echo),
+        )
+        .unwrap();
+
+    // Not packed as a handler
+    let res = dispatcher.register_vbox("bad", VBox::unit());
+    assert!(res.unwrap_err().is_unit());
+
+    assert_eq!(vec!["echo", "len"], dispatcher.names());
+    assert!(dispatcher.contains("len"));
+
+    let res = dispatcher
+        .dispatch("len", into_vbox!(dyn Any + Send, "hello".to_string()))
+        .unwrap();
+    assert_eq!("5", format!("{:?}", from_vbox!(dyn Debug, res)));
+
+    let res = dispatcher.dispatch("echo", VBox::unit()).unwrap();
+    assert!(res.is_unit());
+
+    // Handler error
+    let res = dispatcher.dispatch("len", VBox::unit());
+    let err = res.unwrap_err();
+    assert!(matches!(err, DispatchError::Handler(_)));
+    assert_eq!("handler error: expect a String", err.to_string());
+
+    // Unregister
+    assert!(dispatcher.unregister("echo").is_some());
+    assert!(!dispatcher.contains("echo"));
+}
+
+#[test]
+fn test_dispatcher_not_found() {
+    let dispatcher = Dispatcher::new();
+
+    let res = dispatcher.dispatch("foo", VBox::unit());
+    let err = res.unwrap_err();
+    assert_eq!("no handler registered under: foo", err.to_string());
+
+    let DispatchError::NotFound { name, args } = err else {
+        panic!("expect NotFound");
+    };
+    assert_eq!("foo", name);
+    assert!(args.is_unit(), "args are returned");
+}
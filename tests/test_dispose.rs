@@ -0,0 +1,99 @@
+use std::fmt::Debug;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use futures::channel::oneshot;
+use futures::executor::block_on;
+use vbox::into_vbox;
+use vbox::into_vbox_disposable;
+use vbox::AsyncDispose;
+use vbox::BoxFuture;
+
+/// A resource whose cleanup waits for the peer to acknowledge.
+#[derive(Debug)]
+struct Conn {
+    id: u64,
+    #[allow(dead_code)]
+    ack: Arc<Mutex<Option<oneshot::Receiver<()>>>>,
+    log: mpsc::Sender<String>,
+}
+
+impl AsyncDispose for Conn {
+    fn dispose(self) -> BoxFuture<'static, ()> {
+        Box::pin(async move {
+            let ack = self.ack.lock().unwrap().take().unwrap();
+            ack.await.unwrap();
+            self.log.send(format!("disposed {}", self.id)).unwrap();
+        })
+    }
+}
+
+impl Drop for Conn {
+    fn drop(&mut self) {
+        self.log.send(format!("dropped {}", self.id)).unwrap();
+    }
+}
+
+fn conn(id: u64, log: mpsc::Sender<String>) -> (Conn, oneshot::Sender<()>) {
+    let (tx, rx) = oneshot::channel();
+    let c = Conn {
+        id,
+        ack: Arc::new(Mutex::new(Some(rx))),
+        log,
+    };
+    (c, tx)
+}
+
+#[test]
+fn test_dispose_async() {
+    let (log_tx, log_rx) = mpsc::channel();
+    let (c, ack) = conn(1, log_tx);
+
+    let vbox = into_vbox_disposable!(dyn Debug + Send, c);
+    assert!(vbox.is_disposable());
+
+    let fu = vbox.dispose();
+    assert!(log_rx.try_recv().is_err(), "nothing before the future runs");
+
+    ack.send(()).unwrap();
+    block_on(fu);
+
+    // Dropped after the async cleanup, which owns the value.
+    assert_eq!(
+        vec!["disposed 1", "dropped 1"],
+        log_rx.try_iter().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_dispose_sync_drop_fallback() {
+    let (log_tx, log_rx) = mpsc::channel();
+    let (c, _ack) = conn(2, log_tx);
+
+    let vbox = into_vbox_disposable!(dyn Debug + Send, c);
+    drop(vbox);
+
+    assert_eq!(vec!["dropped 2"], log_rx.try_iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_dispose_without_finalizer() {
+    let vbox = into_vbox!(dyn Debug + Send, 1u64);
+    assert!(!vbox.is_disposable());
+    block_on(vbox.dispose());
+}
+
+#[test]
+fn test_dispose_survives_raw_round_trip() {
+    let (log_tx, log_rx) = mpsc::channel();
+    let (c, ack) = conn(3, log_tx);
+
+    let vbox = into_vbox_disposable!(dyn Debug + Send, c);
+    let vbox = unsafe { vbox::VBox::from_raw(vbox.into_raw()) };
+    assert!(vbox.is_disposable());
+
+    ack.send(()).unwrap();
+    block_on(vbox.dispose());
+    assert_eq!("disposed 3", log_rx.recv().unwrap());
+}
@@ -0,0 +1,141 @@
+use std::fmt::Debug;
+use std::thread;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::Canceled;
+use vbox::Envelope;
+use vbox::VBox;
+
+#[test]
+fn test_envelope_request_response() {
+    let (req, rx) = Envelope::request(into_vbox!(dyn Debug, 3u64));
+    let req = req.with_meta("from", "test");
+
+    let id = req.id;
+    assert!(req.expects_reply());
+    assert_eq!(Some("test"), req.meta.get("from").map(|s| s.as_str()));
+
+    let h = thread::spawn(move || {
+        let mut req = req;
+        let body = std::mem::replace(&mut req.body, VBox::unit());
+        let n = body.into_inner::<u64>().unwrap();
+
+        req.reply(into_vbox!(dyn Debug, n + 1)).unwrap();
+        assert!(!req.expects_reply());
+
+        // Reply twice is an error
+        let res = req.reply(VBox::unit());
+        assert!(res.unwrap_err().is_unit());
+    });
+
+    let resp = rx.recv().unwrap();
+    assert_eq!("4", format!("{:?}", from_vbox!(dyn Debug, resp)));
+
+    h.join().unwrap();
+
+    let next = Envelope::new(VBox::unit());
+    assert!(next.id > id, "id is unique");
+    assert!(!next.expects_reply());
+}
+
+#[test]
+fn test_envelope_dropped_without_reply() {
+    let (req, rx) = Envelope::request(VBox::unit());
+    drop(req);
+
+    assert_eq!(Canceled, rx.recv().unwrap_err());
+}
+
+#[test]
+fn test_envelope_requester_gone() {
+    let (mut req, rx) = Envelope::request(VBox::unit());
+    drop(rx);
+
+    let res = req.reply(into_vbox!(dyn Debug, 1u64));
+    assert!(res.unwrap_err().is::<u64>());
+}
+
+#[test]
+fn test_envelope_reorder() {
+    use vbox::ReorderBuffer;
+    use vbox::Sequencer;
+
+    let sequencer = Sequencer::new();
+    let envs = (0..5u64)
+        .map(|i| {
+            sequencer.stamp(Envelope::new(into_vbox!(dyn Debug + Send, i)))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(Some(4), envs[4].seq);
+
+    let mut buf = ReorderBuffer::new();
+    let mut got = vec![];
+
+    // Arrive as 3, 1, 0, 4, 2
+    let mut envs = envs.into_iter().map(Some).collect::<Vec<_>>();
+    for i in [3, 1, 0, 4, 2] {
+        buf.push(envs[i].take().unwrap()).unwrap();
+        got.extend(buf.drain_ready().into_iter().map(|e| e.seq.unwrap()));
+        if i == 3 {
+            assert_eq!(vec![0..3], buf.missing());
+        }
+    }
+
+    assert_eq!(vec![0, 1, 2, 3, 4], got);
+    assert!(buf.is_empty());
+    assert_eq!(5, buf.expected());
+}
+
+#[test]
+fn test_envelope_reorder_rejects() {
+    use vbox::ReorderBuffer;
+
+    let mut buf = ReorderBuffer::starting_at(10);
+
+    // No sequence number
+    assert!(buf.push(Envelope::new(VBox::unit())).is_err());
+
+    // Already passed
+    assert!(buf.push(Envelope::new(VBox::unit()).with_seq(9)).is_err());
+
+    // Duplicate
+    buf.push(Envelope::new(VBox::unit()).with_seq(11)).unwrap();
+    let dup = buf.push(Envelope::new(VBox::unit()).with_seq(11)).unwrap_err();
+    assert_eq!(Some(11), dup.seq);
+
+    assert_eq!(1, buf.len());
+    assert!(buf.pop().is_none());
+
+    // Far ahead: the gap is reported as a range.
+    buf.push(Envelope::new(VBox::unit()).with_seq(u64::MAX)).unwrap();
+    assert_eq!(vec![10..11, 12..u64::MAX], buf.missing());
+}
+
+#[test]
+fn test_envelope_reorder_skip() {
+    use vbox::ReorderBuffer;
+
+    let mut buf = ReorderBuffer::new();
+    for seq in [1, 2, 5] {
+        buf.push(Envelope::new(VBox::unit()).with_seq(seq)).unwrap();
+    }
+    assert_eq!(vec![0..1, 3..5], buf.missing());
+
+    // Give up waiting for 0.
+    let skipped = buf.skip_to(3);
+    assert_eq!(
+        vec![Some(1), Some(2)],
+        skipped.iter().map(|e| e.seq).collect::<Vec<_>>()
+    );
+    assert_eq!(3, buf.expected());
+    assert!(buf.skip_to(2).is_empty());
+
+    assert!(buf.pop().is_none());
+    buf.skip_to(5);
+    assert_eq!(Some(5), buf.pop().unwrap().seq);
+    assert_eq!(
+        "ReorderBuffer { expected: 6, held: [] }",
+        format!("{:?}", buf)
+    );
+}
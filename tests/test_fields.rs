@@ -0,0 +1,214 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::into_vbox_fields;
+use vbox::RecordFields;
+
+#[derive(Debug)]
+struct Request {
+    id: u64,
+    path: String,
+    retries: Option<u32>,
+}
+
+impl Display for Request {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.id)
+    }
+}
+
+impl RecordFields for Request {
+    fn record_fields(&self, record: &mut dyn FnMut(&str, &dyn Debug)) {
+        record("id", &self.id);
+        record("path", &self.path);
+        if let Some(r) = self.retries {
+            record("retries", &r);
+        }
+    }
+}
+
+fn request(id: u64, retries: Option<u32>) -> Request {
+    Request {
+        id,
+        path: format!("/{}", id),
+        retries,
+    }
+}
+
+#[test]
+fn test_fields_display() {
+    let vbox = into_vbox_fields!(dyn Display, request(1, None));
+    assert!(vbox.has_fields());
+    assert_eq!(r#"id=1 path="/1""#, vbox.fields().to_string());
+
+    let vbox = into_vbox_fields!(dyn Display, request(2, Some(3)));
+    assert_eq!(r#"id=2 path="/2" retries=3"#, vbox.fields().to_string());
+
+    // The payload is intact.
+    let d = from_vbox!(dyn Display, vbox);
+    assert_eq!("#2", d.to_string());
+}
+
+#[test]
+fn test_fields_debug() {
+    let vbox = into_vbox_fields!(dyn Display, request(1, Some(0)));
+    assert_eq!(
+        r#"{"id": 1, "path": "/1", "retries": 0}"#,
+        format!("{:?}", vbox.fields())
+    );
+
+    let s = format!("{:?}", vbox);
+    assert!(
+        s.contains(r#"fields: {"id": 1, "path": "/1", "retries": 0}"#),
+        "{}",
+        s
+    );
+    vbox.discard();
+}
+
+#[test]
+fn test_fields_not_captured() {
+    let vbox = into_vbox!(dyn Display, request(1, None));
+    assert!(!vbox.has_fields());
+    assert_eq!("", vbox.fields().to_string());
+    assert_eq!("{}", format!("{:?}", vbox.fields()));
+    assert!(!vbox.record_fields(|_, _| unreachable!()));
+    assert!(!format!("{:?}", vbox).contains("fields"));
+    vbox.discard();
+}
+
+#[test]
+fn test_record_fields() {
+    let vbox = into_vbox_fields!(dyn Display, request(7, Some(1)));
+
+    let mut got = vec![];
+    assert!(
+        vbox.record_fields(
+            |name, value| got.push(format!("{}:{:?}", name, value))
+        )
+    );
+    assert_eq!(vec!["id:7", r#"path:"/7""#, "retries:1"], got);
+    vbox.discard();
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_fields_tracing() {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use tracing::field::Field;
+    use tracing::field::Visit;
+    use tracing::span;
+    use tracing::Event;
+    use tracing::Metadata;
+    use tracing::Subscriber;
+
+    /// Collect the fields of every event as `name=value` strings.
+    struct Collect(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Collect {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0.lock().unwrap().push(format!(
+                "{}={:?}",
+                field.name(),
+                value
+            ));
+        }
+    }
+
+    impl Subscriber for Collect {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut Collect(self.0.clone()));
+        }
+
+        fn enter(&self, _span: &span::Id) {}
+
+        fn exit(&self, _span: &span::Id) {}
+    }
+
+    let got = Arc::new(Mutex::new(vec![]));
+    let subscriber = Collect(got.clone());
+
+    let vbox = into_vbox_fields!(dyn Display, request(5, Some(2)));
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(fields = vbox.fields().as_tracing_value(), "sent");
+        tracing::info!(fields = %vbox.fields(), "sent");
+    });
+
+    let want = r#"fields=id=5 path="/5" retries=2"#;
+    assert_eq!(
+        vec!["message=sent", want, "message=sent", want],
+        *got.lock().unwrap()
+    );
+    vbox.discard();
+}
+
+#[cfg(feature = "valuable")]
+#[test]
+fn test_fields_valuable() {
+    use valuable::Mappable;
+    use valuable::NamedValues;
+    use valuable::Valuable;
+    use valuable::Value;
+    use valuable::Visit;
+
+    #[derive(Default)]
+    struct Entries(Vec<(String, String)>);
+
+    impl Visit for Entries {
+        fn visit_value(&mut self, value: Value<'_>) {
+            if let Value::Mappable(m) = value {
+                m.visit(self);
+            }
+        }
+
+        fn visit_entry(&mut self, key: Value<'_>, value: Value<'_>) {
+            let k = key.as_str().unwrap().to_string();
+            let v = value.as_str().unwrap().to_string();
+            self.0.push((k, v));
+        }
+
+        fn visit_named_fields(&mut self, _named_values: &NamedValues<'_>) {
+            unreachable!()
+        }
+    }
+
+    let vbox = into_vbox_fields!(dyn Display, request(4, None));
+    let fields = vbox.fields();
+    assert_eq!((2, Some(2)), fields.size_hint());
+
+    let mut entries = Entries::default();
+    valuable::visit(&fields, &mut entries);
+    assert_eq!(
+        vec![
+            ("id".to_string(), "4".to_string()),
+            ("path".to_string(), r#""/4""#.to_string()),
+        ],
+        entries.0
+    );
+
+    assert!(matches!(fields.as_value(), Value::Mappable(_)));
+    vbox.discard();
+
+    // Nothing is visited without captured fields.
+    let vbox = into_vbox!(dyn Display, request(1, None));
+    let mut entries = Entries::default();
+    valuable::visit(&vbox.fields(), &mut entries);
+    assert!(entries.0.is_empty());
+    vbox.discard();
+}
@@ -0,0 +1,109 @@
+use std::panic;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use vbox::into_vbox;
+use vbox::Finalizers;
+
+#[test]
+fn test_finalizers_reverse_order() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    {
+        let finalizers = Finalizers::new();
+
+        let l = log.clone();
+        finalizers.register("a", move || l.lock().unwrap().push("a"));
+
+        let l = log.clone();
+        finalizers
+            .register_vbox(
+                "b",
+                into_vbox!(dyn FnOnce() + Send, move || l
+                    .lock()
+                    .unwrap()
+                    .push("b")),
+            )
+            .unwrap();
+
+        assert_eq!(vec!["a", "b"], finalizers.names());
+        assert!(log.lock().unwrap().is_empty());
+    }
+
+    assert_eq!(vec!["b", "a"], *log.lock().unwrap(), "run on drop");
+}
+
+#[test]
+fn test_finalizers_shutdown_once() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let finalizers = Finalizers::new();
+
+    let l = log.clone();
+    finalizers.register("a", move || l.lock().unwrap().push("a"));
+
+    finalizers.shutdown();
+    finalizers.shutdown();
+    drop(finalizers);
+
+    assert_eq!(vec!["a"], *log.lock().unwrap());
+}
+
+#[test]
+fn test_finalizers_panic() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let finalizers = Finalizers::new();
+
+    let l = log.clone();
+    finalizers.register("a", move || l.lock().unwrap().push("a"));
+    finalizers.register("b", || panic!("b failed"));
+    let l = log.clone();
+    finalizers.register("c", move || l.lock().unwrap().push("c"));
+
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        finalizers.shutdown();
+    }));
+    assert_eq!(
+        &"b failed",
+        res.unwrap_err().downcast_ref::<&str>().unwrap()
+    );
+
+    assert_eq!(vec!["c", "a"], *log.lock().unwrap());
+}
+
+#[test]
+fn test_finalizers_panic_while_unwinding() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let l = log.clone();
+    let res = panic::catch_unwind(move || {
+        let finalizers = Finalizers::new();
+        finalizers.register("a", move || l.lock().unwrap().push("a"));
+        finalizers.register("b", || panic!("b failed"));
+        panic!("outer");
+    });
+
+    // The panic of the finalizer is swallowed instead of aborting.
+    assert_eq!(&"outer", res.unwrap_err().downcast_ref::<&str>().unwrap());
+    assert_eq!(vec!["a"], *log.lock().unwrap());
+}
+
+#[test]
+fn test_finalizers_register_wrong_type() {
+    let finalizers = Finalizers::new();
+
+    let res = finalizers.register_vbox("x", into_vbox!(dyn Fn() + Send, || {}));
+    assert!(res.unwrap_err().is_dyn::<dyn Fn() + Send>());
+    assert!(finalizers.names().is_empty());
+}
+
+#[test]
+fn test_finalizers_global() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let l = log.clone();
+    Finalizers::global()
+        .register("global", move || l.lock().unwrap().push("global"));
+
+    Finalizers::global().shutdown();
+    assert_eq!(vec!["global"], *log.lock().unwrap());
+}
@@ -0,0 +1,80 @@
+use std::any::Any;
+use std::fmt::Debug;
+
+use vbox::inspector;
+use vbox::into_vbox;
+use vbox::into_vbox_dyn;
+use vbox::DeadLetterQueue;
+
+#[test]
+fn test_inspector_register() {
+    struct Order {
+        id: u64,
+        lines: Vec<u64>,
+    }
+
+    let vbox = into_vbox!(dyn Any + Send, Order {
+        id: 1,
+        lines: vec![1, 2, 3],
+    });
+    assert!(!inspector::is_registered::<Order>());
+    assert_eq!(None, vbox.summary());
+
+    assert!(!inspector::register(|o: &Order| {
+        format!("order {}, {} lines", o.id, o.lines.len())
+    }));
+    assert!(inspector::is_registered::<Order>());
+    assert_eq!(Some("order 1, 3 lines".to_string()), vbox.summary());
+
+    // Replaced
+    assert!(inspector::register(|o: &Order| format!("#{}", o.id)));
+    assert_eq!(Some("#1".to_string()), vbox.summary());
+
+    assert!(inspector::unregister::<Order>());
+    assert!(!inspector::unregister::<Order>());
+    assert_eq!(None, vbox.summary());
+
+    vbox.discard();
+}
+
+#[test]
+fn test_inspector_debug() {
+    #[derive(Debug)]
+    struct Ping(u64);
+
+    inspector::register(|p: &Ping| format!("ping {}", p.0));
+
+    let vbox = into_vbox!(dyn Debug + Send, Ping(3));
+    let s = format!("{:?}", vbox);
+    assert!(s.contains(r#"summary: "ping 3""#), "{}", s);
+    vbox.discard();
+
+    // The concrete type is unknown.
+    let b: Box<dyn Debug + Send> = Box::new(Ping(4));
+    let vbox = into_vbox_dyn!(dyn Debug + Send, b);
+    assert_eq!(None, vbox.summary());
+    vbox.discard();
+}
+
+#[test]
+fn test_inspector_dead_letter() {
+    struct Job(&'static str);
+    struct Other;
+
+    inspector::register(|j: &Job| format!("job {}", j.0));
+
+    let dlq = DeadLetterQueue::new(4);
+    dlq.no_handler(into_vbox!(dyn Any + Send, Job("a")));
+    dlq.no_handler(into_vbox!(dyn Any + Send, Other));
+
+    let lines = dlq.inspect(|l| l.to_string());
+    assert_eq!(
+        vec![
+            "#0 test_inspector::test_inspector_dead_letter::Job: no handler (job a)",
+            "#1 test_inspector::test_inspector_dead_letter::Other: no handler",
+        ],
+        lines
+    );
+
+    assert_eq!(Some("job a".to_string()), dlq.pop().unwrap().summary());
+}
@@ -0,0 +1,57 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::thread;
+
+use vbox::into_vbox;
+use vbox::into_vbox_dyn;
+use vbox::ImplIndex;
+use vbox::VTableInterner;
+
+#[test]
+fn test_interner() {
+    let interner = VTableInterner::new();
+    assert!(interner.is_empty());
+
+    let a = into_vbox!(dyn Debug, 1u64);
+    assert!(interner.index_of(&a).is_none());
+
+    let ia = interner.intern(&a);
+    assert_eq!(0, ia.as_u16());
+    assert_eq!(ia, interner.intern(&into_vbox!(dyn Debug, 2u64)));
+    assert!(interner.matches(ia, &a));
+
+    // Another trait or another type is another pair.
+    let ib = interner.intern(&into_vbox!(dyn Display, 1u64));
+    let ic = interner.intern(&into_vbox!(dyn Debug, 1u32));
+    assert_eq!(1, ib.as_u16());
+    assert_eq!(2, ic.as_u16());
+    assert_eq!(3, interner.len());
+
+    assert_eq!(Some("u64"), interner.type_name(ia));
+    assert_eq!(Some("dyn core::fmt::Display"), interner.trait_name(ib));
+
+    // The concrete type is unknown, identified by the vtable.
+    let b: Box<dyn Debug + Send> = Box::new(1u8);
+    let d = into_vbox_dyn!(dyn Debug + Send, b);
+    let id = interner.intern(&d);
+    assert_eq!(None, interner.type_name(id));
+    assert_eq!(
+        Some("dyn core::fmt::Debug + core::marker::Send"),
+        interner.trait_name(id)
+    );
+
+    let unassigned = ImplIndex::from_u16(100);
+    assert_eq!(None, interner.trait_name(unassigned));
+    assert_eq!("#100", unassigned.to_string());
+}
+
+#[test]
+fn test_interner_global_across_threads() {
+    let h = thread::spawn(|| {
+        VTableInterner::global().intern(&into_vbox!(dyn Debug, 'x'))
+    });
+    let i = h.join().unwrap();
+
+    let vbox = into_vbox!(dyn Debug, 'y');
+    assert_eq!(Some(i), VTableInterner::global().index_of(&vbox));
+}
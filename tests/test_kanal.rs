@@ -0,0 +1,84 @@
+#![cfg(feature = "kanal")]
+
+use std::fmt::Debug;
+use std::fmt::Display;
+
+use futures::executor::block_on;
+use vbox::kanal;
+
+#[test]
+fn test_kanal_sync() {
+    let (tx, rx) = kanal::bounded::<dyn Display + Send>(1);
+
+    let mut msg = Some(Box::new(1u64) as Box<dyn Display + Send>);
+    assert!(tx.try_send_option(&mut msg).unwrap());
+    assert!(msg.is_none());
+
+    // Full: the message stays, in the same allocation.
+    let b: Box<dyn Display + Send> = Box::new(2u64);
+    let addr = &*b as *const _ as *const ();
+    let mut msg = Some(b);
+    assert!(!tx.try_send_option(&mut msg).unwrap());
+    let b = msg.unwrap();
+    assert_eq!(addr, &*b as *const _ as *const ());
+    assert_eq!("2", b.to_string());
+
+    assert_eq!(1, rx.len());
+    assert_eq!("1", rx.try_recv().unwrap().unwrap().to_string());
+    assert!(rx.try_recv().unwrap().is_none());
+
+    let t = std::thread::spawn(move || {
+        for i in 0..3u64 {
+            tx.send(Box::new(i)).unwrap();
+        }
+    });
+    let got: Vec<_> = (0..3).map(|_| rx.recv().unwrap().to_string()).collect();
+    assert_eq!(vec!["0", "1", "2"], got);
+
+    t.join().unwrap();
+    assert!(rx.recv().is_err(), "all senders are dropped");
+}
+
+#[test]
+fn test_kanal_async() {
+    let (tx, rx) = kanal::unbounded_async::<dyn Debug + Send>();
+
+    block_on(async {
+        tx.send(Box::new(1u64)).await.unwrap();
+        assert_eq!("1", format!("{:?}", rx.recv().await.unwrap()));
+    });
+
+    // Switch flavors
+    let tx = tx.to_sync();
+    let rx = rx.to_sync();
+    tx.send(Box::new("a")).unwrap();
+    assert_eq!(r#""a""#, format!("{:?}", rx.recv().unwrap()));
+
+    let (tx, rx) = kanal::bounded::<dyn Debug + Send>(1);
+    let rx = rx.to_async();
+    tx.send(Box::new(2u64)).unwrap();
+    assert_eq!(
+        "2",
+        block_on(async { format!("{:?}", rx.recv().await.unwrap()) })
+    );
+
+    rx.close();
+    assert!(tx.is_closed());
+    assert!(tx.send(Box::new(3u64)).is_err());
+}
+
+#[test]
+fn test_kanal_erased() {
+    let (tx, rx) = kanal::unbounded::<dyn Debug + Send>();
+
+    let erased_rx = rx.into_erased();
+    tx.send(Box::new(5u64)).unwrap();
+
+    let vbox = erased_rx.recv().unwrap();
+    assert!(vbox.is_dyn::<dyn Debug + Send>());
+
+    let rx = kanal::Receiver::<dyn Debug + Send>::from_erased(erased_rx);
+    tx.send(Box::new(6u64)).unwrap();
+    assert_eq!("6", format!("{:?}", rx.recv().unwrap()));
+    drop(vbox);
+}
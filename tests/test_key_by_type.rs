@@ -0,0 +1,60 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fmt::Display;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::into_vbox_dyn;
+use vbox::KeyByType;
+use vbox::TypeKey;
+
+#[test]
+fn test_key_by_type_eq() {
+    let a = KeyByType::new(into_vbox!(dyn Debug, 1u64));
+    let b = KeyByType::from(into_vbox!(dyn Display, 2u64));
+    let c = KeyByType::new(into_vbox!(dyn Debug, 1u32));
+
+    // The trait does not matter, only the concrete type.
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_eq!(TypeKey::of::<u64>(), a.key());
+    assert_eq!(Some(std::any::TypeId::of::<u64>()), a.key().type_id());
+
+    // Unknown concrete type
+    let boxed: Box<dyn Debug + Send> = Box::new(1u64);
+    let d = KeyByType::new(into_vbox_dyn!(dyn Debug + Send, boxed));
+    assert_ne!(a, d);
+    assert_eq!(None, d.key().type_id());
+
+    let vbox = b.into_inner();
+    assert_eq!("2", from_vbox!(dyn Display, vbox).to_string());
+    assert!(a.as_vbox().is::<u64>());
+}
+
+// The lost-message tracker of the `debug-unconsumed` feature is interior
+// mutable, but it is not part of the key.
+#[allow(clippy::mutable_key_type)]
+#[test]
+fn test_key_by_type_in_maps() {
+    let mut slots: HashMap<KeyByType, &str> = HashMap::new();
+    slots.insert(KeyByType::new(into_vbox!(dyn Debug, 1u64)), "first");
+    slots.insert(KeyByType::new(into_vbox!(dyn Debug, "s")), "str");
+
+    // One slot per type: the old key is kept, the value is replaced.
+    slots.insert(KeyByType::new(into_vbox!(dyn Debug, 2u64)), "second");
+    assert_eq!(2, slots.len());
+    assert_eq!(Some(&"second"), slots.get(&TypeKey::of::<u64>()));
+    assert_eq!(Some(&"str"), slots.get(&TypeKey::of::<&str>()));
+    assert_eq!(None, slots.get(&TypeKey::of::<u8>()));
+
+    let vbox = into_vbox!(dyn Debug, 3u64);
+    assert!(slots.contains_key(&TypeKey::of_vbox(&vbox)));
+
+    let mut ordered: BTreeMap<KeyByType, u64> = BTreeMap::new();
+    ordered.insert(KeyByType::new(into_vbox!(dyn Debug, 1u64)), 1);
+    ordered.insert(KeyByType::new(into_vbox!(dyn Debug, 2u64)), 2);
+    ordered.insert(KeyByType::new(into_vbox!(dyn Debug, 3u8)), 3);
+    assert_eq!(2, ordered.len());
+    assert_eq!(Some(&2), ordered.get(&TypeKey::of::<u64>()));
+}
@@ -0,0 +1,53 @@
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use vbox::from_local_vbox;
+use vbox::into_local_vbox;
+use vbox::into_vbox;
+use vbox::LocalVBox;
+
+/// Mimic a `JsValue` wrapper, which is not `Send`.
+#[derive(Debug)]
+struct JsHandle(Rc<RefCell<Vec<u64>>>);
+
+trait Callback {
+    fn call(&mut self, n: u64);
+}
+
+impl Callback for JsHandle {
+    fn call(&mut self, n: u64) {
+        self.0.borrow_mut().push(n);
+    }
+}
+
+#[test]
+fn test_local_vbox_pack_unpack() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    let mut lvbox: LocalVBox =
+        into_local_vbox!(dyn Callback, JsHandle(log.clone()));
+    assert!(lvbox.is::<JsHandle>());
+
+    lvbox.as_dyn_mut::<dyn Callback>().call(1);
+
+    let mut cb = from_local_vbox!(dyn Callback, lvbox);
+    cb.call(2);
+
+    assert_eq!(vec![1, 2], *log.borrow());
+
+    drop(cb);
+    assert_eq!(1, Rc::strong_count(&log), "payload is dropped");
+}
+
+#[test]
+fn test_local_vbox_from_box_and_vbox() {
+    let b: Box<dyn Debug> = Box::new(Rc::new(3u64));
+    let lvbox = LocalVBox::from_box(b);
+    assert!(!lvbox.is::<Rc<u64>>(), "concrete type is unknown");
+    assert_eq!("3", format!("{:?}", lvbox.as_dyn::<dyn Debug>()));
+
+    let lvbox: LocalVBox = into_vbox!(dyn Debug, 5u64).into();
+    let lvbox = lvbox.into_inner::<u32>().unwrap_err();
+    assert_eq!(5u64, lvbox.into_inner::<u64>().unwrap());
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "log")]
+
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+use log::Log;
+use log::Metadata;
+use log::Record;
+use vbox::from_vbox;
+use vbox::into_vbox;
+
+struct Collector {
+    events: Mutex<Vec<String>>,
+}
+
+impl Log for Collector {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.events.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+static COLLECTOR: Collector = Collector {
+    events: Mutex::new(Vec::new()),
+};
+
+#[test]
+fn test_log_events() {
+    log::set_logger(&COLLECTOR).unwrap();
+    log::set_max_level(log::LevelFilter::Debug);
+
+    let vb = into_vbox!(dyn Debug, 3u64);
+    let d = from_vbox!(dyn Debug, vb);
+    drop(d);
+
+    let vb = into_vbox!(dyn Debug, 4u64);
+    vb.discard();
+
+    let events = COLLECTOR.events.lock().unwrap().clone();
+    let this_file = file!();
+
+    assert_eq!(4, events.len(), "{:?}", events);
+    assert!(events[0].starts_with("VBox pack: dyn core::fmt::Debug at"));
+    assert!(events[0].contains(this_file), "{:?}", events);
+    assert!(events[1].starts_with("VBox unpack: dyn core::fmt::Debug at"));
+    assert!(events[1].contains(this_file), "{:?}", events);
+    assert!(events[2].starts_with("VBox pack: dyn core::fmt::Debug at"));
+    assert_eq!("VBox drop: dyn core::fmt::Debug", events[3]);
+}
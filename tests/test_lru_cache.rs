@@ -0,0 +1,107 @@
+use std::fmt::Debug;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::LruCache;
+use vbox::LruCapacity;
+use vbox::VBox;
+
+fn keys<K: Clone>(entries: &[(K, VBox)]) -> Vec<K> {
+    entries.iter().map(|(k, _)| k.clone()).collect()
+}
+
+#[test]
+fn test_lru_cache_by_entries() {
+    let mut cache = LruCache::new(LruCapacity::Entries(2));
+    assert_eq!(LruCapacity::Entries(2), cache.capacity());
+
+    assert!(cache.insert(1, into_vbox!(dyn Debug, "one")).is_empty());
+    assert!(cache.insert(2, into_vbox!(dyn Debug, "two")).is_empty());
+
+    // Peeking does not mark it as used.
+    assert!(cache.peek_vbox(&1).is_some());
+    let evicted = cache.insert(3, into_vbox!(dyn Debug, "three"));
+    assert_eq!(vec![1], keys(&evicted));
+    assert!(!cache.contains_key(&1));
+
+    // Getting marks it as used.
+    assert_eq!(
+        "\"two\"",
+        format!("{:?}", cache.get::<dyn Debug, _>(&2).unwrap())
+    );
+    let evicted = cache.insert(4, into_vbox!(dyn Debug, "four"));
+    assert_eq!(vec![3], keys(&evicted));
+
+    // Replacing returns the old entry, and evicts nothing.
+    let replaced = cache.insert(2, into_vbox!(dyn Debug, "TWO"));
+    assert_eq!(vec![2], keys(&replaced));
+    let (_, old) = replaced.into_iter().next().unwrap();
+    assert_eq!("\"two\"", format!("{:?}", from_vbox!(dyn Debug, old)));
+    assert_eq!(2, cache.len());
+
+    let (k, _) = cache.pop_lru().unwrap();
+    assert_eq!(4, k);
+
+    let vbox = cache.remove(&2).unwrap();
+    assert_eq!("\"TWO\"", format!("{:?}", from_vbox!(dyn Debug, vbox)));
+    assert!(cache.is_empty());
+    assert!(cache.pop_lru().is_none());
+}
+
+#[test]
+fn test_lru_cache_by_bytes() {
+    let mut cache = LruCache::new(LruCapacity::Bytes(12));
+
+    cache.insert("a", into_vbox!(dyn Debug, 1u32));
+    cache.insert("b", into_vbox!(dyn Debug, 2u64));
+    cache.insert("unit", into_vbox!(dyn Debug, ()));
+    assert_eq!(12, cache.bytes());
+    assert_eq!(3, cache.len());
+
+    // Evict until it fits.
+    let evicted = cache.insert("c", into_vbox!(dyn Debug, 3u64));
+    assert_eq!(vec!["a", "b"], keys(&evicted));
+    assert_eq!(8, cache.bytes());
+
+    // A payload larger than the capacity is evicted at once.
+    let evicted = cache.insert("big", into_vbox!(dyn Debug, [0u8; 13]));
+    assert_eq!(vec!["unit", "c", "big"], keys(&evicted));
+    assert_eq!(0, cache.bytes());
+    assert!(cache.is_empty());
+
+    // Shrink
+    cache.insert("a", into_vbox!(dyn Debug, 1u32));
+    cache.insert("b", into_vbox!(dyn Debug, 2u32));
+    let evicted = cache.set_capacity(LruCapacity::Entries(1));
+    assert_eq!(vec!["a"], keys(&evicted));
+    assert_eq!(4, cache.bytes());
+}
+
+#[test]
+fn test_lru_cache_replace_through_get_vbox() {
+    let mut cache = LruCache::new(LruCapacity::Bytes(16));
+    cache.insert("a", into_vbox!(dyn Debug, 1u64));
+
+    // A payload of another size is still counted with the inserted size.
+    *cache.get_vbox("a").unwrap() = into_vbox!(dyn Debug, [0u8; 1000]);
+    assert_eq!(8, cache.bytes());
+
+    let vbox = cache.remove("a").unwrap();
+    assert_eq!(1000, vbox.size_of_payload());
+    assert_eq!(0, cache.bytes());
+
+    // So does pop_lru().
+    cache.insert("b", into_vbox!(dyn Debug, 1u64));
+    *cache.get_vbox("b").unwrap() = into_vbox!(dyn Debug, ());
+    let evicted = cache.pop_lru().unwrap();
+    assert_eq!("b", evicted.0);
+    assert_eq!(0, cache.bytes());
+}
+
+#[test]
+#[should_panic(expected = "expected type_id")]
+fn test_lru_cache_get_type_mismatch() {
+    let mut cache = LruCache::new(LruCapacity::Entries(1));
+    cache.insert(1, into_vbox!(dyn Debug, 1u64));
+    let _ = cache.get::<dyn std::fmt::Display, _>(&1);
+}
@@ -0,0 +1,150 @@
+//! The macros must not depend on anything imported at the call site.
+
+#![no_implicit_prelude]
+
+// Shadow the names the macros might otherwise resolve at the call site.
+#[allow(dead_code)]
+struct Box;
+#[allow(dead_code)]
+struct VBox;
+
+macro_rules! pack_debug {
+    ($v: expr) => {
+        ::vbox::into_vbox!(
+            dyn ::std::fmt::Debug + ::std::marker::Send + ::std::marker::Sync,
+            $v
+        )
+    };
+}
+
+macro_rules! unpack_debug {
+    ($v: expr) => {
+        ::vbox::from_vbox!(
+            dyn ::std::fmt::Debug + ::std::marker::Send + ::std::marker::Sync,
+            $v
+        )
+    };
+}
+
+#[test]
+fn test_macros_without_imports() {
+    let vb: ::vbox::VBox = pack_debug!(3u64);
+    let got = unpack_debug!(vb);
+    ::std::assert_eq!("3", ::std::format!("{:?}", got));
+
+    let b: ::std::boxed::Box<
+        dyn ::std::fmt::Debug + ::std::marker::Send + ::std::marker::Sync,
+    > = ::std::boxed::Box::new(4u64);
+    let vb = ::vbox::into_vbox_dyn!(
+        dyn ::std::fmt::Debug + ::std::marker::Send + ::std::marker::Sync,
+        b
+    );
+    let got = ::vbox::leak_vbox!(
+        dyn ::std::fmt::Debug + ::std::marker::Send + ::std::marker::Sync,
+        vb
+    );
+    ::std::assert_eq!("4", ::std::format!("{:?}", got));
+}
+
+#[test]
+fn test_compound_trait_objects() {
+    let mut vb = ::vbox::into_vbox!(
+        dyn (::std::ops::FnMut() -> u64) + ::std::marker::Send + 'static,
+        || 5
+    );
+    let got = ::vbox::with_vbox!(
+        dyn (::std::ops::FnMut() -> u64) + ::std::marker::Send + 'static,
+        &mut vb,
+        |f| f()
+    );
+    ::std::assert_eq!(5, got);
+
+    ::vbox::replace_vbox!(
+        dyn (::std::ops::FnMut() -> u64) + ::std::marker::Send + 'static,
+        &mut vb,
+        || 6
+    );
+
+    let vb = ::vbox::map_vbox!(
+        dyn (::std::ops::FnMut() -> u64) + ::std::marker::Send + 'static -> dyn ::std::fmt::Debug + ::std::marker::Send,
+        vb,
+        |mut f| f()
+    );
+    let got =
+        ::vbox::from_vbox!(dyn ::std::fmt::Debug + ::std::marker::Send, vb);
+    ::std::assert_eq!("6", ::std::format!("{:?}", got));
+
+    let vb = ::vbox::into_vbox!(for<'a> dyn ::std::ops::Fn(&'a str) -> &'a str, |s| s);
+    let f =
+        ::vbox::from_vbox!(for<'a> dyn ::std::ops::Fn(&'a str) -> &'a str, vb);
+    ::std::assert_eq!("x", f("x"));
+}
+
+#[test]
+fn test_vresult_macros_without_imports() {
+    let res: ::std::result::Result<u64, u64> = ::std::result::Result::Ok(7);
+    let vres = ::vbox::into_vresult!(
+        dyn ::std::fmt::Debug + ::std::marker::Send,
+        dyn ::std::fmt::Debug + ::std::marker::Send,
+        res
+    );
+    let vres = ::vbox::map_ok_vbox!(
+        dyn ::std::fmt::Debug + ::std::marker::Send -> dyn ::std::fmt::Display,
+        vres,
+        |a| ::std::format!("<{:?}>", a)
+    );
+    let vres = ::vbox::map_err_vbox!(
+        dyn ::std::fmt::Debug + ::std::marker::Send -> dyn ::std::fmt::Debug,
+        vres,
+        |e| e
+    );
+    let got = ::vbox::from_vresult!(
+        dyn ::std::fmt::Display,
+        dyn ::std::fmt::Debug,
+        vres
+    );
+    ::std::assert_eq!("<7>", ::std::string::ToString::to_string(&got.unwrap()));
+}
+
+#[test]
+fn test_guard_vbox_without_imports() {
+    let mut vb = pack_debug!(8u64);
+    let g = ::vbox::guard_vbox!(
+        dyn ::std::fmt::Debug + ::std::marker::Send + ::std::marker::Sync,
+        &mut vb
+    );
+    ::std::assert_eq!("8", ::std::format!("{:?}", &*g));
+}
+
+#[test]
+fn test_static_vtable_without_imports() {
+    static VT: ::vbox::VTable<dyn ::std::fmt::Debug + ::std::marker::Send> = ::vbox::static_vtable!(
+        dyn ::std::fmt::Debug + ::std::marker::Send,
+        u64
+    );
+
+    let vb = VT.pack(9u64);
+    let got =
+        ::vbox::from_vbox!(dyn ::std::fmt::Debug + ::std::marker::Send, vb);
+    ::std::assert_eq!("9", ::std::format!("{:?}", got));
+}
+
+trait Message {
+    type Result;
+}
+
+::vbox::actor_message! {
+    struct Command(::vbox::VBox);
+
+    impl Message {
+        type Result = u64;
+    }
+}
+
+#[test]
+fn test_actor_message_without_imports() {
+    let cmd: Command = ::std::convert::From::from(pack_debug!(9u64));
+    ::std::assert!(cmd.is::<u64>());
+    let got = unpack_debug!(cmd.into_inner());
+    ::std::assert_eq!("9", ::std::format!("{:?}", got));
+}
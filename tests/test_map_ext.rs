@@ -0,0 +1,53 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fmt::Display;
+
+use vbox::insert_erased;
+use vbox::VBox;
+use vbox::VBoxMapExt;
+
+#[test]
+fn test_hash_map() {
+    let mut m: HashMap<String, VBox> = HashMap::new();
+
+    assert!(insert_erased!(m, "a".to_string(), dyn Debug, 1u64).is_none());
+    assert!(insert_erased!(m, "b".to_string(), dyn Display, 2u64).is_none());
+
+    assert_eq!("1", format!("{:?}", m.get_as::<dyn Debug>("a").unwrap()));
+    assert_eq!("2", m.get_as::<dyn Display>("b").unwrap().to_string());
+    assert!(m.get_as::<dyn Debug>("c").is_none());
+
+    let b = m.remove_as::<dyn Display>("b").unwrap();
+    assert_eq!("2", b.to_string());
+    assert!(m.remove_as::<dyn Display>("b").is_none());
+    assert_eq!(1, m.len());
+}
+
+#[test]
+fn test_btree_map_get_as_mut() {
+    let mut m: BTreeMap<u64, VBox> = BTreeMap::new();
+    insert_erased!(m, 1, dyn Debug, vec![1u64]);
+
+    // Replace with a value of another concrete type.
+    let old = insert_erased!(m, 1, dyn Debug, "x").unwrap();
+    assert!(old.is::<Vec<u64>>());
+    old.discard();
+
+    let v = m.get_as_mut::<dyn Debug>(&1).unwrap();
+    assert_eq!("\"x\"", format!("{:?}", v));
+}
+
+#[test]
+fn test_remove_as_mismatch_keeps_entry() {
+    let mut m: HashMap<&'static str, VBox> = HashMap::new();
+    insert_erased!(m, "a", dyn Debug, 1u64);
+
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        m.remove_as::<dyn Display>("a");
+    }));
+    assert!(res.is_err());
+
+    assert!(m.contains_key("a"));
+    assert_eq!("1", format!("{:?}", m.get_as::<dyn Debug>("a").unwrap()));
+}
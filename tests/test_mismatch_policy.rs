@@ -0,0 +1,102 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::process::Command;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::try_from_vbox;
+use vbox::MismatchPolicy;
+use vbox::VBox;
+
+/// Set in the child process that is expected to abort.
+const CHILD: &str = "VBOX_TEST_ABORT_CHILD";
+
+/// Run `test` in a child process with `CHILD=mode`, and return its stderr if
+/// it aborted.
+fn run_child(test: &str, mode: &str) -> String {
+    let out = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", test, "--nocapture", "--test-threads=1"])
+        .env(CHILD, mode)
+        .output()
+        .unwrap();
+
+    assert!(!out.status.success(), "child did not abort");
+    let stderr = String::from_utf8_lossy(&out.stderr).to_string();
+    assert!(!stderr.contains("panicked"), "child panicked: {}", stderr);
+    stderr
+}
+
+#[test]
+fn test_mismatch_policy_default() {
+    assert_eq!(MismatchPolicy::Panic, MismatchPolicy::global());
+
+    let mut vb: VBox = into_vbox!(dyn Debug + Send, 1u64);
+    assert_eq!(MismatchPolicy::Panic, vb.mismatch_policy());
+
+    vb.set_mismatch_policy(MismatchPolicy::Abort);
+    assert_eq!(MismatchPolicy::Abort, vb.mismatch_policy());
+
+    // Kept by raw parts
+    let mut vb = unsafe { VBox::from_raw(vb.into_raw()) };
+    assert_eq!(MismatchPolicy::Abort, vb.mismatch_policy());
+
+    // The panic-free forms are not affected
+    let res = try_from_vbox!(dyn Display + Send, vb);
+    let Err(err) = res else {
+        panic!("expect Err");
+    };
+    vb = err.into_inner();
+
+    // Unpacked as the right type
+    assert_eq!("1", format!("{:?}", from_vbox!(dyn Debug + Send, vb)));
+
+    let vb = VBox::builder(2u64).mismatch_policy(MismatchPolicy::Abort).build();
+    assert_eq!(MismatchPolicy::Abort, vb.mismatch_policy());
+    let _ = vb.into_inner::<u64>();
+}
+
+#[test]
+fn test_mismatch_policy_panic() {
+    let vb: VBox = into_vbox!(dyn Debug + Send, 1u64);
+
+    let res = std::panic::catch_unwind(move || {
+        let _ = from_vbox!(dyn Display + Send, vb);
+    });
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_mismatch_policy_abort_per_box() {
+    if std::env::var(CHILD).as_deref() == Ok("per-box") {
+        let mut vb: VBox = into_vbox!(dyn Debug + Send, 1u64);
+        vb.set_mismatch_policy(MismatchPolicy::Abort);
+        let _ = from_vbox!(dyn Display + Send, vb);
+        return;
+    }
+
+    let stderr = run_child("test_mismatch_policy_abort_per_box", "per-box");
+    assert!(
+        stderr.contains(
+            "VBox type mismatch, aborting: \
+             packed as: u64 as dyn core::fmt::Debug + core::marker::Send, \
+             expected: dyn core::fmt::Display + core::marker::Send"
+        ),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_mismatch_policy_abort_global() {
+    if std::env::var(CHILD).as_deref() == Ok("global") {
+        MismatchPolicy::set_global(MismatchPolicy::Abort);
+
+        let vb: VBox = into_vbox!(dyn Debug + Send, 1u64);
+        assert_eq!(MismatchPolicy::Abort, vb.mismatch_policy());
+        vb.as_dyn::<dyn Display + Send>();
+        return;
+    }
+
+    let stderr = run_child("test_mismatch_policy_abort_global", "global");
+    assert!(stderr.contains("VBox type mismatch, aborting"));
+}
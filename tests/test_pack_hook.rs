@@ -0,0 +1,72 @@
+#![cfg(feature = "pack-hook")]
+
+use std::fmt::Debug;
+use std::panic;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use vbox::into_vbox;
+use vbox::pack_hook;
+
+/// The hook is process wide, thus all cases are in one test.
+#[test]
+fn test_pack_hook() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    let s = seen.clone();
+    pack_hook::set_hook(move |info| {
+        s.lock()
+            .unwrap()
+            .push(format!("{} {} {:?}", info.type_name, info.size, info.tag));
+
+        if info.tag == Some("small") && info.size > 64 * 1024 {
+            return Err("payload over 64 KiB".to_string());
+        }
+        Ok(())
+    });
+
+    let _a = into_vbox!(dyn Debug, 1u64);
+    let _b = pack_hook::with_tag("small", || into_vbox!(dyn Debug, 2u32));
+
+    let res = panic::catch_unwind(|| {
+        pack_hook::with_tag("small", || {
+            into_vbox!(dyn Debug + Send, vec![0u8; 10])
+        });
+        pack_hook::with_tag("small", || {
+            into_vbox!(dyn Debug + Send, Box::new([0u8; 128 * 1024]))
+        });
+        pack_hook::with_tag("small", || {
+            into_vbox!(dyn Debug + Send, [0u8; 128 * 1024])
+        });
+    });
+    let err = res.unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert!(
+        msg.starts_with("VBox pack is rejected: payload over 64 KiB"),
+        "{}",
+        msg
+    );
+
+    // The tag is restored after the closure returns or panics.
+    let _c = into_vbox!(dyn Debug, 3u8);
+
+    pack_hook::clear_hook();
+    let _d = into_vbox!(dyn Debug, 4u8);
+
+    assert_eq!(
+        vec![
+            "dyn core::fmt::Debug 8 None".to_string(),
+            "dyn core::fmt::Debug 4 Some(\"small\")".to_string(),
+            "dyn core::fmt::Debug + core::marker::Send 24 Some(\"small\")"
+                .to_string(),
+            "dyn core::fmt::Debug + core::marker::Send 8 Some(\"small\")"
+                .to_string(),
+            format!(
+                "dyn core::fmt::Debug + core::marker::Send {} Some(\"small\")",
+                128 * 1024
+            ),
+            "dyn core::fmt::Debug 1 None".to_string(),
+        ],
+        *seen.lock().unwrap()
+    );
+}
@@ -0,0 +1,139 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use vbox::from_vbox;
+use vbox::into_packed_vbox;
+use vbox::into_vbox;
+use vbox::PackedVBox;
+use vbox::VBox;
+
+#[derive(Debug)]
+struct Counted(Arc<AtomicUsize>);
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_packed_one_allocation() {
+    let packed: PackedVBox = into_packed_vbox!(dyn Debug + Send, [1u64, 2, 3]);
+
+    assert_eq!(
+        std::mem::size_of::<usize>(),
+        std::mem::size_of::<PackedVBox>()
+    );
+    assert_eq!(24, packed.size_of_payload());
+
+    assert_eq!(
+        "[1, 2, 3]",
+        format!("{:?}", packed.as_dyn::<dyn Debug + Send>())
+    );
+}
+
+#[test]
+fn test_packed_type_check() {
+    let mut packed: PackedVBox = into_packed_vbox!(dyn Display + Send, 3u64);
+
+    assert!(packed.is_dyn::<dyn Display + Send>());
+    assert!(!packed.is_dyn::<dyn Debug + Send>());
+    assert!(packed.is::<u64>());
+
+    let err = packed.try_as_dyn::<dyn Debug + Send>().unwrap_err();
+    assert_eq!("dyn core::fmt::Display + core::marker::Send", err.actual);
+
+    assert_eq!("3", packed.as_dyn_mut::<dyn Display + Send>().to_string());
+
+    let res = std::panic::catch_unwind(move || {
+        let _ = packed.as_dyn::<dyn Debug + Send>();
+    });
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_packed_unpack() {
+    let mut packed: PackedVBox = into_packed_vbox!(dyn Display + Send, 3u64);
+
+    let err = packed.try_as_dyn_mut::<dyn Debug + Send>().unwrap_err();
+    assert_eq!("dyn core::fmt::Debug + core::marker::Send", err.expected);
+    assert_eq!(
+        "3",
+        packed.try_as_dyn_mut::<dyn Display + Send>().unwrap().to_string()
+    );
+
+    // The packed value is returned intact.
+    let err = packed.try_unpack::<dyn Debug + Send>().unwrap_err();
+    assert_eq!(
+        "dyn core::fmt::Display + core::marker::Send",
+        err.mismatch().actual
+    );
+    let packed = err.into_inner();
+
+    let d = packed.try_unpack::<dyn Display + Send>().unwrap();
+    assert_eq!("3", d.to_string());
+
+    let packed: PackedVBox = into_packed_vbox!(dyn Display + Send, 4u64);
+    assert_eq!("4", packed.unpack::<dyn Display + Send>().to_string());
+
+    let packed: PackedVBox = into_packed_vbox!(dyn Display + Send, 5u64);
+    let res = std::panic::catch_unwind(move || {
+        let _ = packed.unpack::<dyn Debug + Send>();
+    });
+    assert!(res.is_err());
+
+    let packed: PackedVBox = into_packed_vbox!(dyn Display + Send, 6u64);
+    let vbox = packed.into_vbox();
+    assert_eq!("6", from_vbox!(dyn Display + Send, vbox).to_string());
+}
+
+#[test]
+fn test_packed_into_inner() {
+    let packed: PackedVBox = into_packed_vbox!(dyn Debug + Send, 3u64);
+
+    let packed = packed.into_inner::<u32>().unwrap_err();
+    assert_eq!(3u64, packed.into_inner::<u64>().unwrap());
+}
+
+#[test]
+fn test_packed_convert() {
+    let mut vbox: VBox = into_vbox!(dyn Debug + Send, String::from("foo"));
+    vbox.set_tag(7);
+
+    let packed = PackedVBox::from(vbox);
+    assert_eq!(7, packed.tag());
+    assert_eq!(Some(r#""foo""#.to_string()), packed.debug_string());
+
+    let vbox = VBox::from(packed);
+    assert_eq!(7, vbox.tag());
+    assert_eq!(
+        r#""foo""#,
+        format!("{:?}", from_vbox!(dyn Debug + Send, vbox))
+    );
+
+    // Zero-sized payload
+    let packed = PackedVBox::from(VBox::unit());
+    assert_eq!(0, packed.size_of_payload());
+    assert!(VBox::from(packed).is_unit());
+}
+
+#[test]
+fn test_packed_drop() {
+    let dropped = Arc::new(AtomicUsize::new(0));
+
+    let packed = into_packed_vbox!(dyn Debug + Send, Counted(dropped.clone()));
+    drop(packed);
+    assert_eq!(1, dropped.load(Ordering::Relaxed));
+
+    // Moving between the representations does not drop the payload.
+    let vbox: VBox = into_vbox!(dyn Debug + Send, Counted(dropped.clone()));
+    let packed = PackedVBox::from(vbox);
+    let vbox = VBox::from(packed);
+    assert_eq!(1, dropped.load(Ordering::Relaxed));
+
+    drop(vbox);
+    assert_eq!(2, dropped.load(Ordering::Relaxed));
+}
@@ -0,0 +1,135 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Debug;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+
+use vbox::into_vbox;
+use vbox::Pipeline;
+use vbox::VBox;
+use vbox::VError;
+
+#[derive(Debug)]
+struct TooLarge(u64);
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "too large: {}", self.0)
+    }
+}
+
+impl Error for TooLarge {}
+
+#[derive(Debug)]
+struct Enriched {
+    #[allow(dead_code)]
+    n: u64,
+    #[allow(dead_code)]
+    seq: usize,
+}
+
+fn build(seen: Arc<AtomicUsize>) -> Pipeline {
+    let metrics = Pipeline::new().stage("metrics", move |vbox: VBox| {
+        seen.fetch_add(1, Ordering::Relaxed);
+        Ok(vbox)
+    });
+
+    let seq = AtomicUsize::new(0);
+    Pipeline::new()
+        .stage("validate", |vbox: VBox| match vbox.into_inner::<u64>() {
+            Ok(n) if n > 100 => Err(VError::new(TooLarge(n))),
+            Ok(n) => Ok(into_vbox!(dyn Debug + Send, n)),
+            Err(vbox) => Ok(vbox),
+        })
+        .then(metrics)
+        .stage("enrich", move |vbox: VBox| match vbox.into_inner::<u64>() {
+            Ok(n) => {
+                let seq = seq.fetch_add(1, Ordering::Relaxed);
+                Ok(into_vbox!(dyn Debug + Send, Enriched { n, seq }))
+            }
+            Err(vbox) => Ok(vbox),
+        })
+}
+
+#[test]
+fn test_pipeline_on_send() {
+    let seen = Arc::new(AtomicUsize::new(0));
+    let pipeline = build(seen.clone());
+    assert_eq!(vec!["validate", "metrics", "enrich"], pipeline.names());
+
+    let (tx, rx) = mpsc::channel::<VBox>();
+    let h = thread::spawn(move || {
+        rx.iter()
+            .map(|v| format!("{:?}", v.as_dyn::<dyn Debug + Send>()))
+            .collect::<Vec<_>>()
+    });
+
+    for vbox in [
+        into_vbox!(dyn Debug + Send, 1u64),
+        into_vbox!(dyn Debug + Send, 200u64),
+        into_vbox!(dyn Debug + Send, "s"),
+        into_vbox!(dyn Debug + Send, 2u64),
+    ] {
+        match pipeline.run(vbox) {
+            Ok(vbox) => tx.send(vbox).unwrap(),
+            Err(e) => {
+                assert_eq!("validate", e.stage());
+                assert_eq!(
+                    "pipeline stage 'validate' failed: too large: 200",
+                    e.to_string()
+                );
+                assert!(e.into_error().find::<TooLarge>().is_some());
+            }
+        }
+    }
+    drop(tx);
+
+    assert_eq!(
+        vec![
+            "Enriched { n: 1, seq: 0 }",
+            "\"s\"",
+            "Enriched { n: 2, seq: 1 }"
+        ],
+        h.join().unwrap()
+    );
+    assert_eq!(3, seen.load(Ordering::Relaxed));
+}
+
+#[test]
+fn test_pipeline_shared_on_receive() {
+    let seen = Arc::new(AtomicUsize::new(0));
+    let pipeline = Arc::new(build(seen.clone()));
+
+    let hs = (0..4u64)
+        .map(|i| {
+            let p = pipeline.clone();
+            thread::spawn(move || {
+                p.run(into_vbox!(dyn Debug + Send, i)).unwrap().discard();
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for h in hs {
+        h.join().unwrap();
+    }
+    assert_eq!(4, seen.load(Ordering::Relaxed));
+}
+
+#[test]
+fn test_pipeline_empty() {
+    let pipeline = Pipeline::new();
+    assert!(pipeline.is_empty());
+
+    let out = pipeline.run(into_vbox!(dyn Debug, 3u64)).unwrap();
+    assert_eq!(3u64, out.into_inner::<u64>().unwrap());
+}
+
+#[test]
+#[should_panic]
+fn test_pipeline_push_vbox_wrong_type() {
+    let mut pipeline = Pipeline::new();
+    pipeline.push_vbox("bad", into_vbox!(dyn Debug, 1u64));
+}
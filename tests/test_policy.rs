@@ -0,0 +1,96 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::panic;
+
+use vbox::into_vbox;
+use vbox::policy::CheckPolicy;
+use vbox::policy::Checked;
+use vbox::policy::DebugChecked;
+use vbox::policy::Logging;
+use vbox::policy::Unchecked;
+use vbox::policy::Verified;
+use vbox::PolicyVBox;
+use vbox::VBox;
+
+fn mismatch_panics<P: CheckPolicy>(pv: PolicyVBox<P>) -> bool {
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let _ = pv.unpack::<dyn Display>();
+    }));
+    res.is_err()
+}
+
+#[test]
+fn test_checked() {
+    let mut pv: PolicyVBox<Checked> = into_vbox!(dyn Debug, 1u64).into();
+    assert!(pv.is_dyn::<dyn Debug>());
+    assert_eq!("1", format!("{:?}", pv.as_dyn::<dyn Debug>()));
+    assert_eq!("1", format!("{:?}", pv.as_dyn_mut::<dyn Debug>()));
+    assert_eq!("1", format!("{:?}", pv.unpack::<dyn Debug>()));
+
+    let pv = PolicyVBox::<Checked>::new(into_vbox!(dyn Debug, 1u64));
+    assert!(mismatch_panics(pv));
+}
+
+#[test]
+fn test_logging() {
+    let pv = PolicyVBox::<Logging>::new(into_vbox!(dyn Debug, 2u64));
+    assert_eq!("2", format!("{:?}", pv.unpack::<dyn Debug>()));
+
+    let pv = PolicyVBox::<Logging>::new(into_vbox!(dyn Debug, 2u64));
+    assert!(mismatch_panics(pv));
+}
+
+#[test]
+fn test_debug_checked() {
+    let pv = unsafe {
+        PolicyVBox::<DebugChecked>::new_unchecked(into_vbox!(dyn Debug, 3u64))
+    };
+    assert_eq!("3", format!("{:?}", pv.unpack::<dyn Debug>()));
+
+    if cfg!(debug_assertions) {
+        let pv = unsafe {
+            PolicyVBox::<DebugChecked>::new_unchecked(into_vbox!(
+                dyn Debug,
+                3u64
+            ))
+        };
+        assert!(mismatch_panics(pv));
+    }
+}
+
+#[test]
+fn test_unchecked() {
+    let pv = unsafe {
+        PolicyVBox::<Unchecked>::new_unchecked(into_vbox!(dyn Debug, 4u64))
+    };
+    assert_eq!("4", format!("{:?}", pv.as_dyn::<dyn Debug>()));
+
+    // Back to a VBox, which always checks.
+    let vb: VBox = pv.into_vbox();
+    assert!(vb.is_dyn::<dyn Debug>());
+}
+
+/// A user defined policy.
+struct OnlyDebug;
+
+impl CheckPolicy for OnlyDebug {
+    fn check<U>(vbox: &VBox)
+    where U: ?Sized + 'static {
+        assert!(vbox.is_dyn::<U>(), "type mismatch");
+        assert!(vbox.is_dyn::<dyn Debug>(), "only dyn Debug is allowed");
+    }
+}
+
+unsafe impl Verified for OnlyDebug {}
+
+#[test]
+fn test_user_defined_policy() {
+    let pv = PolicyVBox::<OnlyDebug>::new(into_vbox!(dyn Debug, 5u64));
+    assert_eq!("5", format!("{:?}", pv.unpack::<dyn Debug>()));
+
+    let pv = PolicyVBox::<OnlyDebug>::new(into_vbox!(dyn Display, 5u64));
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let _ = pv.unpack::<dyn Display>();
+    }));
+    assert!(res.is_err());
+}
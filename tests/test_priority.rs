@@ -0,0 +1,102 @@
+use std::any::Any;
+
+use vbox::into_vbox;
+use vbox::PriorityMailbox;
+use vbox::VBox;
+
+fn msg(n: u64) -> VBox {
+    into_vbox!(dyn Any + Send, n)
+}
+
+fn pop_all(mailbox: &mut PriorityMailbox) -> Vec<(u8, u64)> {
+    let mut got = vec![];
+    while let Some((p, m)) = mailbox.pop_with_priority() {
+        got.push((p, m.into_inner::<u64>().unwrap()));
+    }
+    got
+}
+
+#[test]
+fn test_priority_mailbox_order() {
+    let mut mailbox = PriorityMailbox::new();
+    assert!(mailbox.is_empty());
+
+    mailbox.push(0, msg(1));
+    mailbox.push(5, msg(2));
+    mailbox.push(0, msg(3));
+    mailbox.push(9, msg(4));
+    mailbox.push(5, msg(5));
+
+    assert_eq!(5, mailbox.len());
+    assert!(!mailbox.is_empty());
+
+    assert_eq!(
+        vec![(9, 4), (5, 2), (5, 5), (0, 1), (0, 3)],
+        pop_all(&mut mailbox)
+    );
+    assert!(mailbox.is_empty());
+}
+
+#[test]
+fn test_priority_mailbox_starvation() {
+    let mut mailbox = PriorityMailbox::new().with_starvation_limit(2);
+
+    mailbox.push(0, msg(0));
+    for i in 1..=5 {
+        mailbox.push(9, msg(i));
+    }
+
+    assert_eq!(
+        vec![(9, 1), (9, 2), (9, 3), (0, 0), (9, 4), (9, 5)],
+        pop_all(&mut mailbox)
+    );
+
+    // Messages pushed later are not starved by earlier deliveries.
+    mailbox.push(0, msg(6));
+    mailbox.push(9, msg(7));
+    assert_eq!(vec![(9, 7), (0, 6)], pop_all(&mut mailbox));
+}
+
+#[test]
+fn test_priority_mailbox_no_starvation_limit() {
+    let mut mailbox = PriorityMailbox::new().with_starvation_limit(u64::MAX);
+
+    mailbox.push(0, msg(0));
+    for i in 1..=100 {
+        mailbox.push(9, msg(i));
+    }
+
+    let got = pop_all(&mut mailbox);
+    assert_eq!(Some(&(0, 0)), got.last());
+}
+
+#[test]
+fn test_priority_mailbox_dump() {
+    let mut mailbox = PriorityMailbox::new();
+    mailbox.push(1, msg(1));
+    mailbox.push(5, into_vbox!(dyn Any + Send, "x"));
+    mailbox.push(1, msg(2));
+
+    let dump = mailbox.dump();
+    let tags = dump
+        .entries
+        .iter()
+        .map(|e| (e.type_name, e.tag.clone().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            ("&str", "priority 5".to_string()),
+            ("u64", "priority 1".to_string()),
+            ("u64", "priority 1".to_string()),
+        ],
+        tags
+    );
+    assert!(dump
+        .to_string()
+        .starts_with("PriorityMailbox: 3 pending, 32 bytes"));
+
+    assert_eq!(3, mailbox.len());
+    while let Some(m) = mailbox.pop() {
+        m.discard();
+    }
+}
@@ -0,0 +1,272 @@
+use std::any::Any;
+use std::future::Future;
+use std::thread;
+
+use futures::executor::block_on;
+use vbox::into_vbox;
+use vbox::TryRecvError;
+use vbox::TrySendError;
+use vbox::VBox;
+use vbox::VQueue;
+
+fn msg(n: u64) -> VBox {
+    into_vbox!(dyn Any + Send, n)
+}
+
+#[test]
+fn test_vqueue_try_send_try_recv() {
+    let q = VQueue::bounded(2);
+    assert_eq!(2, q.capacity());
+    assert!(q.is_empty());
+
+    assert_eq!(Err(TryRecvError::Empty), q.try_recv().map(|_| ()));
+
+    q.try_send(msg(1)).unwrap();
+    q.try_send(msg(2)).unwrap();
+    assert_eq!(2, q.len());
+
+    let err = q.try_send(msg(3)).unwrap_err();
+    assert!(matches!(err, TrySendError::Full(_)));
+    assert_eq!(3, err.into_inner().into_inner::<u64>().unwrap());
+
+    assert_eq!(1, q.try_recv().unwrap().into_inner::<u64>().unwrap());
+    q.try_send(msg(3)).unwrap();
+
+    assert_eq!(2, q.try_recv().unwrap().into_inner::<u64>().unwrap());
+    assert_eq!(3, q.try_recv().unwrap().into_inner::<u64>().unwrap());
+}
+
+#[test]
+fn test_vqueue_backpressure() {
+    let q = VQueue::bounded(1);
+
+    let tx = q.clone();
+    let h = thread::spawn(move || {
+        block_on(async {
+            for i in 0..10 {
+                tx.send(msg(i)).await.unwrap();
+                assert!(tx.len() <= 1);
+            }
+            tx.close();
+        })
+    });
+
+    let got = block_on(async {
+        let mut got = vec![];
+        while let Some(m) = q.recv().await {
+            got.push(m.into_inner::<u64>().unwrap());
+        }
+        got
+    });
+
+    h.join().unwrap();
+    assert_eq!((0..10).collect::<Vec<_>>(), got);
+}
+
+#[test]
+fn test_vqueue_repoll_does_not_grow_wakers() {
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::task::Context;
+
+    use futures::task::ArcWake;
+
+    struct Task;
+
+    impl ArcWake for Task {
+        fn wake_by_ref(_task: &Arc<Self>) {}
+    }
+
+    let task = Arc::new(Task);
+    let waker = futures::task::waker(task.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let q = VQueue::bounded(1);
+    let mut recv = pin!(q.recv());
+
+    // Each waker kept by the queue holds a reference to the task.
+    for _ in 0..10 {
+        assert!(recv.as_mut().poll(&mut cx).is_pending());
+    }
+    assert_eq!(3, Arc::strong_count(&task));
+
+    q.try_send(msg(1)).unwrap();
+    let mut send = pin!(q.send(msg(2)));
+    for _ in 0..10 {
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+    }
+    assert_eq!(3, Arc::strong_count(&task));
+}
+
+#[test]
+fn test_vqueue_close() {
+    let q = VQueue::bounded(1);
+    q.try_send(msg(1)).unwrap();
+
+    // A sender waiting for room is woken up by close.
+    let tx = q.clone();
+    let h = thread::spawn(move || block_on(tx.send(msg(2))));
+
+    q.close();
+    assert!(q.is_closed());
+
+    let res = h.join().unwrap();
+    assert_eq!(2, res.unwrap_err().into_inner::<u64>().unwrap());
+
+    let err = q.try_send(msg(3)).unwrap_err();
+    assert!(matches!(err, TrySendError::Closed(_)));
+
+    // Messages in the queue are still received.
+    let got = block_on(q.recv()).unwrap();
+    assert_eq!(1, got.into_inner::<u64>().unwrap());
+
+    assert!(block_on(q.recv()).is_none());
+    assert_eq!(Err(TryRecvError::Closed), q.try_recv().map(|_| ()));
+}
+
+#[test]
+fn test_vqueue_watermarks_messages() {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use vbox::Watermarks;
+
+    let events = Arc::new(Mutex::new(Vec::<String>::new()));
+
+    let q = VQueue::bounded(10);
+    let (e1, e2) = (events.clone(), events.clone());
+    q.set_watermarks(
+        Watermarks::messages(1, 3)
+            .on_high(move |d| e1.lock().unwrap().push(format!("high {}", d)))
+            .on_low(move |d| e2.lock().unwrap().push(format!("low {}", d))),
+    );
+    let bp = q.backpressure();
+
+    for i in 0..4 {
+        q.try_send(msg(i)).unwrap();
+    }
+    assert!(bp.is_high());
+    assert_eq!(4, bp.depth());
+
+    for _ in 0..3 {
+        q.try_recv().unwrap().discard();
+    }
+    assert!(!bp.is_high());
+
+    // Rising again fires again.
+    block_on(async {
+        q.send(msg(5)).await.unwrap();
+        q.send(msg(6)).await.unwrap();
+    });
+    assert!(bp.is_high());
+
+    assert_eq!(vec!["high 3", "low 1", "high 3"], *events.lock().unwrap());
+
+    q.clear_watermarks();
+    assert!(!bp.is_high());
+    while let Ok(m) = q.try_recv() {
+        m.discard();
+    }
+}
+
+#[test]
+fn test_vqueue_watermarks_bytes() {
+    use vbox::DepthUnit;
+    use vbox::Watermarks;
+
+    let q = VQueue::bounded(10);
+    q.set_watermarks(Watermarks::new(DepthUnit::Bytes, 8, 64));
+    let bp = q.backpressure();
+
+    q.try_send(into_vbox!(dyn Any + Send, [0u8; 32])).unwrap();
+    assert_eq!(32, q.bytes());
+    assert!(!bp.is_high());
+
+    q.try_send(into_vbox!(dyn Any + Send, [0u8; 32])).unwrap();
+    assert_eq!(64, bp.depth());
+    assert!(bp.is_high());
+
+    q.try_send(msg(1)).unwrap();
+    assert_eq!(72, q.bytes());
+
+    q.try_recv().unwrap().discard();
+    q.try_recv().unwrap().discard();
+    assert_eq!(8, q.bytes());
+    assert!(!bp.is_high());
+
+    q.try_recv().unwrap().discard();
+    assert_eq!(0, q.bytes());
+}
+
+#[test]
+fn test_vqueue_backpressure_wait_low() {
+    use vbox::Watermarks;
+
+    let q = VQueue::bounded(10);
+    q.set_watermarks(Watermarks::messages(0, 2));
+    let bp = q.backpressure();
+
+    q.try_send(msg(1)).unwrap();
+    q.try_send(msg(2)).unwrap();
+    assert!(bp.is_high());
+
+    let consumer = {
+        let q = q.clone();
+        thread::spawn(move || {
+            let mut got = vec![];
+            while got.len() < 2 {
+                if let Ok(m) = q.try_recv() {
+                    got.push(m.into_inner::<u64>().unwrap());
+                } else {
+                    thread::yield_now();
+                }
+            }
+            got
+        })
+    };
+
+    // The producer throttles until the consumer drains the queue.
+    block_on(bp.wait_low());
+    assert!(!bp.is_high());
+    assert!(q.is_empty());
+
+    assert_eq!(vec![1, 2], consumer.join().unwrap());
+}
+
+#[test]
+fn test_vqueue_dump() {
+    let q = VQueue::bounded(4);
+    assert_eq!("VQueue: 0 pending, 0 bytes", q.dump().to_string());
+
+    q.try_send(msg(1)).unwrap();
+    q.try_send(into_vbox!(dyn Any + Send, [0u8; 3])).unwrap();
+
+    let dump = q.dump();
+    assert_eq!(2, dump.entries.len());
+    assert_eq!(11, dump.bytes());
+
+    let e = &dump.entries[0];
+    assert_eq!("u64", e.type_name);
+    assert_eq!("dyn core::any::Any + core::marker::Send", e.packed_as);
+    assert_eq!(8, e.size);
+    assert_eq!(None, e.tag);
+    assert!(e.age.is_some());
+    assert!(dump.entries[0].age >= dump.entries[1].age);
+
+    let s = dump.to_string();
+    let lines = s.lines().collect::<Vec<_>>();
+    assert_eq!("VQueue: 2 pending, 11 bytes", lines[0]);
+    assert!(
+        lines[1].starts_with(
+            "  #0 u64 as dyn core::any::Any + core::marker::Send, 8 bytes, age "
+        ),
+        "{}",
+        s
+    );
+    assert!(lines[2].starts_with("  #1 [u8; 3] as "), "{}", s);
+
+    // Dumping does not consume the messages.
+    assert_eq!(1, q.try_recv().unwrap().into_inner::<u64>().unwrap());
+    assert_eq!(1, q.dump().entries.len());
+    q.try_recv().unwrap().discard();
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "recycle")]
+
+use std::fmt::Debug;
+use std::sync::mpsc;
+use std::thread;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::recycle;
+use vbox::VBox;
+
+/// The capacity is global, thus all cases are in one test.
+#[test]
+fn test_recycle() {
+    // Dropped allocations are reused by the next pack of the same layout.
+    {
+        let a = into_vbox!(dyn Debug, 1u64);
+        let p = a.data_ptr();
+        drop(a);
+        assert_eq!(1, recycle::cached());
+
+        let b = into_vbox!(dyn Debug, 2i64);
+        assert_eq!(p, b.data_ptr());
+        assert_eq!(0, recycle::cached());
+        assert_eq!("2", format!("{:?}", from_vbox!(dyn Debug, b)));
+    }
+
+    // `into_inner()` releases the allocation to the cache.
+    {
+        let a = into_vbox!(dyn Debug, 3u32);
+        let p = a.data_ptr();
+        assert_eq!(Ok(3), a.into_inner::<u32>().map_err(|_| ()));
+        assert_eq!(1, recycle::cached());
+
+        // Another layout does not reuse it.
+        let b = into_vbox!(dyn Debug, 4u16);
+        assert_ne!(p, b.data_ptr());
+        assert_eq!(1, recycle::cached());
+        drop(b);
+        assert_eq!(2, recycle::cached());
+
+        recycle::clear();
+        assert_eq!(0, recycle::cached());
+    }
+
+    // Zero sized and large payloads are not cached.
+    {
+        drop(into_vbox!(dyn Debug, ()));
+        drop(into_vbox!(dyn Debug, [0u8; recycle::MAX_SIZE + 1]));
+        assert_eq!(0, recycle::cached());
+    }
+
+    // At most `capacity()` allocations are kept per layout.
+    {
+        assert_eq!(recycle::DEFAULT_CAPACITY, recycle::capacity());
+        recycle::set_capacity(2);
+
+        let vs: Vec<VBox> =
+            (0..3u64).map(|i| into_vbox!(dyn Debug, i)).collect();
+        drop(vs);
+        assert_eq!(2, recycle::cached());
+
+        recycle::set_capacity(recycle::DEFAULT_CAPACITY);
+        recycle::clear();
+    }
+
+    // Ping-pong: each thread packs into what it freed.
+    {
+        let (tx1, rx1) = mpsc::channel::<VBox>();
+        let (tx2, rx2) = mpsc::channel::<VBox>();
+
+        let h = thread::spawn(move || {
+            for vbox in rx1 {
+                let n = from_vbox!(dyn Debug + Send, vbox);
+                let s = format!("{:?}", n);
+                drop(n);
+                tx2.send(into_vbox!(dyn Debug + Send, s.len() as u64)).unwrap();
+            }
+        });
+
+        tx1.send(into_vbox!(dyn Debug + Send, 0u64)).unwrap();
+        for i in 0..100u64 {
+            let reply = rx2.recv().unwrap();
+            assert_eq!(Ok(1), reply.into_inner::<u64>().map_err(|_| ()));
+            assert_eq!(1, recycle::cached());
+            tx1.send(into_vbox!(dyn Debug + Send, i % 10)).unwrap();
+        }
+        drop(tx1);
+        h.join().unwrap();
+        recycle::clear();
+    }
+}
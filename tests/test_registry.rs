@@ -0,0 +1,63 @@
+use std::fmt::Debug;
+use std::thread;
+
+use vbox::get_as;
+use vbox::into_vbox;
+use vbox::registry;
+use vbox::registry::GetError;
+use vbox::VBox;
+
+trait Clock {
+    fn now(&self) -> u64;
+}
+
+struct FixedClock(u64);
+
+impl Clock for FixedClock {
+    fn now(&self) -> u64 {
+        self.0
+    }
+}
+
+#[test]
+fn test_registry_set_once() {
+    assert!(!registry::contains("test_set_once"));
+
+    registry::set("test_set_once", into_vbox!(dyn Clock + Sync, FixedClock(1)))
+        .unwrap();
+    assert!(registry::contains("test_set_once"));
+
+    let res = registry::set("test_set_once", VBox::unit());
+    assert!(res.unwrap_err().is_unit(), "can only be set once");
+
+    let clock = get_as!("test_set_once", dyn Clock + Sync).unwrap();
+    assert_eq!(1, clock.now());
+
+    let h = thread::spawn(|| {
+        get_as!("test_set_once", dyn Clock + Sync).unwrap().now()
+    });
+    assert_eq!(1, h.join().unwrap());
+}
+
+#[test]
+fn test_registry_errors() {
+    let res = get_as!("test_not_found", dyn Clock + Sync);
+    assert_eq!(
+        Err(GetError::NotFound {
+            name: "test_not_found".to_string()
+        }),
+        res.map(|_| ())
+    );
+
+    registry::set("test_mismatch", into_vbox!(dyn Debug + Sync, 1u64)).unwrap();
+
+    let res = get_as!("test_mismatch", dyn Clock + Sync);
+    let err = res.map(|_| ()).unwrap_err();
+    assert!(matches!(err, GetError::TypeMismatch { .. }));
+    assert!(err
+        .to_string()
+        .starts_with("test_mismatch is not registered as: "));
+
+    let debug = get_as!("test_mismatch", dyn Debug + Sync).unwrap();
+    assert_eq!("1", format!("{:?}", debug));
+}
@@ -0,0 +1,68 @@
+use std::fmt::Debug;
+use std::sync::mpsc;
+
+use futures::executor::block_on;
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::run_remote;
+use vbox::run_remote_on;
+
+#[test]
+fn test_run_remote() {
+    let main = std::thread::current().id();
+
+    let res = block_on(run_remote(move || {
+        let on_other_thread = std::thread::current().id() != main;
+        into_vbox!(dyn Debug, on_other_thread)
+    }));
+
+    assert!(res.unwrap().into_inner::<bool>().unwrap());
+}
+
+#[test]
+fn test_run_remote_panic() {
+    let res = block_on(run_remote(|| panic!("job failed: {}", 3)));
+
+    let p = res.unwrap_err();
+    assert_eq!(Some("job failed: 3"), p.message());
+}
+
+#[test]
+fn test_run_remote_on() {
+    // A single worker that runs the submitted tasks.
+    let (task_tx, task_rx) = mpsc::channel::<Box<dyn FnOnce() + Send>>();
+    let worker = std::thread::spawn(move || {
+        for task in task_rx {
+            task();
+        }
+    });
+
+    let f1 = run_remote_on(
+        |t| task_tx.send(t).unwrap(),
+        || into_vbox!(dyn Debug, 1u64),
+    );
+    let f2 = run_remote_on(
+        |t| task_tx.send(t).unwrap(),
+        || into_vbox!(dyn Debug, 2u64),
+    );
+
+    assert_eq!(
+        "1",
+        format!("{:?}", from_vbox!(dyn Debug, block_on(f1).unwrap()))
+    );
+    assert_eq!(
+        "2",
+        format!("{:?}", from_vbox!(dyn Debug, block_on(f2).unwrap()))
+    );
+
+    drop(task_tx);
+    worker.join().unwrap();
+}
+
+#[test]
+fn test_run_remote_on_dropped_task() {
+    let res = block_on(run_remote_on(drop, || into_vbox!(dyn Debug, 1u64)));
+
+    let p = res.unwrap_err();
+    assert_eq!(Some("the job is dropped before it completes"), p.message());
+}
@@ -0,0 +1,106 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Debug;
+use std::time::Duration;
+
+use vbox::into_vbox;
+use vbox::Retry;
+use vbox::VError;
+
+#[derive(Debug)]
+struct Unavailable(u32);
+
+impl fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unavailable: attempt {}", self.0)
+    }
+}
+
+impl Error for Unavailable {}
+
+fn ms(n: u64) -> Duration {
+    Duration::from_millis(n)
+}
+
+#[test]
+fn test_retry_backoff() {
+    let r = Retry::new()
+        .initial_backoff(ms(10))
+        .max_backoff(ms(50))
+        .multiplier(2.0);
+
+    assert_eq!(ms(10), r.backoff(1));
+    assert_eq!(ms(20), r.backoff(2));
+    assert_eq!(ms(40), r.backoff(3));
+    assert_eq!(ms(50), r.backoff(4));
+    assert_eq!(ms(50), r.backoff(u32::MAX));
+}
+
+#[test]
+fn test_retry_gives_up_with_last_error() {
+    let r = Retry::new().max_attempts(4).initial_backoff(ms(10)).jitter(0.0);
+
+    let mut delays = vec![];
+    let mut attempt = 0;
+    let err = r
+        .run_with_sleep(
+            || {
+                attempt += 1;
+                Err(VError::new(Unavailable(attempt)))
+            },
+            |d| delays.push(d),
+        )
+        .unwrap_err();
+
+    assert_eq!(4, attempt);
+    assert_eq!(vec![ms(10), ms(20), ms(40)], delays);
+    assert_eq!("unavailable: attempt 4", err.to_string());
+    assert!(err.find::<Unavailable>().is_some());
+}
+
+#[test]
+fn test_retry_jitter() {
+    let r = Retry::new()
+        .max_attempts(50)
+        .initial_backoff(ms(100))
+        .multiplier(1.0)
+        .jitter(0.5);
+
+    let mut delays = vec![];
+    let _ = r.run_with_sleep(
+        || Err(VError::new(Unavailable(0))),
+        |d| delays.push(d),
+    );
+
+    assert_eq!(49, delays.len());
+    assert!(delays.iter().all(|d| *d > ms(50) && *d <= ms(100)));
+    assert!(
+        delays.iter().any(|d| *d != delays[0]),
+        "delays are jittered"
+    );
+}
+
+#[test]
+fn test_retry_run_vbox() {
+    let mut attempt = 0u32;
+    let op = into_vbox!(
+        dyn FnMut() -> Result<vbox::VBox, VError> + Send,
+        move || {
+            attempt += 1;
+            if attempt < 2 {
+                Err(VError::new(Unavailable(attempt)))
+            } else {
+                Ok(into_vbox!(dyn Debug + Send, attempt))
+            }
+        }
+    );
+
+    let res = Retry::new().initial_backoff(ms(1)).run_vbox(op).unwrap();
+    assert_eq!(2u32, res.into_inner::<u32>().unwrap());
+}
+
+#[test]
+#[should_panic(expected = "max_attempts must be at least 1")]
+fn test_retry_zero_attempts() {
+    let _ = Retry::new().max_attempts(0);
+}
@@ -0,0 +1,156 @@
+use std::any::Any;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+use vbox::into_vbox;
+use vbox::Router;
+use vbox::TaggedVBox;
+use vbox::VBox;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    Add,
+    Reset,
+    Unknown,
+}
+
+#[test]
+fn test_router_multiplexed_channel() {
+    let total = Arc::new(Mutex::new(0u64));
+
+    let mut router = Router::new();
+
+    let t = total.clone();
+    router.on(Kind::Add, move |vbox| {
+        *t.lock().unwrap() += vbox.into_inner::<u64>().unwrap();
+    });
+
+    let t = total.clone();
+    router
+        .register(
+            Kind::Reset,
+            into_vbox!(dyn FnMut(VBox) + Send, move |_vbox: VBox| {
+                *t.lock().unwrap() = 0;
+            }),
+        )
+        .unwrap();
+
+    // Not packed as a handler
+    let res = router.register(Kind::Unknown, VBox::unit());
+    assert!(res.unwrap_err().is_unit());
+
+    assert!(router.contains(&Kind::Add));
+    assert!(!router.contains(&Kind::Unknown));
+
+    let (tx, rx) = mpsc::channel::<TaggedVBox<Kind>>();
+
+    let h = thread::spawn(move || {
+        let mut unrouted = vec![];
+        for msg in rx {
+            if let Err(msg) = router.route(msg) {
+                unrouted.push(msg.into_parts());
+            }
+        }
+        unrouted
+    });
+
+    let add =
+        |n: u64| TaggedVBox::new(Kind::Add, into_vbox!(dyn Any + Send, n));
+
+    tx.send(add(3)).unwrap();
+    tx.send(TaggedVBox::new(Kind::Reset, VBox::unit())).unwrap();
+    tx.send(add(4)).unwrap();
+    tx.send(add(5)).unwrap();
+    tx.send(TaggedVBox::new(Kind::Unknown, VBox::unit())).unwrap();
+    drop(tx);
+
+    let unrouted = h.join().unwrap();
+    assert_eq!(9, *total.lock().unwrap());
+
+    assert_eq!(1, unrouted.len());
+    assert_eq!(Kind::Unknown, unrouted[0].0);
+    assert!(unrouted[0].1.is_unit());
+}
+
+#[test]
+fn test_router_unregister() {
+    let mut router = Router::new();
+    router.on(1u8, |_vbox| {});
+
+    assert!(router.route(TaggedVBox::new(1, VBox::unit())).is_ok());
+
+    assert!(router.unregister(&1).is_some());
+    assert!(router.route(TaggedVBox::new(1, VBox::unit())).is_err());
+}
+
+#[test]
+fn test_fanout_filters() {
+    use std::fmt::Debug;
+    use std::fmt::Display;
+
+    use vbox::Fanout;
+    use vbox::RouteFilter;
+
+    let log = Arc::new(Mutex::new(Vec::<String>::new()));
+    let mut fanout = Fanout::new();
+
+    let l = log.clone();
+    let all = fanout.subscribe(RouteFilter::all(), move |msg| {
+        l.lock().unwrap().push(format!("all {:?}", msg.tag));
+    });
+
+    let l = log.clone();
+    fanout.subscribe(
+        RouteFilter::tag(Kind::Add)
+            .and(RouteFilter::dyn_trait::<dyn Display>()),
+        move |msg| {
+            let d = msg.vbox.as_dyn::<dyn Display>();
+            l.lock().unwrap().push(format!("add {}", d));
+        },
+    );
+
+    let l = log.clone();
+    fanout.subscribe(
+        RouteFilter::predicate(|msg| msg.vbox.is::<u64>())
+            .or(RouteFilter::tag(Kind::Reset)),
+        move |msg| {
+            l.lock().unwrap().push(format!("u64/reset {:?}", msg.tag));
+        },
+    );
+    assert_eq!(3, fanout.len());
+
+    let msgs = [
+        TaggedVBox::new(Kind::Add, into_vbox!(dyn Display, 1u64)),
+        TaggedVBox::new(Kind::Add, into_vbox!(dyn Debug, 2u32)),
+        TaggedVBox::new(Kind::Reset, VBox::unit()),
+    ];
+    let delivered = msgs.iter().map(|m| fanout.publish(m)).collect::<Vec<_>>();
+    assert_eq!(vec![3, 1, 2], delivered);
+
+    assert_eq!(
+        vec![
+            "all Add",
+            "add 1",
+            "u64/reset Add",
+            "all Add",
+            "all Reset",
+            "u64/reset Reset"
+        ],
+        *log.lock().unwrap()
+    );
+
+    // The payloads are not moved out.
+    let [a, b, c] = msgs;
+    assert_eq!(1u64, a.vbox.into_inner::<u64>().unwrap());
+    assert_eq!(2u32, b.vbox.into_inner::<u32>().unwrap());
+    assert!(c.vbox.is_unit());
+
+    assert!(fanout.unsubscribe(all));
+    assert!(!fanout.unsubscribe(all));
+    assert_eq!(
+        0,
+        fanout.publish(&TaggedVBox::new(Kind::Unknown, VBox::unit()))
+    );
+}
@@ -0,0 +1,71 @@
+use std::future::Future;
+use std::panic;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use futures::channel::oneshot;
+use futures::executor::block_on;
+use vbox::into_vbox;
+
+#[test]
+fn test_scope_waits_for_all_tasks() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let ret = vbox::scope(|s| {
+        for i in 0..5u64 {
+            let l = log.clone();
+            s.spawn_erased(into_vbox!(dyn FnOnce() + Send, move || {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                l.lock().unwrap().push(i);
+            }));
+        }
+
+        let l = log.clone();
+        s.spawn(move || l.lock().unwrap().push(100));
+        "done"
+    });
+
+    assert_eq!("done", ret);
+
+    let mut got = log.lock().unwrap().clone();
+    got.sort();
+    assert_eq!(vec![0, 1, 2, 3, 4, 100], got);
+}
+
+#[test]
+fn test_scope_propagates_panic() {
+    let res = panic::catch_unwind(|| {
+        vbox::scope(|s| {
+            s.spawn_erased(into_vbox!(dyn FnOnce() + Send, || panic!("boom")));
+        })
+    });
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_async_scope_polls_concurrently() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    // The first task waits for the second one.
+    let (tx, rx) = oneshot::channel::<u64>();
+
+    let l = log.clone();
+    let l2 = log.clone();
+    let fu = vbox::async_scope(move |s| {
+        s.spawn_erased(into_vbox!(
+            dyn Future<Output = ()> + Send,
+            async move {
+                let n = rx.await.unwrap();
+                l.lock().unwrap().push(n);
+            }
+        ));
+        s.spawn(async move {
+            l2.lock().unwrap().push(1);
+            tx.send(2).unwrap();
+        });
+        3
+    });
+
+    assert_eq!(3, block_on(fu));
+    assert_eq!(vec![1, 2], *log.lock().unwrap());
+}
@@ -0,0 +1,350 @@
+#![cfg(feature = "script")]
+
+use std::any::Any;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::thread;
+
+use vbox::into_vbox;
+use vbox::script::ScriptBridge;
+use vbox::script::ScriptError;
+use vbox::VBox;
+use vbox::VError;
+
+fn arg<T: Send + 'static>(v: T) -> VBox {
+    into_vbox!(dyn Any + Send, v)
+}
+
+#[test]
+fn test_script_bridge_register_fn() {
+    let mut bridge = ScriptBridge::new();
+    assert!(!bridge.register_fn("answer", || 42i64));
+    assert!(!bridge.register_fn("neg", |a: i64| -a));
+    assert!(!bridge.register_fn("concat", |a: String, b: &'static str| a + b));
+    assert!(!bridge
+        .register_fn("sum4", |a: i64, b: i64, c: i64, d: i64| a + b + c + d));
+
+    assert_eq!(vec!["answer", "concat", "neg", "sum4"], bridge.names());
+
+    let got = bridge.call("answer", vec![]).unwrap();
+    assert_eq!(42, got.into_inner::<i64>().unwrap());
+
+    let got = bridge.call("neg", vec![arg(3i64)]).unwrap();
+    assert_eq!(-3, got.into_inner::<i64>().unwrap());
+
+    let got =
+        bridge.call("concat", vec![arg("a".to_string()), arg("b")]).unwrap();
+    assert_eq!("ab", got.into_inner::<String>().unwrap());
+
+    let args = (1..=4i64).map(arg).collect();
+    let got = bridge.call("sum4", args).unwrap();
+    assert_eq!(10, got.into_inner::<i64>().unwrap());
+
+    // Replaced
+    assert!(bridge.register_fn("answer", || 0i64));
+    assert!(bridge.unregister("answer"));
+    assert!(!bridge.contains("answer"));
+}
+
+#[test]
+fn test_script_bridge_errors() {
+    let mut bridge = ScriptBridge::new();
+    bridge.register_fn("neg", |a: i64| -a);
+    bridge.register("fail", |_args| Err(VError::new(std::fmt::Error)));
+
+    let err = bridge.call("nope", vec![]).unwrap_err();
+    assert!(matches!(err, ScriptError::NotFound { .. }));
+    assert_eq!("no host function: nope", err.to_string());
+
+    let err = bridge.call("neg", vec![arg(1i64), arg(2i64)]).unwrap_err();
+    assert_eq!("neg: expected 1 arguments, got 2", err.to_string());
+
+    let err = bridge.call("neg", vec![arg(1u8)]).unwrap_err();
+    assert_eq!("neg: argument 0 is not: i64", err.to_string());
+
+    let err = bridge.call("fail", vec![]).unwrap_err();
+    assert!(err.to_string().starts_with("fail failed: "));
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[test]
+fn test_script_bridge_opaque_host_object() {
+    trait Handle: Debug + Send {
+        fn id(&self) -> u64;
+    }
+
+    #[derive(Debug)]
+    struct File(u64);
+    impl Handle for File {
+        fn id(&self) -> u64 {
+            self.0
+        }
+    }
+
+    let mut bridge = ScriptBridge::new();
+    bridge.register("open", |_args| Ok(into_vbox!(dyn Handle, File(7))));
+    bridge.register("id", |mut args: Vec<VBox>| {
+        let handle = args.pop().unwrap().unpack::<dyn Handle>();
+        Ok(arg(handle.id()))
+    });
+
+    // The script holds the handle without knowing what it is.
+    let handle = bridge.call("open", vec![]).unwrap();
+    let id = bridge.call("id", vec![handle]).unwrap();
+    assert_eq!(7, id.into_inner::<u64>().unwrap());
+}
+
+#[test]
+fn test_script_bridge_shared() {
+    let mut bridge = ScriptBridge::new();
+    bridge.register_fn("double", |a: u64| a * 2);
+    let bridge = Arc::new(bridge);
+
+    let hs = (0..4u64)
+        .map(|i| {
+            let bridge = bridge.clone();
+            thread::spawn(move || {
+                let got = bridge.call("double", vec![arg(i)]).unwrap();
+                got.into_inner::<u64>().unwrap()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let got = hs.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>();
+    assert_eq!(vec![0, 2, 4, 6], got);
+}
+
+#[test]
+fn test_host_object() {
+    use vbox::script::HostObject;
+    use vbox::script::HostObjectError;
+
+    trait Counter: Send {
+        fn incr(&mut self) -> u64;
+    }
+
+    struct C(u64);
+    impl Counter for C {
+        fn incr(&mut self) -> u64 {
+            self.0 += 1;
+            self.0
+        }
+    }
+
+    let mut obj = HostObject::from(into_vbox!(dyn Counter, C(0)));
+    assert!(obj.is_dyn::<dyn Counter>());
+    assert!(obj.type_name().unwrap().contains("Counter"));
+
+    assert_eq!(Ok(1), obj.with_mut::<dyn Counter, _>(|c| c.incr()));
+    assert_eq!(Ok(2), obj.with_mut::<dyn Counter, _>(|c| c.incr()));
+
+    let err = obj.with::<dyn Debug, _>(|_| ()).unwrap_err();
+    assert!(matches!(err, HostObjectError::TypeMismatch { .. }));
+    assert!(err.to_string().starts_with("host object is packed as: "));
+
+    // A mismatched take leaves the payload in place.
+    assert!(obj.take::<dyn Debug>().is_err());
+    let Ok(mut c) = obj.take::<dyn Counter>() else {
+        panic!("expect Ok");
+    };
+    assert_eq!(3, c.incr());
+
+    assert!(obj.is_taken());
+    assert_eq!(None, obj.type_name());
+    assert_eq!(
+        Some(HostObjectError::Taken),
+        obj.with::<dyn Counter, _>(|_| ()).err()
+    );
+    assert!(obj.take_vbox().is_none());
+}
+
+#[test]
+fn test_host_object_through_bridge() {
+    use vbox::script::HostObject;
+
+    let mut bridge = ScriptBridge::new();
+    bridge.register("open", |_args| {
+        let obj = HostObject::new(into_vbox!(dyn Debug + Send, "conn"));
+        Ok(arg(obj))
+    });
+    bridge.register_fn("describe", |obj: HostObject| {
+        obj.with::<dyn Debug + Send, _>(|d| format!("{:?}", d)).unwrap()
+    });
+
+    let obj = bridge.call("open", vec![]).unwrap();
+    let got = bridge.call("describe", vec![obj]).unwrap();
+    assert_eq!(r#""conn""#, got.into_inner::<String>().unwrap());
+}
+
+#[cfg(feature = "rhai")]
+#[test]
+fn test_register_rhai() {
+    use vbox::script::register_rhai;
+
+    let mut bridge = ScriptBridge::new();
+    bridge.register_fn("add", |a: i64, b: i64| a + b);
+    bridge.register_fn("greet", |name: String| format!("hello {}", name));
+    bridge.register_fn("half", |a: f64| a / 2.0);
+    bridge.register_fn("flip", |a: bool| !a);
+    bridge.register_fn("unit", || ());
+    bridge.register_fn("port", || 3u16);
+
+    let mut engine = rhai::Engine::new();
+    register_rhai(&mut engine, &Arc::new(bridge));
+
+    assert_eq!(6, engine.eval::<i64>("add(add(1, 2), 3)").unwrap());
+    assert_eq!(
+        "hello rhai",
+        engine.eval::<String>(r#"greet("rh" + "ai")"#).unwrap()
+    );
+    assert_eq!(1.5, engine.eval::<f64>("half(3.0)").unwrap());
+    assert!(engine.eval::<bool>("flip(false)").unwrap());
+    engine.eval::<()>("unit()").unwrap();
+
+    let err = engine.eval::<i64>("add(1)").unwrap_err();
+    assert!(
+        err.to_string().contains("add: expected 2 arguments, got 1"),
+        "{}",
+        err
+    );
+
+    let err = engine.eval::<i64>(r#"add(1, "2")"#).unwrap_err();
+    assert!(
+        err.to_string().contains("add: argument 1 is not: i64"),
+        "{}",
+        err
+    );
+
+    let err = engine.eval::<i64>("add([1], 2)").unwrap_err();
+    assert!(
+        err.to_string().contains("add: argument 0 of type array"),
+        "{}",
+        err
+    );
+
+    let err = engine.eval::<i64>("port()").unwrap_err();
+    assert!(
+        err.to_string().contains("port: result of type u16 can not be"),
+        "{}",
+        err
+    );
+}
+
+#[cfg(feature = "mlua")]
+#[test]
+fn test_register_lua() {
+    use vbox::script::register_lua;
+
+    let mut bridge = ScriptBridge::new();
+    bridge.register_fn("add", |a: i64, b: i64| a + b);
+    bridge.register_fn("greet", |name: String| format!("hello {}", name));
+    bridge.register_fn("half", |a: f64| a / 2.0);
+    bridge.register_fn("flip", |a: bool| !a);
+    bridge.register_fn("unit", || ());
+    bridge.register_fn("port", || 3u16);
+
+    let lua = mlua::Lua::new();
+    register_lua(&lua, &Arc::new(bridge)).unwrap();
+
+    let eval = |code: &str| lua.load(code).eval::<mlua::Value>();
+
+    assert_eq!(
+        6,
+        lua.load("return add(add(1, 2), 3)").eval::<i64>().unwrap()
+    );
+    assert_eq!(
+        "hello lua",
+        lua.load(r#"return greet("l" .. "ua")"#).eval::<String>().unwrap()
+    );
+    assert_eq!(1.5, lua.load("return half(3.0)").eval::<f64>().unwrap());
+    assert!(lua.load("return flip(false)").eval::<bool>().unwrap());
+    assert!(eval("return unit()").unwrap().is_nil());
+
+    let err = eval("return add(1)").unwrap_err();
+    assert!(
+        err.to_string().contains("add: expected 2 arguments, got 1"),
+        "{}",
+        err
+    );
+
+    let err = eval(r#"return add(1, "2")"#).unwrap_err();
+    assert!(
+        err.to_string().contains("add: argument 1 is not: i64"),
+        "{}",
+        err
+    );
+
+    let err = eval("return add({}, 2)").unwrap_err();
+    assert!(
+        err.to_string().contains("add: argument 0 of type table"),
+        "{}",
+        err
+    );
+
+    let err = eval("return port()").unwrap_err();
+    assert!(
+        err.to_string().contains("port: result of type u16 can not be"),
+        "{}",
+        err
+    );
+}
+
+#[cfg(feature = "mlua")]
+#[test]
+fn test_host_object_through_lua() {
+    use vbox::script::register_lua;
+    use vbox::script::HostObject;
+    use vbox::script::HostObjectError;
+
+    trait Handle: Debug + Send {
+        fn id(&self) -> u64;
+    }
+
+    #[derive(Debug)]
+    struct File(u64);
+    impl Handle for File {
+        fn id(&self) -> u64 {
+            self.0
+        }
+    }
+
+    let mut bridge = ScriptBridge::new();
+    bridge.register_fn("open", |id: i64| {
+        HostObject::new(into_vbox!(dyn Handle, File(id as u64)))
+    });
+    bridge.register_fn("close", |mut obj: HostObject| {
+        obj.take::<dyn Handle>().unwrap().id() as i64
+    });
+
+    let lua = mlua::Lua::new();
+    register_lua(&lua, &Arc::new(bridge)).unwrap();
+
+    // The script holds the handle without knowing what it is.
+    let got = lua.load("return tostring(open(1))").eval::<String>().unwrap();
+    assert!(
+        got.starts_with("HostObject(") && got.contains("Handle"),
+        "{}",
+        got
+    );
+
+    let got = lua.load("local f = open(2); return close(f)").eval::<i64>();
+    assert_eq!(2, got.unwrap());
+
+    // Passing a handle to a host function moves the payload out.
+    let code = "local f = open(3); close(f); return tostring(f), close(f)";
+    let err = lua.load(code).eval::<mlua::Value>().unwrap_err();
+    assert!(
+        err.to_string().contains("close: argument 0 is a taken host object"),
+        "{}",
+        err
+    );
+
+    // Back in Rust, the payload is unpacked with the check of `dyn Trait`.
+    let ud = lua.load("return open(4)").eval::<mlua::AnyUserData>().unwrap();
+    let mut obj = ud.take::<HostObject>().unwrap();
+    assert!(matches!(
+        obj.take::<dyn std::fmt::Display>(),
+        Err(HostObjectError::TypeMismatch { .. })
+    ));
+    assert_eq!(4, obj.take::<dyn Handle>().unwrap().id());
+}
@@ -0,0 +1,125 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::thread;
+
+use vbox::into_vbox;
+use vbox::ShardedQueue;
+
+#[test]
+fn test_sharded_queue_local_and_steal() {
+    let q = ShardedQueue::new(3);
+    assert_eq!(3, q.shards());
+
+    q.push_to(0, into_vbox!(dyn Debug, 1u64));
+    q.push_to(0, into_vbox!(dyn Debug, 2u64));
+    q.push_to(0, into_vbox!(dyn Debug, 3u64));
+    assert_eq!(3, q.len());
+
+    // Own shard: from the front
+    assert_eq!(1u64, q.pop(0).unwrap().into_inner::<u64>().unwrap());
+
+    // Others steal from the back
+    assert!(q.pop_local(1).is_none());
+    assert_eq!(3u64, q.pop(1).unwrap().into_inner::<u64>().unwrap());
+    assert_eq!(2u64, q.pop(2).unwrap().into_inner::<u64>().unwrap());
+
+    assert!(q.pop(0).is_none());
+    assert!(q.is_empty());
+}
+
+#[test]
+fn test_sharded_queue_by_key_and_vtable() {
+    let q = ShardedQueue::new(4);
+
+    for i in 0..10u64 {
+        q.push_by_key("session-1", into_vbox!(dyn Debug, i));
+    }
+    for i in 0..10u32 {
+        q.push(into_vbox!(dyn Debug, i));
+    }
+
+    // Messages with the same key or the same vtable are in one shard, in order.
+    let mut by_key = vec![];
+    let mut by_vtable = vec![];
+
+    for shard in 0..4 {
+        let mut u64s = vec![];
+        let mut u32s = vec![];
+        while let Some(v) = q.pop_local(shard) {
+            match v.into_inner::<u64>() {
+                Ok(x) => u64s.push(x),
+                Err(v) => u32s.push(v.into_inner::<u32>().unwrap()),
+            }
+        }
+        if !u64s.is_empty() {
+            by_key.push(u64s);
+        }
+        if !u32s.is_empty() {
+            by_vtable.push(u32s);
+        }
+    }
+
+    assert_eq!(vec![(0..10u64).collect::<Vec<_>>()], by_key);
+    assert_eq!(vec![(0..10u32).collect::<Vec<_>>()], by_vtable);
+}
+
+#[test]
+fn test_sharded_queue_concurrent() {
+    let q = Arc::new(ShardedQueue::new(4));
+
+    let producers: Vec<_> = (0..4u64)
+        .map(|p| {
+            let q = q.clone();
+            thread::spawn(move || {
+                for i in 0..1000u64 {
+                    q.push_by_key(&(p, i), into_vbox!(dyn Debug, i));
+                }
+            })
+        })
+        .collect();
+    for p in producers {
+        p.join().unwrap();
+    }
+
+    let consumers: Vec<_> = (0..4)
+        .map(|c| {
+            let q = q.clone();
+            thread::spawn(move || {
+                let mut sum = 0;
+                while let Some(v) = q.pop(c) {
+                    sum += v.into_inner::<u64>().unwrap();
+                }
+                sum
+            })
+        })
+        .collect();
+
+    let sum: u64 = consumers.into_iter().map(|c| c.join().unwrap()).sum();
+    assert_eq!(4 * (0..1000u64).sum::<u64>(), sum);
+    assert!(q.is_empty());
+}
+
+#[test]
+fn test_sharded_queue_dump() {
+    let q = ShardedQueue::new(2);
+    q.push_to(1, into_vbox!(dyn Debug, 1u64));
+    q.push_to(0, into_vbox!(dyn Debug, 2u32));
+
+    let dump = q.dump();
+    let entries = dump
+        .entries
+        .iter()
+        .map(|e| (e.type_name, e.tag.clone().unwrap(), e.size))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            ("u32", "shard 0".to_string(), 4),
+            ("u64", "shard 1".to_string(), 8),
+        ],
+        entries
+    );
+
+    while let Some(m) = q.pop(0) {
+        m.discard();
+    }
+}
@@ -0,0 +1,220 @@
+use std::fmt::Debug;
+use std::thread;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::register_pod;
+use vbox::shm::PodRegistry;
+use vbox::shm::PopError;
+use vbox::shm::ShmRing;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(C)]
+struct Tick {
+    seq: u64,
+    price: f64,
+}
+
+unsafe impl vbox::shm::Pod for Tick {}
+
+fn registry() -> PodRegistry {
+    let registry = PodRegistry::new();
+    register_pod!(registry, "tick", Tick => dyn Debug + Send);
+    register_pod!(registry, "u32", u32 => dyn Debug + Send);
+    registry
+}
+
+#[test]
+fn test_shm_push_pop() {
+    let registry = registry();
+    let mut region = vec![0u64; 16];
+    let mut ring =
+        unsafe { ShmRing::init(region.as_mut_ptr() as *mut u8, 128) };
+    assert_eq!(64, ring.capacity());
+    assert!(ring.is_empty());
+
+    assert!(ring.pop(&registry).unwrap().is_none());
+
+    // Not registered
+    let vbox = into_vbox!(dyn Debug + Send, 1u64);
+    assert!(!registry.supports(&vbox));
+    let vbox = ring.push(&registry, vbox).unwrap_err();
+    assert_eq!("1", format!("{:?}", from_vbox!(dyn Debug + Send, vbox)));
+
+    let t = Tick { seq: 1, price: 2.5 };
+    ring.push(&registry, into_vbox!(dyn Debug + Send, t)).unwrap();
+    ring.push(&registry, into_vbox!(dyn Debug + Send, 7u32)).unwrap();
+    assert_eq!(32 + 24, ring.len());
+
+    // Full
+    let vbox = into_vbox!(dyn Debug + Send, 8u32);
+    let vbox = ring.push(&registry, vbox).unwrap_err();
+    assert!(vbox.is::<u32>());
+
+    let got = ring.pop(&registry).unwrap().unwrap();
+    assert!(got.is::<Tick>());
+    assert_eq!(Ok(t), got.into_inner::<Tick>().map_err(|_| ()));
+
+    // Wrap around: the record does not fit at the end.
+    ring.push(&registry, into_vbox!(dyn Debug + Send, t)).unwrap();
+
+    let got = ring.pop(&registry).unwrap().unwrap();
+    assert_eq!("7", format!("{:?}", from_vbox!(dyn Debug + Send, got)));
+
+    let got = ring.pop(&registry).unwrap().unwrap();
+    assert_eq!(
+        "Tick { seq: 1, price: 2.5 }",
+        format!("{:?}", from_vbox!(dyn Debug + Send, got))
+    );
+
+    assert!(ring.is_empty());
+}
+
+#[test]
+fn test_shm_unknown_pod() {
+    let mut region = vec![0u64; 16];
+    let mut ring =
+        unsafe { ShmRing::init(region.as_mut_ptr() as *mut u8, 128) };
+
+    ring.push(&registry(), into_vbox!(dyn Debug + Send, 7u32)).unwrap();
+
+    // The other end registers a different set of types.
+    let other = PodRegistry::new();
+    register_pod!(other, "tick", Tick => dyn Debug + Send);
+
+    let err = ring.pop(&other).unwrap_err();
+    assert!(matches!(err, PopError::UnknownPod { .. }));
+    assert!(err.to_string().starts_with("no type is registered with id: "));
+    assert!(ring.is_empty());
+}
+
+#[test]
+fn test_shm_corrupted() {
+    let registry = registry();
+    let mut region = vec![0u64; 16];
+    let ptr = region.as_mut_ptr() as *mut u8;
+    let mut ring = unsafe { ShmRing::init(ptr, 128) };
+
+    ring.push(&registry, into_vbox!(dyn Debug + Send, 7u32)).unwrap();
+
+    // The other process writes a size beyond the buffer: the header is 8
+    // u64s, the record starts with the type id and the size.
+    unsafe { *(ptr as *mut u64).add(9) = u64::MAX };
+
+    let err = ring.pop(&registry).unwrap_err();
+    assert_eq!(
+        PopError::Corrupted {
+            offset: 0,
+            size: u64::MAX
+        },
+        err
+    );
+    assert_eq!(
+        "corrupted record at offset 0 with size 18446744073709551615",
+        err.to_string()
+    );
+
+    // Nothing is popped.
+    assert!(!ring.is_empty());
+}
+
+#[test]
+fn test_shm_push_corrupted_positions() {
+    let registry = registry();
+    let mut region = vec![0u64; 16];
+    let ptr = region.as_mut_ptr() as *mut u8;
+    let mut ring = unsafe { ShmRing::init(ptr, 128) };
+
+    ring.push(&registry, into_vbox!(dyn Debug + Send, 7u32)).unwrap();
+    assert_eq!(24, ring.len());
+
+    // The header is `magic, capacity, head, tail` in u64s.
+    let tail = unsafe { (ptr as *mut u64).add(3) };
+
+    // The consumer is ahead of the producer.
+    unsafe { *tail = 100 };
+    assert_eq!(0, ring.len());
+    let vbox = ring.push(&registry, into_vbox!(dyn Debug + Send, 8u32));
+    assert!(vbox.unwrap_err().is::<u32>());
+
+    // The consumer is too far behind the producer: more than the capacity is
+    // used.
+    unsafe { *tail = 0 };
+    unsafe { *(ptr as *mut u64).add(2) = 1000 };
+    assert_eq!(64, ring.len());
+    let vbox = ring.push(&registry, into_vbox!(dyn Debug + Send, 8u32));
+    assert!(vbox.unwrap_err().is::<u32>());
+}
+
+#[test]
+fn test_shm_push_too_large() {
+    let registry = registry();
+    register_pod!(registry, "u64x8", [u64; 8] => dyn Debug + Send);
+
+    let mut region = vec![0u64; 16];
+    let mut ring =
+        unsafe { ShmRing::init(region.as_mut_ptr() as *mut u8, 128) };
+
+    // 16 bytes of the record header and 64 bytes of the payload.
+    let vbox = into_vbox!(dyn Debug + Send, [0u64; 8]);
+    let vbox = ring.push(&registry, vbox).unwrap_err();
+    assert!(vbox.is::<[u64; 8]>());
+    assert!(ring.is_empty());
+}
+
+#[test]
+fn test_shm_attach() {
+    let mut region = vec![0u64; 16];
+    let ptr = region.as_mut_ptr() as *mut u8;
+
+    let res = std::panic::catch_unwind(|| unsafe { ShmRing::attach(ptr, 128) });
+    assert!(res.is_err());
+
+    let _ring = unsafe { ShmRing::init(ptr, 128) };
+    let ring = unsafe { ShmRing::attach(ptr, 128) };
+    assert_eq!(64, ring.capacity());
+}
+
+#[test]
+fn test_shm_threads() {
+    let mut region = vec![0u64; 64];
+    let ptr = region.as_mut_ptr() as *mut u8;
+    let mut tx = unsafe { ShmRing::init(ptr, 512) };
+    let mut rx = unsafe { ShmRing::attach(ptr, 512) };
+
+    let n = 1000;
+
+    thread::scope(|s| {
+        s.spawn(move || {
+            let registry = registry();
+            for seq in 0..n {
+                let mut vbox = into_vbox!(dyn Debug + Send, Tick {
+                    seq,
+                    price: seq as f64 / 2.0
+                });
+                while let Err(v) = tx.push(&registry, vbox) {
+                    vbox = v;
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let registry = registry();
+        let mut seq = 0;
+        while seq < n {
+            let Some(vbox) = rx.pop(&registry).unwrap() else {
+                thread::yield_now();
+                continue;
+            };
+            let t = vbox.into_inner::<Tick>().map_err(|_| ()).unwrap();
+            assert_eq!(
+                Tick {
+                    seq,
+                    price: seq as f64 / 2.0
+                },
+                t
+            );
+            seq += 1;
+        }
+    });
+}
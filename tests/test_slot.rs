@@ -0,0 +1,104 @@
+use vbox::LoanError;
+use vbox::Slot;
+use vbox::VBox;
+
+/// A dispatch loop that hands the message to a user callback, and finds out
+/// whether it is returned.
+fn dispatch(
+    slot: &mut Slot,
+    callback: impl FnOnce(VBox, vbox::Loan, &mut Slot),
+) -> &'static str {
+    let (vbox, loan) = slot.loan().unwrap();
+    callback(vbox, loan, slot);
+
+    if slot.is_lost() {
+        slot.clear_lost();
+        "lost"
+    } else if slot.is_occupied() {
+        "returned"
+    } else {
+        "discarded"
+    }
+}
+
+#[test]
+fn test_slot_loan() {
+    let mut slot: Slot = Slot::new(VBox::unit());
+
+    assert_eq!("returned", dispatch(&mut slot, |v, l, s| s.restore(l, v)));
+    assert_eq!("discarded", dispatch(&mut slot, |_v, l, s| s.discard(l)));
+
+    slot.put(VBox::unit()).unwrap();
+    assert_eq!("lost", dispatch(&mut slot, |_v, _l, _s| {}));
+
+    assert!(!slot.is_loaned());
+    assert!(!slot.is_occupied());
+    assert!(slot.loan().is_none());
+}
+
+#[test]
+fn test_slot_put_take() {
+    let mut slot = Slot::empty();
+    slot.put(1u64).unwrap();
+    assert_eq!(Err(2), slot.put(2));
+
+    let (v, loan) = slot.loan().unwrap();
+    assert_eq!(1, v);
+    assert!(slot.is_loaned());
+    assert_eq!(Err(3), slot.put(3), "can not put while loaned");
+
+    slot.restore(loan, 4);
+    assert_eq!(Some(4), slot.take());
+    assert_eq!(None, slot.take());
+}
+
+#[test]
+#[should_panic(expected = "the Loan is not from this Slot")]
+fn test_slot_foreign_loan() {
+    let mut a = Slot::new(1u64);
+    let mut b = Slot::new(2u64);
+
+    let (_, _la) = a.loan().unwrap();
+    let (v, lb) = b.loan().unwrap();
+    a.restore(lb, v);
+}
+
+#[test]
+fn test_slot_check_loan() {
+    let mut a = Slot::new(1u64);
+    let mut b = Slot::new(2u64);
+
+    let (va, la) = a.loan().unwrap();
+    let (vb, lb) = b.loan().unwrap();
+    assert_ne!(la.generation(), lb.generation());
+
+    assert_eq!(Ok(()), a.check_loan(&la));
+    let err = a.check_loan(&lb).unwrap_err();
+    assert_eq!(
+        LoanError::Mismatch {
+            loan: lb.generation(),
+            current: la.generation(),
+        },
+        err
+    );
+    assert_eq!(
+        format!(
+            "the Loan is not from this Slot: loan #{}, current #{}",
+            lb.generation(),
+            la.generation()
+        ),
+        err.to_string()
+    );
+
+    a.restore(la, va);
+
+    // `a` is not loaned any more.
+    assert_eq!(
+        Err(LoanError::NotLoaned {
+            loan: lb.generation()
+        }),
+        a.check_loan(&lb)
+    );
+
+    b.restore(lb, vb);
+}
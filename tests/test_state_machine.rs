@@ -0,0 +1,107 @@
+use std::any::Any;
+
+use vbox::into_vbox;
+use vbox::Next;
+use vbox::StateMachine;
+use vbox::VBox;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Door {
+    Closed,
+    Open,
+    Locked,
+}
+
+struct Open;
+struct Close;
+struct Lock;
+
+fn locked(ctx: &mut Vec<&'static str>, _ev: VBox) -> Next<Door> {
+    ctx.push("still locked");
+    Next::Stay
+}
+
+#[test]
+fn test_state_machine_transitions() {
+    let mut sm = StateMachine::<Door, Vec<&'static str>>::new(Door::Closed);
+
+    sm.on(Door::Closed, |ctx, ev| {
+        if ev.is_signal::<Open>() {
+            ctx.push("open");
+            Next::Goto(Door::Open)
+        } else if ev.is_signal::<Lock>() {
+            ctx.push("lock");
+            Next::Goto(Door::Locked)
+        } else {
+            Next::Stay
+        }
+    });
+    sm.on(Door::Open, |ctx, ev| {
+        if ev.is_signal::<Close>() {
+            ctx.push("close");
+            Next::Goto(Door::Closed)
+        } else {
+            Next::Stay
+        }
+    });
+
+    // A plugin provided state
+    sm.register(
+        Door::Locked,
+        into_vbox!(
+            dyn FnMut(&mut Vec<&'static str>, VBox) -> Next<Door> + Send,
+            locked
+        ),
+    )
+    .unwrap();
+
+    // Not packed as a transition handler
+    let res = sm.register(Door::Locked, VBox::unit());
+    assert!(res.unwrap_err().is_unit());
+
+    let mut ctx = Vec::new();
+
+    sm.handle(&mut ctx, VBox::signal(Open)).unwrap();
+    assert_eq!(Some(&Door::Open), sm.state());
+
+    sm.handle(&mut ctx, VBox::signal(Lock)).unwrap();
+    assert_eq!(Some(&Door::Open), sm.state());
+
+    sm.handle(&mut ctx, VBox::signal(Close)).unwrap();
+    sm.handle(&mut ctx, VBox::signal(Lock)).unwrap();
+    sm.handle(&mut ctx, VBox::signal(Open)).unwrap();
+    assert_eq!(Some(&Door::Locked), sm.state());
+
+    assert_eq!(vec!["open", "close", "lock", "still locked"], ctx);
+}
+
+#[test]
+fn test_state_machine_done_and_unhandled() {
+    let mut sm = StateMachine::<u8, u64>::new(0);
+
+    sm.on(0, |ctx, ev| {
+        let any = vbox::from_vbox!(dyn Any + Send, ev);
+        *ctx += *any.downcast::<u64>().unwrap();
+        Next::Goto(1)
+    });
+    sm.on(1, |_ctx, _ev| Next::Done);
+
+    let mut ctx = 0;
+
+    sm.handle(&mut ctx, into_vbox!(dyn Any + Send, 3u64)).unwrap();
+    assert_eq!(3, ctx);
+
+    sm.handle(&mut ctx, VBox::unit()).unwrap();
+    assert!(sm.is_done());
+    assert_eq!(None, sm.state());
+
+    // Done state machine returns the event
+    let res = sm.handle(&mut ctx, VBox::unit());
+    assert!(res.unwrap_err().is_unit());
+
+    // No handler for the current state returns the event
+    let mut sm = StateMachine::<u8, u64>::new(5);
+    let res = sm.handle(&mut ctx, VBox::unit());
+    assert!(res.unwrap_err().is_unit());
+    assert_eq!(Some(&5), sm.state());
+}
@@ -0,0 +1,99 @@
+#![cfg(feature = "stats")]
+
+use std::fmt::Debug;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::into_vbox_dyn;
+use vbox::replace_vbox;
+use vbox::stats;
+use vbox::VBox;
+
+trait Job {}
+
+#[derive(Debug)]
+struct A;
+impl Job for A {}
+
+#[derive(Debug)]
+struct B(#[allow(dead_code)] u64);
+impl Job for B {}
+
+struct Big(#[allow(dead_code)] [u8; 64]);
+impl Job for Big {}
+
+/// The counters are global, thus all cases are in one test.
+#[test]
+fn test_stats() {
+    let a1 = into_vbox!(dyn Job + Send, A);
+    let a2 = into_vbox!(dyn Job + Send, A);
+    let b1 = into_vbox!(dyn Job + Send, B(1));
+
+    let _ = from_vbox!(dyn Job + Send, a1);
+    let _ = a2.into_inner::<A>().unwrap();
+    drop(b1);
+
+    let a = stats::of_type::<A>();
+    assert_eq!((2, 2, 0, 0), (a.packed, a.unpacked, a.dropped, a.live()));
+
+    let b = stats::of_type::<B>();
+    assert_eq!((1, 0, 1, 0), (b.packed, b.unpacked, b.dropped, b.live()));
+
+    let job = stats::of_trait::<dyn Job + Send>();
+    assert_eq!((3, 2, 1), (job.packed, job.unpacked, job.dropped));
+
+    // A raw VBox is live
+    let raw = into_vbox!(dyn Job + Send, B(2)).into_raw();
+    assert_eq!(1, stats::of_type::<B>().live());
+    let _ = unsafe { VBox::from_raw(raw) }.into_inner::<B>().unwrap();
+    assert_eq!(0, stats::of_type::<B>().live());
+
+    // The replaced payload is dropped.
+    let mut vb = into_vbox!(dyn Job + Send, B(3));
+    replace_vbox!(dyn Job + Send, &mut vb, B(4));
+    let b = stats::of_type::<B>();
+    assert_eq!((4, 2, 1), (b.packed, b.dropped, b.live()));
+    assert_eq!(0, stats::of_type::<()>().packed, "no placeholder is packed");
+    drop(vb);
+
+    // The concrete type is unknown.
+    let _ = into_vbox_dyn!(dyn Job + Send, Box::new(A));
+    let a = stats::of_type::<A>();
+    assert_eq!((2, 0), (a.packed, a.dropped));
+    assert_eq!(7, stats::of_trait::<dyn Job + Send>().packed);
+
+    // Bytes held
+    let big = into_vbox!(dyn Job + Send, Big([0; 64]));
+    let b5 = into_vbox!(dyn Job + Send, B(5));
+    assert_eq!(64, stats::of_type::<Big>().live_bytes);
+    assert_eq!(8, stats::of_type::<B>().live_bytes);
+    assert_eq!(72, stats::of_trait::<dyn Job + Send>().live_bytes);
+
+    let top: Vec<_> = stats::by_type_bytes().iter().map(|c| c.name).collect();
+    assert_eq!(
+        vec![std::any::type_name::<Big>(), std::any::type_name::<B>()],
+        top
+    );
+    let top = stats::by_trait_bytes();
+    assert_eq!(std::any::type_name::<dyn Job + Send>(), top[0].name);
+
+    let _ = from_vbox!(dyn Job + Send, big);
+    drop(b5);
+    assert_eq!(0, stats::of_trait::<dyn Job + Send>().live_bytes);
+    assert!(stats::by_type_bytes().is_empty());
+
+    // Listed by name
+    let names: Vec<_> = stats::by_type().iter().map(|c| c.name).collect();
+    assert!(names.contains(&std::any::type_name::<A>()));
+    assert!(names.contains(&std::any::type_name::<B>()));
+
+    let names: Vec<_> = stats::by_trait().iter().map(|c| c.name).collect();
+    assert!(names.contains(&std::any::type_name::<dyn Job + Send>()));
+
+    // Not packed yet
+    assert_eq!(0, stats::of_trait::<dyn Debug + Sync>().packed);
+
+    stats::reset();
+    assert_eq!(0, stats::of_type::<A>().packed);
+    assert_eq!(0, stats::of_trait::<dyn Job + Send>().packed);
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "stream")]
+
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::thread;
+
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use futures::StreamExt;
+use vbox::into_vbox;
+use vbox::stream::VBoxStreamExt;
+use vbox::VBox;
+
+#[test]
+fn test_unpack_stream_combinators() {
+    let (tx, rx) = mpsc::unbounded::<VBox>();
+
+    let h = thread::spawn(move || {
+        for i in 0..5u64 {
+            tx.unbounded_send(into_vbox!(dyn Display + Send, i)).unwrap();
+        }
+    });
+
+    let got = block_on(
+        rx.unpack::<dyn Display + Send>()
+            .map(|d| d.to_string())
+            .filter(|s| futures::future::ready(s != "2"))
+            .collect::<Vec<_>>(),
+    );
+    h.join().unwrap();
+
+    assert_eq!(vec!["0", "1", "3", "4"], got);
+}
+
+#[test]
+fn test_try_unpack_stream() {
+    let (tx, rx) = mpsc::unbounded::<VBox>();
+    tx.unbounded_send(into_vbox!(dyn Display + Send, 1u64)).unwrap();
+    tx.unbounded_send(into_vbox!(dyn Debug + Send, 2u64)).unwrap();
+    drop(tx);
+
+    let got =
+        block_on(rx.try_unpack::<dyn Display + Send>().collect::<Vec<_>>());
+    assert_eq!(2, got.len());
+
+    let mut got = got.into_iter();
+    assert_eq!("1", got.next().unwrap().unwrap().to_string());
+
+    let Err(rejected) = got.next().unwrap() else {
+        panic!("expect Err");
+    };
+    assert!(rejected.is_dyn::<dyn Debug + Send>());
+    assert_eq!(2u64, rejected.into_inner::<u64>().unwrap());
+}
+
+#[test]
+fn test_unpack_stream_wrong_type_panics() {
+    let (tx, rx) = mpsc::unbounded::<VBox>();
+    tx.unbounded_send(into_vbox!(dyn Debug + Send, 1u64)).unwrap();
+    drop(tx);
+
+    let res =
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+            block_on(rx.unpack::<dyn Display + Send>().next())
+        }));
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_unpack_stream_into_inner() {
+    let (tx, rx) = mpsc::unbounded::<VBox>();
+    tx.unbounded_send(VBox::unit()).unwrap();
+    drop(tx);
+
+    let s = rx.unpack::<dyn Display + Send>().into_inner();
+    let got = block_on(s.collect::<Vec<_>>());
+    assert!(got[0].is_unit());
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "test-util")]
+
+use std::fmt::Debug;
+use std::thread;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::test_util::assert_drop_once;
+use vbox::test_util::assert_not_packed_as;
+use vbox::test_util::assert_packed_as;
+use vbox::test_util::CallRecorder;
+use vbox::test_util::DropCounter;
+use vbox::test_util::Handle;
+use vbox::test_util::Plus;
+use vbox::Router;
+use vbox::VBox;
+
+#[test]
+fn test_drop_counter() {
+    let counter = DropCounter::new();
+
+    let vb = into_vbox!(dyn Plus, counter.token());
+    let vb2 = into_vbox!(dyn Debug, counter.token());
+    assert_eq!(0, counter.count());
+
+    let got =
+        thread::spawn(move || from_vbox!(dyn Plus, vb).plus(2)).join().unwrap();
+    assert_eq!(2, got);
+    assert_eq!(1, counter.count());
+
+    drop(vb2);
+    assert_eq!(2, counter.count());
+}
+
+#[test]
+fn test_assert_drop_once() {
+    assert_drop_once(|token| {
+        let vb = into_vbox!(dyn Plus, token);
+        assert_packed_as::<dyn Plus>(&vb);
+        assert_not_packed_as::<dyn Plus + Sync>(&vb);
+    });
+}
+
+#[test]
+#[should_panic(expected = "the payload must be dropped exactly once")]
+fn test_assert_drop_once_leaked() {
+    assert_drop_once(|token| {
+        let vb = into_vbox!(dyn Plus, token);
+        let _ = vbox::leak_vbox!(dyn Plus, vb);
+    });
+}
+
+#[test]
+#[should_panic(expected = "expected VBox packed as dyn core::fmt::Debug")]
+fn test_assert_packed_as_mismatch() {
+    let vb = into_vbox!(dyn Plus, DropCounter::new().token());
+    assert_packed_as::<dyn Debug>(&vb);
+}
+
+#[test]
+fn test_call_recorder() {
+    let recorder = CallRecorder::new();
+
+    let mut router = Router::new();
+    router.on(1, recorder.handler("one"));
+    router.on(2, recorder.handler("two"));
+
+    router.route(vbox::TaggedVBox::new(2, VBox::unit())).unwrap();
+    router.route(vbox::TaggedVBox::new(1, VBox::unit())).unwrap();
+
+    let mut h =
+        from_vbox!(dyn Handle, into_vbox!(dyn Handle, recorder.handle("h")));
+    h.handle("hi");
+
+    assert_eq!(vec!["two", "one", "h: hi"], recorder.calls());
+}
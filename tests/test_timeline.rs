@@ -0,0 +1,106 @@
+#![cfg(feature = "timeline")]
+
+use std::fmt::Debug;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::into_vbox_dyn;
+use vbox::timeline;
+use vbox::timeline::Kind;
+
+/// The buffer is global, thus all cases are in one test.
+#[test]
+fn test_timeline() {
+    timeline::clear();
+    assert_eq!("", timeline::dump());
+
+    let line = line!() + 1;
+    let a = timeline::with_tag("requests", || into_vbox!(dyn Debug, 1u64));
+    let b = into_vbox_dyn!(dyn Debug + Send, Box::new(2u32));
+    let _ = from_vbox!(dyn Debug + Send, b);
+    let _ = a.into_inner::<u64>().unwrap();
+
+    let events = timeline::events();
+    let got: Vec<_> = events
+        .iter()
+        .map(|e| (e.kind, e.type_name, e.trait_name, e.tag, e.site.line()))
+        .collect();
+
+    assert_eq!(
+        vec![
+            (
+                Kind::Pack,
+                Some("u64"),
+                "dyn core::fmt::Debug",
+                Some("requests"),
+                line
+            ),
+            (
+                Kind::Pack,
+                None,
+                "dyn core::fmt::Debug + core::marker::Send",
+                None,
+                line + 1
+            ),
+            (
+                Kind::Unpack,
+                None,
+                "dyn core::fmt::Debug + core::marker::Send",
+                None,
+                line + 2
+            ),
+            (
+                Kind::Unpack,
+                Some("u64"),
+                "dyn core::fmt::Debug",
+                None,
+                line + 3
+            ),
+        ],
+        got
+    );
+    for e in events.iter() {
+        assert!(e.site.file().ends_with("test_timeline.rs"));
+    }
+    assert!(events.windows(2).all(|w| w[0].at <= w[1].at));
+
+    let dump = timeline::dump();
+    let lines: Vec<_> = dump.lines().collect();
+    assert_eq!(4, lines.len());
+    assert!(
+        lines[0].starts_with(
+            "+0.000000s pack   u64 as dyn core::fmt::Debug [requests] at "
+        ),
+        "{}",
+        lines[0]
+    );
+    assert!(
+        lines[3].contains("unpack u64 as dyn core::fmt::Debug at "),
+        "{}",
+        lines[3]
+    );
+
+    // Bounded
+    timeline::set_capacity(2);
+    assert_eq!(2, timeline::capacity());
+    assert_eq!(Kind::Unpack, timeline::events()[0].kind);
+
+    let _ = into_vbox!(dyn Debug, 3u8).into_inner::<u8>().unwrap();
+    let events = timeline::events();
+    assert_eq!(2, events.len());
+    assert_eq!(
+        (Kind::Pack, Some("u8")),
+        (events[0].kind, events[0].type_name)
+    );
+    assert_eq!(
+        (Kind::Unpack, Some("u8")),
+        (events[1].kind, events[1].type_name)
+    );
+
+    // Stopped
+    timeline::set_capacity(0);
+    let _ = into_vbox!(dyn Debug, 4u8).into_inner::<u8>().unwrap();
+    assert!(timeline::events().is_empty());
+
+    timeline::set_capacity(timeline::DEFAULT_CAPACITY);
+}
@@ -0,0 +1,133 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::time::Duration;
+use std::time::Instant;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::TtlCache;
+
+const MINUTE: Duration = Duration::from_secs(60);
+
+#[test]
+fn test_ttl_cache_insert_get_remove() {
+    let mut cache: TtlCache<String> = TtlCache::new(MINUTE);
+    assert_eq!(MINUTE, cache.default_ttl());
+    assert!(cache.is_empty());
+
+    assert!(cache
+        .insert("a".to_string(), into_vbox!(dyn Debug, 1u64))
+        .is_none());
+    cache.insert("b".to_string(), into_vbox!(dyn Display, "b"));
+    assert_eq!(2, cache.len());
+
+    assert!(cache.contains_key("a"));
+    assert_eq!(
+        "1",
+        format!("{:?}", cache.get::<dyn Debug, _>("a").unwrap())
+    );
+    assert_eq!("b", cache.get::<dyn Display, _>("b").unwrap().to_string());
+    assert!(cache.get::<dyn Debug, _>("c").is_none());
+
+    // Replace
+    let old =
+        cache.insert("a".to_string(), into_vbox!(dyn Debug, 2u64)).unwrap();
+    assert_eq!("1", format!("{:?}", from_vbox!(dyn Debug, old)));
+    assert_eq!(2, cache.len());
+
+    let vbox = cache.remove("a").unwrap();
+    assert_eq!("2", format!("{:?}", from_vbox!(dyn Debug, vbox)));
+    assert!(cache.remove("a").is_none());
+    assert_eq!(1, cache.len());
+
+    // The replaced entry does not expire the new one.
+    let expired = cache.sweep_at(Instant::now() + MINUTE * 2);
+    assert_eq!(1, expired.len());
+    assert!(cache.is_empty());
+    assert!(cache.next_expiry().is_none());
+    for (_, vbox) in expired {
+        vbox.discard();
+    }
+}
+
+#[test]
+#[should_panic(expected = "expected type_id")]
+fn test_ttl_cache_get_type_mismatch() {
+    let mut cache = TtlCache::new(MINUTE);
+    cache.insert(1u64, into_vbox!(dyn Debug, 1u64));
+    let _ = cache.get::<dyn Display, _>(&1);
+}
+
+#[test]
+fn test_ttl_cache_expire_and_sweep() {
+    let mut cache = TtlCache::new(MINUTE);
+    let start = Instant::now();
+
+    cache.insert_with_ttl(1u64, into_vbox!(dyn Debug, "one"), MINUTE * 2);
+    cache.insert(2u64, into_vbox!(dyn Debug, "two"));
+    cache.insert_with_ttl(3u64, into_vbox!(dyn Debug, "three"), Duration::ZERO);
+
+    // Expired, but not yet swept.
+    assert_eq!(3, cache.len());
+    assert!(!cache.contains_key(&3));
+    assert!(cache.get::<dyn Debug, _>(&3).is_none());
+
+    let next = cache.next_expiry().unwrap();
+    assert!(next <= Instant::now());
+
+    let expired = cache.sweep();
+    assert_eq!(vec![3], expired.iter().map(|(k, _)| *k).collect::<Vec<_>>());
+    assert_eq!(2, cache.len());
+
+    let next = cache.next_expiry().unwrap();
+    assert!(next >= start + MINUTE);
+    assert!(next < start + MINUTE * 2);
+
+    // Sweep in the order they expire.
+    let expired = cache.sweep_at(start + MINUTE * 3);
+    let got: Vec<_> = expired
+        .into_iter()
+        .map(|(k, v)| (k, format!("{:?}", from_vbox!(dyn Debug, v))))
+        .collect();
+    assert_eq!(
+        vec![(2, "\"two\"".to_string()), (1, "\"one\"".to_string())],
+        got
+    );
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_ttl_cache_remove_expired() {
+    let mut cache = TtlCache::new(Duration::ZERO);
+    cache.insert("a", into_vbox!(dyn Debug, 1u64));
+
+    assert!(cache.remove("a").is_none());
+    assert!(cache.is_empty());
+    assert!(cache.next_expiry().is_none());
+}
+
+#[test]
+fn test_ttl_cache_never_expire() {
+    let mut cache = TtlCache::new(Duration::MAX);
+    cache.insert("a", into_vbox!(dyn Debug, 1u64));
+
+    assert!(cache.contains_key("a"));
+    assert!(cache.next_expiry().is_none());
+    assert!(cache.sweep_at(Instant::now() + MINUTE * 60).is_empty());
+
+    let vbox = cache.remove("a").unwrap();
+    assert_eq!("1", format!("{:?}", from_vbox!(dyn Debug, vbox)));
+}
+
+#[test]
+fn test_ttl_cache_insert_replaces_expired() {
+    let mut cache = TtlCache::new(MINUTE);
+    cache.insert_with_ttl("a", into_vbox!(dyn Debug, 1u64), Duration::ZERO);
+
+    // The expired entry is not returned, as remove() does.
+    assert!(cache.insert("a", into_vbox!(dyn Debug, 2u64)).is_none());
+    assert_eq!(1, cache.len());
+
+    let vbox = cache.remove("a").unwrap();
+    assert_eq!("2", format!("{:?}", from_vbox!(dyn Debug, vbox)));
+}
@@ -0,0 +1,77 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::panic;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::into_vbox_dyn;
+use vbox::replace_vbox;
+use vbox::TypeCheck;
+use vbox::VBox;
+
+/// The global mode is shared by all tests in this file, thus all cases are in
+/// one test.
+#[test]
+fn test_type_check() {
+    assert_eq!(TypeCheck::TypeId, TypeCheck::global());
+
+    // Per VBox
+    let mut vb: VBox = into_vbox!(dyn Debug + Send, 10u64);
+    assert_eq!(TypeCheck::TypeId, vb.type_check());
+
+    unsafe { vb.set_type_check(TypeCheck::TypeName) };
+    assert_eq!(TypeCheck::TypeName, vb.type_check());
+
+    assert!(vb.is_dyn::<dyn Debug + Send>());
+    assert!(!vb.is_dyn::<dyn Debug>());
+    assert!(!vb.is_dyn::<dyn Display>());
+    assert!(vb.is::<u64>());
+    assert!(!vb.is::<i64>());
+    assert!(!vb.is::<u32>());
+
+    // Kept when replaced, and in raw parts
+    replace_vbox!(dyn Debug + Send, &mut vb, 11u64);
+    assert_eq!(TypeCheck::TypeName, vb.type_check());
+    replace_vbox!(dyn Debug + Send, &mut vb, 12u8);
+    assert_eq!(TypeCheck::TypeName, vb.type_check());
+
+    let vb = unsafe { VBox::from_raw(vb.into_raw()) };
+    assert_eq!(TypeCheck::TypeName, vb.type_check());
+
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let _ = from_vbox!(dyn Debug, vb);
+    }));
+    let err = res.unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert!(msg.contains("expected type_id"), "{}", msg);
+
+    // The concrete type is unknown
+    let mut vb = into_vbox_dyn!(dyn Debug + Send, Box::new(1u64));
+    unsafe { vb.set_type_check(TypeCheck::TypeName) };
+    assert!(!vb.is::<u64>());
+    assert!(vb.is_dyn::<dyn Debug + Send>());
+
+    // The vtable of a zero-sized payload agrees with its layout too
+    let mut vb: VBox = into_vbox!(dyn Debug + Send, ());
+    unsafe { vb.set_type_check(TypeCheck::TypeName) };
+    assert!(vb.is_dyn::<dyn Debug + Send>());
+
+    // Global
+    unsafe { TypeCheck::set_global(TypeCheck::TypeName) };
+    assert_eq!(TypeCheck::TypeName, TypeCheck::global());
+
+    let vb = into_vbox!(dyn Display, 5u64);
+    assert_eq!(TypeCheck::TypeName, vb.type_check());
+    assert!(vb.is::<u64>());
+    let vb = vb.into_inner::<u32>().unwrap_err();
+    assert_eq!("5", from_vbox!(dyn Display, vb).to_string());
+
+    // The per VBox mode takes precedence
+    let mut vb = into_vbox!(dyn Display, 6u64);
+    unsafe { vb.set_type_check(TypeCheck::TypeId) };
+    assert_eq!(TypeCheck::TypeId, vb.type_check());
+    assert_eq!(6u64, vb.into_inner::<u64>().unwrap());
+
+    unsafe { TypeCheck::set_global(TypeCheck::TypeId) };
+    assert_eq!(TypeCheck::TypeId, TypeCheck::global());
+}
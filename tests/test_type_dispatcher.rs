@@ -0,0 +1,92 @@
+use std::any::Any;
+use std::fmt::Debug;
+use std::sync::mpsc;
+use std::thread;
+
+use vbox::into_vbox;
+use vbox::into_vbox_dyn;
+use vbox::TypeDispatcher;
+use vbox::VBox;
+
+#[derive(Debug)]
+struct Add(u64);
+
+#[derive(Debug)]
+struct Reset;
+
+#[test]
+fn test_type_dispatcher_over_channel() {
+    let (tx, rx) = mpsc::channel::<VBox>();
+    let (out_tx, out_rx) = mpsc::channel::<String>();
+
+    let h = thread::spawn(move || {
+        let mut total = 0u64;
+        let mut d = TypeDispatcher::new();
+
+        let (add_tx, add_rx) = mpsc::channel::<u64>();
+        d.on(move |a: Box<Add>| add_tx.send(a.0).unwrap());
+
+        let reset_tx = out_tx.clone();
+        d.on(move |_: Box<Reset>| reset_tx.send("reset".to_string()).unwrap());
+
+        for vbox in rx {
+            match d.dispatch(vbox) {
+                Ok(()) => {}
+                Err(unhandled) => out_tx
+                    .send(format!(
+                        "unhandled: {:?}",
+                        unhandled.as_dyn::<dyn Debug + Send>()
+                    ))
+                    .unwrap(),
+            }
+            while let Ok(v) = add_rx.try_recv() {
+                total += v;
+            }
+        }
+        total
+    });
+
+    // Packed as different traits, routed by the concrete type only.
+    tx.send(into_vbox!(dyn Debug + Send, Add(1))).unwrap();
+    tx.send(into_vbox!(dyn Any + Send, Add(2))).unwrap();
+    tx.send(into_vbox!(dyn Debug + Send, Reset)).unwrap();
+    tx.send(into_vbox!(dyn Debug + Send, 5u64)).unwrap();
+    drop(tx);
+
+    assert_eq!(3, h.join().unwrap());
+    assert_eq!(
+        vec!["reset".to_string(), "unhandled: 5".to_string()],
+        out_rx.iter().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_type_dispatcher_register() {
+    let mut d = TypeDispatcher::new();
+    assert!(d.is_empty());
+
+    d.on(|_: Box<Add>| {});
+    d.on(|_: Box<Add>| {});
+    assert_eq!(1, d.len());
+    assert!(d.contains::<Add>());
+    assert!(!d.contains::<Reset>());
+
+    assert!(d.unregister::<Add>().is_some());
+    assert!(d.unregister::<Add>().is_none());
+
+    let unhandled = d.dispatch(into_vbox!(dyn Debug, Add(1))).unwrap_err();
+    assert!(unhandled.is::<Add>());
+    unhandled.discard();
+}
+
+#[test]
+fn test_type_dispatcher_unknown_concrete_type() {
+    let mut d = TypeDispatcher::new();
+    d.on(|_: Box<Add>| panic!("must not be called"));
+
+    let b: Box<dyn Debug + Send> = Box::new(Add(1));
+    let vbox = into_vbox_dyn!(dyn Debug + Send, b);
+
+    let unhandled = d.dispatch(vbox).unwrap_err();
+    unhandled.discard();
+}
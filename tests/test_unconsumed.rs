@@ -0,0 +1,61 @@
+#![cfg(feature = "debug-unconsumed")]
+
+use std::fmt::Debug;
+use std::panic;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::replace_vbox;
+use vbox::unconsumed;
+use vbox::unconsumed::Action;
+use vbox::with_vbox;
+
+/// The action and the counter are global, thus all cases are in one test.
+#[test]
+fn test_unconsumed() {
+    unconsumed::set_action(Action::Count);
+    assert_eq!(Action::Count, unconsumed::action());
+
+    let before = unconsumed::count();
+
+    // Consumed
+    let vb = into_vbox!(dyn Debug, 1u64);
+    let _ = from_vbox!(dyn Debug, vb);
+
+    let vb = into_vbox!(dyn Debug, 2u64);
+    let _ = vb.into_inner::<u64>().unwrap();
+
+    let mut vb = into_vbox!(dyn Debug, 3u64);
+    with_vbox!(dyn Debug, &mut vb, |_d| {});
+    drop(vb);
+
+    let mut vb = into_vbox!(dyn Debug, 4u64);
+    replace_vbox!(dyn Debug, &mut vb, 5u64);
+    replace_vbox!(dyn Debug, &mut vb, "different layout");
+    let _ = from_vbox!(dyn Debug, vb);
+
+    into_vbox!(dyn Debug, 6u64).discard();
+
+    assert_eq!(before, unconsumed::count());
+
+    // Not consumed
+    let vb = into_vbox!(dyn Debug, 7u64);
+    drop(vb);
+    assert_eq!(before + 1, unconsumed::count());
+
+    // Panic, with the type name and the creation site.
+    unconsumed::set_action(Action::Panic);
+
+    let vb = into_vbox!(dyn Debug, 8u64);
+    let line = line!() - 1;
+    let err = panic::catch_unwind(move || drop(vb)).unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+
+    assert!(msg.starts_with(
+        "VBox dropped without being unpacked: dyn core::fmt::Debug"
+    ));
+    assert!(msg.contains(&format!("{}:{}", file!(), line)), "{}", msg);
+    assert_eq!(before + 2, unconsumed::count());
+
+    unconsumed::set_action(Action::Warn);
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::thread;
+
+use futures::executor::block_on;
+use vbox::into_vbox;
+use vbox::with_vbox_async;
+use vbox::BoxFuture;
+use vbox::VAsync;
+use vbox::VBox;
+
+/// The trait `#[async_trait]` generates for:
+///
+/// ```ignore
+/// #[async_trait]
+/// trait Store {
+///     async fn get(&self, key: u64) -> Option<String>;
+///     async fn put(&mut self, key: u64, value: String);
+/// }
+/// ```
+trait Store: Send + Sync {
+    fn get<'life0, 'async_trait>(
+        &'life0 self,
+        key: u64,
+    ) -> BoxFuture<'async_trait, Option<String>>
+    where
+        'life0: 'async_trait,
+        Self: 'async_trait;
+
+    fn put<'life0, 'async_trait>(
+        &'life0 mut self,
+        key: u64,
+        value: String,
+    ) -> BoxFuture<'async_trait, ()>
+    where
+        'life0: 'async_trait,
+        Self: 'async_trait;
+}
+
+#[derive(Default)]
+struct Mem {
+    data: HashMap<u64, String>,
+}
+
+impl Store for Mem {
+    fn get<'life0, 'async_trait>(
+        &'life0 self,
+        key: u64,
+    ) -> BoxFuture<'async_trait, Option<String>>
+    where
+        'life0: 'async_trait,
+        Self: 'async_trait,
+    {
+        Box::pin(async move { self.data.get(&key).cloned() })
+    }
+
+    fn put<'life0, 'async_trait>(
+        &'life0 mut self,
+        key: u64,
+        value: String,
+    ) -> BoxFuture<'async_trait, ()>
+    where
+        'life0: 'async_trait,
+        Self: 'async_trait,
+    {
+        Box::pin(async move {
+            self.data.insert(key, value);
+        })
+    }
+}
+
+#[test]
+fn test_vasync_call() {
+    let mut store = VAsync::new(into_vbox!(dyn Store, Mem::default()));
+    assert!(store.is_dyn::<dyn Store>());
+
+    block_on(store.call_mut::<dyn Store, _, _>(|s| s.put(1, "a".to_string())));
+    let got = block_on(store.call::<dyn Store, _, _>(|s| s.get(1)));
+    assert_eq!(Some("a".to_string()), got);
+
+    let vbox = store.into_vbox();
+    let got = block_on(with_vbox_async!(dyn Store, &vbox, |s| s.get(2)));
+    assert_eq!(None, got);
+}
+
+#[test]
+fn test_vasync_send_and_await() {
+    let (tx, rx) = mpsc::channel::<VBox>();
+
+    let mut mem = Mem::default();
+    mem.data.insert(3, "c".to_string());
+    tx.send(into_vbox!(dyn Store, mem)).unwrap();
+
+    let h = thread::spawn(move || {
+        let store = VAsync::from(rx.recv().unwrap());
+        let fu: BoxFuture<'static, _> =
+            store.into_call::<dyn Store, _, _>(|s| s.get(3));
+
+        // The future owns the trait object, and is sent to another thread.
+        thread::spawn(move || block_on(fu)).join().unwrap()
+    });
+
+    assert_eq!(Some("c".to_string()), h.join().unwrap());
+}
+
+#[test]
+#[should_panic(expected = "expected type_id")]
+fn test_vasync_type_mismatch() {
+    let store =
+        VAsync::new(into_vbox!(dyn std::fmt::Debug + Send + Sync, 1u64));
+    drop(store.into_call::<dyn Store, _, _>(|s| s.get(1)));
+}
@@ -0,0 +1,67 @@
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+use vbox::VCall;
+
+#[derive(Default)]
+struct Store {
+    kv: Mutex<Vec<(String, u64)>>,
+}
+
+impl Store {
+    fn put(&self, k: &str, v: u64) -> usize {
+        let mut kv = self.kv.lock().unwrap();
+        kv.push((k.to_string(), v));
+        kv.len()
+    }
+
+    fn get(&self, k: &str) -> Option<u64> {
+        let kv = self.kv.lock().unwrap();
+        kv.iter().find(|(key, _)| key == k).map(|(_, v)| *v)
+    }
+}
+
+#[test]
+fn test_vcall_thread_hopping() {
+    let store = Arc::new(Store::default());
+
+    let (tx, rx) = mpsc::channel::<VCall>();
+
+    // The worker executes calls without knowing `Store`.
+    let worker = thread::spawn(move || {
+        rx.into_iter().map(|call| call.run()).collect::<Vec<_>>()
+    });
+
+    tx.send(
+        VCall::on(store.clone())
+            .with_args(("a".to_string(), 1u64))
+            .method(|s: Arc<Store>, (k, v)| s.put(&k, v)),
+    )
+    .unwrap();
+    tx.send(
+        VCall::on(store.clone())
+            .with_args("a")
+            .method(|s: Arc<Store>, k| s.get(k)),
+    )
+    .unwrap();
+    tx.send(VCall::on(store.clone()).method(|_s: Arc<Store>, ()| ())).unwrap();
+    drop(tx);
+
+    let mut rets = worker.join().unwrap().into_iter();
+
+    assert_eq!(1usize, rets.next().unwrap().into_inner::<usize>().unwrap());
+    assert_eq!(
+        Some(1u64),
+        rets.next().unwrap().into_inner::<Option<u64>>().unwrap()
+    );
+    assert!(rets.next().unwrap().is_unit());
+}
+
+#[test]
+fn test_vcall_debug() {
+    let call = VCall::on(1u64).method(|a, ()| a + 1);
+    assert!(format!("{:?}", call).starts_with("VCall { method: "));
+    assert_eq!(2u64, call.run().into_inner::<u64>().unwrap());
+}
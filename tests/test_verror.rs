@@ -0,0 +1,82 @@
+use std::error::Error;
+use std::fmt;
+
+use vbox::into_vbox;
+use vbox::VError;
+
+#[derive(Debug)]
+struct Io {
+    code: i32,
+}
+
+impl fmt::Display for Io {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "io error: {}", self.code)
+    }
+}
+
+impl Error for Io {}
+
+/// An error with an erased source.
+#[derive(Debug)]
+struct Storage {
+    source: VError,
+}
+
+impl fmt::Display for Storage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "storage error")
+    }
+}
+
+impl Error for Storage {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+#[test]
+fn test_verror_transparent() {
+    let err = VError::new(VError::new(VError::new(Io { code: 5 })));
+
+    assert_eq!("io error: 5", err.to_string());
+    assert_eq!("Io { code: 5 }", format!("{:?}", err));
+    assert!(err.source().is_none());
+    assert_eq!(1, err.chain().count());
+}
+
+#[test]
+fn test_verror_source_chain() {
+    let storage = Storage {
+        source: VError::new(VError::new(Io { code: 7 })),
+    };
+    let err = VError::new(VError::new(storage));
+
+    let chain: Vec<_> = err.chain().map(|e| e.to_string()).collect();
+    assert_eq!(vec!["storage error", "io error: 7"], chain);
+
+    // `source()` skips the erased layers.
+    let source = err.source().unwrap();
+    assert_eq!(7, source.downcast_ref::<Io>().unwrap().code);
+
+    assert_eq!(7, err.find::<Io>().unwrap().code);
+    assert!(err.find::<Storage>().is_some());
+    assert!(err.find::<fmt::Error>().is_none());
+}
+
+#[test]
+fn test_verror_from_vbox() {
+    let vbox = into_vbox!(dyn Error + Send + Sync, Io { code: 1 });
+    let err = VError::from_vbox(vbox);
+    assert_eq!(1, err.find::<Io>().unwrap().code);
+
+    let vbox = err.into_vbox();
+    assert!(vbox.is::<Io>());
+}
+
+#[test]
+#[should_panic(expected = "VError must be packed as dyn Error + Send + Sync")]
+fn test_verror_from_vbox_wrong_type() {
+    let vbox = into_vbox!(dyn Error + Send, Io { code: 1 });
+    let _ = VError::from_vbox(vbox);
+}
@@ -0,0 +1,73 @@
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::mpsc;
+use std::thread;
+
+use futures::channel::oneshot;
+use futures::executor::block_on;
+use vbox::into_vbox;
+use vbox::join_vbox;
+use vbox::vjoin;
+use vbox::VBox;
+
+#[test]
+fn test_vjoin_scatter_gather() {
+    // Work items complete in the reverse order they are listed.
+    let mut senders = vec![];
+    let work = (0..4u64)
+        .map(|i| {
+            let (tx, rx) = oneshot::channel::<u64>();
+            senders.push(tx);
+            into_vbox!(dyn Future<Output = VBox> + Send, async move {
+                let v = rx.await.unwrap();
+                into_vbox!(dyn Debug + Send, (i, v))
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let (done_tx, done_rx) = mpsc::channel();
+    let h = thread::spawn(move || {
+        let got = block_on(vjoin!(work));
+        done_tx.send(()).unwrap();
+        got
+    });
+
+    for (i, tx) in senders.into_iter().enumerate().rev() {
+        assert!(done_rx.try_recv().is_err());
+        tx.send(i as u64 * 100).unwrap();
+    }
+
+    let got = h.join().unwrap();
+    let got = got
+        .into_iter()
+        .map(|v| v.into_inner::<(u64, u64)>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(vec![(0, 0), (1, 100), (2, 200), (3, 300)], got);
+}
+
+#[test]
+fn test_vjoin_typed_output() {
+    let work = vec![
+        into_vbox!(dyn Future<Output = String> + Send, async {
+            "a".to_string()
+        }),
+        into_vbox!(dyn Future<Output = String> + Send, async {
+            "b".to_string()
+        }),
+    ];
+    assert_eq!(vec!["a", "b"], block_on(vjoin!(work, String)));
+}
+
+#[test]
+fn test_vjoin_empty() {
+    let got: Vec<VBox> = block_on(vjoin!(Vec::new()));
+    assert!(got.is_empty());
+}
+
+#[test]
+#[should_panic]
+fn test_vjoin_wrong_output_type() {
+    let work =
+        vec![into_vbox!(dyn Future<Output = u64> + Send, async { 1u64 })];
+    drop(join_vbox::<u32>(work));
+}
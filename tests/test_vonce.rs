@@ -0,0 +1,74 @@
+use std::fmt::Debug;
+use std::thread;
+use std::time::Duration;
+
+use futures::executor::block_on;
+use vbox::into_vbox;
+use vbox::Canceled;
+use vbox::VBox;
+use vbox::VOnce;
+
+#[test]
+fn test_vonce_blocking() {
+    let (tx, rx) = VOnce::channel();
+
+    let h = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        tx.send(into_vbox!(dyn Debug, 3u64)).unwrap();
+    });
+
+    let got = rx.recv_as::<dyn Debug>().unwrap();
+    assert_eq!("3", format!("{:?}", got));
+
+    h.join().unwrap();
+}
+
+#[test]
+fn test_vonce_await() {
+    let (tx, rx) = VOnce::channel();
+
+    let h = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        tx.send(into_vbox!(dyn Debug, 3u64)).unwrap();
+    });
+
+    let got = block_on(rx).unwrap();
+    assert_eq!(3u64, got.into_inner::<u64>().unwrap());
+
+    h.join().unwrap();
+}
+
+#[test]
+fn test_vonce_try_recv() {
+    let (tx, mut rx) = VOnce::channel();
+    assert!(rx.try_recv().unwrap().is_none());
+
+    tx.send(VBox::unit()).unwrap();
+    assert!(rx.try_recv().unwrap().unwrap().is_unit());
+}
+
+#[test]
+fn test_vonce_sender_dropped() {
+    let (tx, rx) = VOnce::channel();
+
+    let h = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        drop(tx);
+    });
+
+    assert_eq!(Canceled, block_on(rx).unwrap_err());
+
+    h.join().unwrap();
+}
+
+#[test]
+fn test_vonce_receiver_dropped() {
+    let (tx, rx) = VOnce::channel();
+    assert!(!tx.is_canceled());
+
+    drop(rx);
+    assert!(tx.is_canceled());
+
+    let res = tx.send(VBox::unit());
+    assert!(res.unwrap_err().is_unit());
+}
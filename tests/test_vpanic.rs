@@ -0,0 +1,43 @@
+use std::any::Any;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::thread;
+
+use vbox::from_vbox;
+use vbox::VPanic;
+
+#[test]
+fn test_vpanic_message() {
+    let p = VPanic::catch(|| panic!("static")).unwrap_err();
+    assert_eq!(Some("static"), p.message());
+    assert_eq!("panicked: static", p.to_string());
+
+    let p = VPanic::catch(|| panic!("formatted {}", 1)).unwrap_err();
+    assert_eq!(Some("formatted 1"), p.message());
+
+    let p = VPanic::catch(|| panic::panic_any(5u64)).unwrap_err();
+    assert_eq!(None, p.message());
+    assert_eq!("panicked with a non-string payload", p.to_string());
+    assert_eq!(Some(&5u64), p.payload().downcast_ref::<u64>());
+
+    assert_eq!(3, VPanic::catch(|| 3).unwrap());
+}
+
+#[test]
+fn test_vpanic_preserve_payload() {
+    let p: VPanic =
+        thread::spawn(|| panic::panic_any(7u32)).join().unwrap_err().into();
+
+    let vbox = p.into_vbox();
+    assert!(vbox.is::<u32>());
+    let payload = from_vbox!(dyn Any + Send, vbox);
+    assert_eq!(Some(&7u32), payload.downcast_ref::<u32>());
+}
+
+#[test]
+fn test_vpanic_resume() {
+    let p = VPanic::catch(|| panic::panic_any(9i32)).unwrap_err();
+
+    let p2 = VPanic::catch(AssertUnwindSafe(move || p.resume())).unwrap_err();
+    assert_eq!(Some(&9i32), p2.into_payload().downcast_ref::<i32>());
+}
@@ -0,0 +1,68 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Display;
+
+use vbox::from_vbox;
+use vbox::from_vresult;
+use vbox::into_vresult;
+use vbox::map_err_vbox;
+use vbox::map_ok_vbox;
+use vbox::VResult;
+
+trait Reply {
+    fn text(&self) -> String;
+}
+
+struct Pong(u64);
+
+impl Reply for Pong {
+    fn text(&self) -> String {
+        format!("pong-{}", self.0)
+    }
+}
+
+#[derive(Debug)]
+struct Timeout;
+
+impl Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "timeout")
+    }
+}
+
+impl Error for Timeout {}
+
+fn handle(ok: bool) -> VResult {
+    let res = if ok { Ok(Pong(1)) } else { Err(Timeout) };
+    into_vresult!(dyn Reply, dyn Error + Send, res)
+}
+
+#[test]
+fn test_vresult_round_trip() {
+    let res = from_vresult!(dyn Reply, dyn Error + Send, handle(true));
+    assert_eq!("pong-1", res.unwrap().text());
+
+    let res = from_vresult!(dyn Reply, dyn Error + Send, handle(false));
+    assert_eq!("timeout", res.err().unwrap().to_string());
+}
+
+#[test]
+fn test_map_ok_and_err_vbox() {
+    let vres = map_ok_vbox!(dyn Reply -> dyn Debug, handle(true), |r| r.text());
+    let vres =
+        map_err_vbox!(dyn Error + Send -> dyn Debug, vres, |e| e.to_string());
+    assert_eq!(
+        r#""pong-1""#,
+        format!("{:?}", from_vbox!(dyn Debug, vres.unwrap()))
+    );
+
+    let vres =
+        map_ok_vbox!(dyn Reply -> dyn Debug, handle(false), |r| r.text());
+    let vres =
+        map_err_vbox!(dyn Error + Send -> dyn Debug, vres, |e| e.to_string());
+    assert_eq!(
+        r#""timeout""#,
+        format!("{:?}", from_vbox!(dyn Debug, vres.unwrap_err()))
+    );
+}
@@ -0,0 +1,97 @@
+use std::future::Future;
+
+use futures::channel::oneshot;
+use futures::executor::block_on;
+use futures::FutureExt;
+use vbox::into_vbox;
+use vbox::select_either;
+use vbox::select_vbox;
+use vbox::VBox;
+use vbox::VEither;
+use vbox::VFuture;
+
+fn ready(v: u64) -> VBox {
+    into_vbox!(dyn Future<Output = u64> + Send, async move { v })
+}
+
+fn from_rx(rx: oneshot::Receiver<u64>) -> VBox {
+    into_vbox!(
+        dyn Future<Output = u64> + Send,
+        async move { rx.await.unwrap() }
+    )
+}
+
+#[test]
+fn test_vfuture_in_select_loop() {
+    let (tx1, rx1) = oneshot::channel();
+    let (tx2, rx2) = oneshot::channel();
+
+    let mut a = VFuture::<u64>::new(from_rx(rx1)).fuse();
+    let mut b = VFuture::<u64>::new(from_rx(rx2)).fuse();
+
+    tx2.send(2).unwrap();
+    tx1.send(1).unwrap();
+
+    let got = block_on(async {
+        let mut got = vec![];
+        loop {
+            futures::select! {
+                v = a => got.push(("a", v)),
+                v = b => got.push(("b", v)),
+                complete => break,
+            }
+        }
+        got.sort();
+        got
+    });
+    assert_eq!(vec![("a", 1), ("b", 2)], got);
+}
+
+#[test]
+fn test_select_either_keeps_loser() {
+    let (tx, rx) = oneshot::channel();
+    let slow = from_rx(rx);
+    let fast = into_vbox!(dyn Future<Output = String> + Send, async {
+        "x".to_string()
+    });
+
+    let VEither::Right(v, slow) =
+        block_on(select_either::<u64, String>(slow, fast))
+    else {
+        panic!("expect Right");
+    };
+    assert_eq!("x", v);
+
+    // The unfinished future can still be awaited.
+    tx.send(7).unwrap();
+    assert_eq!(7, block_on(slow));
+}
+
+#[test]
+fn test_select_vbox_rest_in_order() {
+    let (tx0, rx0) = oneshot::channel();
+    let (tx2, rx2) = oneshot::channel();
+
+    let futs = vec![from_rx(rx0), ready(1), from_rx(rx2)];
+    let (v, i, rest) = block_on(select_vbox::<u64>(futs));
+    assert_eq!((1, 1), (v, i));
+    assert_eq!(2, rest.len());
+
+    tx2.send(2).unwrap();
+    let (v, i, rest) = block_on(select_vbox(
+        rest.into_iter()
+            .map(|f| into_vbox!(dyn Future<Output = u64> + Send, f))
+            .collect(),
+    ));
+    assert_eq!((2, 1), (v, i));
+
+    tx0.send(0).unwrap();
+    let [last] = <[VFuture<u64>; 1]>::try_from(rest).unwrap();
+    assert_eq!(0, block_on(last));
+}
+
+#[test]
+#[should_panic(expected = "select_vbox() with no future")]
+fn test_select_vbox_empty() {
+    drop(select_vbox::<u64>(vec![]));
+}
@@ -0,0 +1,121 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::thread;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::SlabError;
+use vbox::SlabKey;
+use vbox::VSlab;
+
+#[test]
+fn test_vslab_insert_get_remove() {
+    let mut slab = VSlab::new();
+    assert!(slab.is_empty());
+
+    let a = slab.insert(into_vbox!(dyn Debug, 1u64));
+    let b = slab.insert(into_vbox!(dyn Display, "b"));
+    assert_eq!(2, slab.len());
+    assert!(slab.contains(a));
+
+    assert_eq!("1", format!("{:?}", slab.get::<dyn Debug>(a).unwrap()));
+    assert_eq!("b", slab.get::<dyn Display>(b).unwrap().to_string());
+
+    let err = slab.get::<dyn Display>(a).map(|_| ()).unwrap_err();
+    assert!(matches!(err, SlabError::TypeMismatch { key, .. } if key == a));
+    assert_eq!(
+        "0v0 is stored as: dyn core::fmt::Debug, expected: dyn core::fmt::Display",
+        err.to_string()
+    );
+
+    // A mismatched remove_as() leaves it in the slab.
+    assert!(slab.remove_as::<dyn Display>(a).is_err());
+    assert!(slab.contains(a));
+    let d = slab.remove_as::<dyn Debug>(a).unwrap();
+    assert_eq!("1", format!("{:?}", d));
+    assert!(!slab.contains(a));
+    assert_eq!(1, slab.len());
+
+    assert_eq!(
+        Err(SlabError::NotFound { key: a }),
+        slab.get::<dyn Debug>(a).map(|_| ())
+    );
+    assert_eq!(
+        "nothing is stored under: 0v0",
+        SlabError::NotFound { key: a }.to_string()
+    );
+    assert!(slab.remove(a).is_none());
+
+    let vbox = slab.remove(b).unwrap();
+    assert_eq!("b", from_vbox!(dyn Display, vbox).to_string());
+    assert!(slab.is_empty());
+}
+
+#[test]
+fn test_vslab_stale_key() {
+    let mut slab = VSlab::new();
+
+    let a = slab.insert(into_vbox!(dyn Debug, 1u64));
+    slab.remove(a).unwrap().discard();
+
+    // The entry is reused with another generation.
+    let b = slab.insert(into_vbox!(dyn Debug, 2u64));
+    assert_eq!(a.index(), b.index());
+    assert_eq!(a.generation() + 1, b.generation());
+
+    assert!(!slab.contains(a));
+    assert!(slab.get_vbox(a).is_none());
+    assert!(slab.remove(a).is_none());
+    assert_eq!("2", format!("{:?}", slab.get::<dyn Debug>(b).unwrap()));
+
+    let stale = SlabError::Stale { key: a, current: b };
+    assert_eq!(Err(stale.clone()), slab.get::<dyn Debug>(a).map(|_| ()));
+    assert_eq!(Err(stale.clone()), slab.get_mut::<dyn Debug>(a).map(|_| ()));
+    assert_eq!(
+        Err(stale.clone()),
+        slab.remove_as::<dyn Debug>(a).map(|_| ())
+    );
+    assert_eq!(
+        "0v0 is stale, the entry is reused by: 0v1",
+        stale.to_string()
+    );
+    assert!(slab.contains(b));
+
+    // A key from a later generation, e.g., forged, is not stale.
+    let forged = SlabKey::from_u64(b.to_u64() + (1 << 32));
+    assert_eq!(
+        Err(SlabError::NotFound { key: forged }),
+        slab.get::<dyn Debug>(forged).map(|_| ())
+    );
+}
+
+#[test]
+fn test_vslab_get_mut_and_iter() {
+    let mut slab = VSlab::new();
+    let a = slab.insert(into_vbox!(dyn Iterator<Item = u64>, 0..3u64));
+    let b = slab.insert(into_vbox!(dyn Debug, 5u8));
+
+    let it = slab.get_mut::<dyn Iterator<Item = u64>>(a).unwrap();
+    assert_eq!(Some(0), it.next());
+
+    let keys: Vec<_> = slab.iter().map(|(k, _)| k).collect();
+    assert_eq!(vec![a, b], keys);
+
+    let it = slab.remove_as::<dyn Iterator<Item = u64>>(a).unwrap();
+    assert_eq!(vec![1, 2], it.collect::<Vec<_>>());
+}
+
+#[test]
+fn test_vslab_key_in_message() {
+    let mut slab = VSlab::new();
+    let key = slab.insert(into_vbox!(dyn Debug, "payload"));
+
+    let v = key.to_u64();
+    let got = thread::spawn(move || SlabKey::from_u64(v)).join().unwrap();
+    assert_eq!(key, got);
+
+    assert_eq!(
+        "\"payload\"",
+        format!("{:?}", slab.get::<dyn Debug>(got).unwrap())
+    );
+}
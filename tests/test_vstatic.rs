@@ -0,0 +1,97 @@
+use std::fmt::Debug;
+
+use vbox::erased_static_ref;
+use vbox::vstatic;
+use vbox::ErasedStaticRef;
+use vbox::VStatic;
+
+trait Handler {
+    fn handle(&self, n: u64) -> u64;
+}
+
+struct Add(u64);
+struct Mul(u64);
+
+impl Handler for Add {
+    fn handle(&self, n: u64) -> u64 {
+        n + self.0
+    }
+}
+
+impl Handler for Mul {
+    fn handle(&self, n: u64) -> u64 {
+        n * self.0
+    }
+}
+
+static ADD: Add = Add(1);
+
+static TABLE: [VStatic; 2] =
+    [vstatic!(dyn Handler, &ADD), vstatic!(dyn Handler, &Mul(3))];
+
+const DEBUG: VStatic = vstatic!(dyn Debug, &10u64);
+
+#[test]
+fn test_vstatic_table() {
+    let got: Vec<_> =
+        TABLE.iter().map(|h| h.get::<dyn Handler>().handle(5)).collect();
+    assert_eq!(vec![6, 15], got);
+
+    let h: &'static dyn Handler = TABLE[0].get::<dyn Handler>();
+    assert!(std::ptr::eq(
+        h as *const dyn Handler as *const (),
+        &ADD as *const Add as *const ()
+    ));
+}
+
+#[test]
+fn test_vstatic_copy_and_send() {
+    let copied = DEBUG;
+    let h =
+        std::thread::spawn(move || format!("{:?}", copied.get::<dyn Debug>()));
+    assert_eq!("10", h.join().unwrap());
+    assert_eq!("10", format!("{:?}", DEBUG.get::<dyn Debug>()));
+}
+
+#[test]
+#[should_panic(expected = "expected type_id")]
+fn test_vstatic_type_mismatch() {
+    let _ = DEBUG.get::<dyn Handler>();
+}
+
+static ERASED: [ErasedStaticRef; 2] = [
+    erased_static_ref!(dyn Handler, &ADD),
+    erased_static_ref!(dyn Handler, &Mul(3)),
+];
+
+#[test]
+fn test_erased_static_ref() {
+    if !cfg!(debug_assertions) {
+        assert_eq!(
+            2 * std::mem::size_of::<usize>(),
+            std::mem::size_of::<ErasedStaticRef>()
+        );
+    }
+
+    // Safe: every `ErasedStaticRef` in this test is built from the type it
+    // is got as.
+    let got: Vec<_> = ERASED
+        .iter()
+        .map(|h| unsafe { h.get::<dyn Handler>() }.handle(5))
+        .collect();
+    assert_eq!(vec![6, 15], got);
+
+    let h = ERASED[0];
+    assert_eq!(&ADD as *const Add as *const (), h.data_ptr());
+    let h =
+        std::thread::spawn(move || unsafe { h.get::<dyn Handler>() }.handle(1));
+    assert_eq!(2, h.join().unwrap());
+
+    // Built at runtime
+    let leaked: &'static Add = Box::leak(Box::new(Add(7)));
+    let h = erased_static_ref!(dyn Handler, leaked);
+    assert_eq!(8, unsafe { h.get::<dyn Handler>() }.handle(1));
+
+    let h = ErasedStaticRef::from(DEBUG);
+    assert_eq!("10", format!("{:?}", unsafe { h.get::<dyn Debug>() }));
+}
@@ -0,0 +1,83 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::panic;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use vbox::from_vbox;
+use vbox::into_vbox;
+use vbox::static_vtable;
+use vbox::VTable;
+
+trait Plus {
+    fn plus(&self, n: u64) -> u64;
+}
+
+struct Add(u64);
+
+impl Plus for Add {
+    fn plus(&self, n: u64) -> u64 {
+        self.0 + n
+    }
+}
+
+impl Drop for Add {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+static PLUS_ADD: VTable<dyn Plus + Send> = static_vtable!(dyn Plus + Send, Add);
+
+const TABLE: [VTable<dyn Debug>; 2] = [
+    static_vtable!(dyn Debug, u64),
+    static_vtable!(dyn Debug, String),
+];
+
+#[test]
+fn test_static_vtable() {
+    let vb = PLUS_ADD.pack(Add(3));
+    assert!(vb.is::<Add>());
+    assert!(PLUS_ADD.is_vtable_of(&vb));
+    assert_eq!(PLUS_ADD.as_ptr(), vb.vtable_ptr());
+
+    let p = from_vbox!(dyn Plus + Send, vb);
+    assert_eq!(5, p.plus(2));
+    drop(p);
+    assert_eq!(1, DROPPED.load(Ordering::Relaxed));
+
+    // Dropped without unpacking
+    drop(PLUS_ADD.pack(Add(4)));
+    assert_eq!(2, DROPPED.load(Ordering::Relaxed));
+
+    let vb = TABLE[1].pack("foo".to_string());
+    assert_eq!("\"foo\"", format!("{:?}", from_vbox!(dyn Debug, vb)));
+
+    let vb = TABLE[0].pack(7u64);
+    assert_eq!(7u64, vb.into_inner::<u64>().unwrap());
+
+    // Zero-sized payload
+    static DEBUG_UNIT: VTable<dyn Debug> = static_vtable!(dyn Debug, ());
+    let vb = DEBUG_UNIT.pack(());
+    assert_eq!("()", format!("{:?}", from_vbox!(dyn Debug, vb)));
+}
+
+#[test]
+fn test_static_vtable_other_vbox() {
+    let vb = into_vbox!(dyn Display, 1u64);
+    assert!(!TABLE[0].is_vtable_of(&vb));
+}
+
+#[test]
+fn test_static_vtable_wrong_concrete_type() {
+    let res = panic::catch_unwind(|| TABLE[0].pack(1u32));
+    let err = res.unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert!(
+        msg.contains("expected concrete type: u64, actual: u32"),
+        "{}",
+        msg
+    );
+}
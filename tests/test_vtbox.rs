@@ -1,15 +1,23 @@
 use std::fmt::Debug;
 use std::marker::PhantomData;
-use std::pin::Pin;
 use std::sync::atomic::AtomicU64;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-use futures::future::BoxFuture;
 use futures::Future;
 use vbox::from_vbox;
+use vbox::from_vbox_vec;
+use vbox::guard_vbox;
 use vbox::into_vbox;
+use vbox::into_vbox_debug;
+use vbox::into_vbox_dyn;
+use vbox::leak_vbox;
+use vbox::map_vbox;
+use vbox::replace_vbox;
+use vbox::static_vtable;
+use vbox::with_vbox;
 use vbox::VBox;
+use vbox::VTable;
 
 #[test]
 fn test_fn_once() {
@@ -152,3 +160,1008 @@ fn test_fn_return_vbox_future() {
     let got = futures::executor::block_on(fu);
     assert_eq!(3, got);
 }
+
+#[test]
+fn test_into_vbox_dyn() {
+    trait Plus {
+        fn plus(&self, s: u64) -> u64;
+    }
+
+    struct Foo {
+        a: Arc<AtomicU64>,
+    }
+
+    impl Plus for Foo {
+        fn plus(&self, s: u64) -> u64 {
+            s + 1
+        }
+    }
+
+    impl Drop for Foo {
+        fn drop(&mut self) {
+            self.a.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let drop_cnt = Arc::new(AtomicU64::new(0));
+
+    let b: Box<dyn Plus + Send> = Box::new(Foo {
+        a: drop_cnt.clone(),
+    });
+
+    let vb: VBox = into_vbox_dyn!(dyn Plus + Send, b);
+    let p: Box<dyn Plus + Send> = from_vbox!(dyn Plus + Send, vb);
+    assert_eq!(4, p.plus(3));
+
+    drop(p);
+    assert_eq!(1, drop_cnt.load(Ordering::Relaxed), "drop is called");
+
+    {
+        let b: Box<dyn Plus + Send> = Box::new(Foo {
+            a: drop_cnt.clone(),
+        });
+        let _vb: VBox = into_vbox_dyn!(dyn Plus + Send, b);
+    }
+    assert_eq!(2, drop_cnt.load(Ordering::Relaxed), "drop is called");
+}
+
+#[test]
+fn test_leak() {
+    trait Plus {
+        fn plus(&self, s: u64) -> u64;
+    }
+
+    impl Plus for u64 {
+        fn plus(&self, s: u64) -> u64 {
+            self + s
+        }
+    }
+
+    let vb: VBox = into_vbox!(dyn Plus + Sync, 3u64);
+    let p: &'static (dyn Plus + Sync) = leak_vbox!(dyn Plus + Sync, vb);
+
+    let got = std::thread::spawn(move || p.plus(1)).join().unwrap();
+    assert_eq!(4, got);
+}
+
+#[test]
+fn test_into_inner() {
+    struct Foo {
+        a: Arc<AtomicU64>,
+    }
+
+    impl Debug for Foo {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Foo")
+        }
+    }
+
+    impl Drop for Foo {
+        fn drop(&mut self) {
+            self.a.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let drop_cnt = Arc::new(AtomicU64::new(0));
+
+    let vb: VBox = into_vbox!(dyn Debug, Foo {
+        a: drop_cnt.clone()
+    });
+    assert!(vb.is::<Foo>());
+    assert!(!vb.is::<u64>());
+
+    let vb = vb.into_inner::<u64>().unwrap_err();
+    assert_eq!(0, drop_cnt.load(Ordering::Relaxed));
+
+    let got: Foo = vb.into_inner::<Foo>().unwrap();
+    assert_eq!(0, drop_cnt.load(Ordering::Relaxed));
+
+    drop(got);
+    assert_eq!(1, drop_cnt.load(Ordering::Relaxed), "drop is called once");
+
+    // The concrete type is unknown for a VBox built from `Box<dyn Trait>`.
+    let b: Box<dyn Debug + Send> = Box::new(3u64);
+    let vb: VBox = into_vbox_dyn!(dyn Debug + Send, b);
+    assert!(!vb.is::<u64>());
+    assert!(vb.into_inner::<u64>().is_err());
+}
+
+#[test]
+fn test_map_vbox() {
+    use std::fmt::Display;
+
+    trait Plus {
+        fn plus(&self, s: u64) -> u64;
+    }
+
+    impl Plus for u64 {
+        fn plus(&self, s: u64) -> u64 {
+            self + s
+        }
+    }
+
+    let vb: VBox = into_vbox!(dyn Plus, 3u64);
+    let vb = map_vbox!(dyn Plus -> dyn Debug, vb, |p| p.plus(1));
+    let vb = map_vbox!(dyn Debug -> dyn Display, vb, |d| format!("({:?})", d));
+
+    let p: Box<dyn Display> = from_vbox!(dyn Display, vb);
+    assert_eq!("(4)", p.to_string());
+
+    // Trait objects with return types
+    let vb: VBox = into_vbox!(dyn FnOnce() -> u64, || 5u64);
+    let vb = map_vbox!(dyn FnOnce() -> u64 -> dyn Debug, vb, |f| f() + 1);
+
+    let d = from_vbox!(dyn Debug, vb);
+    assert_eq!("6", format!("{:?}", d));
+}
+
+#[test]
+fn test_with_vbox() {
+    trait Counter {
+        fn incr(&mut self) -> u64;
+    }
+
+    impl Counter for u64 {
+        fn incr(&mut self) -> u64 {
+            *self += 1;
+            *self
+        }
+    }
+
+    let mut vb: VBox = into_vbox!(dyn Counter, 0u64);
+
+    let got = with_vbox!(dyn Counter, &mut vb, |c| c.incr());
+    assert_eq!(1, got);
+
+    // Early return from the closure leaves the VBox intact.
+    let got = with_vbox!(dyn Counter, &mut vb, |c| {
+        if c.incr() == 2 {
+            return 100;
+        }
+        c.incr()
+    });
+    assert_eq!(100, got);
+
+    assert_eq!(2u64, vb.into_inner::<u64>().unwrap());
+}
+
+#[test]
+fn test_replace_vbox() {
+    use std::fmt::Display;
+
+    struct Foo {
+        a: Arc<AtomicU64>,
+        n: u64,
+    }
+
+    impl Display for Foo {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Foo({})", self.n)
+        }
+    }
+
+    impl Drop for Foo {
+        fn drop(&mut self) {
+            self.a.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let drop_cnt = Arc::new(AtomicU64::new(0));
+    let new_foo = |n| Foo {
+        a: drop_cnt.clone(),
+        n,
+    };
+
+    let mut vb: VBox = into_vbox!(dyn Display, new_foo(1));
+
+    // Same layout: the allocation is reused.
+    let data_addr = with_vbox!(dyn Display, &mut vb, |d| {
+        d as *mut dyn Display as *mut () as usize
+    });
+    replace_vbox!(dyn Display, &mut vb, new_foo(2));
+    assert_eq!(
+        1,
+        drop_cnt.load(Ordering::Relaxed),
+        "old payload is dropped"
+    );
+
+    let got = with_vbox!(dyn Display, &mut vb, |d| {
+        assert_eq!(data_addr, d as *mut dyn Display as *mut () as usize);
+        d.to_string()
+    });
+    assert_eq!("Foo(2)", got);
+
+    // Different layout
+    replace_vbox!(dyn Display, &mut vb, "hello".to_string());
+    assert_eq!(
+        2,
+        drop_cnt.load(Ordering::Relaxed),
+        "old payload is dropped"
+    );
+    assert!(vb.is::<String>());
+
+    // Different trait
+    replace_vbox!(dyn Debug, &mut vb, vec![1u64, 2]);
+    let d = from_vbox!(dyn Debug, vb);
+    assert_eq!("[1, 2]", format!("{:?}", d));
+}
+
+#[test]
+fn test_replace_vbox_drop_panics() {
+    use std::any::Any;
+    use std::panic::catch_unwind;
+    use std::panic::AssertUnwindSafe;
+
+    #[derive(Debug)]
+    struct PanicOnDrop(u64);
+
+    impl Drop for PanicOnDrop {
+        fn drop(&mut self) {
+            if self.0 == 1 {
+                panic!("drop {}", self.0);
+            }
+        }
+    }
+
+    let mut vb: VBox = into_vbox!(dyn Debug + Send, PanicOnDrop(1));
+
+    // Same layout: the panic unwinds out of `replace_vbox!`.
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        replace_vbox!(dyn Debug + Send, &mut vb, PanicOnDrop(2));
+    }));
+    assert!(res.is_err());
+
+    // The `VBox` is left with a `()`.
+    assert!(vb.is::<()>());
+    assert!(vb.try_as_dyn::<dyn Debug + Send>().is_err());
+    let unit = from_vbox!(dyn Any + Send, vb);
+    assert!(unit.downcast::<()>().is_ok());
+}
+
+#[test]
+fn test_payload_layout() {
+    #[repr(align(16))]
+    struct Aligned([u8; 48]);
+
+    impl Debug for Aligned {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Aligned")
+        }
+    }
+
+    let vb: VBox = into_vbox!(dyn Debug, 3u32);
+    assert_eq!(4, vb.size_of_payload());
+    assert_eq!(4, vb.align());
+
+    let vb: VBox = into_vbox!(dyn Debug, Aligned([0; 48]));
+    assert_eq!(48, vb.size_of_payload());
+    assert_eq!(16, vb.align());
+
+    let vb: VBox = into_vbox!(dyn Debug, ());
+    assert_eq!(0, vb.size_of_payload());
+    assert_eq!(1, vb.align());
+
+    let b: Box<dyn Debug + Send> = Box::new(3u16);
+    let vb: VBox = into_vbox_dyn!(dyn Debug + Send, b);
+    assert_eq!(2, vb.size_of_payload());
+    assert_eq!(2, vb.align());
+}
+
+#[test]
+fn test_higher_ranked_fn() {
+    fn first_word(s: &str) -> &str {
+        s.split(' ').next().unwrap()
+    }
+
+    // `for<'a> dyn Trait` form, with a fn item
+    let vb: VBox = into_vbox!(for<'a> dyn Fn(&'a str) -> &'a str, first_word);
+    let f = from_vbox!(for<'a> dyn Fn(&'a str) -> &'a str, vb);
+    assert_eq!("hello", f(&String::from("hello world")));
+
+    // `for<'a> dyn Trait` form, with a closure
+    let prefix = 2;
+    let vb: VBox = into_vbox!(for<'a> dyn Fn(&'a [u8]) -> &'a [u8], move |s| {
+        &s[..prefix]
+    });
+
+    // `dyn for<'a> Trait` is the same type as `for<'a> dyn Trait`
+    let f = from_vbox!(dyn for<'a> Fn(&'a [u8]) -> &'a [u8], vb);
+    let bytes = b"abc".to_vec();
+    assert_eq!(b"ab", f(&bytes));
+
+    // `dyn for<'a> Trait` form, with a closure
+    let vb: VBox = into_vbox!(dyn for<'a> Fn(&'a str) -> &'a str, |s| s.trim());
+    let f = from_vbox!(dyn for<'a> Fn(&'a str) -> &'a str, vb);
+    assert_eq!("a", f(" a "));
+
+    // Elided lifetime is also higher-ranked
+    let vb: VBox =
+        into_vbox!(dyn for<'a> FnMut(&'a str) -> usize, |s: &str| { s.len() });
+    let mut f = from_vbox!(dyn FnMut(&str) -> usize, vb);
+    assert_eq!(3, f(&String::from("abc")));
+
+    // Multiple bounds
+    let vb: VBox =
+        into_vbox!(dyn (for<'a> Fn(&'a str) -> &'a str) + Send, first_word);
+    let f = from_vbox!(dyn (for<'a> Fn(&'a str) -> &'a str) + Send, vb);
+    assert_eq!("a", f("a b"));
+}
+
+#[test]
+fn test_generic_trait_and_associated_type() {
+    trait Handler {
+        type Request;
+        fn handle(&self, req: Self::Request) -> u64;
+    }
+
+    trait Convert<T> {
+        fn convert(&self) -> T;
+    }
+
+    struct Doubler;
+
+    impl Handler for Doubler {
+        type Request = u64;
+        fn handle(&self, req: u64) -> u64 {
+            req * 2
+        }
+    }
+
+    impl Convert<String> for u64 {
+        fn convert(&self) -> String {
+            format!("#{}", self)
+        }
+    }
+
+    impl Convert<u64> for u64 {
+        fn convert(&self) -> u64 {
+            self + 1
+        }
+    }
+
+    let vb: VBox = into_vbox!(dyn Handler<Request = u64>, Doubler);
+    let h = from_vbox!(dyn Handler<Request = u64>, vb);
+    assert_eq!(6, h.handle(3));
+
+    let vb: VBox = into_vbox!(dyn Convert<String>, 3u64);
+    let vb = map_vbox!(dyn Convert<String> -> dyn Convert<u64>, vb, |c| {
+        c.convert().len() as u64
+    });
+    let c = from_vbox!(dyn Convert<u64>, vb);
+    assert_eq!(3, c.convert());
+
+    let mut vb: VBox = into_vbox!(dyn Iterator<Item = (u64, String)>, {
+        vec![(1u64, "a".to_string())].into_iter()
+    });
+    let got = with_vbox!(dyn Iterator<Item = (u64, String)>, &mut vb, |it| {
+        it.next()
+    });
+    assert_eq!(Some((1, "a".to_string())), got);
+
+    // Generic lifetime parameter and associated type projection
+    trait Parse<'a> {
+        type Output;
+        fn parse(&self, s: &'a str) -> Self::Output;
+    }
+
+    struct Head;
+
+    impl<'a> Parse<'a> for Head {
+        type Output = &'a str;
+        fn parse(&self, s: &'a str) -> &'a str {
+            &s[..1]
+        }
+    }
+
+    let vb: VBox = into_vbox!(for<'a> dyn Parse<'a, Output = &'a str>, Head);
+    let p = from_vbox!(for<'a> dyn Parse<'a, Output = &'a str>, vb);
+    assert_eq!("x", p.parse(&String::from("xyz")));
+}
+
+#[test]
+fn test_higher_ranked_trait() {
+    trait Visitor<'a> {
+        fn visit(&mut self, s: &'a str) -> &'a str;
+    }
+
+    #[derive(Debug)]
+    struct FirstWord;
+
+    impl<'a> Visitor<'a> for FirstWord {
+        fn visit(&mut self, s: &'a str) -> &'a str {
+            s.split(' ').next().unwrap()
+        }
+    }
+
+    static FIRST_WORD: VTable<dyn for<'a> Visitor<'a>> =
+        static_vtable!(for<'a> dyn Visitor<'a>, FirstWord);
+
+    let s = String::from("hello world");
+
+    // Both spellings are the same trait object type.
+    let vb: VBox = into_vbox!(dyn for<'a> Visitor<'a>, FirstWord);
+    assert!(vb.is_dyn::<dyn for<'a> Visitor<'a>>());
+    let mut v = from_vbox!(for<'a> dyn Visitor<'a>, vb);
+    assert_eq!("hello", v.visit(&s));
+
+    let vb = FIRST_WORD.pack(FirstWord);
+    let mut v = from_vbox!(dyn for<'a> Visitor<'a>, vb);
+    assert_eq!("hello", v.visit(&s));
+
+    // Extra bounds
+    let mut vb: VBox = into_vbox!(dyn for<'a> Visitor<'a> + Send, FirstWord);
+    let got = with_vbox!(dyn for<'a> Visitor<'a> + Send, &mut vb, |v| {
+        v.visit(&s).len()
+    });
+    assert_eq!(5, got);
+
+    // The `for<'a> dyn Trait` form in the other macros
+    let mut vb: VBox = into_vbox_debug!(for<'a> dyn Visitor<'a>, FirstWord);
+    assert_eq!(Some("FirstWord".to_string()), vb.debug_string());
+
+    let got =
+        with_vbox!(for<'a> dyn Visitor<'a>, &mut vb, |v| v.visit(&s).len());
+    assert_eq!(5, got);
+
+    replace_vbox!(for<'a> dyn Visitor<'a>, &mut vb, FirstWord);
+
+    let vb = map_vbox!(for<'a> dyn Visitor<'a> -> dyn Debug, vb, |mut v| {
+        v.visit(&s).to_string()
+    });
+    assert_eq!("\"hello\"", format!("{:?}", from_vbox!(dyn Debug, vb)));
+
+    let vb: VBox = into_vbox!(for<'a> dyn Visitor<'a>, FirstWord);
+    let v: &'static mut dyn for<'a> Visitor<'a> =
+        leak_vbox!(for<'a> dyn Visitor<'a>, vb);
+    assert_eq!("hello", v.visit(&s));
+}
+
+#[test]
+#[should_panic(expected = "expected type_id")]
+fn test_generic_trait_identity() {
+    trait Convert<T> {
+        fn convert(&self) -> T;
+    }
+
+    impl Convert<String> for u64 {
+        fn convert(&self) -> String {
+            self.to_string()
+        }
+    }
+
+    let vb: VBox = into_vbox!(dyn Convert<String>, 3u64);
+    let _c = from_vbox!(dyn Convert<u64>, vb);
+}
+
+#[test]
+fn test_unit_and_signal() {
+    use std::any::Any;
+
+    struct Ping;
+    struct Shutdown;
+
+    let vb = VBox::unit();
+    assert!(vb.is_unit());
+    assert!(!vb.is_signal::<Ping>());
+    assert_eq!(0, vb.size_of_payload());
+
+    let vb = VBox::signal(Ping);
+    assert!(vb.is_signal::<Ping>());
+    assert!(!vb.is_signal::<Shutdown>());
+    assert!(!vb.is_unit());
+
+    let any = from_vbox!(dyn Any + Send, vb);
+    assert!(any.is::<Ping>());
+
+    // A value packed as another trait is not a signal
+    let vb: VBox = into_vbox!(dyn Debug, ());
+    assert!(!vb.is_unit());
+}
+
+#[test]
+fn test_into_raw() {
+    struct Foo {
+        a: Arc<AtomicU64>,
+    }
+
+    impl Drop for Foo {
+        fn drop(&mut self) {
+            self.a.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let drop_cnt = Arc::new(AtomicU64::new(0));
+
+    let vb: VBox = into_vbox!(dyn Send, Foo {
+        a: drop_cnt.clone()
+    });
+    let raw = vb.into_raw();
+    assert_eq!(0, drop_cnt.load(Ordering::Relaxed), "not dropped");
+
+    let vb = unsafe { VBox::from_raw(raw) };
+    assert!(vb.is::<Foo>());
+    drop(vb);
+    assert_eq!(1, drop_cnt.load(Ordering::Relaxed), "dropped once");
+}
+
+#[test]
+fn test_new_unchecked() {
+    let fat: *mut dyn Debug = Box::into_raw(Box::new(String::from("foo")));
+    let (data, vtable): (*mut (), *const ()) =
+        unsafe { std::mem::transmute(fat) };
+
+    let vb = unsafe { VBox::new_unchecked::<dyn Debug>(data, vtable, None) };
+    assert!(!vb.is::<String>(), "concrete type is not supplied");
+    assert_eq!(std::mem::size_of::<String>(), vb.size_of_payload());
+    assert_eq!(r#""foo""#, format!("{:?}", from_vbox!(dyn Debug, vb)));
+
+    let fat: *mut dyn Debug = Box::into_raw(Box::new(String::from("bar")));
+    let (data, vtable): (*mut (), *const ()) =
+        unsafe { std::mem::transmute(fat) };
+
+    let vb = unsafe {
+        VBox::new_unchecked::<dyn Debug>(
+            data,
+            vtable,
+            Some(std::any::TypeId::of::<String>()),
+        )
+    };
+    assert_eq!("bar", vb.into_inner::<String>().unwrap());
+}
+
+#[test]
+fn test_data_ptr_and_vtable_ptr() {
+    let vb: VBox = into_vbox!(dyn Debug, 10u64);
+    let data = vb.data_ptr();
+    let vtable = vb.vtable_ptr();
+
+    let raw = vb.into_raw();
+    assert_eq!(data, raw.data() as *const ());
+    assert_eq!(vtable, raw.vtable());
+
+    let vb = unsafe { VBox::from_raw(raw) };
+    let p: Box<dyn Debug> = from_vbox!(dyn Debug, vb);
+    assert_eq!(data, &*p as *const dyn Debug as *const ());
+}
+
+#[test]
+fn test_same_impl() {
+    let a: VBox = into_vbox!(dyn Debug, 1u64);
+    let b: VBox = into_vbox!(dyn Debug, 2u64);
+    let c: VBox = into_vbox!(dyn Debug, 3u32);
+    let d: VBox = into_vbox!(dyn Debug + Send, 4u64);
+
+    assert!(a.same_impl(&b));
+    assert!(!a.same_impl(&c));
+    assert!(!a.same_impl(&d));
+
+    // The concrete type is unknown: compare the vtable pointers.
+    let e = into_vbox_dyn!(dyn Debug + Send, Box::new(5u64));
+    let f = into_vbox_dyn!(dyn Debug + Send, Box::new(6u64));
+    assert!(e.same_impl(&f));
+    assert!(!e.same_impl(&d));
+
+    // A vtable shared by two concrete types, as if identical vtables are
+    // merged, is not the same implementation.
+    #[derive(Debug)]
+    #[repr(transparent)]
+    struct Id(#[allow(dead_code)] u64);
+
+    let data = Box::into_raw(Box::new(7u64)) as *mut ();
+    let g = unsafe {
+        VBox::new_unchecked::<dyn Debug>(
+            data,
+            a.vtable_ptr(),
+            Some(std::any::TypeId::of::<Id>()),
+        )
+    };
+    assert_eq!(a.vtable_ptr(), g.vtable_ptr());
+    assert!(!a.same_impl(&g));
+}
+
+#[test]
+fn test_downcast_first() {
+    use vbox::downcast_first;
+
+    fn describe(vb: VBox) -> Result<String, VBox> {
+        downcast_first!(
+            vb,
+            |n: u64| format!("u64: {}", n),
+            |s: String| format!("String: {}", s),
+            |v: Vec<u8>| format!("bytes: {}", v.len()),
+        )
+    }
+
+    let got = describe(into_vbox!(dyn Debug, 3u64));
+    assert_eq!("u64: 3", got.unwrap());
+
+    let got = describe(into_vbox!(dyn Debug, vec![1u8, 2]));
+    assert_eq!("bytes: 2", got.unwrap());
+
+    let got = describe(into_vbox!(dyn Debug, 3u32));
+    let leftover = got.unwrap_err();
+    assert_eq!("3", format!("{:?}", from_vbox!(dyn Debug, leftover)));
+
+    // Single candidate without trailing comma
+    let vb: VBox = into_vbox!(dyn Debug, 1i8);
+    let got = downcast_first!(vb, |n: i8| n + 1);
+    assert_eq!(2, got.unwrap());
+}
+
+#[test]
+fn test_vbox_enum() {
+    use std::fmt::Display;
+
+    use vbox::vbox_enum;
+
+    trait Command {
+        fn exec(&self) -> u64;
+    }
+
+    impl Command for u64 {
+        fn exec(&self) -> u64 {
+            *self * 2
+        }
+    }
+
+    vbox_enum! {
+        /// Messages of a test channel.
+        enum Msg {
+            Cmd(dyn Command + Send),
+            Text(dyn Display + Send),
+        }
+    }
+
+    fn handle(msg: Msg) -> String {
+        match msg {
+            Msg::Cmd(c) => c.exec().to_string(),
+            Msg::Text(t) => t.to_string(),
+        }
+    }
+
+    let vb: VBox = into_vbox!(dyn Command + Send, 3u64);
+    assert!(vb.is_dyn::<dyn Command + Send>());
+    assert!(!vb.is_dyn::<dyn Command>());
+    assert_eq!("6", handle(Msg::try_from(vb).unwrap()));
+
+    let msg = Msg::from(Box::new("foo") as Box<dyn Display + Send>);
+    let vb: VBox = msg.into();
+    assert_eq!("foo", handle(Msg::try_from(vb).unwrap()));
+
+    let vb: VBox = into_vbox!(dyn Debug, 1u64);
+    let vb = Msg::try_from(vb).map(|_| ()).unwrap_err();
+    assert!(vb.is_dyn::<dyn Debug>());
+}
+
+#[test]
+fn test_payload_in_one_allocation() {
+    // The payload is where a `Box` would put it: no extra header before it.
+    let b: Box<dyn Debug + Send> = Box::new([1u64, 2, 3]);
+    let addr = &*b as *const (dyn Debug + Send) as *const ();
+
+    let vb = into_vbox_dyn!(dyn Debug + Send, b);
+    assert_eq!(addr, vb.data_ptr());
+    assert_eq!(24, vb.size_of_payload());
+}
+
+#[test]
+#[should_panic(
+    expected = "dyn core::fmt::Debug + core::marker::Send + core::marker::Sync"
+)]
+fn test_auto_traits_mismatch() {
+    let vb: VBox = into_vbox!(dyn Debug + Send + Sync, 3u64);
+    assert!(vb.is_dyn::<dyn Debug + Send + Sync>());
+    assert!(!vb.is_dyn::<dyn Debug + Send>());
+    assert!(!vb.is_dyn::<dyn Debug>());
+
+    let _d = from_vbox!(dyn Debug + Send, vb);
+}
+
+#[test]
+fn test_explicit_static_lifetime() {
+    static GREETING: &str = "hello";
+
+    let suffix = String::from(" world");
+    let greeting: &'static str = GREETING;
+    let f = move || greeting.len() + suffix.len();
+
+    let vb: VBox = into_vbox!(dyn Fn() -> usize + Send + 'static, f);
+    assert!(vb.is_dyn::<dyn Fn() -> usize + Send>());
+
+    let f = from_vbox!(dyn Fn() -> usize + Send, vb);
+    assert_eq!(11, f());
+
+    let vb: VBox = into_vbox!(dyn Debug + Send + 'static, &GREETING);
+    let d = from_vbox!(dyn Debug + Send + 'static, vb);
+    assert_eq!("\"hello\"", format!("{:?}", d));
+}
+
+#[test]
+fn test_into_vbox_debug() {
+    trait Command {
+        fn run(&self) -> u64;
+    }
+
+    #[derive(Debug)]
+    struct Ping(u64);
+
+    impl Command for Ping {
+        fn run(&self) -> u64 {
+            self.0
+        }
+    }
+
+    let vb: VBox = into_vbox_debug!(dyn Command, Ping(3));
+    assert_eq!(Some("Ping(3)".to_string()), vb.debug_string());
+    assert_eq!(Some(7), vb.inspect(|args| args.to_string().len()));
+    assert!(format!("{:?}", vb).contains("payload: Ping(3)"));
+
+    // Kept by raw parts
+    let vb = unsafe { VBox::from_raw(vb.into_raw()) };
+    assert_eq!(Some("Ping(3)".to_string()), vb.debug_string());
+
+    let cmd = from_vbox!(dyn Command, vb);
+    assert_eq!(3, cmd.run());
+
+    // Not captured
+    struct Pong(u64);
+
+    impl Command for Pong {
+        fn run(&self) -> u64 {
+            self.0
+        }
+    }
+
+    let mut vb: VBox = into_vbox!(dyn Command, Pong(4));
+    assert_eq!(None, vb.debug_string());
+    assert_eq!(None, vb.inspect(|_| ()));
+    assert!(!format!("{:?}", vb).contains("payload"));
+
+    // Dropped when the payload is replaced
+    let mut vb2: VBox = into_vbox_debug!(dyn Command, Ping(5));
+    std::mem::swap(&mut vb, &mut vb2);
+    replace_vbox!(dyn Command, &mut vb, Ping(6));
+    assert_eq!(None, vb.debug_string());
+}
+
+#[test]
+fn test_into_vbox_auto_capture() {
+    use std::any::Any;
+    use std::sync::mpsc;
+
+    #[derive(Debug, Clone)]
+    struct Ping(u64);
+
+    // Debug and Clone
+    let vb: VBox = into_vbox!(dyn Any + Send, Ping(3));
+    assert_eq!(Some("Ping(3)".to_string()), vb.debug_string());
+    assert_eq!(None, vb.display_string());
+
+    let copy = vb.try_clone().unwrap();
+    assert!(copy.same_impl(&vb));
+    assert_eq!(
+        3,
+        from_vbox!(dyn Any + Send, copy).downcast::<Ping>().unwrap().0
+    );
+    assert_eq!(
+        3,
+        from_vbox!(dyn Any + Send, vb).downcast::<Ping>().unwrap().0
+    );
+
+    // Debug, Clone and Display
+    let vb: VBox = into_vbox!(dyn Debug + Send, 5u64);
+    assert_eq!(Some("5".to_string()), vb.display_string());
+    assert!(format!("{:?}", vb).contains("payload: 5"));
+    let copy = vb.try_clone().unwrap();
+    assert_eq!("5", format!("{:?}", from_vbox!(dyn Debug + Send, copy)));
+    let _ = from_vbox!(dyn Debug + Send, vb);
+
+    // Clone without Debug is not captured: opt in with the builder.
+    #[derive(Clone)]
+    struct Token(u64);
+
+    let vb: VBox = into_vbox!(dyn Any + Send, Token(2));
+    assert!(vb.try_clone().is_none());
+    let vb = vb.into_inner::<Token>().ok().unwrap();
+    let vb = VBox::builder(vb).with_clone().build();
+    assert_eq!(
+        2,
+        vb.try_clone().unwrap().into_inner::<Token>().ok().unwrap().0
+    );
+    vb.discard();
+
+    // None: a closure capturing a value that is not `Clone`
+    let (tx, rx) = mpsc::channel::<u64>();
+    let vb: VBox =
+        into_vbox!(dyn FnOnce() -> u64 + Send, move || { rx.recv().unwrap() });
+    assert_eq!(None, vb.debug_string());
+    assert_eq!(None, vb.display_string());
+    assert!(vb.try_clone().is_none());
+
+    tx.send(7).unwrap();
+    assert_eq!(7, from_vbox!(dyn FnOnce() -> u64 + Send, vb)());
+
+    // Only the bounds are known in a generic function
+    fn pack<T: Debug + Send + 'static>(v: T) -> VBox {
+        into_vbox!(dyn Any + Send, v)
+    }
+
+    let vb = pack(Ping(4));
+    assert_eq!(Some("Ping(4)".to_string()), vb.debug_string());
+    assert!(vb.try_clone().is_none());
+    let _ = from_vbox!(dyn Any + Send, vb);
+}
+
+#[test]
+fn test_any_conversions() {
+    use std::any::Any;
+
+    // Sync flavor
+    let any: Box<dyn Any + Send + Sync> = Box::new(3u64);
+    let vb = VBox::from(any);
+    assert!(vb.is::<u64>());
+    assert!(vb.is_dyn::<dyn Any + Send + Sync>());
+
+    let any: Box<dyn Any + Send + Sync> = vb.try_into().unwrap();
+    assert_eq!(Some(&3u64), any.downcast_ref::<u64>());
+
+    // Non-Sync flavor
+    let any: Box<dyn Any + Send> = Box::new(std::cell::Cell::new(4u64));
+    let vb = VBox::from(any);
+    let vb = vb.into_any_sync().unwrap_err();
+    let any = vb.into_any().unwrap();
+    assert_eq!(4, any.downcast_ref::<std::cell::Cell<u64>>().unwrap().get());
+
+    // Sync can be converted to non-Sync
+    let vb = into_vbox!(dyn Any + Send + Sync, 5u64);
+    let any: Box<dyn Any + Send> = vb.try_into().unwrap();
+    assert_eq!(Some(&5u64), any.downcast_ref::<u64>());
+
+    // Not packed as Any
+    let vb = into_vbox!(dyn Debug, 6u64);
+    let vb = vb.into_any().unwrap_err();
+    let vb = vb.into_any_sync().unwrap_err();
+    assert_eq!(6u64, vb.into_inner::<u64>().unwrap());
+}
+
+#[test]
+fn test_guard_vbox() {
+    trait Counter {
+        fn incr(&mut self) -> u64;
+    }
+
+    impl Counter for u64 {
+        fn incr(&mut self) -> u64 {
+            *self += 1;
+            *self
+        }
+    }
+
+    fn handle(vbox: &mut VBox, fail: bool) -> Result<u64, ()> {
+        let mut c = guard_vbox!(dyn Counter, vbox);
+        let n = c.incr();
+        if fail {
+            return Err(());
+        }
+        Ok(n + c.incr())
+    }
+
+    let mut vb: VBox = into_vbox!(dyn Counter, 0u64);
+
+    assert_eq!(Err(()), handle(&mut vb, true));
+    assert_eq!(Ok(2 + 3), handle(&mut vb, false));
+
+    // The VBox is intact after a panic in the handler.
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let mut c = guard_vbox!(dyn Counter, &mut vb);
+        c.incr();
+        panic!("handler failed");
+    }));
+    assert!(res.is_err());
+
+    assert_eq!(4u64, vb.into_inner::<u64>().unwrap());
+}
+
+#[test]
+#[should_panic(expected = "expected type_id")]
+fn test_guard_vbox_type_mismatch() {
+    let mut vb: VBox = into_vbox!(dyn Debug, 0u64);
+    let _g = guard_vbox!(dyn std::fmt::Display, &mut vb);
+}
+
+#[test]
+fn test_from_vbox_vec() {
+    use std::fmt::Display;
+
+    let vboxes = vec![
+        into_vbox!(dyn Display, 1u64),
+        into_vbox!(dyn Display, "a"),
+        into_vbox!(dyn Display, 2u64),
+        into_vbox!(dyn Display, 'c'),
+    ];
+
+    let displays = from_vbox_vec!(dyn Display, vboxes);
+    let s = displays.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+    assert_eq!(vec!["1", "a", "2", "c"], s);
+
+    let empty = from_vbox_vec!(dyn Display, Vec::new());
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_try_unpack_vec_mismatch() {
+    use std::fmt::Display;
+
+    let vboxes = vec![
+        into_vbox!(dyn Display, 1u64),
+        into_vbox!(dyn Display, 2u64),
+        into_vbox!(dyn Debug, 3u64),
+    ];
+
+    // Nothing is unpacked if any mismatches.
+    let Err(vboxes) = VBox::try_unpack_vec::<dyn Display>(vboxes) else {
+        panic!("expect Err");
+    };
+    let got = vboxes
+        .into_iter()
+        .map(|v| v.into_inner::<u64>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(vec![1, 2, 3], got);
+}
+
+#[test]
+#[should_panic(expected = "expected type_id")]
+fn test_from_vbox_vec_mismatch() {
+    use std::fmt::Display;
+
+    let vboxes =
+        vec![into_vbox!(dyn Display, 1u64), into_vbox!(dyn Debug, 2u64)];
+    let _ = from_vbox_vec!(dyn Display, vboxes);
+}
+
+#[test]
+fn test_try_unpack() {
+    use std::fmt::Display;
+
+    use vbox::try_from_vbox;
+    use vbox::TypeMismatch;
+
+    let vbox = into_vbox!(dyn Debug, 1u64);
+
+    let Err(err) = try_from_vbox!(dyn Display, vbox) else {
+        panic!("expect Err");
+    };
+    assert_eq!(
+        TypeMismatch {
+            expected: "dyn core::fmt::Display",
+            actual: "dyn core::fmt::Debug",
+        },
+        err.mismatch()
+    );
+    assert_eq!(
+        "can not unpack VBox: packed as: dyn core::fmt::Debug, expected: dyn core::fmt::Display",
+        err.to_string()
+    );
+
+    let d = try_from_vbox!(dyn Debug, err.into_inner()).unwrap();
+    assert_eq!("1", format!("{:?}", d));
+}
+
+#[test]
+fn test_try_as_dyn() {
+    use std::fmt::Display;
+
+    let mut vbox = into_vbox!(dyn Iterator<Item = u64>, 1..3u64);
+
+    assert!(vbox.try_as_dyn::<dyn Display>().is_err());
+    let err = vbox.try_as_dyn_mut::<dyn Display>().err().unwrap();
+    assert_eq!("dyn core::fmt::Display", err.expected);
+
+    let it = vbox.try_as_dyn_mut::<dyn Iterator<Item = u64>>().unwrap();
+    assert_eq!(Some(1), it.next());
+
+    let it = vbox.try_as_dyn::<dyn Iterator<Item = u64>>().unwrap();
+    assert_eq!((1, Some(1)), it.size_hint());
+
+    vbox.discard();
+}
@@ -0,0 +1,90 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+
+use vbox::from_vbox_vec;
+use vbox::into_vbox;
+use vbox::into_vbox_debug;
+use vbox::VVec;
+
+#[test]
+fn test_vvec_collect() {
+    let col: VVec = (0..3u64).map(|i| into_vbox!(dyn Display, i)).collect();
+    assert_eq!(3, col.len());
+
+    let got = col.iter().map(|v| v.as_dyn::<dyn Display>().to_string());
+    assert_eq!(vec!["0", "1", "2"], got.collect::<Vec<_>>());
+
+    let empty: VVec = std::iter::empty().collect();
+    assert!(empty.is_empty());
+}
+
+#[test]
+#[should_panic(
+    expected = "VVec of u64 as dyn core::fmt::Display can not hold u32 as dyn core::fmt::Display"
+)]
+fn test_vvec_collect_mismatch() {
+    let _col: VVec =
+        vec![into_vbox!(dyn Display, 1u64), into_vbox!(dyn Display, 2u32)]
+            .into_iter()
+            .collect();
+}
+
+#[test]
+fn test_vvec_push() {
+    let mut col = VVec::new();
+    assert!(col.push_value(1u64).is_err());
+
+    col.push(into_vbox!(dyn Display, 1u64)).unwrap();
+
+    // Same concrete type, different trait.
+    let v = col.push(into_vbox!(dyn Debug, 2u64)).unwrap_err();
+    assert_eq!(2, v.into_inner::<u64>().unwrap());
+
+    assert_eq!(Err(3u32), col.push_value(3u32));
+    col.push_value(3u64).unwrap();
+    assert_eq!(2, col.len());
+
+    let displays = from_vbox_vec!(dyn Display, col.into_vec());
+    let got = displays.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+    assert_eq!(vec!["1", "3"], got);
+}
+
+#[test]
+fn test_vvec_extend() {
+    let mut col = VVec::new();
+    col.extend([into_vbox_debug!(dyn Display, 1u64)]);
+    col.extend([into_vbox!(dyn Display, 2u64)]);
+    col.extend_values([3u64, 4]);
+    assert_eq!(4, col.len());
+
+    // Values are packed the same way as the first element, including the
+    // captured `Debug`.
+    assert_eq!(Some("4".to_string()), col.get(3).unwrap().debug_string());
+    assert!(col.get(3).unwrap().same_impl(col.get(0).unwrap()));
+
+    let got = col
+        .into_iter()
+        .map(|v| v.into_inner::<u64>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(vec![1, 2, 3, 4], got);
+}
+
+#[test]
+#[should_panic(expected = "VVec::extend_values() on an empty VVec")]
+fn test_vvec_extend_values_empty() {
+    let mut col = VVec::new();
+    col.extend_values([1u64]);
+}
+
+#[test]
+fn test_vvec_debug() {
+    let col: VVec = vec![into_vbox!(dyn Display, 1u64)].into_iter().collect();
+    assert_eq!(
+        r#"VVec { impl: "u64 as dyn core::fmt::Display", len: 1 }"#,
+        format!("{:?}", col)
+    );
+    assert_eq!("VVec { len: 0 }", format!("{:?}", VVec::new()));
+    for v in col {
+        v.discard();
+    }
+}
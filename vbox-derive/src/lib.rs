@@ -0,0 +1,239 @@
+//! Derive macros for [vbox](https://docs.rs/vbox).
+//!
+//! Use them via the `derive` feature of `vbox`, instead of depending on this
+//! crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::parse_macro_input;
+use syn::punctuated::Punctuated;
+use syn::DeriveInput;
+use syn::GenericParam;
+use syn::Ident;
+use syn::ItemTrait;
+use syn::Token;
+use syn::TypeParamBound;
+
+/// Generate an `into_vbox_<trait>()` method for each trait listed in
+/// `#[vbox(traits(...))]`, so that a message type advertises exactly which
+/// erased views it supports.
+///
+/// Each method packs `self` as `dyn Trait` and returns the `VBox`. The method
+/// name is built from the last path segment of the first bound in snake case,
+/// e.g., `Debug + Sync` generates `into_vbox_debug()`, which packs `self` as
+/// `dyn Debug + Sync`.
+///
+/// ```ignore
+/// #[derive(Debug, vbox::IntoVBox)]
+/// #[vbox(traits(Debug, Command))]
+/// struct Ping;
+///
+/// let vbox = Ping.into_vbox_command();
+/// let cmd = vbox::from_vbox!(dyn Command, vbox);
+/// ```
+#[proc_macro_derive(IntoVBox, attributes(vbox))]
+pub fn derive_into_vbox(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand_into_vbox(input) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Generate an `ErasableAs<Trait>` extension trait for a trait, with a blanket
+/// impl for every `T: Trait + Send + 'static`, so that a call site reads
+/// `value.erase()` instead of `into_vbox!(dyn Trait, value)`.
+///
+/// The extra bounds in the arguments, e.g., `#[erasable(Sync)]`, are added to
+/// the trait object, i.e., `value.erase()` packs `value` as `dyn Trait +
+/// Sync`.
+///
+/// A trait with lifetime parameters, e.g., `trait Visitor<'a>`, is packed as
+/// the higher-ranked `dyn for<'a> Visitor<'a>`.
+///
+/// Import only one `ErasableAs<Trait>` where `erase()` is called, if a type
+/// implements more than one erasable trait.
+///
+/// ```ignore
+/// #[vbox::erasable]
+/// trait Command {
+///     fn name(&self) -> String;
+/// }
+///
+/// let vbox = Ping.erase();
+/// let cmd = vbox::from_vbox!(dyn Command, vbox);
+/// ```
+#[proc_macro_attribute]
+pub fn erasable(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let extra = parse_macro_input!(attr with Bounds::parse_terminated);
+    let item = parse_macro_input!(item as ItemTrait);
+
+    match expand_erasable(extra, item) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+type Bounds = Punctuated<TypeParamBound, Token![+]>;
+
+fn expand_erasable(
+    extra: Bounds,
+    item: ItemTrait,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut lifetimes = Vec::new();
+    for param in item.generics.params.iter() {
+        match param {
+            GenericParam::Lifetime(lt) if lt.bounds.is_empty() => {
+                lifetimes.push(&lt.lifetime);
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &item.generics,
+                    "expect a trait without type or const parameters, \
+                     or bounds on lifetime parameters",
+                ));
+            }
+        }
+    }
+
+    let vis = &item.vis;
+    let name = &item.ident;
+    let erasable = Ident::new(&format!("ErasableAs{}", name), name.span());
+
+    // A trait with lifetime parameters is erased as a higher-ranked trait
+    // object, e.g., `dyn for<'a> Visitor<'a>`.
+    let bound = if lifetimes.is_empty() {
+        quote!(#name)
+    } else {
+        quote!(for<#(#lifetimes),*> #name<#(#lifetimes),*>)
+    };
+
+    let extra = extra.iter().collect::<Vec<_>>();
+    let dyn_ty = quote!(dyn #bound #(+ #extra)*);
+
+    let ty = dyn_ty
+        .to_string()
+        .replace(" :: ", "::")
+        .replace(" <", "<")
+        .replace("< ", "<")
+        .replace(" >", ">");
+    let trait_doc = format!("Pack a value into a `VBox` as `{}`.", ty);
+    let method_doc = format!("Pack `self` into a `VBox` as `{}`.", ty);
+
+    Ok(quote! {
+        #item
+
+        #[doc = #trait_doc]
+        #vis trait #erasable {
+            #[doc = #method_doc]
+            fn erase(self) -> ::vbox::VBox;
+        }
+
+        impl<T> #erasable for T
+        where T: #bound #(+ #extra)* + ::std::marker::Send + 'static
+        {
+            fn erase(self) -> ::vbox::VBox {
+                ::vbox::into_vbox!(#dyn_ty, self)
+            }
+        }
+    })
+}
+
+fn expand_into_vbox(
+    input: DeriveInput,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let traits = parse_traits(&input)?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+
+    let methods = traits
+        .iter()
+        .map(|bounds| {
+            let method = method_name(bounds)?;
+            let doc = format!(
+                "Pack `self` into a `VBox` as `dyn {}`.",
+                quote!(#bounds).to_string().replace(" :: ", "::")
+            );
+
+            Ok(quote! {
+                #[doc = #doc]
+                pub fn #method(self) -> ::vbox::VBox
+                where Self: #bounds + ::std::marker::Send + 'static {
+                    ::vbox::into_vbox!(dyn #bounds, self)
+                }
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #(#methods)*
+        }
+    })
+}
+
+/// Parse `#[vbox(traits(A, B + Sync))]` into a list of bounds.
+fn parse_traits(input: &DeriveInput) -> syn::Result<Vec<Bounds>> {
+    let mut traits = Vec::new();
+
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("vbox")) {
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("traits") {
+                return Err(meta.error("expect `traits(...)`"));
+            }
+
+            let content;
+            syn::parenthesized!(content in meta.input);
+
+            let list = Punctuated::<Bounds, Token![,]>::parse_terminated_with(
+                &content,
+                Bounds::parse_separated_nonempty,
+            )?;
+            traits.extend(list);
+            Ok(())
+        })?;
+    }
+
+    if traits.is_empty() {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "expect `#[vbox(traits(...))]` to list the traits to pack as",
+        ));
+    }
+
+    Ok(traits)
+}
+
+/// Build `into_vbox_<trait>` from the first trait in the bounds.
+fn method_name(bounds: &Bounds) -> syn::Result<Ident> {
+    let first = bounds.iter().find_map(|b| match b {
+        TypeParamBound::Trait(t) => t.path.segments.last(),
+        _ => None,
+    });
+
+    let Some(seg) = first else {
+        return Err(syn::Error::new(Span::call_site(), "expect a trait"));
+    };
+
+    let name = format!("into_vbox_{}", snake_case(&seg.ident.to_string()));
+    Ok(Ident::new(&name, seg.ident.span()))
+}
+
+fn snake_case(s: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in s.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
@@ -0,0 +1,75 @@
+use std::fmt::Debug;
+
+use vbox::from_vbox;
+use vbox::IntoVBox;
+
+trait Command {
+    fn name(&self) -> String;
+}
+
+#[derive(Debug, IntoVBox)]
+#[vbox(traits(Debug, Command))]
+struct Ping {
+    seq: u64,
+}
+
+impl Command for Ping {
+    fn name(&self) -> String {
+        format!("ping-{}", self.seq)
+    }
+}
+
+#[derive(Debug, IntoVBox)]
+#[vbox(traits(Debug + Sync))]
+#[vbox(traits(std::fmt::Display))]
+struct Wrapper<T>(T);
+
+impl<T: std::fmt::Display> std::fmt::Display for Wrapper<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}]", self.0)
+    }
+}
+
+#[test]
+fn test_derive_into_vbox() {
+    let vbox = Ping { seq: 1 }.into_vbox_command();
+    let cmd = from_vbox!(dyn Command, vbox);
+    assert_eq!("ping-1", cmd.name());
+
+    let vbox = Ping { seq: 2 }.into_vbox_debug();
+    let d = from_vbox!(dyn Debug, vbox);
+    assert_eq!("Ping { seq: 2 }", format!("{:?}", d));
+}
+
+#[test]
+fn test_derive_into_vbox_generic() {
+    let vbox = Wrapper(3u64).into_vbox_debug();
+    assert!(vbox.is_dyn::<dyn Debug + Sync>());
+    let d = from_vbox!(dyn Debug + Sync, vbox);
+    assert_eq!("Wrapper(3)", format!("{:?}", d));
+
+    let vbox = Wrapper("x").into_vbox_display();
+    let d = from_vbox!(dyn std::fmt::Display, vbox);
+    assert_eq!("[x]", d.to_string());
+}
+
+trait Visitor<'a> {
+    fn visit(&mut self, s: &'a str) -> &'a str;
+}
+
+#[derive(IntoVBox)]
+#[vbox(traits(for<'a> Visitor<'a> + Send))]
+struct Last;
+
+impl<'a> Visitor<'a> for Last {
+    fn visit(&mut self, s: &'a str) -> &'a str {
+        s.split(' ').last().unwrap()
+    }
+}
+
+#[test]
+fn test_derive_into_vbox_higher_ranked() {
+    let vbox = Last.into_vbox_visitor();
+    let mut v = from_vbox!(dyn for<'a> Visitor<'a> + Send, vbox);
+    assert_eq!("world", v.visit(&String::from("hello world")));
+}
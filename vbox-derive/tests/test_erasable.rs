@@ -0,0 +1,84 @@
+use std::fmt::Debug;
+
+use vbox::erasable;
+use vbox::from_vbox;
+
+#[erasable]
+trait Command {
+    fn name(&self) -> String;
+}
+
+#[erasable(Sync)]
+pub trait Query: Debug {
+    fn key(&self) -> u64;
+}
+
+#[derive(Debug)]
+struct Ping {
+    seq: u64,
+}
+
+impl Command for Ping {
+    fn name(&self) -> String {
+        format!("ping-{}", self.seq)
+    }
+}
+
+impl Query for Ping {
+    fn key(&self) -> u64 {
+        self.seq
+    }
+}
+
+#[test]
+fn test_erasable() {
+    let vbox = ErasableAsCommand::erase(Ping { seq: 1 });
+    assert!(vbox.is::<Ping>());
+    let cmd = from_vbox!(dyn Command, vbox);
+    assert_eq!("ping-1", cmd.name());
+}
+
+#[test]
+fn test_erasable_with_extra_bounds() {
+    let vbox = ErasableAsQuery::erase(Ping { seq: 2 });
+    assert!(vbox.is_dyn::<dyn Query + Sync>());
+    let q = from_vbox!(dyn Query + Sync, vbox);
+    assert_eq!(2, q.key());
+}
+
+mod method_call {
+    use vbox::from_vbox;
+
+    use super::Command;
+    use super::ErasableAsCommand;
+    use super::Ping;
+
+    #[test]
+    fn test_erasable_method_call() {
+        let vbox = Ping { seq: 3 }.erase();
+        assert_eq!("ping-3", from_vbox!(dyn Command, vbox).name());
+    }
+}
+
+#[erasable(Sync)]
+trait Visitor<'a> {
+    fn visit(&mut self, s: &'a str) -> &'a str;
+}
+
+struct FirstWord;
+
+impl<'a> Visitor<'a> for FirstWord {
+    fn visit(&mut self, s: &'a str) -> &'a str {
+        s.split(' ').next().unwrap()
+    }
+}
+
+#[test]
+fn test_erasable_with_lifetime() {
+    let vbox = ErasableAsVisitor::erase(FirstWord);
+    assert!(vbox.is_dyn::<dyn for<'a> Visitor<'a> + Sync>());
+
+    let mut v = from_vbox!(dyn for<'a> Visitor<'a> + Sync, vbox);
+    let s = String::from("hello world");
+    assert_eq!("hello", v.visit(&s));
+}